@@ -0,0 +1,53 @@
+//! Time-to-configured for a cgroup: `apply_limits` against one setter per
+//! limit
+//!
+//! The cgroup lives under a fake root in the temp dir, so the writes are
+//! plain files and what is measured is the runtime overhead that batching
+//! saves. Run with `cargo bench -p vortex-cgroup`.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use vortex_cgroup::{CGroupConfig, CGroupController, ResourceBackend};
+use vortex_core::{ContainerId, CpuCores, CpuLimit, MemoryLimit, MemorySize, ResourceLimits};
+
+fn apply_limits(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let root = std::env::temp_dir().join(format!("vortex-bench-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    let controller = runtime
+        .block_on(CGroupController::with_config(
+            ContainerId::new("bench").unwrap(),
+            CGroupConfig::new().with_root(&root),
+        ))
+        .unwrap();
+    let limits = ResourceLimits {
+        cpu: Some(CpuLimit::new(CpuCores::new(0.5))),
+        cpu_weight: Some(50),
+        memory: Some(MemoryLimit::new(MemorySize::from_mb(64))),
+        pids: Some(32),
+        io: Vec::new(),
+    };
+
+    let mut group = c.benchmark_group("time_to_configured");
+    group.bench_function("apply_limits", |b| {
+        b.to_async(&runtime)
+            .iter(|| async { controller.apply_limits(&limits).await.unwrap() });
+    });
+    group.bench_function("one_setter_per_limit", |b| {
+        b.to_async(&runtime).iter(|| async {
+            controller.set_pids_limit(32).await.unwrap();
+            controller.set_cpu_weight(50).await.unwrap();
+            controller.set_cpu_limit(limits.cpu.unwrap()).await.unwrap();
+            controller
+                .set_memory_limit(limits.memory.unwrap())
+                .await
+                .unwrap();
+        });
+    });
+    group.finish();
+
+    runtime.block_on(controller.cleanup()).unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+criterion_group!(benches, apply_limits);
+criterion_main!(benches);
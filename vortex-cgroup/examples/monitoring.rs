@@ -47,6 +47,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 ContainerEvent::MemoryPressure { id, percentage, .. } => {
                     println!("⚠️  Container {} memory at {:.1}%", id, percentage);
                 }
+                ContainerEvent::MemoryThrottled { id, events, .. } => {
+                    println!("⚠️  Container {} hit memory.high {} time(s)", id, events);
+                }
                 ContainerEvent::StatsUpdate { stats, .. } => {
                     println!(
                         "📊 CPU: {:.2}s, Memory: {}",
@@ -2,9 +2,33 @@
 
 use async_trait::async_trait;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
-use vortex_core::{CpuLimit, MemoryLimit, MemorySize, ProcessId, ResourceStats, Result};
+use vortex_core::{
+    CpuLimit, Error, IoLimit, MemoryLimit, MemorySize, ProcessId, ResourceLimits, ResourceRates,
+    ResourceStats, Result,
+};
+
+/// What kind of backend a [`ResourceBackend`] is, for diagnostics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    /// A real cgroup v2 group
+    CGroup,
+    /// [`MockBackend`], which touches nothing
+    Mock,
+    /// Any other implementation
+    Custom,
+}
+
+impl std::fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::CGroup => "cgroup-v2",
+            Self::Mock => "mock",
+            Self::Custom => "custom",
+        })
+    }
+}
 
 /// Trait for resource management backends
 ///
@@ -17,6 +41,14 @@ use vortex_core::{CpuLimit, MemoryLimit, MemorySize, ProcessId, ResourceStats, R
 /// All implementations must be `Send + Sync` for use across async tasks.
 #[async_trait]
 pub trait ResourceBackend: Send + Sync {
+    /// What kind of backend this is, e.g. to spot a mock wired into a
+    /// real run
+    ///
+    /// The default is [`BackendKind::Custom`].
+    fn kind(&self) -> BackendKind {
+        BackendKind::Custom
+    }
+
     /// Set CPU limit
     ///
     /// # Errors
@@ -29,6 +61,52 @@ pub trait ResourceBackend: Send + Sync {
     /// Returns error if limit cannot be set
     async fn set_memory_limit(&self, limit: MemoryLimit) -> Result<()>;
 
+    /// Set the maximum number of processes
+    ///
+    /// The default rejects the limit, for backends that cannot enforce it.
+    ///
+    /// # Errors
+    /// Returns error if limit cannot be set
+    async fn set_pids_limit(&self, max: u64) -> Result<()> {
+        let _ = max;
+        Err(unsupported("pids"))
+    }
+
+    /// Set the relative CPU weight, from 1 to 10000
+    ///
+    /// The default rejects the limit, for backends that cannot enforce it.
+    ///
+    /// # Errors
+    /// Returns error if limit cannot be set
+    async fn set_cpu_weight(&self, weight: u16) -> Result<()> {
+        let _ = weight;
+        Err(unsupported("CPU weight"))
+    }
+
+    /// Set the IO caps for one device
+    ///
+    /// The default rejects the limit, for backends that cannot enforce it.
+    ///
+    /// # Errors
+    /// Returns error if limit cannot be set
+    async fn set_io_limit(&self, limit: IoLimit) -> Result<()> {
+        let _ = limit;
+        Err(unsupported("IO"))
+    }
+
+    /// Set every limit in `limits`
+    ///
+    /// Limits are applied in a fixed order: pids, CPU weight, CPU, IO and
+    /// memory last, since lowering it can make the kernel reclaim or
+    /// OOM-kill right away. The first failure stops the rest.
+    ///
+    /// # Errors
+    /// Returns [`Error::LimitsPartiallyApplied`] naming the limits already
+    /// set, or the failure itself if it came first
+    async fn apply_limits(&self, limits: &ResourceLimits) -> Result<()> {
+        apply_in_order(self, limits).await
+    }
+
     /// Add a process to this resource group
     ///
     /// # Errors
@@ -41,6 +119,23 @@ pub trait ResourceBackend: Send + Sync {
     /// Returns error if stats cannot be read
     async fn stats(&self) -> Result<ResourceStats>;
 
+    /// Get resource rates over a window as long as the time passed since
+    /// `since`, such as the previous round of a polling loop
+    ///
+    /// The default takes a snapshot, sleeps for that long and takes another.
+    /// Backends that can compute rates more cheaply should override it.
+    ///
+    /// # Errors
+    /// Returns error if stats cannot be read
+    async fn stats_delta(&self, since: Instant) -> Result<ResourceRates> {
+        let window = since.elapsed();
+        let before = self.stats().await?;
+        let start = Instant::now();
+        tokio::time::sleep(window).await;
+        let after = self.stats().await?;
+        Ok(ResourceRates::between(&before, &after, start.elapsed()))
+    }
+
     /// Cleanup resources
     ///
     /// # Errors
@@ -48,6 +143,75 @@ pub trait ResourceBackend: Send + Sync {
     async fn cleanup(&self) -> Result<()>;
 }
 
+/// Error for a limit a backend does not support
+fn unsupported(limit: &str) -> Error {
+    Error::InvalidConfig {
+        message: format!("{limit} limits are not supported by this backend"),
+    }
+}
+
+/// Apply `limits` in the order documented on
+/// [`ResourceBackend::apply_limits`]
+async fn apply_in_order<B: ResourceBackend + ?Sized>(
+    backend: &B,
+    limits: &ResourceLimits,
+) -> Result<()> {
+    let mut applied: Vec<&str> = Vec::new();
+
+    if let Some(pids) = limits.pids {
+        backend
+            .set_pids_limit(pids)
+            .await
+            .map_err(|e| partially_applied(&applied, "pids", e))?;
+        applied.push("pids");
+    }
+    if let Some(weight) = limits.cpu_weight {
+        backend
+            .set_cpu_weight(weight)
+            .await
+            .map_err(|e| partially_applied(&applied, "CPU weight", e))?;
+        applied.push("CPU weight");
+    }
+    if let Some(cpu) = limits.cpu {
+        backend
+            .set_cpu_limit(cpu)
+            .await
+            .map_err(|e| partially_applied(&applied, "CPU", e))?;
+        applied.push("CPU");
+    }
+    if !limits.io.is_empty() {
+        for io in &limits.io {
+            backend
+                .set_io_limit(*io)
+                .await
+                .map_err(|e| partially_applied(&applied, "IO", e))?;
+        }
+        applied.push("IO");
+    }
+    if let Some(memory) = limits.memory {
+        backend
+            .set_memory_limit(memory)
+            .await
+            .map_err(|e| partially_applied(&applied, "memory", e))?;
+    }
+
+    Ok(())
+}
+
+/// Error for `limit` failing with `e` after the limits in `applied` were set
+///
+/// The failure itself when nothing was set yet.
+pub(crate) fn partially_applied(applied: &[&str], limit: &str, e: Error) -> Error {
+    if applied.is_empty() {
+        return e;
+    }
+    Error::LimitsPartiallyApplied {
+        applied: applied.iter().map(ToString::to_string).collect(),
+        failed: limit.to_string(),
+        message: e.to_string(),
+    }
+}
+
 /// Mock backend for testing (doesn't touch filesystem)
 ///
 /// # Example
@@ -76,13 +240,50 @@ pub struct MockBackend {
 
 #[derive(Default)]
 struct MockState {
-    cpu_limit: Option<CpuLimit>,
-    memory_limit: Option<MemoryLimit>,
+    limits: ResourceLimits,
     processes: Vec<ProcessId>,
     stats: ResourceStats,
+    memory_high_per_read: u64,
+    /// CPU time each stats read adds; `None` for the default 100ms
+    cpu_per_read: Option<Duration>,
+    /// Throttled time each stats read adds, in one throttled period
+    throttle_per_read: Duration,
+    swap_per_read: MemorySize,
+    /// Bytes read and written per stats read
+    io_per_read: (u64, u64),
     call_count: usize,
 }
 
+impl MockState {
+    /// Simulate realistic usage growth for one stats read
+    fn advance(&mut self) {
+        self.call_count += 1;
+        self.stats.cpu_usage += self.cpu_per_read.unwrap_or(Duration::from_millis(100));
+        self.stats.memory_high_events += self.memory_high_per_read;
+        self.stats.memory_current =
+            MemorySize::from_mb((self.stats.memory_current.as_mb() + 10.0).min(500.0) as u64);
+
+        if self.stats.memory_current > self.stats.memory_peak {
+            self.stats.memory_peak = self.stats.memory_current;
+        }
+
+        if !self.throttle_per_read.is_zero() {
+            self.stats.cpu_throttled += self.throttle_per_read;
+            self.stats.cpu_nr_periods += 1;
+            self.stats.cpu_nr_throttled += 1;
+        }
+
+        self.stats.swap_current = self.stats.swap_current + self.swap_per_read;
+        if self.stats.swap_current > self.stats.swap_peak {
+            self.stats.swap_peak = self.stats.swap_current;
+        }
+
+        let (read, write) = self.io_per_read;
+        self.stats.io_read_bytes += read;
+        self.stats.io_write_bytes += write;
+    }
+}
+
 impl MockBackend {
     /// Create a new mock backend
     #[must_use]
@@ -107,14 +308,48 @@ impl MockBackend {
         self.state.lock().await.stats = stats;
     }
 
+    /// Make each stats read report `events` more `memory.high` events
+    /// (for testing)
+    pub async fn set_memory_high_rate(&self, events: u64) {
+        self.state.lock().await.memory_high_per_read = events;
+    }
+
+    /// Make each stats read add `cpu` of CPU time instead of 100ms; zero
+    /// simulates an idle container (for testing)
+    pub async fn set_cpu_per_read(&self, cpu: Duration) {
+        self.state.lock().await.cpu_per_read = Some(cpu);
+    }
+
+    /// Make each stats read add `throttled` of throttled CPU time; zero,
+    /// the default, never throttles (for testing)
+    pub async fn set_throttle_rate(&self, throttled: Duration) {
+        self.state.lock().await.throttle_per_read = throttled;
+    }
+
+    /// Make each stats read grow swap usage, and with it the swap peak, by
+    /// `swap` (for testing)
+    pub async fn set_swap_rate(&self, swap: MemorySize) {
+        self.state.lock().await.swap_per_read = swap;
+    }
+
+    /// Make each stats read add `read` and `write` bytes of I/O (for testing)
+    pub async fn set_io_rate(&self, read: u64, write: u64) {
+        self.state.lock().await.io_per_read = (read, write);
+    }
+
     /// Get the current CPU limit (for testing)
     pub async fn cpu_limit(&self) -> Option<CpuLimit> {
-        self.state.lock().await.cpu_limit
+        self.state.lock().await.limits.cpu
     }
 
     /// Get the current memory limit (for testing)
     pub async fn memory_limit(&self) -> Option<MemoryLimit> {
-        self.state.lock().await.memory_limit
+        self.state.lock().await.limits.memory
+    }
+
+    /// Get every limit set so far, by any of the setters (for testing)
+    pub async fn limits(&self) -> ResourceLimits {
+        self.state.lock().await.limits.clone()
     }
 }
 
@@ -132,9 +367,13 @@ impl std::fmt::Debug for MockBackend {
 
 #[async_trait]
 impl ResourceBackend for MockBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Mock
+    }
+
     async fn set_cpu_limit(&self, limit: CpuLimit) -> Result<()> {
         let mut state = self.state.lock().await;
-        state.cpu_limit = Some(limit);
+        state.limits.cpu = Some(limit);
         state.call_count += 1;
 
         tracing::debug!(cores = limit.cores.as_f64(), "Mock: Set CPU limit");
@@ -144,7 +383,7 @@ impl ResourceBackend for MockBackend {
 
     async fn set_memory_limit(&self, limit: MemoryLimit) -> Result<()> {
         let mut state = self.state.lock().await;
-        state.memory_limit = Some(limit);
+        state.limits.memory = Some(limit);
         state.call_count += 1;
 
         tracing::debug!(
@@ -156,6 +395,44 @@ impl ResourceBackend for MockBackend {
         Ok(())
     }
 
+    async fn set_pids_limit(&self, max: u64) -> Result<()> {
+        let mut state = self.state.lock().await;
+        state.limits.pids = Some(max);
+        state.call_count += 1;
+        drop(state);
+
+        tracing::debug!(max, "Mock: Set pids limit");
+
+        Ok(())
+    }
+
+    async fn set_cpu_weight(&self, weight: u16) -> Result<()> {
+        let mut state = self.state.lock().await;
+        state.limits.cpu_weight = Some(weight);
+        state.call_count += 1;
+        drop(state);
+
+        tracing::debug!(weight, "Mock: Set CPU weight");
+
+        Ok(())
+    }
+
+    async fn set_io_limit(&self, limit: IoLimit) -> Result<()> {
+        let mut state = self.state.lock().await;
+        // One entry per device, like io.max
+        state
+            .limits
+            .io
+            .retain(|io| (io.major, io.minor) != (limit.major, limit.minor));
+        state.limits.io.push(limit);
+        state.call_count += 1;
+        drop(state);
+
+        tracing::debug!(limit = %limit, "Mock: Set IO limit");
+
+        Ok(())
+    }
+
     async fn add_process(&self, pid: ProcessId) -> Result<()> {
         let mut state = self.state.lock().await;
 
@@ -176,16 +453,7 @@ impl ResourceBackend for MockBackend {
 
     async fn stats(&self) -> Result<ResourceStats> {
         let mut state = self.state.lock().await;
-        state.call_count += 1;
-
-        // Simulate realistic usage growth
-        state.stats.cpu_usage += Duration::from_millis(100);
-        state.stats.memory_current =
-            MemorySize::from_mb((state.stats.memory_current.as_mb() + 10.0).min(500.0) as u64);
-
-        if state.stats.memory_current > state.stats.memory_peak {
-            state.stats.memory_peak = state.stats.memory_current;
-        }
+        state.advance();
 
         tracing::trace!(
             cpu_secs = state.stats.cpu_usage.as_secs_f64(),
@@ -196,6 +464,19 @@ impl ResourceBackend for MockBackend {
         Ok(state.stats.clone())
     }
 
+    /// Instant: one step of growth spread over the time since `since`
+    async fn stats_delta(&self, since: Instant) -> Result<ResourceRates> {
+        let mut state = self.state.lock().await;
+        let before = state.stats.clone();
+        state.advance();
+
+        Ok(ResourceRates::between(
+            &before,
+            &state.stats,
+            since.elapsed(),
+        ))
+    }
+
     async fn cleanup(&self) -> Result<()> {
         let mut state = self.state.lock().await;
         state.call_count += 1;
@@ -271,6 +552,165 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_mock_backend_simulated_rates() {
+        let backend = MockBackend::new();
+        // Off by default
+        let stats = backend.stats().await.unwrap();
+        assert_eq!(stats.cpu_throttled, Duration::ZERO);
+        assert_eq!(stats.swap_peak, MemorySize::from_bytes(0));
+        assert_eq!(stats.io_read_bytes, 0);
+
+        backend.set_throttle_rate(Duration::from_millis(150)).await;
+        backend.set_swap_rate(MemorySize::from_mb(4)).await;
+        backend.set_io_rate(4096, 1024).await;
+        backend.stats().await.unwrap();
+        let stats = backend.stats().await.unwrap();
+        assert_eq!(stats.cpu_throttled, Duration::from_millis(300));
+        assert_eq!(stats.cpu_nr_throttled, 2);
+        assert_eq!(stats.swap_current, MemorySize::from_mb(8));
+        assert_eq!(stats.swap_peak, stats.swap_current);
+        assert_eq!((stats.io_read_bytes, stats.io_write_bytes), (8192, 2048));
+
+        let rates = backend.stats_delta(second_ago()).await.unwrap();
+        assert!((rates.io_read_per_sec - 4096.0).abs() < 41.0);
+    }
+
+    /// Start of a window of about one second ending now
+    fn second_ago() -> Instant {
+        Instant::now()
+            .checked_sub(Duration::from_secs(1))
+            .expect("system up for a second")
+    }
+
+    #[tokio::test]
+    async fn test_mock_backend_stats_delta() {
+        let backend = MockBackend::new();
+
+        // 100ms of CPU per step over a window of about 1s
+        let rates = backend.stats_delta(second_ago()).await.unwrap();
+        assert!((rates.cpu_percent - 10.0).abs() < 0.1);
+        assert_eq!(rates.memory_delta, 10 * 1024 * 1024);
+        assert_eq!(backend.call_count().await, 1);
+    }
+
+    /// Backend relying on the provided `stats_delta`
+    struct Sampled(MockBackend);
+
+    #[async_trait]
+    impl ResourceBackend for Sampled {
+        async fn set_cpu_limit(&self, limit: CpuLimit) -> Result<()> {
+            self.0.set_cpu_limit(limit).await
+        }
+
+        async fn set_memory_limit(&self, limit: MemoryLimit) -> Result<()> {
+            self.0.set_memory_limit(limit).await
+        }
+
+        async fn add_process(&self, pid: ProcessId) -> Result<()> {
+            self.0.add_process(pid).await
+        }
+
+        async fn stats(&self) -> Result<ResourceStats> {
+            self.0.stats().await
+        }
+
+        async fn cleanup(&self) -> Result<()> {
+            self.0.cleanup().await
+        }
+    }
+
+    #[test]
+    fn test_backend_kind() {
+        assert_eq!(MockBackend::new().kind(), BackendKind::Mock);
+        // Implementations that don't say are custom
+        assert_eq!(Sampled(MockBackend::new()).kind(), BackendKind::Custom);
+        assert_eq!(BackendKind::CGroup.to_string(), "cgroup-v2");
+    }
+
+    #[tokio::test]
+    async fn test_default_stats_delta_samples_twice() {
+        let backend = Sampled(MockBackend::new());
+
+        let since = Instant::now();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let rates = backend.stats_delta(since).await.unwrap();
+        assert!(rates.cpu_percent > 0.0);
+        assert!(since.elapsed() >= Duration::from_millis(40));
+        assert_eq!(rates.memory_delta, 10 * 1024 * 1024);
+        assert_eq!(backend.0.call_count().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_mock_backend_apply_limits() {
+        let backend = MockBackend::new();
+        let limits = ResourceLimits {
+            cpu: Some(CpuLimit::new(CpuCores::new(0.5))),
+            cpu_weight: Some(200),
+            memory: Some(MemoryLimit::new(MemorySize::from_mb(256))),
+            pids: Some(64),
+            io: vec![IoLimit {
+                write_bps: Some(1024 * 1024),
+                ..IoLimit::new(8, 0)
+            }],
+        };
+
+        backend.apply_limits(&limits).await.unwrap();
+        assert_eq!(backend.limits().await, limits);
+        // One call per limit
+        assert_eq!(backend.call_count().await, 5);
+
+        // Unset limits are left alone
+        backend
+            .apply_limits(&ResourceLimits {
+                pids: Some(128),
+                ..ResourceLimits::default()
+            })
+            .await
+            .unwrap();
+        let current = backend.limits().await;
+        assert_eq!(current.pids, Some(128));
+        assert_eq!(current.cpu, limits.cpu);
+        assert_eq!(current.io, limits.io);
+    }
+
+    #[tokio::test]
+    async fn test_apply_limits_reports_what_was_set() {
+        // Relies on the defaults, which reject pids, weight and IO
+        let backend = Sampled(MockBackend::new());
+        let mut limits = ResourceLimits {
+            cpu: Some(CpuLimit::new(CpuCores::new(1.0))),
+            memory: Some(MemoryLimit::new(MemorySize::from_mb(64))),
+            ..ResourceLimits::default()
+        };
+        backend.apply_limits(&limits).await.unwrap();
+
+        // The failure comes first, so it is returned as is
+        limits.pids = Some(10);
+        assert!(matches!(
+            backend.apply_limits(&limits).await,
+            Err(Error::InvalidConfig { .. })
+        ));
+
+        // CPU went through before IO failed; memory was never tried
+        limits.pids = None;
+        limits.io = vec![IoLimit::new(8, 0)];
+        limits.memory = Some(MemoryLimit::new(MemorySize::from_mb(32)));
+        match backend.apply_limits(&limits).await {
+            Err(Error::LimitsPartiallyApplied {
+                applied, failed, ..
+            }) => {
+                assert_eq!(applied, vec!["CPU"]);
+                assert_eq!(failed, "IO");
+            }
+            other => panic!("Expected LimitsPartiallyApplied, got {other:?}"),
+        }
+        assert_eq!(
+            backend.0.memory_limit().await,
+            Some(MemoryLimit::new(MemorySize::from_mb(64)))
+        );
+    }
+
     #[tokio::test]
     async fn test_mock_backend_duplicate_process() {
         let backend = MockBackend::new();
@@ -0,0 +1,149 @@
+//! `CGroup` controller configuration
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// `CGroup` v2 root path
+pub const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Vortex cgroup namespace
+pub const VORTEX_NAMESPACE: &str = "vortex";
+
+/// Default time budget for removing a cgroup directory that is still busy
+pub const DEFAULT_CLEANUP_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Configuration for where and how container cgroups are created
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CGroupConfig {
+    /// `CGroup` v2 mount point
+    pub root: PathBuf,
+
+    /// Directory under the root that holds vortex containers, which may be
+    /// nested, e.g. an existing `machine.slice/vortex.slice`
+    pub namespace: String,
+
+    /// Optional parent group (pod) between the namespace and the container
+    pub parent: Option<String>,
+
+    /// Kill the processes of a running container with the same ID instead
+    /// of refusing to create its cgroup
+    pub replace: bool,
+
+    /// How long cleanup keeps retrying `rmdir` on a cgroup that is still
+    /// busy (EBUSY/ENOTEMPTY) before giving up
+    pub cleanup_timeout: Duration,
+}
+
+impl Default for CGroupConfig {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from(CGROUP_ROOT),
+            namespace: VORTEX_NAMESPACE.to_string(),
+            parent: None,
+            replace: false,
+            cleanup_timeout: DEFAULT_CLEANUP_TIMEOUT,
+        }
+    }
+}
+
+impl CGroupConfig {
+    /// Create a new configuration with default paths
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the cgroup v2 mount point
+    #[must_use]
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Set the vortex namespace directory name
+    #[must_use]
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// Place containers under a parent group, e.g. a pod
+    #[must_use]
+    pub fn with_parent(mut self, parent: impl Into<String>) -> Self {
+        self.parent = Some(parent.into());
+        self
+    }
+
+    /// Take over the cgroup of a running container with the same ID
+    #[must_use]
+    pub const fn with_replace(mut self, replace: bool) -> Self {
+        self.replace = replace;
+        self
+    }
+
+    /// Set how long cleanup retries removing a busy cgroup
+    ///
+    /// `Duration::ZERO` makes a single attempt.
+    #[must_use]
+    pub const fn with_cleanup_timeout(mut self, timeout: Duration) -> Self {
+        self.cleanup_timeout = timeout;
+        self
+    }
+
+    /// Directory that holds all vortex containers
+    #[must_use]
+    pub fn vortex_root(&self) -> PathBuf {
+        self.root.join(&self.namespace)
+    }
+
+    /// Directory that container cgroups are created in
+    ///
+    /// This is the vortex root, or `<vortex root>/<parent>` when a parent is set.
+    #[must_use]
+    pub fn container_parent(&self) -> PathBuf {
+        let mut path = self.vortex_root();
+        if let Some(ref parent) = self.parent {
+            path.push(parent);
+        }
+        path
+    }
+
+    /// Get the cgroup v2 mount point
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config() {
+        let config = CGroupConfig::default();
+        assert_eq!(config.root(), Path::new("/sys/fs/cgroup"));
+        assert_eq!(config.vortex_root(), PathBuf::from("/sys/fs/cgroup/vortex"));
+        assert_eq!(config.cleanup_timeout, DEFAULT_CLEANUP_TIMEOUT);
+    }
+
+    #[test]
+    fn test_builder_pattern() {
+        let config = CGroupConfig::new()
+            .with_root("/tmp/cgroup")
+            .with_namespace("test");
+
+        assert_eq!(config.vortex_root(), PathBuf::from("/tmp/cgroup/test"));
+        assert_eq!(config.container_parent(), config.vortex_root());
+    }
+
+    #[test]
+    fn test_parent_path() {
+        let config = CGroupConfig::new().with_parent("web");
+
+        assert_eq!(
+            config.container_parent(),
+            PathBuf::from("/sys/fs/cgroup/vortex/web")
+        );
+    }
+}
@@ -1,34 +1,85 @@
 //! CGroup v2 controller implementation
 
 use async_trait::async_trait;
+use nix::sys::statvfs::{FsFlags, statvfs};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 use tokio::fs;
 use tokio::sync::Mutex;
 use vortex_core::{
-    ContainerId, CpuLimit, Error, MemoryLimit, MemorySize, ProcessId, ResourceStats, Result,
+    ContainerId, CpuCores, CpuLimit, Error, IoLimit, MemoryLimit, MemorySize, ProcessId,
+    ResourceLimits, ResourceStats, Result,
 };
 
-use crate::backend::ResourceBackend;
+use crate::backend::{BackendKind, ResourceBackend, partially_applied};
+use crate::config::CGroupConfig;
+use crate::devices::{self, DeviceRule};
 
-/// CGroup v2 root path
-const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+/// Poll interval while waiting for killed processes to leave (milliseconds)
+const KILL_POLL_MS: u64 = 10;
 
-/// Vortex cgroup namespace
-const VORTEX_NAMESPACE: &str = "vortex";
+/// First pause between `rmdir` attempts on a busy cgroup, doubled each retry
+const REMOVE_BACKOFF_START: Duration = Duration::from_millis(1);
 
-/// Delay for kernel cleanup operations (milliseconds)
-const KERNEL_CLEANUP_DELAY_MS: u64 = 10;
+/// How many times to re-check for killed processes before giving up
+const KILL_WAIT_ATTEMPTS: u32 = 50;
 
-/// Required CGroup controllers
-const REQUIRED_CONTROLLERS: &[&str] = &["cpu", "memory", "io"];
+/// Controllers vortex needs for resource limits
+pub const REQUIRED_CONTROLLERS: &[&str] = &["cpu", "memory", "io"];
+
+/// Attempts per controller when enabling it in `cgroup.subtree_control`
+const ENABLE_CONTROLLER_ATTEMPTS: u32 = 3;
+
+/// Initial backoff between enable attempts (milliseconds), doubled each retry
+const ENABLE_CONTROLLER_BACKOFF_MS: u64 = 20;
+
+/// Set once the `Drop` fallback has warned, so it only shouts once
+static DROP_FALLBACK_WARNED: AtomicBool = AtomicBool::new(false);
 
 /// CGroup v2 controller for resource management
+///
+/// # Cleanup
+///
+/// Always finish with an explicit, awaited cleanup:
+///
+/// ```no_run
+/// use vortex_cgroup::CGroupController;
+/// use vortex_core::ContainerId;
+///
+/// # async fn example() -> vortex_core::Result<()> {
+/// let controller = CGroupController::new(ContainerId::new("web")?).await?;
+/// // ... run the container ...
+/// controller.cleanup().await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Cleanup takes `&self`, and [`ResourceBackend::cleanup`] does the same, so
+/// a controller shared behind an `Arc` (say with a [`ResourceMonitor`]) can
+/// be cleaned up by its owner once the monitor has stopped.
+///
+/// [`ResourceMonitor`]: crate::ResourceMonitor
+///
+/// Dropping an active controller falls back to a synchronous best-effort
+/// cleanup. It uses blocking `std::fs` calls and, inside a tokio runtime,
+/// skips the kernel settle delay rather than sleeping on a worker thread, so
+/// removal is more likely to fail there.
+///
+/// With [`Self::with_keep_on_exit`], cleanup only empties the cgroup and
+/// leaves the directory and its stat files for post-mortem inspection;
+/// [`Self::remove`] deletes it later.
+#[must_use = "an unused controller leaves its cgroup behind until dropped"]
 pub struct CGroupController {
     container_id: ContainerId,
+    config: CGroupConfig,
     path: PathBuf,
-    active: bool,
+    /// Whether cleanup (or the `Drop` fallback) still has to remove the
+    /// cgroup; atomic so a controller shared with a monitor can clean up
+    /// through `&self`
+    active: AtomicBool,
+    keep_on_exit: bool,
 }
 
 /// Shared controller type for use with `Arc<Mutex<>>`
@@ -43,21 +94,32 @@ impl CGroupController {
     /// 3. Prepare for resource management
     ///
     /// # Errors
-    /// Returns error if cgroup creation fails (e.g., permission denied)
+    /// Returns error if cgroup creation fails (e.g., permission denied, or
+    /// [`Error::CGroupReadOnly`] when the cgroup filesystem is mounted read-only)
     pub async fn new(container_id: ContainerId) -> Result<Self> {
+        Self::with_config(container_id, CGroupConfig::default()).await
+    }
+
+    /// Create a new `CGroup` controller using a custom configuration
+    ///
+    /// # Errors
+    /// Returns error if cgroup creation fails
+    #[tracing::instrument(skip_all, fields(container_id = %container_id))]
+    pub async fn with_config(container_id: ContainerId, config: CGroupConfig) -> Result<Self> {
         tracing::debug!(
             container_id = %container_id,
+            root = %config.root().display(),
             "Creating CGroup controller"
         );
 
-        let path = Path::new(CGROUP_ROOT)
-            .join(VORTEX_NAMESPACE)
-            .join(container_id.as_str());
+        let path = container_path(&config, &container_id)?;
 
         let mut controller = Self {
             container_id,
+            config,
             path,
-            active: true,
+            active: AtomicBool::new(true),
+            keep_on_exit: false,
         };
 
         controller.create().await?;
@@ -71,6 +133,134 @@ impl CGroupController {
         Ok(controller)
     }
 
+    /// Attach to an existing container cgroup without owning it
+    ///
+    /// Nothing is created or enabled, and the returned controller is
+    /// inactive: neither `cleanup` nor `Drop` will touch the cgroup. Use this
+    /// to read stats of a container owned by another process, or to change
+    /// its limits or add processes to it, as [`Self::join_existing`] does.
+    ///
+    /// # Errors
+    /// Returns [`Error::ContainerNotFound`] if the cgroup does not exist
+    pub fn attach(container_id: ContainerId) -> Result<Self> {
+        Self::attach_with_config(container_id, CGroupConfig::default())
+    }
+
+    /// Attach to an existing container cgroup using a custom configuration
+    ///
+    /// # Errors
+    /// Returns [`Error::ContainerNotFound`] if the cgroup does not exist
+    pub fn attach_with_config(container_id: ContainerId, config: CGroupConfig) -> Result<Self> {
+        let path = container_path(&config, &container_id)?;
+
+        if !path.is_dir() {
+            return Err(Error::ContainerNotFound {
+                id: container_id.to_string(),
+            });
+        }
+
+        tracing::debug!(
+            container_id = %container_id,
+            path = %path.display(),
+            "Attached to existing cgroup"
+        );
+
+        Ok(Self {
+            container_id,
+            config,
+            path,
+            active: AtomicBool::new(false),
+            keep_on_exit: false,
+        })
+    }
+
+    /// Attach read-only to the cgroup of any process, including ones vortex
+    /// did not start
+    ///
+    /// The cgroup is looked up in `/proc/<pid>/cgroup` and resolved under
+    /// `config.root`. Like [`Self::attach`] the controller is inactive; its
+    /// ID is `pid-<pid>`.
+    ///
+    /// # Errors
+    /// Returns [`Error::ProcessNotFound`] if the process does not exist, or
+    /// [`Error::CGroup`] if it is only in cgroup v1 hierarchies or its cgroup
+    /// is not visible from this cgroup namespace
+    pub fn attach_pid(pid: ProcessId, config: CGroupConfig) -> Result<Self> {
+        let content = std::fs::read_to_string(format!("/proc/{pid}/cgroup"))
+            .map_err(|_| Error::ProcessNotFound { pid: pid.as_raw() })?;
+
+        let relative = unified_cgroup_path(&content).ok_or_else(|| Error::CGroup {
+            message: format!("Process {pid} is not in a cgroup v2 hierarchy (cgroup v1 only)"),
+        })?;
+        // Cgroups outside our cgroup namespace show up as `/..`
+        if relative.split('/').any(|part| part == "..") {
+            return Err(Error::CGroup {
+                message: format!(
+                    "Cgroup {relative} of process {pid} is outside this cgroup namespace"
+                ),
+            });
+        }
+
+        let path = match relative.trim_start_matches('/') {
+            "" => config.root.clone(),
+            relative => config.root.join(relative),
+        };
+        if !path.is_dir() {
+            return Err(Error::CGroup {
+                message: format!(
+                    "Cgroup {} of process {pid} not found (is cgroup v2 mounted at {}?)",
+                    path.display(),
+                    config.root.display()
+                ),
+            });
+        }
+
+        tracing::debug!(pid = %pid, path = %path.display(), "Attached to process cgroup");
+
+        Ok(Self {
+            container_id: ContainerId::new(format!("pid-{pid}"))?,
+            config,
+            path,
+            active: AtomicBool::new(false),
+            keep_on_exit: false,
+        })
+    }
+
+    /// Take ownership of an existing container cgroup
+    ///
+    /// Like [`Self::attach`] nothing is created, but the returned controller
+    /// is active: `cleanup` removes the cgroup. Use this to stop a container
+    /// started by another process.
+    ///
+    /// # Errors
+    /// Returns [`Error::ContainerNotFound`] if the cgroup does not exist
+    pub fn adopt(container_id: ContainerId) -> Result<Self> {
+        Self::adopt_with_config(container_id, CGroupConfig::default())
+    }
+
+    /// Take ownership of an existing container cgroup using a custom configuration
+    ///
+    /// # Errors
+    /// Returns [`Error::ContainerNotFound`] if the cgroup does not exist
+    pub fn adopt_with_config(container_id: ContainerId, config: CGroupConfig) -> Result<Self> {
+        let controller = Self::attach_with_config(container_id, config)?;
+        controller.active.store(true, Ordering::Relaxed);
+        Ok(controller)
+    }
+
+    /// Join the cgroup of an existing container to add processes to it
+    ///
+    /// An alias of [`Self::attach`], named for sidecars that share a
+    /// container's cgroup: nothing is created, enabled or limited, and the
+    /// cgroup stays with its owner. Use [`Self::attach_with_config`] for a
+    /// custom configuration.
+    ///
+    /// # Errors
+    /// Returns [`Error::ContainerNotFound`] if the cgroup does not exist
+    pub fn join_existing(container_id: ContainerId) -> Result<Self> {
+        Self::attach(container_id)
+    }
+
     /// Create a shared (Arc<Mutex<>>) controller for concurrent access
     ///
     /// # Errors
@@ -95,513 +285,2383 @@ impl CGroupController {
     /// Check if controller is active
     #[must_use]
     pub fn is_active(&self) -> bool {
-        self.active
+        self.active.load(Ordering::Relaxed)
     }
 
-    /// Create the cgroup directory hierarchy and enable controllers
-    async fn create(&mut self) -> Result<()> {
-        // Step 1: Create directory structure
-        self.create_directory_hierarchy().await?;
-
-        // Step 2: Enable controllers at each level
-        self.enable_controllers_in_hierarchy().await?;
+    /// Keep the emptied cgroup on cleanup instead of removing it
+    ///
+    /// `memory.events`, `memory.peak` and the other stat files stay readable
+    /// after the container exits, e.g. to find out why it was OOM killed.
+    pub const fn with_keep_on_exit(mut self, keep: bool) -> Self {
+        self.keep_on_exit = keep;
+        self
+    }
 
-        Ok(())
+    /// Check if cleanup keeps the cgroup directory
+    #[must_use]
+    pub const fn keeps_on_exit(&self) -> bool {
+        self.keep_on_exit
     }
 
-    /// Create the directory hierarchy for this cgroup
-    async fn create_directory_hierarchy(&self) -> Result<()> {
-        let root = Path::new(CGROUP_ROOT);
-        let vortex_root = root.join(VORTEX_NAMESPACE);
+    /// Give up ownership without removing the cgroup
+    ///
+    /// For containers that outlive this process, e.g. detached ones; whoever
+    /// stops them later takes over with [`Self::adopt`].
+    pub fn release(self) {
+        self.active.store(false, Ordering::Relaxed);
+    }
 
-        // Create vortex directory if it doesn't exist
-        if !vortex_root.exists() {
-            fs::create_dir_all(&vortex_root).await.map_err(|e| {
-                tracing::error!(
-                    path = %vortex_root.display(),
-                    error = %e,
-                    "Failed to create vortex directory"
-                );
-                Error::CGroup {
-                    message: format!(
-                        "Failed to create vortex directory: {}\nPath: {}",
-                        e,
-                        vortex_root.display()
-                    ),
-                }
-            })?;
+    /// Get the controller configuration
+    #[must_use]
+    pub const fn config(&self) -> &CGroupConfig {
+        &self.config
+    }
 
-            tracing::info!(
-                path = %vortex_root.display(),
-                "Created vortex cgroup directory"
-            );
-        }
+    /// List the processes currently in the cgroup
+    ///
+    /// Returns an empty list when the cgroup has no processes.
+    ///
+    /// # Errors
+    /// Returns error if `cgroup.procs` cannot be read
+    pub async fn processes(&self) -> Result<Vec<ProcessId>> {
+        read_id_list(&self.path.join("cgroup.procs")).await
+    }
 
-        // Create container directory
-        fs::create_dir_all(&self.path).await.map_err(|e| {
-            tracing::error!(
-                path = %self.path.display(),
-                error = %e,
-                "Failed to create container directory"
-            );
-            Error::CGroup {
-                message: format!(
-                    "Failed to create container directory: {}\nPath: {}",
-                    e,
-                    self.path.display()
-                ),
-            }
+    /// Controllers available in the cgroup, from `cgroup.controllers`
+    ///
+    /// Stat files only exist for these, so a cgroup whose parent did not
+    /// delegate e.g. `memory` has no `memory.current`.
+    ///
+    /// # Errors
+    /// Returns error if `cgroup.controllers` cannot be read
+    pub async fn available_controllers(&self) -> Result<Vec<String>> {
+        let file = self.path.join("cgroup.controllers");
+        let content = fs::read_to_string(&file).await.map_err(|e| Error::CGroup {
+            message: format!("Failed to read {}: {e}", file.display()),
         })?;
-
-        tracing::debug!(
-            path = %self.path.display(),
-            "CGroup directory created"
-        );
-
-        Ok(())
+        Ok(parse_controllers(&content))
     }
 
-    /// Enable controllers at all levels in the hierarchy
-    async fn enable_controllers_in_hierarchy(&self) -> Result<()> {
-        let root = Path::new(CGROUP_ROOT);
-        let vortex_root = root.join(VORTEX_NAMESPACE);
+    /// List the thread IDs currently in the cgroup
+    ///
+    /// # Errors
+    /// Returns error if `cgroup.threads` cannot be read
+    pub async fn thread_ids(&self) -> Result<Vec<ProcessId>> {
+        read_id_list(&self.path.join("cgroup.threads")).await
+    }
 
-        // Enable at root level (best effort)
-        self.enable_controllers_at(root).await;
+    /// Number of live cgroups below this one, from `cgroup.stat`
+    ///
+    /// # Errors
+    /// Returns error if `cgroup.stat` cannot be read
+    pub async fn nr_descendants(&self) -> Result<u64> {
+        self.read_cgroup_stat("nr_descendants").await
+    }
 
-        // Enable at vortex level (best effort)
-        self.enable_controllers_at(&vortex_root).await;
+    /// Number of removed cgroups below this one the kernel hasn't freed yet
+    ///
+    /// A count that keeps growing means cleanup isn't keeping up, e.g.
+    /// because page cache still pins the memory of removed cgroups.
+    ///
+    /// # Errors
+    /// Returns error if `cgroup.stat` cannot be read
+    pub async fn nr_dying_descendants(&self) -> Result<u64> {
+        self.read_cgroup_stat("nr_dying_descendants").await
+    }
 
-        Ok(())
+    /// One counter of `cgroup.stat`, 0 when the kernel doesn't report it
+    async fn read_cgroup_stat(&self, key: &str) -> Result<u64> {
+        let file = self.path.join("cgroup.stat");
+        let content = fs::read_to_string(&file).await.map_err(|e| Error::CGroup {
+            message: format!("Failed to read {}: {e}", file.display()),
+        })?;
+        Ok(keyed_value(&content, key).unwrap_or(0))
     }
 
-    /// Enable controllers at a specific path
+    /// Read back the CPU limit currently configured in `cpu.max`
     ///
-    /// This is best-effort and will not fail if controllers cannot be enabled
-    /// (they might be managed by systemd or already enabled at a higher level)
-    async fn enable_controllers_at(&self, path: &Path) {
-        let controllers_file = path.join("cgroup.controllers");
-        let control_file = path.join("cgroup.subtree_control");
+    /// Returns `None` when the cgroup has no CPU limit.
+    ///
+    /// # Errors
+    /// Returns error if `cpu.max` cannot be read or parsed
+    pub async fn current_cpu_limit(&self) -> Result<Option<CpuLimit>> {
+        let cpu_max_file = self.path.join("cpu.max");
+        let content = fs::read_to_string(&cpu_max_file)
+            .await
+            .map_err(|e| Error::CGroup {
+                message: format!("Failed to read {}: {e}", cpu_max_file.display()),
+            })?;
+        CpuLimit::from_cpu_max_str(&content)
+    }
 
-        // Skip if control file doesn't exist
-        if !control_file.exists() {
-            tracing::trace!(
-                path = %path.display(),
-                "Subtree control file doesn't exist, skipping"
-            );
-            return;
-        }
+    /// Read back the memory limit currently configured in `memory.max`
+    ///
+    /// Returns `None` when the cgroup has no memory limit.
+    ///
+    /// # Errors
+    /// Returns error if `memory.max` cannot be read or parsed
+    pub async fn current_memory_limit(&self) -> Result<Option<MemorySize>> {
+        let file = self.path.join("memory.max");
+        let content = fs::read_to_string(&file).await.map_err(|e| Error::CGroup {
+            message: format!("Failed to read {}: {e}", file.display()),
+        })?;
+        parse_memory_max(&content)
+    }
 
-        // Read available controllers
-        let available = match fs::read_to_string(&controllers_file).await {
-            Ok(content) => content,
-            Err(e) => {
-                tracing::trace!(
-                    path = %path.display(),
-                    error = %e,
-                    "Could not read available controllers"
-                );
-                return;
-            }
+    /// Set the memory limit like [`ResourceBackend::set_memory_limit`], then
+    /// read it back
+    ///
+    /// A limit below `memory.current` is refused unless `force` is set,
+    /// since the kernel would reclaim and then OOM-kill straight away.
+    /// The limit is rounded up to whole pages first; returns the limit in
+    /// effect when the kernel did not keep the rounded one as is.
+    ///
+    /// # Errors
+    /// Returns [`Error::MemoryBelowUsage`] for a limit below usage without
+    /// `force`, [`Error::InvalidConfig`] for one below [`MIN_MEMORY_PAGES`],
+    /// or error if the limit cannot be written or read back
+    pub async fn set_memory_limit_checked(
+        &self,
+        limit: MemoryLimit,
+        force: bool,
+    ) -> Result<Option<MemorySize>> {
+        let aligned = page_aligned_memory(limit.limit, page_size())?;
+        self.check_memory_usage(aligned, force).await?;
+        let limit = MemoryLimit {
+            limit: aligned,
+            ..limit
         };
+        ResourceBackend::set_memory_limit(self, limit).await?;
+        self.verify_memory_limit(aligned).await
+    }
 
-        // Read currently enabled controllers
-        let enabled = fs::read_to_string(&control_file).await.unwrap_or_default();
-
-        // Determine which controllers need to be enabled
-        let to_enable: Vec<&str> = REQUIRED_CONTROLLERS
-            .iter()
-            .copied()
-            .filter(|c| available.contains(c) && !enabled.contains(c))
-            .collect();
-
-        if to_enable.is_empty() {
-            tracing::trace!(
-                path = %path.display(),
-                "All required controllers already enabled"
-            );
-            return;
+    /// Refuse a memory limit below `memory.current` unless `force` is set
+    ///
+    /// Passes when the memory controller is missing, as there is no usage
+    /// to compare against.
+    ///
+    /// # Errors
+    /// Returns [`Error::MemoryBelowUsage`] for a limit below usage without
+    /// `force`
+    pub async fn check_memory_usage(&self, requested: MemorySize, force: bool) -> Result<()> {
+        let Ok(usage) = self.read_single_value("memory.current").await else {
+            return Ok(());
+        };
+        let usage = MemorySize::from_bytes(usage);
+        if requested >= usage {
+            return Ok(());
         }
-
-        // Try to enable each controller individually
-        // This is more robust than enabling all at once
-        for controller in &to_enable {
-            let cmd = format!("+{}", controller);
-
-            match fs::write(&control_file, &cmd).await {
-                Ok(()) => {
-                    tracing::debug!(
-                        path = %path.display(),
-                        controller = %controller,
-                        "Enabled controller"
-                    );
-                }
-                Err(e) => {
-                    // Just log at debug level - this is expected in many cases
-                    // (systemd management, already enabled at higher level, etc.)
-                    tracing::debug!(
-                        path = %path.display(),
-                        controller = %controller,
-                        error = %e,
-                        "Could not enable controller (may be managed at higher level)"
-                    );
-                }
-            }
+        if !force {
+            return Err(Error::MemoryBelowUsage { requested, usage });
         }
+
+        tracing::warn!(
+            container_id = %self.container_id,
+            memory = %requested,
+            usage = %usage,
+            "Forcing memory limit below current usage"
+        );
+        Ok(())
     }
 
-    /// Cleanup the cgroup
+    /// Compare `memory.max` with the limit that was written
     ///
-    /// This will:
-    /// 1. Move all processes back to root cgroup
-    /// 2. Wait for kernel cleanup
-    /// 3. Remove the cgroup directory
+    /// Returns the effective limit when it differs from `requested`.
     ///
     /// # Errors
-    /// Returns error if cleanup fails
-    pub async fn cleanup(&mut self) -> Result<()> {
-        if !self.active {
-            tracing::debug!("CGroup already cleaned up");
-            return Ok(());
+    /// Returns error if `memory.max` cannot be read, or reads `max` so that
+    /// no limit applies at all
+    pub async fn verify_memory_limit(&self, requested: MemorySize) -> Result<Option<MemorySize>> {
+        let Some(effective) = self.current_memory_limit().await? else {
+            return Err(Error::CGroup {
+                message: format!("memory.max reads max after setting a limit of {requested}"),
+            });
+        };
+        if effective == requested {
+            return Ok(None);
         }
 
-        tracing::debug!(
+        tracing::warn!(
             container_id = %self.container_id,
-            "Cleaning up cgroup"
+            requested = %requested,
+            effective = %effective,
+            "Kernel adjusted the memory limit"
         );
+        Ok(Some(effective))
+    }
 
-        // Move processes to root cgroup
-        self.move_processes_to_root().await;
-
-        // Small delay for kernel cleanup
-        tokio::time::sleep(Duration::from_millis(KERNEL_CLEANUP_DELAY_MS)).await;
+    /// Read every limit currently set on the cgroup
+    ///
+    /// Unlike for [`ResourceBackend::apply_limits`], `None` here means
+    /// unlimited (`max`), so [`Self::restore_limits`] can put the snapshot
+    /// back as it was. Files of controllers that are not enabled are skipped
+    /// and their limits left `None`; `cpu.weight` is `None` only then.
+    ///
+    /// # Errors
+    /// Returns error if a limit file exists but cannot be read or parsed
+    pub async fn snapshot_limits(&self) -> Result<ResourceLimits> {
+        let mut limits = ResourceLimits::default();
 
-        // Remove directory
-        self.remove_cgroup_directory().await;
+        if let Some(content) = self.read_limit_file("cpu.max").await? {
+            limits.cpu = CpuLimit::from_cpu_max_str(&content)?;
+        }
+        if let Some(content) = self.read_limit_file("cpu.weight").await? {
+            let weight = content.trim();
+            limits.cpu_weight = Some(weight.parse().map_err(|_| Error::CGroup {
+                message: format!("Invalid cpu.weight value '{weight}'"),
+            })?);
+        }
+        if let Some(content) = self.read_limit_file("memory.max").await?
+            && let Some(limit) = parse_memory_max(&content)?
+        {
+            let swap = match self.read_limit_file("memory.swap.max").await? {
+                Some(content) => parse_memory_max(&content)?,
+                None => None,
+            };
+            limits.memory = Some(MemoryLimit { limit, swap });
+        }
+        if let Some(content) = self.read_limit_file("pids.max").await? {
+            let value = content.trim();
+            if value != "max" {
+                limits.pids = Some(value.parse().map_err(|_| Error::CGroup {
+                    message: format!("Invalid pids.max value '{value}'"),
+                })?);
+            }
+        }
+        if let Some(content) = self.read_limit_file("io.max").await? {
+            limits.io = parse_io_max(&content)?;
+        }
 
-        self.active = false;
-        Ok(())
+        Ok(limits)
     }
 
-    /// Move all processes in this cgroup back to the root cgroup
-    async fn move_processes_to_root(&self) {
-        let procs_file = self.path.join("cgroup.procs");
-        let root_procs = Path::new(CGROUP_ROOT).join("cgroup.procs");
-
-        match fs::read_to_string(&procs_file).await {
-            Ok(pids_str) => {
-                for line in pids_str.lines() {
-                    if let Ok(pid) = line.trim().parse::<i32>() {
-                        if let Err(e) = fs::write(&root_procs, pid.to_string()).await {
-                            tracing::debug!(
-                                pid = pid,
-                                error = %e,
-                                "Could not move process to root cgroup"
-                            );
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                tracing::debug!(
+    /// Set every limit back to a [`Self::snapshot_limits`] snapshot
+    ///
+    /// Limits that are `None` are reset to `max`, and devices in `io.max`
+    /// that the snapshot does not list lose their caps. A swap limit is only
+    /// restored along with a memory limit.
+    ///
+    /// # Errors
+    /// Returns error on the first limit that cannot be written; the ones
+    /// before it are already restored
+    pub async fn restore_limits(&self, limits: &ResourceLimits) -> Result<()> {
+        match limits.cpu {
+            Some(cpu) => self.set_cpu_limit(cpu).await?,
+            None => self.reset_limit("cpu.max", "CPU").await?,
+        }
+        if let Some(weight) = limits.cpu_weight {
+            self.set_cpu_weight(weight).await?;
+        }
+        if let Some(memory) = limits.memory {
+            ResourceBackend::set_memory_limit(self, memory).await?;
+            if memory.swap.is_none() {
+                self.reset_limit("memory.swap.max", "swap").await?;
+            }
+        } else {
+            self.reset_limit("memory.max", "memory").await?;
+            self.reset_limit("memory.swap.max", "swap").await?;
+        }
+        match limits.pids {
+            Some(max) => self.set_pids_limit(max).await?,
+            None => self.reset_limit("pids.max", "pids").await?,
+        }
+
+        if let Some(content) = self.read_limit_file("io.max").await? {
+            for current in parse_io_max(&content)? {
+                let kept = limits
+                    .io
+                    .iter()
+                    .any(|io| (io.major, io.minor) == (current.major, current.minor));
+                if !kept {
+                    self.set_io_limit(IoLimit::new(current.major, current.minor))
+                        .await?;
+                }
+            }
+        }
+        for &io in &limits.io {
+            self.set_io_limit(io).await?;
+        }
+
+        tracing::info!(container_id = %self.container_id, "Restored resource limits");
+        Ok(())
+    }
+
+    /// Content of a limit file, or `None` if its controller is not enabled
+    async fn read_limit_file(&self, filename: &str) -> Result<Option<String>> {
+        match fs::read_to_string(self.path.join(filename)).await {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::CGroup {
+                message: format!("Failed to read {filename}: {e}"),
+            }),
+        }
+    }
+
+    /// Write `max` to a limit file if its controller is enabled
+    async fn reset_limit(&self, filename: &str, limit: &str) -> Result<()> {
+        if !fs::try_exists(self.path.join(filename))
+            .await
+            .unwrap_or(false)
+        {
+            return Ok(());
+        }
+        self.write_limit(filename, "max", limit).await
+    }
+
+    /// Set `memory.oom.group`, so the OOM killer kills every process in the
+    /// cgroup together instead of picking one
+    ///
+    /// # Errors
+    /// Returns error if `memory.oom.group` cannot be written, e.g. when the
+    /// memory controller is not enabled
+    pub async fn set_oom_group(&self, enabled: bool) -> Result<()> {
+        let file = self.path.join("memory.oom.group");
+        fs::write(&file, if enabled { "1" } else { "0" })
+            .await
+            .map_err(|e| Error::CGroup {
+                message: format!("Failed to write {}: {e}", file.display()),
+            })?;
+
+        tracing::info!(
+            container_id = %self.container_id,
+            enabled,
+            "Set memory.oom.group"
+        );
+        Ok(())
+    }
+
+    /// Reset `memory.peak` and `memory.swap.peak` to the current usage
+    ///
+    /// The peak files accumulate since the cgroup was created; Linux 6.12
+    /// made them writable to start over, e.g. between phases of a
+    /// benchmark. A reset only applies to reads through the descriptor that
+    /// wrote it, so the returned [`PeakReset`] keeps the files open and is
+    /// what reads the new peaks. No swap peak is tracked if the kernel lacks
+    /// `memory.swap.peak` or swap accounting.
+    ///
+    /// # Errors
+    /// Returns [`Error::CGroup`] if the kernel cannot reset `memory.peak`,
+    /// i.e. it is missing or read-only
+    pub async fn reset_peaks(&self) -> Result<PeakReset> {
+        let path = self.path.clone();
+        let reset = tokio::task::spawn_blocking(move || {
+            let memory = reset_peak(&path.join("memory.peak"))?;
+            let swap = reset_peak(&path.join("memory.swap.peak"))
+                .inspect_err(|e| tracing::debug!(error = %e, "Not tracking the swap peak"))
+                .ok();
+            Ok::<_, Error>(PeakReset { memory, swap })
+        })
+        .await??;
+
+        tracing::info!(container_id = %self.container_id, "Reset memory peaks");
+        Ok(reset)
+    }
+
+    /// Turn the cgroup into a threaded one, so threads of one process can be
+    /// spread over it and its siblings with [`Self::add_thread`]
+    ///
+    /// Only threaded controllers (`cpu`, `cpuset`, `perf_event` and `pids`)
+    /// work below a threaded cgroup, so its parent must not have `memory`
+    /// or `io` enabled in `cgroup.subtree_control`. The parent becomes the
+    /// "domain threaded" root of the subtree, which it can only be while it
+    /// is a valid domain itself or already threaded. The change cannot be
+    /// undone short of removing the cgroup.
+    ///
+    /// # Errors
+    /// Returns [`Error::CGroup`] naming the problem if the parent cannot
+    /// host a threaded cgroup, or if `cgroup.type` cannot be written
+    pub async fn set_threaded(&self) -> Result<()> {
+        let parent = self.path.parent().ok_or_else(|| Error::CGroup {
+            message: format!("{} has no parent cgroup", self.path.display()),
+        })?;
+        check_threaded_parent(parent).await?;
+
+        self.write_limit("cgroup.type", "threaded", "threaded mode")
+            .await?;
+
+        // The write succeeds even when the kernel then marks the cgroup
+        // "domain invalid", so check what it became
+        let file = self.path.join("cgroup.type");
+        let kind = fs::read_to_string(&file).await.map_err(|e| Error::CGroup {
+            message: format!("Failed to read {}: {e}", file.display()),
+        })?;
+        if kind.trim() != "threaded" {
+            return Err(Error::CGroup {
+                message: format!("cgroup.type is '{}' instead of threaded", kind.trim()),
+            });
+        }
+
+        tracing::info!(container_id = %self.container_id, "Made cgroup threaded");
+        Ok(())
+    }
+
+    /// Move a single thread into the cgroup through `cgroup.threads`
+    ///
+    /// Unlike [`ResourceBackend::add_process`] the other threads of the
+    /// process stay where they are. The cgroup must be threaded (see
+    /// [`Self::set_threaded`]) and in the same threaded subtree as the
+    /// thread's current cgroup.
+    ///
+    /// # Errors
+    /// Returns error if `cgroup.threads` cannot be written, e.g. for a
+    /// thread outside the subtree
+    pub async fn add_thread(&self, tid: ProcessId) -> Result<()> {
+        let file = self.path.join("cgroup.threads");
+        fs::write(&file, tid.as_raw().to_string())
+            .await
+            .map_err(|e| Error::CGroup {
+                message: format!("Failed to add thread {tid} to {}: {e}", file.display()),
+            })?;
+
+        tracing::debug!(container_id = %self.container_id, tid = %tid, "Added thread");
+        Ok(())
+    }
+
+    /// Restrict the devices processes in the cgroup may open or create
+    ///
+    /// Attaches an eBPF device filter built from `rules`, later ones
+    /// winning, that refuses whatever no rule permits;
+    /// [`DeviceRule::defaults`] is a safe set to start from. A second call
+    /// replaces the filter. See [`crate::devices`] for the kernel and
+    /// privileges this needs.
+    ///
+    /// # Errors
+    /// Returns [`Error::PermissionDenied`] without the privilege to load
+    /// eBPF programs, and [`Error::CGroup`] if the verifier rejects the
+    /// filter or it cannot be attached
+    pub async fn set_device_access(&self, rules: &[DeviceRule]) -> Result<()> {
+        let path = self.path.clone();
+        let rules = rules.to_vec();
+        let count = rules.len();
+        tokio::task::spawn_blocking(move || devices::attach(&path, &rules)).await??;
+
+        tracing::info!(
+            container_id = %self.container_id,
+            rules = count,
+            "Attached device filter"
+        );
+        Ok(())
+    }
+
+    /// Check that the cgroup filesystem at `root` is mounted read-write
+    ///
+    /// Hardened hosts and container-in-container setups (e.g. CI runners)
+    /// often mount `/sys/fs/cgroup` read-only, which otherwise surfaces as an
+    /// opaque mkdir failure.
+    ///
+    /// # Errors
+    /// Returns [`Error::CGroupReadOnly`] if the filesystem is read-only
+    pub fn check_writable(root: &Path) -> Result<()> {
+        match statvfs(root) {
+            Ok(stat) if stat.flags().contains(FsFlags::ST_RDONLY) => {
+                tracing::error!(
+                    path = %root.display(),
+                    "CGroup filesystem is mounted read-only"
+                );
+                Err(Error::CGroupReadOnly {
+                    path: root.to_path_buf(),
+                })
+            }
+            // A missing root is reported by directory creation
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that an existing cgroup can hold vortex containers
+    ///
+    /// This is for a parent vortex does not create itself, such as a
+    /// systemd slice. Vortex creates the container cgroups in it and
+    /// enables controllers in its `cgroup.subtree_control`, so whoever
+    /// manages it has to delegate it (`Delegate=yes` on a systemd unit),
+    /// with every controller in [`REQUIRED_CONTROLLERS`] enabled on the
+    /// levels above.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidConfig`] if `path` is not a cgroup,
+    /// [`Error::CGroupReadOnly`] or [`Error::PermissionDenied`] if vortex cannot
+    /// create cgroups in it and [`Error::CGroup`] if controllers are missing
+    pub fn check_delegated(path: &Path) -> Result<()> {
+        if !path.join("cgroup.controllers").is_file() {
+            return Err(Error::InvalidConfig {
+                message: format!("Cgroup parent {} is not an existing cgroup", path.display()),
+            });
+        }
+
+        Self::check_writable(path)?;
+        let writable = [path.to_path_buf(), path.join("cgroup.subtree_control")]
+            .iter()
+            .all(|file| nix::unistd::access(file, nix::unistd::AccessFlags::W_OK).is_ok());
+        if !writable {
+            return Err(Error::PermissionDenied {
+                operation: format!("create cgroups in {}", path.display()),
+            });
+        }
+
+        let missing = missing_controllers(&Self::root_controllers(path)?);
+        if !missing.is_empty() {
+            return Err(Error::CGroup {
+                message: format!(
+                    "Controllers not delegated to {}: {}",
+                    path.display(),
+                    missing.join(", ")
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Controllers the cgroup v2 hierarchy at `root` offers, from its
+    /// `cgroup.controllers`
+    ///
+    /// Unlike [`available_controllers`](Self::available_controllers) this
+    /// needs no container cgroup, so it suits checks before one is created.
+    ///
+    /// # Errors
+    /// Returns error if `cgroup.controllers` cannot be read, which includes
+    /// cgroup v1 hosts where it does not exist
+    pub fn root_controllers(root: &Path) -> Result<Vec<String>> {
+        let file = root.join("cgroup.controllers");
+        let content = std::fs::read_to_string(&file).map_err(|e| Error::CGroup {
+            message: format!("Failed to read {}: {e}", file.display()),
+        })?;
+        Ok(parse_controllers(&content))
+    }
+
+    /// Whether the hierarchy at `root` offers every controller in
+    /// [`REQUIRED_CONTROLLERS`]
+    ///
+    /// # Errors
+    /// Returns error if `cgroup.controllers` cannot be read
+    pub fn required_controllers_present(root: &Path) -> Result<bool> {
+        Ok(missing_controllers(&Self::root_controllers(root)?).is_empty())
+    }
+
+    /// Create the cgroup directory hierarchy and enable controllers
+    async fn create(&mut self) -> Result<()> {
+        // Step 0: Fail early with a clear message on read-only mounts
+        Self::check_writable(self.config.root())?;
+
+        // Step 1: Don't clobber a container that is still running
+        self.check_existing().await?;
+
+        // Step 2: Create directory structure
+        self.create_directory_hierarchy().await?;
+
+        // Step 3: Enable controllers at each level
+        self.enable_controllers_in_hierarchy().await?;
+
+        Ok(())
+    }
+
+    /// Check whether a cgroup for this ID already has processes
+    ///
+    /// An empty leftover directory is simply reused. A running container is
+    /// an error unless [`CGroupConfig::replace`] is set, in which case its
+    /// processes are killed and the cgroup is taken over.
+    async fn check_existing(&self) -> Result<()> {
+        if !self.path.is_dir() {
+            return Ok(());
+        }
+
+        let cgroups = descendants(&self.path).await;
+        let mut running = false;
+        for cgroup in &cgroups {
+            running |= !read_pids(cgroup).await.is_empty();
+        }
+        if !running {
+            tracing::debug!(path = %self.path.display(), "Reusing empty cgroup directory");
+            return Ok(());
+        }
+
+        if !self.config.replace {
+            return Err(Error::ContainerRunning {
+                id: self.container_id.to_string(),
+            });
+        }
+
+        tracing::warn!(
+            container_id = %self.container_id,
+            "Replacing running container"
+        );
+        self.kill_processes(&cgroups).await;
+        Ok(())
+    }
+
+    /// Create the directory hierarchy for this cgroup
+    async fn create_directory_hierarchy(&self) -> Result<()> {
+        let vortex_root = self.config.vortex_root();
+
+        // Create vortex directory if it doesn't exist
+        if !vortex_root.exists() {
+            fs::create_dir_all(&vortex_root).await.map_err(|e| {
+                tracing::error!(
+                    path = %vortex_root.display(),
+                    error = %e,
+                    "Failed to create vortex directory"
+                );
+                if is_read_only_error(&e) {
+                    return Error::CGroupReadOnly {
+                        path: self.config.root().to_path_buf(),
+                    };
+                }
+                Error::CGroup {
+                    message: format!(
+                        "Failed to create vortex directory: {}\nPath: {}",
+                        e,
+                        vortex_root.display()
+                    ),
+                }
+            })?;
+
+            tracing::info!(
+                path = %vortex_root.display(),
+                "Created vortex cgroup directory"
+            );
+        }
+
+        // Create container directory
+        fs::create_dir_all(&self.path).await.map_err(|e| {
+            tracing::error!(
+                path = %self.path.display(),
+                error = %e,
+                "Failed to create container directory"
+            );
+            if is_read_only_error(&e) {
+                return Error::CGroupReadOnly {
+                    path: self.config.root().to_path_buf(),
+                };
+            }
+            Error::CGroup {
+                message: format!(
+                    "Failed to create container directory: {}\nPath: {}",
+                    e,
+                    self.path.display()
+                ),
+            }
+        })?;
+
+        tracing::debug!(
+            path = %self.path.display(),
+            "CGroup directory created"
+        );
+
+        Ok(())
+    }
+
+    /// Enable controllers at all levels in the hierarchy
+    async fn enable_controllers_in_hierarchy(&self) -> Result<()> {
+        let root = self.config.root();
+        let vortex_root = self.config.vortex_root();
+
+        // Enable at root level (best effort)
+        Self::enable_controllers_at(root).await;
+
+        // Enable at vortex level (best effort)
+        Self::enable_controllers_at(&vortex_root).await;
+
+        // Enable at pod level so the container can use them (best effort)
+        if self.config.parent.is_some() {
+            Self::enable_controllers_at(&self.config.container_parent()).await;
+        }
+
+        Ok(())
+    }
+
+    /// Enable controllers at a specific path
+    ///
+    /// This is best-effort and will not fail if controllers cannot be enabled
+    /// (they might be managed by systemd or already enabled at a higher level)
+    pub(crate) async fn enable_controllers_at(path: &Path) {
+        let controllers_file = path.join("cgroup.controllers");
+        let control_file = path.join("cgroup.subtree_control");
+
+        // Skip if control file doesn't exist
+        if !control_file.exists() {
+            tracing::trace!(
+                path = %path.display(),
+                "Subtree control file doesn't exist, skipping"
+            );
+            return;
+        }
+
+        // Read available controllers
+        let available = match fs::read_to_string(&controllers_file).await {
+            Ok(content) => content,
+            Err(e) => {
+                tracing::trace!(
+                    path = %path.display(),
+                    error = %e,
+                    "Could not read available controllers"
+                );
+                return;
+            }
+        };
+
+        // Read currently enabled controllers
+        let enabled = fs::read_to_string(&control_file).await.unwrap_or_default();
+
+        // Determine which controllers need to be enabled
+        let to_enable: Vec<&str> = REQUIRED_CONTROLLERS
+            .iter()
+            .copied()
+            .filter(|c| has_controller(&available, c) && !has_controller(&enabled, c))
+            .collect();
+
+        if to_enable.is_empty() {
+            tracing::trace!(
+                path = %path.display(),
+                "All required controllers already enabled"
+            );
+            return;
+        }
+
+        // Try to enable each controller individually
+        // This is more robust than enabling all at once
+        for controller in &to_enable {
+            Self::enable_controller(&control_file, controller).await;
+        }
+
+        // Verify what actually landed; a failed write is not always reported
+        // as an error when systemd reorganizes the hierarchy concurrently
+        let enabled = fs::read_to_string(&control_file).await.unwrap_or_default();
+        let missing: Vec<&str> = to_enable
+            .iter()
+            .copied()
+            .filter(|c| !has_controller(&enabled, c))
+            .collect();
+
+        if !missing.is_empty() {
+            tracing::warn!(
+                path = %path.display(),
+                missing = ?missing,
+                "Some controllers could not be enabled; limits for them may not apply"
+            );
+        }
+    }
+
+    /// Write `+<controller>` to a subtree control file
+    ///
+    /// Transient failures (EBUSY/EAGAIN, typically systemd racing us) are
+    /// retried with exponential backoff up to `ENABLE_CONTROLLER_ATTEMPTS`.
+    async fn enable_controller(control_file: &Path, controller: &str) {
+        let cmd = format!("+{controller}");
+        let mut backoff = Duration::from_millis(ENABLE_CONTROLLER_BACKOFF_MS);
+
+        for attempt in 1..=ENABLE_CONTROLLER_ATTEMPTS {
+            match fs::write(control_file, &cmd).await {
+                Ok(()) => {
+                    tracing::debug!(
+                        path = %control_file.display(),
+                        controller = %controller,
+                        attempt,
+                        "Enabled controller"
+                    );
+                    return;
+                }
+                Err(e) if is_transient_error(&e) && attempt < ENABLE_CONTROLLER_ATTEMPTS => {
+                    tracing::debug!(
+                        path = %control_file.display(),
+                        controller = %controller,
+                        attempt,
+                        error = %e,
+                        "Controller enable busy, retrying"
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(e) => {
+                    // Just log at debug level - this is expected in many cases
+                    // (systemd management, already enabled at higher level, etc.)
+                    tracing::debug!(
+                        path = %control_file.display(),
+                        controller = %controller,
+                        attempt,
+                        error = %e,
+                        "Could not enable controller (may be managed at higher level)"
+                    );
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Cleanup the cgroup
+    ///
+    /// Equivalent to [`Self::cleanup_with`] with `force = false`: processes
+    /// are migrated to the root cgroup rather than killed.
+    ///
+    /// # Errors
+    /// Returns error if cleanup fails
+    pub async fn cleanup(&self) -> Result<()> {
+        self.cleanup_with(false).await
+    }
+
+    /// Cleanup the cgroup and every cgroup nested below it
+    ///
+    /// This will:
+    /// 1. Empty the hierarchy with [`Self::empty`]
+    /// 2. Remove the directories bottom-up, retrying ones that are still
+    ///    busy, unless the controller keeps the cgroup on exit
+    ///
+    /// Calling it again after a successful cleanup is a no-op.
+    ///
+    /// # Errors
+    /// Returns error if cleanup fails
+    #[tracing::instrument(skip(self), fields(container_id = %self.container_id))]
+    pub async fn cleanup_with(&self, force: bool) -> Result<()> {
+        if !self.is_active() {
+            tracing::debug!("CGroup already cleaned up");
+            return Ok(());
+        }
+
+        tracing::debug!(
+            container_id = %self.container_id,
+            force,
+            keep = self.keep_on_exit,
+            "Cleaning up cgroup"
+        );
+
+        self.empty(force).await;
+
+        if self.keep_on_exit {
+            tracing::info!(
+                container_id = %self.container_id,
+                path = %self.path.display(),
+                "Keeping cgroup for inspection"
+            );
+        } else {
+            self.remove().await;
+        }
+
+        self.active.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Get every process out of the cgroup and its descendants
+    ///
+    /// The hierarchy is frozen so nothing forks while it is being emptied,
+    /// then processes are moved to the root cgroup, or killed with `SIGKILL`
+    /// when `force` is set. The directories are left in place.
+    pub async fn empty(&self, force: bool) {
+        let cgroups = descendants(&self.path).await;
+
+        Self::set_frozen(&self.path, true).await;
+
+        if force {
+            self.kill_processes(&cgroups).await;
+        } else {
+            for cgroup in &cgroups {
+                self.move_processes_to_root(cgroup).await;
+            }
+        }
+
+        Self::set_frozen(&self.path, false).await;
+    }
+
+    /// Remove the cgroup directory and everything nested below it
+    ///
+    /// The cgroups must already be empty (see [`Self::empty`]); busy
+    /// directories are retried until the configured cleanup timeout.
+    pub async fn remove(&self) {
+        // Deepest first
+        for cgroup in descendants(&self.path).await {
+            self.remove_cgroup_directory(&cgroup).await;
+        }
+    }
+
+    /// Freeze or thaw a cgroup subtree (best effort)
+    async fn set_frozen(path: &Path, frozen: bool) {
+        let freeze_file = path.join("cgroup.freeze");
+        if !fs::try_exists(&freeze_file).await.unwrap_or(false) {
+            return;
+        }
+
+        let value = if frozen { "1" } else { "0" };
+        if let Err(e) = fs::write(&freeze_file, value).await {
+            tracing::debug!(
+                path = %path.display(),
+                frozen,
+                error = %e,
+                "Could not change freezer state"
+            );
+        }
+    }
+
+    /// SIGKILL every process in the given cgroups and wait for them to exit
+    async fn kill_processes(&self, cgroups: &[PathBuf]) {
+        // cgroup.kill (Linux 5.14+) kills the whole subtree in one write
+        let kill_file = self.path.join("cgroup.kill");
+        let killed_by_kernel = fs::try_exists(&kill_file).await.unwrap_or(false)
+            && fs::write(&kill_file, "1").await.is_ok();
+
+        if !killed_by_kernel {
+            for cgroup in cgroups {
+                for pid in read_pids(cgroup).await {
+                    match pid.signal(nix::sys::signal::Signal::SIGKILL) {
+                        Ok(()) | Err(Error::ProcessNotFound { .. }) => {}
+                        Err(e) => {
+                            tracing::debug!(pid = %pid, error = %e, "Could not kill process");
+                        }
+                    }
+                }
+            }
+        }
+
+        // Killed processes leave cgroup.procs asynchronously
+        for _ in 0..KILL_WAIT_ATTEMPTS {
+            let mut remaining = false;
+            for cgroup in cgroups {
+                remaining |= !read_pids(cgroup).await.is_empty();
+            }
+            if !remaining {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(KILL_POLL_MS)).await;
+        }
+
+        tracing::warn!(
+            container_id = %self.container_id,
+            "Processes still present after SIGKILL"
+        );
+    }
+
+    /// Move all processes in a cgroup back to the root cgroup
+    async fn move_processes_to_root(&self, cgroup: &Path) {
+        let root_procs = self.config.root().join("cgroup.procs");
+
+        for pid in read_pids(cgroup).await {
+            if let Err(e) = fs::write(&root_procs, pid.to_string()).await {
+                tracing::debug!(
+                    pid = %pid,
+                    error = %e,
+                    "Could not move process to root cgroup"
+                );
+            }
+        }
+    }
+
+    /// Remove a cgroup directory
+    ///
+    /// The kernel may take a moment to release a cgroup whose processes just
+    /// left, so busy errors are retried with backoff for up to the
+    /// configured cleanup timeout.
+    async fn remove_cgroup_directory(&self, cgroup: &Path) {
+        let mut backoff = REMOVE_BACKOFF_START;
+        let mut waited = Duration::ZERO;
+
+        for attempt in 1.. {
+            let e = match fs::remove_dir(cgroup).await {
+                Ok(()) => {
+                    tracing::info!(
+                        container_id = %self.container_id,
+                        path = %cgroup.display(),
+                        attempts = attempt,
+                        "CGroup removed"
+                    );
+                    return;
+                }
+                Err(e) => e,
+            };
+
+            let remaining = self.config.cleanup_timeout.saturating_sub(waited);
+            if !is_busy_error(&e) || remaining.is_zero() {
+                tracing::warn!(
+                    container_id = %self.container_id,
+                    path = %cgroup.display(),
+                    attempts = attempt,
+                    error = %e,
+                    "Failed to remove cgroup directory (may already be removed)"
+                );
+                return;
+            }
+
+            tracing::debug!(
+                path = %cgroup.display(),
+                attempt,
+                error = %e,
+                "CGroup still busy, retrying removal"
+            );
+            let pause = backoff.min(remaining);
+            tokio::time::sleep(pause).await;
+            waited += pause;
+            backoff *= 2;
+        }
+    }
+}
+
+/// Implement ResourceBackend trait for CGroupController
+#[async_trait]
+impl ResourceBackend for CGroupController {
+    fn kind(&self) -> BackendKind {
+        BackendKind::CGroup
+    }
+
+    #[tracing::instrument(skip(self, limit), fields(container_id = %self.container_id))]
+    async fn set_cpu_limit(&self, limit: CpuLimit) -> Result<()> {
+        self.set_limit(vec![LimitWrite::cpu(limit)?]).await?;
+        tracing::info!(
+            container_id = %self.container_id,
+            cores = limit.cores.as_f64(),
+            "Set CPU limit"
+        );
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self, limit), fields(container_id = %self.container_id))]
+    async fn set_memory_limit(&self, limit: MemoryLimit) -> Result<()> {
+        self.set_limit(LimitWrite::memory(limit)?).await?;
+        tracing::info!(
+            container_id = %self.container_id,
+            memory = %limit.limit,
+            swap = ?limit.swap.map(|swap| swap.to_string()),
+            "Set memory limit"
+        );
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self), fields(container_id = %self.container_id))]
+    async fn set_pids_limit(&self, max: u64) -> Result<()> {
+        self.set_limit(vec![LimitWrite::pids(max)]).await?;
+        tracing::info!(container_id = %self.container_id, max, "Set pids limit");
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self), fields(container_id = %self.container_id))]
+    async fn set_cpu_weight(&self, weight: u16) -> Result<()> {
+        self.set_limit(vec![LimitWrite::cpu_weight(weight)?])
+            .await?;
+        tracing::info!(container_id = %self.container_id, weight, "Set CPU weight");
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self, limit), fields(container_id = %self.container_id))]
+    async fn set_io_limit(&self, limit: IoLimit) -> Result<()> {
+        self.set_limit(vec![LimitWrite::io(&[limit])]).await?;
+        tracing::info!(container_id = %self.container_id, limit = %limit, "Set IO limit");
+        Ok(())
+    }
+
+    /// Write every limit in one blocking task
+    ///
+    /// Each control file is opened once, and the writes happen on the
+    /// blocking pool with plain syscalls: the files are tiny and virtual, so
+    /// a round trip through the async runtime per limit costs more than the
+    /// write itself. The files and values are those the setters write, and
+    /// order and errors are those of the default.
+    #[tracing::instrument(skip(self, limits), fields(container_id = %self.container_id))]
+    async fn apply_limits(&self, limits: &ResourceLimits) -> Result<()> {
+        let (writes, invalid) = limit_writes(limits);
+        if writes.is_empty() && invalid.is_none() {
+            return Ok(());
+        }
+
+        let (applied, failure) = self.perform_writes(writes).await?;
+
+        // An invalid value stops the writes just like a failed one
+        if let Some((limit, error)) = failure.or(invalid) {
+            tracing::error!(
+                container_id = %self.container_id,
+                error = %error,
+                "Failed to set {limit} limit"
+            );
+            return Err(partially_applied(&applied, limit, error));
+        }
+
+        tracing::info!(
+            container_id = %self.container_id,
+            limits = ?applied,
+            "Applied resource limits"
+        );
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self), fields(container_id = %self.container_id, pid = %pid))]
+    async fn add_process(&self, pid: ProcessId) -> Result<()> {
+        let procs_file = self.path.join("cgroup.procs");
+        let pid_str = pid.as_raw().to_string();
+
+        fs::write(&procs_file, pid_str.as_bytes())
+            .await
+            .map_err(|e| {
+                tracing::error!(
+                    container_id = %self.container_id,
+                    pid = pid.as_raw(),
+                    error = %e,
+                    "Failed to add process"
+                );
+                Error::CGroup {
+                    message: format!("Failed to add process {pid}: {e}"),
+                }
+            })?;
+
+        tracing::debug!(
+            container_id = %self.container_id,
+            pid = pid.as_raw(),
+            "Added process to cgroup"
+        );
+
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "debug", skip(self), fields(container_id = %self.container_id))]
+    async fn stats(&self) -> Result<ResourceStats> {
+        let cpu_stats = self.read_cpu_stats().await?;
+        let memory_stats = self.read_memory_stats().await?;
+        let io_stats = self.read_io_stats().await?;
+        let memory_high_events = self.read_memory_high_events().await;
+
+        Ok(ResourceStats {
+            cpu_usage: cpu_stats.usage,
+            cpu_throttled: cpu_stats.throttled,
+            cpu_nr_periods: cpu_stats.nr_periods,
+            cpu_nr_throttled: cpu_stats.nr_throttled,
+            memory_current: memory_stats.0,
+            memory_peak: memory_stats.1,
+            swap_current: memory_stats.2,
+            swap_peak: memory_stats.3,
+            io_read_bytes: io_stats.0,
+            io_write_bytes: io_stats.1,
+            memory_high_events,
+        })
+    }
+
+    async fn cleanup(&self) -> Result<()> {
+        self.cleanup_with(false).await
+    }
+}
+
+impl CGroupController {
+    async fn read_cpu_stats(&self) -> Result<CpuStat> {
+        let cpu_stat_file = self.path.join("cpu.stat");
+
+        let content = fs::read_to_string(&cpu_stat_file)
+            .await
+            .map_err(|e| Error::CGroup {
+                message: format!("Failed to read cpu.stat: {e}"),
+            })?;
+
+        Ok(parse_cpu_stat(&content))
+    }
+
+    /// Read current and peak memory and swap usage
+    ///
+    /// The peak files only exist since Linux 5.19 (`memory.peak`) and 6.5
+    /// (`memory.swap.peak`); without them the current value stands in for
+    /// the peak. Swap accounting may be disabled altogether, which reads as
+    /// zero.
+    async fn read_memory_stats(&self) -> Result<(MemorySize, MemorySize, MemorySize, MemorySize)> {
+        let current = self.read_single_value("memory.current").await?;
+        let peak = self
+            .read_single_value("memory.peak")
+            .await
+            .unwrap_or_else(|e| {
+                tracing::trace!(error = %e, "memory.peak not available, using current as peak");
+                current
+            });
+        let swap_current = self
+            .read_single_value("memory.swap.current")
+            .await
+            .unwrap_or(0);
+        let swap_peak = self
+            .read_single_value("memory.swap.peak")
+            .await
+            .unwrap_or(swap_current);
+
+        Ok((
+            MemorySize::from_bytes(current),
+            MemorySize::from_bytes(peak),
+            MemorySize::from_bytes(swap_current),
+            MemorySize::from_bytes(swap_peak),
+        ))
+    }
+
+    async fn read_io_stats(&self) -> Result<(u64, u64)> {
+        let io_stat_file = self.path.join("io.stat");
+
+        let content = fs::read_to_string(&io_stat_file).await.unwrap_or_default();
+
+        Ok(parse_io_stat(&content))
+    }
+
+    /// Read `hugetlb` and `misc` controller usage
+    ///
+    /// Entries are named `hugetlb.<page size>` (bytes) and `misc.<resource>`
+    /// (the resource's own units). Controllers that aren't enabled simply
+    /// contribute nothing.
+    pub async fn extended_stats(&self) -> Vec<(String, u64)> {
+        let mut usage = Vec::new();
+
+        // Page sizes depend on the machine, so discover them
+        let mut sizes = Vec::new();
+        if let Ok(mut entries) = fs::read_dir(&self.path).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                if let Some(size) = entry.file_name().to_str().and_then(hugetlb_page_size) {
+                    sizes.push(size.to_string());
+                }
+            }
+        }
+        sizes.sort();
+
+        for size in sizes {
+            if let Ok(bytes) = self
+                .read_single_value(&format!("hugetlb.{size}.current"))
+                .await
+            {
+                usage.push((format!("hugetlb.{size}"), bytes));
+            }
+        }
+
+        if let Ok(content) = fs::read_to_string(self.path.join("misc.current")).await {
+            usage.extend(
+                parse_flat_keyed(&content)
+                    .into_iter()
+                    .map(|(resource, value)| (format!("misc.{resource}"), value)),
+            );
+        }
+
+        usage
+    }
+
+    /// `high` counter of `memory.events`, 0 when the file is missing
+    async fn read_memory_high_events(&self) -> u64 {
+        let content = fs::read_to_string(self.path.join("memory.events"))
+            .await
+            .unwrap_or_default();
+
+        keyed_value(&content, "high").unwrap_or(0)
+    }
+
+    /// Write one limit file, logging a failure
+    async fn write_limit(&self, filename: &str, content: &str, limit: &str) -> Result<()> {
+        fs::write(self.path.join(filename), content)
+            .await
+            .map_err(|e| {
+                tracing::error!(
+                    container_id = %self.container_id,
                     error = %e,
-                    "Could not read process list"
+                    "Failed to set {limit} limit"
+                );
+                Error::CGroup {
+                    message: format!("Failed to set {limit} limit: {e}"),
+                }
+            })
+    }
+
+    /// Perform the writes of a single limit, see [`write_limits`]
+    async fn set_limit(&self, writes: Vec<LimitWrite>) -> Result<()> {
+        let (_, failure) = self.perform_writes(writes).await?;
+        match failure {
+            Some((limit, error)) => {
+                tracing::error!(
+                    container_id = %self.container_id,
+                    error = %error,
+                    "Failed to set {limit} limit"
                 );
+                Err(error)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Perform `writes` on the blocking pool, see [`write_limits`]
+    async fn perform_writes(
+        &self,
+        writes: Vec<LimitWrite>,
+    ) -> Result<(Vec<&'static str>, Option<(&'static str, Error)>)> {
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || write_limits(&path, &writes))
+            .await
+            .map_err(|e| Error::CGroup {
+                message: format!("Limit writer task failed: {e}"),
+            })
+    }
+
+    async fn read_single_value(&self, filename: &str) -> Result<u64> {
+        let file = self.path.join(filename);
+        let content = fs::read_to_string(&file).await.map_err(|e| Error::CGroup {
+            message: format!("Failed to read {filename}: {e}"),
+        })?;
+
+        content.trim().parse().map_err(|e| Error::CGroup {
+            message: format!("Failed to parse {filename} value: {e}"),
+        })
+    }
+}
+
+/// Peak memory and swap usage since [`CGroupController::reset_peaks`]
+#[derive(Debug)]
+pub struct PeakReset {
+    memory: std::fs::File,
+    swap: Option<std::fs::File>,
+}
+
+impl PeakReset {
+    /// Peak memory usage since the reset
+    ///
+    /// # Errors
+    /// Returns error if `memory.peak` cannot be read, e.g. once the cgroup
+    /// is gone
+    pub fn memory(&self) -> Result<MemorySize> {
+        read_peak(&self.memory).map(MemorySize::from_bytes)
+    }
+
+    /// Peak swap usage since the reset, if it is tracked
+    ///
+    /// # Errors
+    /// Returns error if `memory.swap.peak` cannot be read
+    pub fn swap(&self) -> Result<Option<MemorySize>> {
+        self.swap
+            .as_ref()
+            .map(|file| read_peak(file).map(MemorySize::from_bytes))
+            .transpose()
+    }
+}
+
+/// Open a peak file and reset it through the new descriptor
+///
+/// Kernels before 6.12 create the peak files read-only; the mode says so
+/// even to root, who could otherwise open them for writing.
+fn reset_peak(file: &Path) -> Result<std::fs::File> {
+    use std::io::Write;
+    use std::os::unix::fs::PermissionsExt;
+
+    let unsupported = |reason: String| Error::CGroup {
+        message: format!(
+            "Cannot reset {}: {reason} (resetting peaks needs Linux 6.12 or later)",
+            file.display()
+        ),
+    };
+    let mode = std::fs::metadata(file)
+        .map_err(|e| unsupported(e.to_string()))?
+        .permissions()
+        .mode();
+    if mode & 0o200 == 0 {
+        return Err(unsupported("the kernel made it read-only".to_string()));
+    }
+
+    let mut peak = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(file)
+        .map_err(|e| unsupported(e.to_string()))?;
+    peak.write_all(b"reset\n")
+        .map_err(|e| unsupported(e.to_string()))?;
+    Ok(peak)
+}
+
+/// Read a peak file from the start, through the descriptor that reset it
+fn read_peak(file: &std::fs::File) -> Result<u64> {
+    use std::os::unix::fs::FileExt;
+
+    let mut buf = [0u8; 32];
+    let len = file.read_at(&mut buf, 0)?;
+    String::from_utf8_lossy(&buf[..len])
+        .trim()
+        .parse()
+        .map_err(|e| Error::CGroup {
+            message: format!("Failed to parse peak value: {e}"),
+        })
+}
+
+/// Cgroup directory of `container_id`, checked to be strictly under the
+/// vortex root
+///
+/// [`ContainerId`] already rules out `/` and `.`, but the pod name is joined
+/// in too, and callers such as `list` take IDs from directory names. Every
+/// component below the mount point must be a plain name, and the part of
+/// the path that exists already must resolve under the vortex root, so a
+/// symlink cannot lead out either.
+///
+/// # Errors
+/// Returns [`Error::InvalidConfig`] if the path would leave the vortex root
+fn container_path(config: &CGroupConfig, container_id: &ContainerId) -> Result<PathBuf> {
+    use std::path::Component;
+
+    let vortex_root = config.vortex_root();
+    let path = config.container_parent().join(container_id.as_str());
+    let escapes = || Error::InvalidConfig {
+        message: format!(
+            "Cgroup path {} of container '{container_id}' is not under {}",
+            path.display(),
+            vortex_root.display()
+        ),
+    };
+
+    let plain = path.strip_prefix(config.root()).is_ok_and(|relative| {
+        relative
+            .components()
+            .all(|part| matches!(part, Component::Normal(_)))
+    });
+    if !plain || !path.starts_with(&vortex_root) || path == vortex_root {
+        return Err(escapes());
+    }
+
+    // Nothing below a missing vortex root exists to be a symlink
+    if let Ok(real_root) = std::fs::canonicalize(&vortex_root) {
+        let existing = path
+            .ancestors()
+            .find(|ancestor| ancestor.exists())
+            .and_then(|ancestor| std::fs::canonicalize(ancestor).ok());
+        let contained = existing.is_some_and(|real| {
+            real.starts_with(&real_root) && (real != real_root || !path.exists())
+        });
+        if !contained {
+            return Err(escapes());
+        }
+    }
+
+    Ok(path)
+}
+
+/// Parse the contents of `memory.max`, where `max` means unlimited
+fn parse_memory_max(content: &str) -> Result<Option<MemorySize>> {
+    let value = content.trim();
+    if value == "max" {
+        return Ok(None);
+    }
+    value
+        .parse()
+        .map(|bytes| Some(MemorySize::from_bytes(bytes)))
+        .map_err(|_| Error::CGroup {
+            message: format!("Invalid memory.max value '{value}'"),
+        })
+}
+
+/// A control file a limit is set through, with the lines to write one by
+/// one and the limit they belong to
+///
+/// Both the single-limit setters and [`CGroupController::apply_limits`]
+/// build their writes here, so they set the same files to the same values.
+struct LimitWrite {
+    filename: &'static str,
+    lines: Vec<String>,
+    limit: &'static str,
+}
+
+impl LimitWrite {
+    fn new(filename: &'static str, line: String, limit: &'static str) -> Self {
+        Self {
+            filename,
+            lines: vec![line],
+            limit,
+        }
+    }
+
+    fn pids(max: u64) -> Self {
+        Self::new("pids.max", max.to_string(), "pids")
+    }
+
+    fn cpu_weight(weight: u16) -> Result<Self> {
+        check_cpu_weight(weight)?;
+        Ok(Self::new("cpu.weight", weight.to_string(), "CPU weight"))
+    }
+
+    fn cpu(limit: CpuLimit) -> Result<Self> {
+        CpuCores::try_new(limit.cores.as_f64())?;
+        let (quota, period) = limit.cores.to_quota();
+        Ok(Self::new("cpu.max", format!("{quota} {period}"), "CPU"))
+    }
+
+    fn io(limits: &[IoLimit]) -> Self {
+        // The kernel takes one device per write
+        Self {
+            filename: "io.max",
+            lines: limits.iter().map(ToString::to_string).collect(),
+            limit: "IO",
+        }
+    }
+
+    /// `memory.max`, then `memory.swap.max` if the limit has swap
+    fn memory(limit: MemoryLimit) -> Result<Vec<Self>> {
+        let max = page_aligned_memory(limit.limit, page_size())?;
+        let mut writes = vec![Self::new(
+            "memory.max",
+            max.as_bytes().to_string(),
+            "memory",
+        )];
+        if let Some(swap) = limit.swap {
+            let swap = swap.as_bytes().to_string();
+            writes.push(Self::new("memory.swap.max", swap, "memory"));
+        }
+        Ok(writes)
+    }
+}
+
+/// Turn `limits` into control file writes, in the order of
+/// [`ResourceBackend::apply_limits`]
+///
+/// The writes end before the first invalid value, which is returned with
+/// its limit.
+fn limit_writes(limits: &ResourceLimits) -> (Vec<LimitWrite>, Option<(&'static str, Error)>) {
+    let mut writes = Vec::new();
+
+    if let Some(pids) = limits.pids {
+        writes.push(LimitWrite::pids(pids));
+    }
+    if let Some(weight) = limits.cpu_weight {
+        match LimitWrite::cpu_weight(weight) {
+            Ok(write) => writes.push(write),
+            Err(e) => return (writes, Some(("CPU weight", e))),
+        }
+    }
+    if let Some(cpu) = limits.cpu {
+        match LimitWrite::cpu(cpu) {
+            Ok(write) => writes.push(write),
+            Err(e) => return (writes, Some(("CPU", e))),
+        }
+    }
+    if !limits.io.is_empty() {
+        writes.push(LimitWrite::io(&limits.io));
+    }
+    if let Some(memory) = limits.memory {
+        match LimitWrite::memory(memory) {
+            Ok(memory) => writes.extend(memory),
+            Err(e) => return (writes, Some(("memory", e))),
+        }
+    }
+
+    (writes, None)
+}
+
+/// Perform `writes` in the cgroup at `path`, stopping at the first failure
+///
+/// Returns the limits fully set, and the limit that failed with its error.
+fn write_limits(
+    path: &Path,
+    writes: &[LimitWrite],
+) -> (Vec<&'static str>, Option<(&'static str, Error)>) {
+    use std::io::Write;
+
+    let mut applied: Vec<&'static str> = Vec::new();
+    for write in writes {
+        let written = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path.join(write.filename))
+            .and_then(|mut file| {
+                write
+                    .lines
+                    .iter()
+                    .try_for_each(|line| file.write_all(line.as_bytes()))
+            });
+
+        if let Err(e) = written {
+            // A limit spread over several files is only set once all are
+            applied.retain(|&limit| limit != write.limit);
+            let message = format!("Failed to set {} limit: {e}", write.limit);
+            return (applied, Some((write.limit, Error::CGroup { message })));
+        }
+        if !applied.contains(&write.limit) {
+            applied.push(write.limit);
+        }
+    }
+    (applied, None)
+}
+
+/// Controllers that work in threaded cgroups
+const THREADED_CONTROLLERS: [&str; 4] = ["cpu", "cpuset", "perf_event", "pids"];
+
+/// Check that `parent` can have a threaded child
+async fn check_threaded_parent(parent: &Path) -> Result<()> {
+    let read = |filename: &'static str| async move {
+        let file = parent.join(filename);
+        fs::read_to_string(&file).await.map_err(|e| Error::CGroup {
+            message: format!("Failed to read {}: {e}", file.display()),
+        })
+    };
+
+    let kind = read("cgroup.type").await?;
+    let kind = kind.trim();
+    if !matches!(kind, "domain" | "domain threaded" | "threaded") {
+        return Err(Error::CGroup {
+            message: format!(
+                "Parent cgroup {} is '{kind}', a threaded cgroup needs a domain or threaded parent",
+                parent.display()
+            ),
+        });
+    }
+
+    let enabled = parse_controllers(&read("cgroup.subtree_control").await?);
+    let domain: Vec<&str> = enabled
+        .iter()
+        .map(String::as_str)
+        .filter(|controller| !THREADED_CONTROLLERS.contains(controller))
+        .collect();
+    if !domain.is_empty() {
+        return Err(Error::CGroup {
+            message: format!(
+                "Parent cgroup {} enables {}, which threaded cgroups cannot use; \
+                 only {} are allowed",
+                parent.display(),
+                domain.join(", "),
+                THREADED_CONTROLLERS.join(", ")
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+/// Refuse a `cpu.weight` outside 1 to 10000
+fn check_cpu_weight(weight: u16) -> Result<()> {
+    if (1..=10_000).contains(&weight) {
+        Ok(())
+    } else {
+        Err(Error::InvalidConfig {
+            message: format!("Invalid CPU weight {weight}: must be between 1 and 10000"),
+        })
+    }
+}
+
+/// Fewest pages a memory limit may allow
+///
+/// Below this the first allocations of any process hit the limit, so the
+/// container would be OOM-killed as soon as it starts.
+pub const MIN_MEMORY_PAGES: u64 = 4;
+
+/// Size of a memory page, the unit the kernel keeps `memory.max` in
+#[must_use]
+pub fn page_size() -> u64 {
+    // sysconf only reads a system constant
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    u64::try_from(size)
+        .ok()
+        .filter(|&size| size > 0)
+        .unwrap_or(4096)
+}
+
+/// Round a memory limit up to whole pages of `page_size` bytes
+///
+/// The kernel would round it down instead, leaving a limit just below the
+/// one asked for.
+///
+/// # Errors
+/// Returns [`Error::InvalidConfig`] for a limit of fewer than
+/// [`MIN_MEMORY_PAGES`] pages
+pub(crate) fn page_aligned_memory(limit: MemorySize, page_size: u64) -> Result<MemorySize> {
+    let bytes = limit.as_bytes();
+    let minimum = MemorySize::from_bytes(MIN_MEMORY_PAGES * page_size);
+    if limit < minimum {
+        return Err(Error::InvalidConfig {
+            message: format!(
+                "Memory limit {limit} is below the minimum of {minimum} \
+                 ({MIN_MEMORY_PAGES} pages); the container would be OOM-killed at once"
+            ),
+        });
+    }
+
+    let aligned =
+        MemorySize::from_bytes(bytes.checked_next_multiple_of(page_size).unwrap_or(bytes));
+    if aligned != limit {
+        tracing::debug!(
+            requested = bytes,
+            effective = aligned.as_bytes(),
+            page_size,
+            "Rounded memory limit up to whole pages"
+        );
+    }
+    Ok(aligned)
+}
+
+/// Parse the contents of `io.max`, one device per line
+fn parse_io_max(content: &str) -> Result<Vec<IoLimit>> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(IoLimit::from_io_max_line)
+        .collect()
+}
+
+/// Check whether an I/O error means the filesystem is read-only
+pub(crate) fn is_read_only_error(e: &std::io::Error) -> bool {
+    e.raw_os_error() == Some(libc::EROFS)
+}
+
+/// [`descendants_depth_first`] on a blocking thread, for async callers
+async fn descendants(path: &Path) -> Vec<PathBuf> {
+    let root = path.to_path_buf();
+    tokio::task::spawn_blocking(move || descendants_depth_first(&root))
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!(path = %path.display(), error = %e, "Could not list nested cgroups");
+            vec![path.to_path_buf()]
+        })
+}
+
+/// List `path` and every cgroup below it, children before parents
+fn descendants_depth_first(path: &Path) -> Vec<PathBuf> {
+    let mut cgroups = Vec::new();
+
+    if let Ok(entries) = std::fs::read_dir(path) {
+        for entry in entries.flatten() {
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                cgroups.extend(descendants_depth_first(&entry.path()));
             }
         }
     }
 
-    /// Remove the cgroup directory
-    async fn remove_cgroup_directory(&self) {
-        match fs::remove_dir(&self.path).await {
-            Ok(()) => {
-                tracing::info!(
-                    container_id = %self.container_id,
-                    path = %self.path.display(),
-                    "CGroup removed"
-                );
-            }
-            Err(e) => {
-                tracing::warn!(
-                    container_id = %self.container_id,
-                    path = %self.path.display(),
-                    error = %e,
-                    "Failed to remove cgroup directory (may already be removed)"
-                );
+    cgroups.push(path.to_path_buf());
+    cgroups
+}
+
+/// Read the PIDs listed in a cgroup's `cgroup.procs`
+async fn read_pids(cgroup: &Path) -> Vec<ProcessId> {
+    match read_id_list(&cgroup.join("cgroup.procs")).await {
+        Ok(pids) => pids,
+        Err(e) => {
+            tracing::debug!(
+                path = %cgroup.display(),
+                error = %e,
+                "Could not read process list"
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Read a newline-separated list of PIDs or TIDs
+async fn read_id_list(file: &Path) -> Result<Vec<ProcessId>> {
+    let content = fs::read_to_string(file).await.map_err(|e| Error::CGroup {
+        message: format!("Failed to read {}: {e}", file.display()),
+    })?;
+    Ok(parse_id_list(&content))
+}
+
+/// Parse the contents of `cgroup.procs` or `cgroup.threads`, skipping blank
+/// or malformed lines
+///
+/// The kernel lists processes outside the reader's PID namespace as `0`;
+/// those are skipped too, since signalling PID 0 would hit our own
+/// process group.
+fn parse_id_list(content: &str) -> Vec<ProcessId> {
+    content
+        .lines()
+        .filter_map(|line| line.trim().parse().ok())
+        .filter(|&pid: &i32| pid > 0)
+        .map(ProcessId::from_raw)
+        .collect()
+}
+
+/// Path of the cgroup v2 entry (`0::<path>`) in `/proc/<pid>/cgroup`
+///
+/// Hybrid hosts list v1 hierarchies as well; those lines are ignored.
+fn unified_cgroup_path(content: &str) -> Option<&str> {
+    content.lines().find_map(|line| line.strip_prefix("0::"))
+}
+
+/// Look up one key of a flat keyed file
+fn keyed_value(content: &str, key: &str) -> Option<u64> {
+    parse_flat_keyed(content)
+        .into_iter()
+        .find_map(|(k, value)| (k == key).then_some(value))
+}
+
+/// Parse a flat keyed file such as `cpu.stat` or `misc.current`
+///
+/// Each line is `<key> <value>`; lines that don't match are skipped.
+fn parse_flat_keyed(content: &str) -> Vec<(String, u64)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let key = parts.next()?;
+            let value = parts.next()?.parse().ok()?;
+            parts.next().is_none().then(|| (key.to_string(), value))
+        })
+        .collect()
+}
+
+/// The fields of `cpu.stat` that go into [`ResourceStats`]
+#[derive(Debug, Default, PartialEq, Eq)]
+struct CpuStat {
+    usage: Duration,
+    throttled: Duration,
+    nr_periods: u64,
+    nr_throttled: u64,
+}
+
+/// CPU usage and throttling from `cpu.stat`
+///
+/// Missing keys read as zero: without the `cpu` controller the file only
+/// has the usage fields.
+fn parse_cpu_stat(content: &str) -> CpuStat {
+    let count = |key| keyed_value(content, key).unwrap_or(0);
+    CpuStat {
+        usage: Duration::from_micros(count("usage_usec")),
+        throttled: Duration::from_micros(count("throttled_usec")),
+        nr_periods: count("nr_periods"),
+        nr_throttled: count("nr_throttled"),
+    }
+}
+
+/// Sum `rbytes` and `wbytes` over all devices in `io.stat`
+///
+/// Each line is `<major:minor> key=value ...`.
+fn parse_io_stat(content: &str) -> (u64, u64) {
+    let mut total_read = 0u64;
+    let mut total_write = 0u64;
+
+    for line in content.lines() {
+        for (key, value) in line
+            .split_whitespace()
+            .skip(1)
+            .filter_map(|p| p.split_once('='))
+        {
+            let value = value.parse::<u64>().unwrap_or(0);
+            match key {
+                "rbytes" => total_read += value,
+                "wbytes" => total_write += value,
+                _ => {}
             }
         }
     }
+
+    (total_read, total_write)
 }
 
-/// Implement ResourceBackend trait for CGroupController
-#[async_trait]
-impl ResourceBackend for CGroupController {
-    async fn set_cpu_limit(&self, limit: CpuLimit) -> Result<()> {
-        let (quota, period) = limit.cores.to_quota();
+/// Page size of a `hugetlb.<size>.current` file name, e.g. `2MB`
+fn hugetlb_page_size(file_name: &str) -> Option<&str> {
+    file_name
+        .strip_prefix("hugetlb.")?
+        .strip_suffix(".current")
+        // Skips the reservation counters (`hugetlb.2MB.rsvd.current`)
+        .filter(|size| !size.contains('.'))
+}
 
-        let cpu_max_file = self.path.join("cpu.max");
-        let content = format!("{quota} {period}");
+/// Blocking counterpart of `remove_cgroup_directory` for the `Drop` fallback
+fn remove_dir_blocking(cgroup: &Path, timeout: Duration) {
+    let mut backoff = REMOVE_BACKOFF_START;
+    let mut waited = Duration::ZERO;
 
-        fs::write(&cpu_max_file, content).await.map_err(|e| {
-            tracing::error!(
-                container_id = %self.container_id,
-                error = %e,
-                "Failed to set CPU limit"
-            );
-            Error::CGroup {
-                message: format!("Failed to set CPU limit: {e}"),
-            }
-        })?;
+    while let Err(e) = std::fs::remove_dir(cgroup) {
+        let remaining = timeout.saturating_sub(waited);
+        if !is_busy_error(&e) || remaining.is_zero() {
+            return;
+        }
+        let pause = backoff.min(remaining);
+        std::thread::sleep(pause);
+        waited += pause;
+        backoff *= 2;
+    }
+}
 
-        tracing::info!(
-            container_id = %self.container_id,
-            cores = limit.cores.as_f64(),
-            quota,
-            period,
-            "Set CPU limit"
-        );
+/// Check whether a failed `rmdir` may succeed once the kernel catches up
+fn is_busy_error(e: &std::io::Error) -> bool {
+    matches!(e.raw_os_error(), Some(libc::EBUSY | libc::ENOTEMPTY))
+}
 
-        Ok(())
-    }
+/// Check whether an I/O error is worth retrying
+fn is_transient_error(e: &std::io::Error) -> bool {
+    matches!(e.raw_os_error(), Some(libc::EBUSY | libc::EAGAIN))
+}
 
-    async fn set_memory_limit(&self, limit: MemoryLimit) -> Result<()> {
-        // Set memory limit
-        let memory_max_file = self.path.join("memory.max");
-        let limit_bytes = limit.limit.as_bytes().to_string();
+/// Parse a controller list such as `cgroup.controllers`
+///
+/// `cgroup.subtree_control` may be passed as well; the `+` some tools
+/// write in front of names is dropped.
+#[must_use]
+pub fn parse_controllers(content: &str) -> Vec<String> {
+    content
+        .split_whitespace()
+        .map(|c| c.trim_start_matches('+').to_string())
+        .collect()
+}
 
-        fs::write(&memory_max_file, &limit_bytes)
-            .await
-            .map_err(|e| {
-                tracing::error!(
-                    container_id = %self.container_id,
-                    error = %e,
-                    "Failed to set memory limit"
-                );
-                Error::CGroup {
-                    message: format!("Failed to set memory limit: {e}"),
-                }
-            })?;
+/// Controllers in [`REQUIRED_CONTROLLERS`] that `available` lacks
+#[must_use]
+pub fn missing_controllers(available: &[String]) -> Vec<&'static str> {
+    REQUIRED_CONTROLLERS
+        .iter()
+        .copied()
+        .filter(|required| !available.iter().any(|c| c == required))
+        .collect()
+}
 
-        // Set swap limit if specified
-        if let Some(swap) = limit.swap {
-            let swap_max_file = self.path.join("memory.swap.max");
-            let swap_bytes = swap.as_bytes().to_string();
+/// Check whether a space-separated controller list contains `name`
+///
+/// Matches whole words so that e.g. `cpuset` does not satisfy `cpu`.
+fn has_controller(list: &str, name: &str) -> bool {
+    list.split_whitespace()
+        .any(|c| c.trim_start_matches('+') == name)
+}
 
-            fs::write(&swap_max_file, &swap_bytes).await.map_err(|e| {
-                tracing::error!(
-                    container_id = %self.container_id,
-                    error = %e,
-                    "Failed to set swap limit"
-                );
-                Error::CGroup {
-                    message: format!("Failed to set swap limit: {e}"),
-                }
-            })?;
+impl Drop for CGroupController {
+    fn drop(&mut self) {
+        if !*self.active.get_mut() {
+            return;
+        }
 
-            tracing::info!(
+        if DROP_FALLBACK_WARNED.swap(true, Ordering::Relaxed) {
+            tracing::debug!(
                 container_id = %self.container_id,
-                memory = %limit.limit,
-                swap = %swap,
-                "Set memory and swap limits"
+                "CGroup not explicitly cleaned up, using Drop fallback"
             );
         } else {
-            tracing::info!(
+            tracing::warn!(
                 container_id = %self.container_id,
-                memory = %limit.limit,
-                "Set memory limit"
+                "CGroup not explicitly cleaned up, using Drop fallback; \
+                 call controller.cleanup().await before dropping"
             );
         }
 
-        Ok(())
-    }
+        // Synchronous cleanup (best effort), including nested cgroups
+        let cgroups = descendants_depth_first(&self.path);
+        let root_procs = self.config.root().join("cgroup.procs");
+        for cgroup in &cgroups {
+            if let Ok(content) = std::fs::read_to_string(cgroup.join("cgroup.procs")) {
+                for pid in parse_id_list(&content) {
+                    let _ = std::fs::write(&root_procs, pid.to_string());
+                }
+            }
+        }
 
-    async fn add_process(&self, pid: ProcessId) -> Result<()> {
-        let procs_file = self.path.join("cgroup.procs");
-        let pid_str = pid.as_raw().to_string();
+        if self.keep_on_exit {
+            *self.active.get_mut() = false;
+            return;
+        }
 
-        fs::write(&procs_file, pid_str.as_bytes())
-            .await
-            .map_err(|e| {
-                tracing::error!(
-                    container_id = %self.container_id,
-                    pid = pid.as_raw(),
-                    error = %e,
-                    "Failed to add process"
-                );
-                Error::CGroup {
-                    message: format!("Failed to add process {pid}: {e}"),
-                }
-            })?;
+        // Retrying would sleep and stall an async worker thread
+        let timeout = if tokio::runtime::Handle::try_current().is_ok() {
+            Duration::ZERO
+        } else {
+            self.config.cleanup_timeout
+        };
+        for cgroup in &cgroups {
+            remove_dir_blocking(cgroup, timeout);
+        }
 
-        tracing::debug!(
-            container_id = %self.container_id,
-            pid = pid.as_raw(),
-            "Added process to cgroup"
-        );
+        *self.active.get_mut() = false;
+    }
+}
 
-        Ok(())
+impl std::fmt::Debug for CGroupController {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CGroupController")
+            .field("container_id", &self.container_id)
+            .field("config", &self.config)
+            .field("path", &self.path)
+            .field("active", &self.is_active())
+            .field("keep_on_exit", &self.keep_on_exit)
+            .finish()
     }
+}
 
-    async fn stats(&self) -> Result<ResourceStats> {
-        let cpu_stats = self.read_cpu_stats().await?;
-        let memory_stats = self.read_memory_stats().await?;
-        let io_stats = self.read_io_stats().await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        Ok(ResourceStats {
-            cpu_usage: cpu_stats.0,
-            cpu_throttled: cpu_stats.1,
-            memory_current: memory_stats.0,
-            memory_peak: memory_stats.1,
-            swap_current: memory_stats.2,
-            swap_peak: memory_stats.3,
-            io_read_bytes: io_stats.0,
-            io_write_bytes: io_stats.1,
-        })
+    #[test]
+    fn test_parse_id_list_skips_invisible_pids() {
+        let pids: Vec<i32> = parse_id_list("42\n0\n\nnot-a-pid\n7\n")
+            .iter()
+            .map(|pid| pid.as_raw())
+            .collect();
+        assert_eq!(pids, vec![42, 7]);
     }
 
-    async fn cleanup(&self) -> Result<()> {
-        tracing::warn!(
-            "cleanup() called through trait interface - use controller.cleanup() directly for mutable access"
+    #[test]
+    fn test_unified_cgroup_path() {
+        assert_eq!(
+            unified_cgroup_path("0::/system.slice/sshd.service\n"),
+            Some("/system.slice/sshd.service")
         );
-        Ok(())
+        // Hybrid layout: v1 hierarchies first, the unified one last
+        let hybrid = "12:memory:/user.slice\n1:name=systemd:/user.slice\n0::/user.slice\n";
+        assert_eq!(unified_cgroup_path(hybrid), Some("/user.slice"));
+        assert_eq!(unified_cgroup_path("4:cpu,cpuacct:/\n3:memory:/\n"), None);
     }
-}
 
-impl CGroupController {
-    async fn read_cpu_stats(&self) -> Result<(Duration, Duration)> {
-        let cpu_stat_file = self.path.join("cpu.stat");
+    #[test]
+    fn test_attach_pid_of_missing_process() {
+        assert!(matches!(
+            CGroupController::attach_pid(ProcessId::from_raw(i32::MAX), CGroupConfig::new()),
+            Err(Error::ProcessNotFound { .. })
+        ));
+    }
 
-        let content = fs::read_to_string(&cpu_stat_file)
-            .await
-            .map_err(|e| Error::CGroup {
-                message: format!("Failed to read cpu.stat: {e}"),
-            })?;
+    #[test]
+    fn test_keyed_value_reads_cgroup_stat() {
+        let stat = "nr_descendants 3\nnr_subsys_cpu 4\nnr_dying_descendants 12\n";
+        assert_eq!(keyed_value(stat, "nr_descendants"), Some(3));
+        assert_eq!(keyed_value(stat, "nr_dying_descendants"), Some(12));
+        // Prefix of another key, and a key older kernels lack
+        assert_eq!(keyed_value(stat, "nr_subsys"), None);
+        assert_eq!(keyed_value("garbage\n", "nr_descendants"), None);
+    }
 
-        let mut usage_usec = 0u64;
-        let mut throttled_usec = 0u64;
+    #[test]
+    fn test_parse_controllers() {
+        assert_eq!(
+            parse_controllers("cpuset cpu io memory hugetlb pids\n"),
+            vec!["cpuset", "cpu", "io", "memory", "hugetlb", "pids"]
+        );
+        assert_eq!(parse_controllers("+cpu +memory"), vec!["cpu", "memory"]);
+        assert!(parse_controllers("\n").is_empty());
+    }
 
-        for line in content.lines() {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() != 2 {
-                continue;
-            }
+    #[test]
+    fn test_missing_controllers() {
+        let all = parse_controllers("cpuset cpu io memory pids");
+        assert!(missing_controllers(&all).is_empty());
+        // `cpuset` must not count as `cpu`
+        let partial = parse_controllers("cpuset memory");
+        assert_eq!(missing_controllers(&partial), vec!["cpu", "io"]);
+    }
 
-            match parts[0] {
-                "usage_usec" => {
-                    usage_usec = parts[1].parse().unwrap_or(0);
-                }
-                "throttled_usec" => {
-                    throttled_usec = parts[1].parse().unwrap_or(0);
-                }
-                _ => {}
-            }
-        }
+    #[test]
+    fn test_root_controllers() {
+        let root = std::env::temp_dir().join(format!("vortex-controllers-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        // No `cgroup.controllers` as on cgroup v1
+        assert!(CGroupController::root_controllers(&root).is_err());
+
+        std::fs::write(root.join("cgroup.controllers"), "cpu memory pids\n").unwrap();
+        assert_eq!(
+            CGroupController::root_controllers(&root).unwrap(),
+            vec!["cpu", "memory", "pids"]
+        );
+        assert!(!CGroupController::required_controllers_present(&root).unwrap());
 
-        Ok((
-            Duration::from_micros(usage_usec),
-            Duration::from_micros(throttled_usec),
-        ))
+        std::fs::write(root.join("cgroup.controllers"), "cpu io memory\n").unwrap();
+        assert!(CGroupController::required_controllers_present(&root).unwrap());
+        std::fs::remove_dir_all(&root).unwrap();
     }
 
-    async fn read_memory_stats(&self) -> Result<(MemorySize, MemorySize, MemorySize, MemorySize)> {
-        let current = self.read_single_value("memory.current").await?;
-        let peak = self.read_single_value("memory.peak").await?;
-        let swap_current = self
-            .read_single_value("memory.swap.current")
-            .await
-            .unwrap_or(0);
-        let swap_peak = self
-            .read_single_value("memory.swap.peak")
-            .await
-            .unwrap_or(0);
+    #[test]
+    fn test_check_delegated() {
+        let slice = std::env::temp_dir()
+            .join(format!("vortex-delegated-{}", std::process::id()))
+            .join("vortex.slice");
+        std::fs::create_dir_all(&slice).unwrap();
+        // A plain directory is no cgroup
+        assert!(matches!(
+            CGroupController::check_delegated(&slice),
+            Err(Error::InvalidConfig { .. })
+        ));
+
+        std::fs::write(slice.join("cgroup.controllers"), "cpu memory pids\n").unwrap();
+        std::fs::write(slice.join("cgroup.subtree_control"), "").unwrap();
+        let Err(Error::CGroup { message }) = CGroupController::check_delegated(&slice) else {
+            panic!("a slice without io should be refused");
+        };
+        assert!(message.ends_with(": io"), "{message}");
 
-        Ok((
-            MemorySize::from_bytes(current),
-            MemorySize::from_bytes(peak),
-            MemorySize::from_bytes(swap_current),
-            MemorySize::from_bytes(swap_peak),
-        ))
+        std::fs::write(slice.join("cgroup.controllers"), "cpu io memory pids\n").unwrap();
+        CGroupController::check_delegated(&slice).unwrap();
+        std::fs::remove_dir_all(slice.parent().unwrap()).unwrap();
     }
 
-    async fn read_io_stats(&self) -> Result<(u64, u64)> {
-        let io_stat_file = self.path.join("io.stat");
+    #[test]
+    fn test_container_path_stays_under_vortex_root() {
+        let root = std::env::temp_dir().join(format!("vortex-escape-{}", std::process::id()));
+        let id = ContainerId::new("web").unwrap();
+        let config = CGroupConfig::new().with_root(&root);
+        assert_eq!(
+            container_path(&config, &id).unwrap(),
+            root.join("vortex").join("web")
+        );
 
-        let content = fs::read_to_string(&io_stat_file).await.unwrap_or_default();
+        // A pod name is joined in as is
+        for parent in ["../../etc", "/etc", "pod/.."] {
+            let config = CGroupConfig::new().with_root(&root).with_parent(parent);
+            let Err(Error::InvalidConfig { message }) = container_path(&config, &id) else {
+                panic!("pod {parent} should be refused");
+            };
+            assert!(message.contains("is not under"), "{message}");
+        }
 
-        let mut total_read = 0u64;
-        let mut total_write = 0u64;
+        // A symlinked pod that points out of the vortex root
+        let outside = root.join("outside");
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::create_dir_all(root.join("vortex")).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("vortex/pod")).unwrap();
+        let config = CGroupConfig::new().with_root(&root).with_parent("pod");
+        assert!(container_path(&config, &id).is_err());
+        assert!(CGroupController::attach_with_config(id.clone(), config).is_err());
 
-        for line in content.lines() {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() < 2 {
-                continue;
-            }
+        let config = CGroupConfig::new().with_root(&root).with_parent("pod2");
+        container_path(&config, &id).unwrap();
 
-            for part in &parts[1..] {
-                if let Some((key, value)) = part.split_once('=') {
-                    match key {
-                        "rbytes" => {
-                            total_read += value.parse::<u64>().unwrap_or(0);
-                        }
-                        "wbytes" => {
-                            total_write += value.parse::<u64>().unwrap_or(0);
-                        }
-                        _ => {}
-                    }
-                }
-            }
-        }
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_threaded_needs_a_suitable_parent() {
+        let root = std::env::temp_dir().join(format!("vortex-threaded-{}", std::process::id()));
+        let config = CGroupConfig::new().with_root(&root);
+        let parent = config.container_parent();
+        std::fs::create_dir_all(parent.join("pool")).unwrap();
+        let controller =
+            CGroupController::attach_with_config(ContainerId::new("pool").unwrap(), config)
+                .unwrap();
+
+        std::fs::write(parent.join("cgroup.type"), "domain\n").unwrap();
+        std::fs::write(parent.join("cgroup.subtree_control"), "cpu memory io\n").unwrap();
+        let Err(Error::CGroup { message }) = controller.set_threaded().await else {
+            panic!("a parent with memory enabled should be refused");
+        };
+        assert!(message.contains("memory, io"), "{message}");
+
+        std::fs::write(parent.join("cgroup.type"), "domain invalid\n").unwrap();
+        std::fs::write(parent.join("cgroup.subtree_control"), "cpu pids\n").unwrap();
+        assert!(controller.set_threaded().await.is_err());
+
+        // With a fit parent the mode is written (a plain directory takes it)
+        std::fs::write(parent.join("cgroup.type"), "domain\n").unwrap();
+        controller.set_threaded().await.unwrap();
 
-        Ok((total_read, total_write))
+        std::fs::remove_dir_all(&root).unwrap();
     }
 
-    async fn read_single_value(&self, filename: &str) -> Result<u64> {
-        let file = self.path.join(filename);
-        let content = fs::read_to_string(&file).await.map_err(|e| Error::CGroup {
-            message: format!("Failed to read {filename}: {e}"),
-        })?;
+    #[tokio::test]
+    async fn test_apply_limits_in_one_batch() {
+        let root = std::env::temp_dir().join(format!("vortex-batch-{}", std::process::id()));
+        let config = CGroupConfig::new().with_root(&root);
+        let path = config.container_parent().join("batch");
+        std::fs::create_dir_all(&path).unwrap();
+        let controller =
+            CGroupController::attach_with_config(ContainerId::new("batch").unwrap(), config)
+                .unwrap();
+
+        let limits = ResourceLimits {
+            cpu: Some(CpuLimit::new(CpuCores::new(1.5))),
+            memory: Some(MemoryLimit::with_swap(
+                MemorySize::from_mb(64),
+                MemorySize::from_mb(0),
+            )),
+            pids: Some(32),
+            io: vec![IoLimit {
+                read_bps: Some(4096),
+                ..IoLimit::new(8, 0)
+            }],
+            ..ResourceLimits::default()
+        };
+        controller.apply_limits(&limits).await.unwrap();
+        let read = |file: &str| std::fs::read_to_string(path.join(file)).unwrap();
+        assert_eq!(read("cpu.max"), "150000 100000");
+        assert_eq!(read("memory.max"), "67108864");
+        assert_eq!(read("memory.swap.max"), "0");
+        assert_eq!(read("pids.max"), "32");
+        assert_eq!(read("io.max"), limits.io[0].to_string());
+
+        // A failure names the limits set before it; swap is part of memory
+        std::fs::remove_file(path.join("memory.swap.max")).unwrap();
+        std::fs::create_dir(path.join("memory.swap.max")).unwrap();
+        let Err(Error::LimitsPartiallyApplied {
+            applied, failed, ..
+        }) = controller.apply_limits(&limits).await
+        else {
+            panic!("writing memory.swap.max should fail");
+        };
+        assert_eq!(applied, vec!["pids", "CPU", "IO"]);
+        assert_eq!(failed, "memory");
 
-        content.trim().parse().map_err(|e| Error::CGroup {
-            message: format!("Failed to parse {filename} value: {e}"),
-        })
+        std::fs::remove_dir_all(&root).unwrap();
     }
-}
 
-impl Drop for CGroupController {
-    fn drop(&mut self) {
-        if !self.active {
-            return;
+    #[tokio::test]
+    async fn test_reset_peaks_needs_writable_peak_files() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("vortex-peak-{}", std::process::id()));
+        let config = CGroupConfig::new().with_root(&root);
+        let path = config.container_parent().join("bench");
+        std::fs::create_dir_all(&path).unwrap();
+        let controller =
+            CGroupController::attach_with_config(ContainerId::new("bench").unwrap(), config)
+                .unwrap();
+        let peak = path.join("memory.peak");
+        let read_only = std::fs::Permissions::from_mode(0o444);
+
+        // Missing before 5.19, read-only before 6.12
+        assert!(matches!(
+            controller.reset_peaks().await,
+            Err(Error::CGroup { .. })
+        ));
+        std::fs::write(&peak, "8192\n").unwrap();
+        std::fs::set_permissions(&peak, read_only.clone()).unwrap();
+        let Err(Error::CGroup { message }) = controller.reset_peaks().await else {
+            panic!("a read-only memory.peak should be refused");
+        };
+        assert!(message.contains("6.12"), "{message}");
+
+        std::fs::set_permissions(&peak, std::fs::Permissions::from_mode(0o644)).unwrap();
+        std::fs::write(path.join("memory.swap.peak"), "0\n").unwrap();
+        std::fs::set_permissions(path.join("memory.swap.peak"), read_only).unwrap();
+        let reset = controller.reset_peaks().await.unwrap();
+        assert_eq!(std::fs::read_to_string(&peak).unwrap(), "reset\n");
+        // The kernel would now report usage since the reset
+        std::fs::write(&peak, "4096\n").unwrap();
+        assert_eq!(reset.memory().unwrap(), MemorySize::from_bytes(4096));
+        assert_eq!(reset.swap().unwrap(), None);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_and_restore_limits() {
+        let root = std::env::temp_dir().join(format!("vortex-snapshot-{}", std::process::id()));
+        let config = CGroupConfig::new().with_root(&root);
+        let path = config.container_parent().join("snap");
+        std::fs::create_dir_all(&path).unwrap();
+        let controller =
+            CGroupController::attach_with_config(ContainerId::new("snap").unwrap(), config)
+                .unwrap();
+        // No controllers enabled, nothing to read
+        assert!(controller.snapshot_limits().await.unwrap().is_empty());
+
+        for (file, content) in [
+            ("cpu.max", "50000 100000\n"),
+            ("cpu.weight", "100\n"),
+            ("memory.max", "268435456\n"),
+            ("memory.swap.max", "max\n"),
+            ("pids.max", "max\n"),
+            ("io.max", "8:0 rbps=1048576 wbps=max riops=max wiops=max\n"),
+        ] {
+            std::fs::write(path.join(file), content).unwrap();
         }
 
-        tracing::warn!(
-            container_id = %self.container_id,
-            "CGroup not explicitly cleaned up, using Drop fallback"
+        let snapshot = controller.snapshot_limits().await.unwrap();
+        assert_eq!(snapshot.cpu, Some(CpuLimit::new(CpuCores::new(0.5))));
+        assert_eq!(snapshot.cpu_weight, Some(100));
+        assert_eq!(
+            snapshot.memory,
+            Some(MemoryLimit::new(MemorySize::from_mb(256)))
+        );
+        assert_eq!(snapshot.pids, None);
+        assert_eq!(
+            snapshot.io,
+            vec![IoLimit {
+                read_bps: Some(1024 * 1024),
+                ..IoLimit::new(8, 0)
+            }]
         );
 
-        // Synchronous cleanup (best effort)
-        let procs_file = self.path.join("cgroup.procs");
-        if let Ok(pids_str) = std::fs::read_to_string(&procs_file) {
-            let root_procs = Path::new(CGROUP_ROOT).join("cgroup.procs");
-            for line in pids_str.lines() {
-                if let Ok(pid) = line.trim().parse::<i32>() {
-                    let _ = std::fs::write(&root_procs, pid.to_string());
-                }
+        let boost = ResourceLimits {
+            cpu: Some(CpuLimit::new(CpuCores::new(2.0))),
+            memory: Some(MemoryLimit::new(MemorySize::from_mb(1024))),
+            pids: Some(64),
+            ..ResourceLimits::default()
+        };
+        controller.apply_limits(&boost).await.unwrap();
+        assert_ne!(controller.snapshot_limits().await.unwrap(), snapshot);
+
+        controller.restore_limits(&snapshot).await.unwrap();
+        assert_eq!(controller.snapshot_limits().await.unwrap(), snapshot);
+        assert_eq!(
+            std::fs::read_to_string(path.join("pids.max")).unwrap(),
+            "max"
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_parse_io_max() {
+        assert_eq!(parse_io_max("").unwrap(), Vec::new());
+        assert_eq!(
+            parse_io_max("8:0 rbps=max wbps=max riops=max wiops=10\n8:16 rbps=1\n")
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(parse_io_max("8:0 rbps=max\nbogus\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_flat_keyed() {
+        let misc = "sev 3\nsev_es 0\n\nbogus\ntoo many 1\n";
+        assert_eq!(
+            parse_flat_keyed(misc),
+            vec![("sev".to_string(), 3), ("sev_es".to_string(), 0)]
+        );
+
+        let cpu_stat = "usage_usec 1500\nuser_usec 1000\nthrottled_usec 20\n";
+        assert_eq!(parse_flat_keyed(cpu_stat).len(), 3);
+    }
+
+    #[test]
+    fn test_parse_cpu_stat() {
+        let cpu_stat = "usage_usec 1500\n\
+                        user_usec 1000\n\
+                        system_usec 500\n\
+                        core_sched.force_idle_usec 0\n\
+                        nr_periods 1000\n\
+                        nr_throttled 45\n\
+                        throttled_usec 20\n\
+                        nr_bursts 0\n\
+                        burst_usec 0\n";
+        assert_eq!(
+            parse_cpu_stat(cpu_stat),
+            CpuStat {
+                usage: Duration::from_micros(1500),
+                throttled: Duration::from_micros(20),
+                nr_periods: 1000,
+                nr_throttled: 45,
             }
-        }
+        );
+        // No cpu controller: usage only
+        assert_eq!(
+            parse_cpu_stat("usage_usec 7\nuser_usec 5\nsystem_usec 2\n"),
+            CpuStat {
+                usage: Duration::from_micros(7),
+                ..CpuStat::default()
+            }
+        );
+    }
 
-        std::thread::sleep(Duration::from_millis(KERNEL_CLEANUP_DELAY_MS));
-        let _ = std::fs::remove_dir(&self.path);
+    #[test]
+    fn test_parse_io_stat() {
+        let io_stat = "8:0 rbytes=1024 wbytes=2048 rios=3 wios=4\n\
+                       8:16 rbytes=1 wbytes=bad\n";
+        assert_eq!(parse_io_stat(io_stat), (1025, 2048));
+        assert_eq!(parse_io_stat(""), (0, 0));
+    }
+
+    #[test]
+    fn test_remove_dir_blocking_gives_up_on_busy_dir() {
+        let dir = std::env::temp_dir().join(format!("vortex-rmdir-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("child")).unwrap();
 
-        self.active = false;
+        // ENOTEMPTY is retried until the timeout runs out
+        let start = std::time::Instant::now();
+        remove_dir_blocking(&dir, Duration::from_millis(20));
+        assert!(dir.exists());
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        std::fs::remove_dir(dir.join("child")).unwrap();
+        remove_dir_blocking(&dir, Duration::ZERO);
+        assert!(!dir.exists());
     }
-}
 
-impl std::fmt::Debug for CGroupController {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("CGroupController")
-            .field("container_id", &self.container_id)
-            .field("path", &self.path)
-            .field("active", &self.active)
-            .finish()
+    #[test]
+    fn test_page_aligned_memory() {
+        let aligned = |bytes| page_aligned_memory(MemorySize::from_bytes(bytes), 4096);
+
+        // Whole pages are kept, anything else goes up to the next page
+        assert_eq!(aligned(16_384).unwrap(), MemorySize::from_bytes(16_384));
+        assert_eq!(aligned(16_385).unwrap(), MemorySize::from_bytes(20_480));
+        assert_eq!(
+            aligned(64 * 1024 * 1024 - 1).unwrap(),
+            MemorySize::from_mb(64)
+        );
+
+        // Fewer than MIN_MEMORY_PAGES pages would OOM at once
+        assert!(matches!(aligned(1), Err(Error::InvalidConfig { .. })));
+        assert!(matches!(aligned(16_383), Err(Error::InvalidConfig { .. })));
+
+        // No rounding past the largest value
+        assert_eq!(aligned(u64::MAX).unwrap(), MemorySize::from_bytes(u64::MAX));
+        assert!(page_size().is_power_of_two());
+    }
+
+    #[test]
+    fn test_hugetlb_page_size() {
+        assert_eq!(hugetlb_page_size("hugetlb.2MB.current"), Some("2MB"));
+        assert_eq!(hugetlb_page_size("hugetlb.1GB.current"), Some("1GB"));
+        assert_eq!(hugetlb_page_size("hugetlb.2MB.rsvd.current"), None);
+        assert_eq!(hugetlb_page_size("hugetlb.2MB.max"), None);
+        assert_eq!(hugetlb_page_size("memory.current"), None);
     }
 }
@@ -0,0 +1,548 @@
+//! Device access control through a `cgroup/dev` eBPF program
+//!
+//! cgroup v2 has no `devices.allow` file. The kernel instead asks an eBPF
+//! program of type `BPF_PROG_TYPE_CGROUP_DEVICE` attached to the cgroup,
+//! which sees the device type, its major and minor number and the access
+//! requested, and returns 1 to permit it or 0 to refuse. This module turns
+//! a list of [`DeviceRule`]s into such a program and attaches it with the
+//! `bpf(2)` system call, the way runc and crun do.
+//!
+//! Needs Linux 4.15 or later built with `CONFIG_CGROUP_BPF`, and
+//! `CAP_SYS_ADMIN` (or `CAP_BPF` with `CAP_NET_ADMIN` from 5.8 on) to load
+//! and attach the program.
+
+use std::fmt;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::path::Path;
+use std::str::FromStr;
+
+use vortex_core::{Error, Result};
+
+/// Kind of device a rule covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    /// Character devices such as `/dev/null`
+    Char,
+    /// Block devices such as disks
+    Block,
+    /// Both kinds
+    All,
+}
+
+/// Operations on a device a rule covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceAccess {
+    /// Opening for reading
+    pub read: bool,
+    /// Opening for writing
+    pub write: bool,
+    /// Creating a device node with `mknod`
+    pub mknod: bool,
+}
+
+impl DeviceAccess {
+    /// Reading, writing and `mknod`
+    pub const ALL: Self = Self {
+        read: true,
+        write: true,
+        mknod: true,
+    };
+
+    /// Only `mknod`; opening the node is still up to the other rules
+    pub const MKNOD: Self = Self {
+        read: false,
+        write: false,
+        mknod: true,
+    };
+
+    /// The `BPF_DEVCG_ACC_*` bits
+    const fn bits(self) -> i32 {
+        (self.mknod as i32) | (self.read as i32) << 1 | (self.write as i32) << 2
+    }
+}
+
+/// One rule of a device filter
+///
+/// As in `devices.allow` and an OCI `linux.resources.devices` list, later
+/// rules win over earlier ones, and access no rule matches is refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceRule {
+    /// Whether a matching access is permitted or refused
+    pub allow: bool,
+    /// Kind of device
+    pub kind: DeviceKind,
+    /// Major number, any if `None`
+    pub major: Option<u32>,
+    /// Minor number, any if `None`
+    pub minor: Option<u32>,
+    /// Operations the rule covers
+    pub access: DeviceAccess,
+}
+
+impl DeviceRule {
+    /// Permit `access` to the matching devices
+    #[must_use]
+    pub const fn allow(
+        kind: DeviceKind,
+        major: Option<u32>,
+        minor: Option<u32>,
+        access: DeviceAccess,
+    ) -> Self {
+        Self {
+            allow: true,
+            kind,
+            major,
+            minor,
+            access,
+        }
+    }
+
+    /// Refuse `access` to the matching devices
+    #[must_use]
+    pub const fn deny(
+        kind: DeviceKind,
+        major: Option<u32>,
+        minor: Option<u32>,
+        access: DeviceAccess,
+    ) -> Self {
+        Self {
+            allow: false,
+            ..Self::allow(kind, major, minor, access)
+        }
+    }
+
+    /// The devices every container may use
+    ///
+    /// `null`, `zero`, `full`, `random`, `urandom`, `tty`, `ptmx` and the
+    /// pseudo-terminals under `/dev/pts`, plus `mknod` of any node, which
+    /// grants no access by itself. Block devices stay out of reach.
+    #[must_use]
+    pub fn defaults() -> Vec<Self> {
+        let char_dev =
+            |major, minor| Self::allow(DeviceKind::Char, Some(major), minor, DeviceAccess::ALL);
+        vec![
+            Self::allow(DeviceKind::Char, None, None, DeviceAccess::MKNOD),
+            Self::allow(DeviceKind::Block, None, None, DeviceAccess::MKNOD),
+            char_dev(1, Some(3)),
+            char_dev(1, Some(5)),
+            char_dev(1, Some(7)),
+            char_dev(1, Some(8)),
+            char_dev(1, Some(9)),
+            char_dev(5, Some(0)),
+            char_dev(5, Some(2)),
+            char_dev(136, None),
+        ]
+    }
+}
+
+/// Formats like `devices.allow`, e.g. `c 1:3 rwm`, with `!` before a deny
+/// rule
+impl fmt::Display for DeviceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let number = |n: Option<u32>| n.map_or_else(|| "*".to_string(), |n| n.to_string());
+        let kind = match self.kind {
+            DeviceKind::Char => 'c',
+            DeviceKind::Block => 'b',
+            DeviceKind::All => 'a',
+        };
+        let mut access = String::new();
+        for (set, c) in [
+            (self.access.read, 'r'),
+            (self.access.write, 'w'),
+            (self.access.mknod, 'm'),
+        ] {
+            if set {
+                access.push(c);
+            }
+        }
+        write!(
+            f,
+            "{}{kind} {}:{} {access}",
+            if self.allow { "" } else { "!" },
+            number(self.major),
+            number(self.minor)
+        )
+    }
+}
+
+/// Parse `[!]TYPE MAJOR:MINOR ACCESS` as written by [`DeviceRule`]'s
+/// `Display`, e.g. `c 1:3 rw`, `!b *:* rwm` or `a *:* m`
+impl FromStr for DeviceRule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |why: &str| Error::InvalidConfig {
+            message: format!("Invalid device rule '{s}': {why}"),
+        };
+
+        let rule = s.trim();
+        let (allow, rule) = rule
+            .strip_prefix('!')
+            .map_or((true, rule), |rule| (false, rule));
+        let mut fields = rule.split_whitespace();
+        let (Some(kind), Some(numbers), Some(access), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(invalid("expected TYPE MAJOR:MINOR ACCESS"));
+        };
+
+        let kind = match kind {
+            "c" => DeviceKind::Char,
+            "b" => DeviceKind::Block,
+            "a" => DeviceKind::All,
+            _ => return Err(invalid("type must be c, b or a")),
+        };
+        let number = |n: &str| match n {
+            "*" => Ok(None),
+            n => n
+                .parse()
+                .map(Some)
+                .map_err(|_| invalid("numbers must be integers or *")),
+        };
+        let (major, minor) = numbers
+            .split_once(':')
+            .ok_or_else(|| invalid("expected MAJOR:MINOR"))?;
+
+        if access.is_empty() || !access.chars().all(|c| "rwm".contains(c)) {
+            return Err(invalid("access must be made of r, w and m"));
+        }
+        let access = DeviceAccess {
+            read: access.contains('r'),
+            write: access.contains('w'),
+            mknod: access.contains('m'),
+        };
+
+        Ok(Self {
+            allow,
+            kind,
+            major: number(major)?,
+            minor: number(minor)?,
+            access,
+        })
+    }
+}
+
+/// One eBPF instruction, laid out as the kernel's `struct bpf_insn`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Insn {
+    code: u8,
+    /// Destination register in the low nibble, source in the high one
+    regs: u8,
+    off: i16,
+    imm: i32,
+}
+
+// Opcodes used by the filter
+const LDX_W: u8 = 0x61;
+const MOV32_REG: u8 = 0xbc;
+const AND32_IMM: u8 = 0x54;
+const RSH32_IMM: u8 = 0x74;
+const MOV64_IMM: u8 = 0xb7;
+const JEQ_IMM: u8 = 0x15;
+const JNE_IMM: u8 = 0x55;
+const JNE_REG: u8 = 0x5d;
+const EXIT: u8 = 0x95;
+
+// Registers: r1 holds the context on entry and is scratch afterwards
+const R0: u8 = 0;
+const R1: u8 = 1;
+const DEV_TYPE: u8 = 2;
+const ACCESS: u8 = 3;
+const MAJOR: u8 = 4;
+const MINOR: u8 = 5;
+
+// Values of `struct bpf_cgroup_dev_ctx`
+const DEV_BLOCK: i32 = 1;
+const DEV_CHAR: i32 = 2;
+
+const fn insn(code: u8, dst: u8, src: u8, off: i16, imm: i32) -> Insn {
+    Insn {
+        code,
+        regs: src << 4 | dst,
+        off,
+        imm,
+    }
+}
+
+/// Assemble the filter for `rules`
+///
+/// The context is `{ u32 access_type; u32 major; u32 minor; }`, with the
+/// device type in the low 16 bits of `access_type` and the access in the
+/// high ones. Rules are checked last first, and the first that matches
+/// decides; each one is a block of tests that jump past the rest of the
+/// block when they fail.
+///
+/// A rule without tests, such as `a *:* rwm`, always decides, so nothing
+/// is emitted after it: the verifier rejects unreachable instructions.
+fn program(rules: &[DeviceRule]) -> Result<Vec<Insn>> {
+    let mut program = vec![
+        insn(LDX_W, DEV_TYPE, R1, 0, 0),
+        insn(AND32_IMM, DEV_TYPE, 0, 0, 0xffff),
+        insn(LDX_W, ACCESS, R1, 0, 0),
+        insn(RSH32_IMM, ACCESS, 0, 0, 16),
+        insn(LDX_W, MAJOR, R1, 4, 0),
+        insn(LDX_W, MINOR, R1, 8, 0),
+    ];
+
+    for rule in rules.iter().rev() {
+        let number = |n: u32| {
+            i32::try_from(n).map_err(|_| Error::InvalidConfig {
+                message: format!("Device number {n} out of range in '{rule}'"),
+            })
+        };
+
+        // Tests with the offset to fix up once the block length is known
+        let mut block = Vec::new();
+        match rule.kind {
+            DeviceKind::Char => block.push(insn(JNE_IMM, DEV_TYPE, 0, 0, DEV_CHAR)),
+            DeviceKind::Block => block.push(insn(JNE_IMM, DEV_TYPE, 0, 0, DEV_BLOCK)),
+            DeviceKind::All => {}
+        }
+        let mask = rule.access.bits();
+        if mask != DeviceAccess::ALL.bits() {
+            block.push(insn(MOV32_REG, R1, ACCESS, 0, 0));
+            block.push(insn(AND32_IMM, R1, 0, 0, mask));
+            block.push(if rule.allow {
+                // Everything requested must be allowed
+                insn(JNE_REG, R1, ACCESS, 0, 0)
+            } else {
+                // Anything requested being denied is enough
+                insn(JEQ_IMM, R1, 0, 0, 0)
+            });
+        }
+        if let Some(major) = rule.major {
+            block.push(insn(JNE_IMM, MAJOR, 0, 0, number(major)?));
+        }
+        if let Some(minor) = rule.minor {
+            block.push(insn(JNE_IMM, MINOR, 0, 0, number(minor)?));
+        }
+        block.push(insn(MOV64_IMM, R0, 0, 0, i32::from(rule.allow)));
+        block.push(insn(EXIT, 0, 0, 0, 0));
+
+        let len = block.len();
+        for (i, insn) in block.iter_mut().enumerate() {
+            if matches!(insn.code, JNE_IMM | JNE_REG | JEQ_IMM) {
+                insn.off = i16::try_from(len - i - 1).unwrap_or(i16::MAX);
+            }
+        }
+        let unconditional = len == 2;
+        program.extend(block);
+        if unconditional {
+            return Ok(program);
+        }
+    }
+
+    // No rule matched
+    program.push(insn(MOV64_IMM, R0, 0, 0, 0));
+    program.push(insn(EXIT, 0, 0, 0, 0));
+    Ok(program)
+}
+
+// bpf(2) commands, program and attach types
+const BPF_PROG_LOAD: libc::c_long = 5;
+const BPF_PROG_ATTACH: libc::c_long = 8;
+const BPF_PROG_TYPE_CGROUP_DEVICE: u32 = 15;
+const BPF_CGROUP_DEVICE: u32 = 6;
+
+/// Leading fields of `union bpf_attr` for `BPF_PROG_LOAD`
+#[repr(C)]
+#[derive(Default)]
+struct ProgLoadAttr {
+    prog_type: u32,
+    insn_cnt: u32,
+    insns: u64,
+    license: u64,
+    log_level: u32,
+    log_size: u32,
+    log_buf: u64,
+    kern_version: u32,
+    prog_flags: u32,
+    prog_name: [u8; 16],
+}
+
+/// Leading fields of `union bpf_attr` for `BPF_PROG_ATTACH`
+#[repr(C)]
+#[derive(Default)]
+struct ProgAttachAttr {
+    target_fd: u32,
+    attach_bpf_fd: u32,
+    attach_type: u32,
+    attach_flags: u32,
+}
+
+fn bpf<T>(cmd: libc::c_long, attr: &mut T) -> std::io::Result<i32> {
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            cmd,
+            std::ptr::from_mut(attr).cast::<libc::c_void>(),
+            std::mem::size_of::<T>(),
+        )
+    };
+    if ret < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        i32::try_from(ret).map_err(|_| std::io::Error::other("bpf returned out of range"))
+    }
+}
+
+fn bpf_error(operation: &str, e: &std::io::Error) -> Error {
+    if e.kind() == std::io::ErrorKind::PermissionDenied {
+        Error::PermissionDenied {
+            operation: format!("{operation} (needs CAP_SYS_ADMIN)"),
+        }
+    } else {
+        Error::CGroup {
+            message: format!("Failed to {operation}: {e}"),
+        }
+    }
+}
+
+/// Load the filter for `rules` into the kernel
+///
+/// A program the verifier rejects comes back as an error carrying the
+/// verifier's log.
+pub(crate) fn load(rules: &[DeviceRule]) -> Result<OwnedFd> {
+    let program = program(rules)?;
+    let mut log = vec![0u8; 64 * 1024];
+    let mut attr = ProgLoadAttr {
+        prog_type: BPF_PROG_TYPE_CGROUP_DEVICE,
+        insn_cnt: u32::try_from(program.len()).unwrap_or(u32::MAX),
+        insns: program.as_ptr() as u64,
+        license: c"Apache-2.0".as_ptr() as u64,
+        log_level: 1,
+        log_size: u32::try_from(log.len()).unwrap_or(u32::MAX),
+        log_buf: log.as_mut_ptr() as u64,
+        ..ProgLoadAttr::default()
+    };
+    attr.prog_name[..6].copy_from_slice(b"vortex");
+
+    match bpf(BPF_PROG_LOAD, &mut attr) {
+        Ok(fd) => Ok(unsafe { OwnedFd::from_raw_fd(fd) }),
+        Err(e) => {
+            let log = String::from_utf8_lossy(&log);
+            let log = log.trim_end_matches('\0').trim();
+            let mut error = bpf_error("load device filter", &e);
+            if let Error::CGroup { ref mut message } = error
+                && !log.is_empty()
+            {
+                *message = format!("{message}\nverifier log:\n{log}");
+            }
+            Err(error)
+        }
+    }
+}
+
+/// Load the filter for `rules` and attach it to the cgroup at `path`
+///
+/// It is attached without `BPF_F_ALLOW_MULTI`, so it replaces the filter a
+/// previous call attached, and cgroups below cannot add their own. The
+/// cgroup holds on to the program, which stays attached after the file
+/// descriptor is closed and goes away with the cgroup.
+pub(crate) fn attach(path: &Path, rules: &[DeviceRule]) -> Result<()> {
+    let program = load(rules)?;
+    let cgroup = std::fs::File::open(path).map_err(|e| Error::CGroup {
+        message: format!("Failed to open {}: {e}", path.display()),
+    })?;
+
+    let mut attr = ProgAttachAttr {
+        target_fd: u32::try_from(cgroup.as_raw_fd()).unwrap_or_default(),
+        attach_bpf_fd: u32::try_from(program.as_raw_fd()).unwrap_or_default(),
+        attach_type: BPF_CGROUP_DEVICE,
+        attach_flags: 0,
+    };
+    bpf(BPF_PROG_ATTACH, &mut attr)
+        .map(|_| ())
+        .map_err(|e| bpf_error(&format!("attach device filter to {}", path.display()), &e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_device_rule() {
+        let null: DeviceRule = "c 1:3 rwm".parse().unwrap();
+        assert_eq!(
+            null,
+            DeviceRule::allow(DeviceKind::Char, Some(1), Some(3), DeviceAccess::ALL)
+        );
+
+        let no_disks: DeviceRule = "!b *:* rw".parse().unwrap();
+        assert!(!no_disks.allow);
+        assert_eq!(no_disks.major, None);
+        assert!(!no_disks.access.mknod);
+        assert_eq!(no_disks.to_string(), "!b *:* rw");
+
+        for rule in [
+            "c 1:3",
+            "x 1:3 r",
+            "c 1 r",
+            "c 1:x r",
+            "c 1:3 rx",
+            "c 1:3 r w",
+        ] {
+            assert!(rule.parse::<DeviceRule>().is_err(), "{rule} was accepted");
+        }
+    }
+
+    #[test]
+    fn test_program_checks_last_rule_first() {
+        let rules = ["a *:* m".parse().unwrap(), "c 1:3 rw".parse().unwrap()];
+        let program = program(&rules).unwrap();
+
+        // Prologue, then the /dev/null rule, whose failed tests all land on
+        // the mknod rule that follows it
+        let null = &program[6..14];
+        assert_eq!(null[0], insn(JNE_IMM, DEV_TYPE, 0, 7, DEV_CHAR));
+        assert_eq!(null[3], insn(JNE_REG, R1, ACCESS, 4, 0));
+        assert_eq!(null[4], insn(JNE_IMM, MAJOR, 0, 3, 1));
+        assert_eq!(null[5], insn(JNE_IMM, MINOR, 0, 2, 3));
+        assert_eq!(null[6], insn(MOV64_IMM, R0, 0, 0, 1));
+
+        // The mknod rule has no type or number tests, then the default deny
+        assert_eq!(program[14], insn(MOV32_REG, R1, ACCESS, 0, 0));
+        assert_eq!(program.len(), 14 + 5 + 2);
+        assert_eq!(program[program.len() - 2], insn(MOV64_IMM, R0, 0, 0, 0));
+    }
+
+    #[test]
+    fn test_program_ends_after_unconditional_rule() {
+        let rules = ["a *:* rwm".parse().unwrap(), "!c 1:3 rwm".parse().unwrap()];
+        let allowed = program(&rules).unwrap();
+
+        // Prologue, the /dev/null deny, then the allow-all block and no
+        // unreachable default deny after it
+        assert_eq!(allowed.len(), 6 + 5 + 2);
+        assert_eq!(allowed[11], insn(MOV64_IMM, R0, 0, 0, 1));
+        assert_eq!(allowed[12], insn(EXIT, 0, 0, 0, 0));
+
+        // Rules before it are never reached either
+        let rules = ["c 1:3 rwm".parse().unwrap(), "!a *:* rwm".parse().unwrap()];
+        let denied = program(&rules).unwrap();
+        assert_eq!(denied.len(), 6 + 2);
+        assert_eq!(denied[6], insn(MOV64_IMM, R0, 0, 0, 0));
+    }
+
+    #[test]
+    fn test_deny_matches_any_denied_access() {
+        let program = program(&["!c *:* w".parse().unwrap()]).unwrap();
+
+        assert_eq!(program[8], insn(AND32_IMM, R1, 0, 0, 4));
+        assert_eq!(program[9], insn(JEQ_IMM, R1, 0, 2, 0));
+        assert_eq!(program[10], insn(MOV64_IMM, R0, 0, 0, 0));
+    }
+
+    #[test]
+    #[ignore = "requires root privileges"]
+    fn test_verifier_accepts_default_rules() {
+        let mut rules = DeviceRule::defaults();
+        rules.push("!b *:* rwm".parse().unwrap());
+        load(&rules).unwrap();
+
+        // The usual first OCI rule leaves nothing unreachable
+        let rules = ["a *:* rwm".parse().unwrap(), "!c 1:3 rwm".parse().unwrap()];
+        load(&rules).unwrap();
+    }
+}
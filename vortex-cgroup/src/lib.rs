@@ -11,12 +11,22 @@
 )]
 
 pub mod backend;
+pub mod config;
 pub mod controller;
+pub mod devices;
+pub mod mode;
 pub mod monitor;
+pub mod multi;
+pub mod pod;
 
-pub use backend::{MockBackend, ResourceBackend};
-pub use controller::CGroupController;
-pub use monitor::ResourceMonitor;
+pub use backend::{BackendKind, MockBackend, ResourceBackend};
+pub use config::CGroupConfig;
+pub use controller::{CGroupController, PeakReset};
+pub use devices::{DeviceAccess, DeviceKind, DeviceRule};
+pub use mode::CGroupMode;
+pub use monitor::{IdleConfig, IdleDetector, ResourceMonitor};
+pub use multi::MultiMonitor;
+pub use pod::PodController;
 
 // Re-export commonly used types
-pub use vortex_core::{CpuLimit, MemoryLimit, ResourceStats};
+pub use vortex_core::{CpuLimit, IoLimit, MemoryLimit, ResourceLimits, ResourceStats};
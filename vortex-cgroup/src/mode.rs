@@ -0,0 +1,144 @@
+//! Detection of the host's cgroup layout
+//!
+//! systemd hosts mount cgroups in one of three ways: unified (cgroup v2
+//! only), legacy (v1 only) or hybrid, where the controllers sit in v1
+//! hierarchies next to an otherwise empty v2 one. A controller is bound to
+//! one hierarchy at a time, so in hybrid mode vortex may find a v2
+//! `cgroup.controllers` that lacks the controllers it needs.
+
+use std::fmt;
+use std::path::Path;
+
+use serde::Serialize;
+
+/// Where the kernel lists the mounts of the calling process
+pub const MOUNTINFO: &str = "/proc/self/mountinfo";
+
+/// How cgroups are mounted on the host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CGroupMode {
+    /// Only cgroup v2, which vortex needs
+    Unified,
+    /// cgroup v1 controller hierarchies next to cgroup v2
+    Hybrid,
+    /// Only cgroup v1
+    Legacy,
+}
+
+impl CGroupMode {
+    /// Detect the mode from [`MOUNTINFO`]
+    ///
+    /// Returns `None` when no cgroup filesystem is mounted or the mount
+    /// table cannot be read.
+    #[must_use]
+    pub fn detect() -> Option<Self> {
+        Self::from_mountinfo(&std::fs::read_to_string(Path::new(MOUNTINFO)).ok()?)
+    }
+
+    /// Determine the mode from the contents of a `mountinfo` file
+    #[must_use]
+    pub fn from_mountinfo(content: &str) -> Option<Self> {
+        let mounts = cgroup_mounts(content);
+        let v2 = mounts.iter().any(|(fs_type, _)| *fs_type == "cgroup2");
+        let v1 = mounts.iter().any(|(fs_type, _)| *fs_type == "cgroup");
+        match (v2, v1) {
+            (true, false) => Some(Self::Unified),
+            (true, true) => Some(Self::Hybrid),
+            (false, true) => Some(Self::Legacy),
+            (false, false) => None,
+        }
+    }
+}
+
+impl fmt::Display for CGroupMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Unified => "unified",
+            Self::Hybrid => "hybrid",
+            Self::Legacy => "legacy",
+        })
+    }
+}
+
+/// Controllers bound to a cgroup v1 hierarchy, by their v2 names
+///
+/// These are unavailable to cgroup v2 and so to vortex. The `name=`
+/// hierarchies systemd uses for tracking carry no controller and are left
+/// out.
+#[must_use]
+pub fn v1_controllers(mountinfo: &str) -> Vec<String> {
+    let mut controllers: Vec<String> = cgroup_mounts(mountinfo)
+        .into_iter()
+        .filter(|(fs_type, _)| *fs_type == "cgroup")
+        .flat_map(|(_, options)| options.split(','))
+        .filter_map(|option| match option {
+            // The v1 block IO controller became `io` in v2
+            "blkio" => Some("io"),
+            "cpu" | "cpuacct" | "cpuset" | "memory" | "devices" | "freezer" | "pids"
+            | "net_cls" | "net_prio" | "perf_event" | "hugetlb" | "rdma" | "misc" => Some(option),
+            _ => None,
+        })
+        .map(str::to_string)
+        .collect();
+    controllers.sort();
+    controllers.dedup();
+    controllers
+}
+
+/// Filesystem type and super options of every cgroup mount
+///
+/// A `mountinfo` line has a variable number of optional fields, ended by a
+/// lone `-`, after which come the filesystem type, the source and the
+/// super options.
+fn cgroup_mounts(content: &str) -> Vec<(&str, &str)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (_, tail) = line.split_once(" - ")?;
+            let mut fields = tail.split_whitespace();
+            let fs_type = fields.next()?;
+            let options = fields.nth(1).unwrap_or("");
+            matches!(fs_type, "cgroup" | "cgroup2").then_some((fs_type, options))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HYBRID: &str = "\
+32 24 0:28 / /sys/fs/cgroup rw,relatime - tmpfs tmpfs rw,mode=755
+33 32 0:29 / /sys/fs/cgroup/cpu,cpuacct rw,relatime shared:9 - cgroup cgroup rw,cpu,cpuacct
+35 32 0:31 / /sys/fs/cgroup/blkio rw,relatime - cgroup cgroup rw,blkio
+41 32 0:37 / /sys/fs/cgroup/systemd rw,relatime - cgroup cgroup rw,xattr,name=systemd
+42 32 0:38 / /sys/fs/cgroup/unified rw,relatime shared:5 - cgroup2 cgroup2 rw,nsdelegate
+";
+
+    #[test]
+    fn test_detect_mode() {
+        assert_eq!(CGroupMode::from_mountinfo(HYBRID), Some(CGroupMode::Hybrid));
+
+        let unified = "30 24 0:26 / /sys/fs/cgroup rw,nosuid shared:4 - cgroup2 cgroup2 rw";
+        assert_eq!(
+            CGroupMode::from_mountinfo(unified),
+            Some(CGroupMode::Unified)
+        );
+
+        let legacy = HYBRID.lines().take(4).collect::<Vec<_>>().join("\n");
+        assert_eq!(
+            CGroupMode::from_mountinfo(&legacy),
+            Some(CGroupMode::Legacy)
+        );
+
+        assert_eq!(CGroupMode::from_mountinfo(""), None);
+        assert_eq!(CGroupMode::Hybrid.to_string(), "hybrid");
+    }
+
+    #[test]
+    fn test_v1_controllers() {
+        assert_eq!(v1_controllers(HYBRID), vec!["cpu", "cpuacct", "io"]);
+        assert!(v1_controllers("30 24 0:26 / /sys/fs/cgroup rw - cgroup2 cgroup2 rw").is_empty());
+    }
+}
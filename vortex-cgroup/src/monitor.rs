@@ -4,12 +4,98 @@
 //! for shared access and channels for event emission.
 
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
-use tokio::time::{interval, Duration};
-use vortex_core::{ContainerEvent, ContainerId, ResourceStats, Result};
+use tokio::sync::{Mutex, Notify, mpsc};
+use tokio::time::{Duration, Instant, Interval, interval};
+use tracing::Instrument;
+use vortex_core::{
+    ContainerEvent, ContainerId, Error, EventBus, ResourceRates, ResourceStats, Result,
+};
 
 use crate::backend::ResourceBackend;
 
+/// Shortest accepted polling interval
+pub const MIN_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Throttling per tick above which a [`ContainerEvent::CpuThrottled`] is sent
+pub(crate) const THROTTLE_EVENT_THRESHOLD: Duration = Duration::from_millis(100);
+
+/// When a container counts as idle
+///
+/// Detection looks at CPU usage only: a container blocked on I/O, a network
+/// peer or a lock uses next to no CPU and looks idle although it is still
+/// working. Pick a window longer than the longest such wait.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IdleConfig {
+    /// CPU usage, as a percentage of one core, below which a reading is idle
+    pub threshold: f64,
+    /// How long usage must stay below `threshold` before the container is
+    /// reported idle
+    pub window: Duration,
+}
+
+impl IdleConfig {
+    /// Threshold unless set otherwise: 1% of one core
+    pub const DEFAULT_THRESHOLD: f64 = 1.0;
+
+    /// Report a container idle after `window` below [`Self::DEFAULT_THRESHOLD`]
+    #[must_use]
+    pub const fn new(window: Duration) -> Self {
+        Self {
+            threshold: Self::DEFAULT_THRESHOLD,
+            window,
+        }
+    }
+
+    /// Set the CPU threshold, as a percentage of one core
+    #[must_use]
+    pub const fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+}
+
+/// Tracks how long CPU usage has stayed below an [`IdleConfig`] threshold
+///
+/// Feed it the rates of each reading with [`Self::observe`]. It reports a
+/// container idle once per idle stretch; a busy reading starts over.
+#[derive(Debug, Clone)]
+pub struct IdleDetector {
+    config: IdleConfig,
+    idle_for: Duration,
+    reported: bool,
+}
+
+impl IdleDetector {
+    /// Start with no idle time
+    #[must_use]
+    pub const fn new(config: IdleConfig) -> Self {
+        Self {
+            config,
+            idle_for: Duration::ZERO,
+            reported: false,
+        }
+    }
+
+    /// Account for a reading covering `elapsed`
+    ///
+    /// Returns how long the container has been idle when that reaches the
+    /// window for the first time in this idle stretch.
+    pub fn observe(&mut self, rates: &ResourceRates, elapsed: Duration) -> Option<Duration> {
+        if rates.cpu_percent >= self.config.threshold {
+            self.idle_for = Duration::ZERO;
+            self.reported = false;
+            return None;
+        }
+
+        self.idle_for += elapsed;
+        if self.reported || self.idle_for < self.config.window {
+            return None;
+        }
+        self.reported = true;
+        Some(self.idle_for)
+    }
+}
+
 /// Resource monitor that runs in the background
 ///
 /// # Example
@@ -41,9 +127,38 @@ use crate::backend::ResourceBackend;
 pub struct ResourceMonitor {
     backend: Arc<dyn ResourceBackend>,
     container_id: ContainerId,
-    interval_secs: u64,
+    interval: Duration,
     running: Arc<Mutex<bool>>,
-    event_tx: Option<mpsc::Sender<ContainerEvent>>,
+    /// Wakes the loop on [`Self::stop`] instead of letting it sleep out
+    /// the interval
+    stopped: Arc<Notify>,
+    events: EventBus,
+    plain: bool,
+    idle: Option<IdleConfig>,
+    /// Ticks from [`Self::with_ticks`], taken by the first [`Self::start`]
+    ticks: std::sync::Mutex<Option<mpsc::Receiver<Duration>>>,
+}
+
+/// What makes the monitor read stats, and how much time each reading covers
+enum Clock {
+    /// A real interval, measured with the wall clock
+    Every { ticker: Interval, last: Instant },
+    /// Ticks from a channel, each with the time it stands for
+    Manual(mpsc::Receiver<Duration>),
+}
+
+impl Clock {
+    /// Wait for the next tick and return the time since the previous one,
+    /// `None` once manual ticks run out
+    async fn tick(&mut self) -> Option<Duration> {
+        match self {
+            Self::Every { ticker, last } => {
+                let now = ticker.tick().await;
+                Some(now - std::mem::replace(last, now))
+            }
+            Self::Manual(ticks) => ticks.recv().await,
+        }
+    }
 }
 
 impl ResourceMonitor {
@@ -52,7 +167,8 @@ impl ResourceMonitor {
     /// # Arguments
     /// * `backend` - The resource backend to monitor
     /// * `container_id` - Container identifier
-    /// * `interval_secs` - How often to collect stats (in seconds)
+    /// * `interval_secs` - How often to collect stats (in seconds); use
+    ///   [`Self::with_interval`] for sub-second polling
     #[must_use]
     pub fn new(
         backend: Arc<dyn ResourceBackend>,
@@ -62,46 +178,154 @@ impl ResourceMonitor {
         Self {
             backend,
             container_id,
-            interval_secs,
+            interval: Duration::from_secs(interval_secs),
             running: Arc::new(Mutex::new(false)),
-            event_tx: None,
+            stopped: Arc::new(Notify::new()),
+            events: EventBus::new(),
+            plain: false,
+            idle: None,
+            ticks: std::sync::Mutex::new(None),
         }
     }
 
+    /// Set the polling interval
+    ///
+    /// Intervals below [`MIN_INTERVAL`] are rejected by [`Self::start`].
+    #[must_use]
+    pub const fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Print the console table without emoji, for logs and plain terminals
+    #[must_use]
+    pub const fn with_plain_output(mut self, plain: bool) -> Self {
+        self.plain = plain;
+        self
+    }
+
+    /// Send a [`ContainerEvent::Idle`] when CPU usage stays below the
+    /// configured threshold for the configured window
+    ///
+    /// Idle time is counted in whole polling intervals, so the event comes
+    /// up to one interval after the window is over.
+    #[must_use]
+    pub const fn with_idle_detection(mut self, idle: IdleConfig) -> Self {
+        self.idle = Some(idle);
+        self
+    }
+
+    /// Read stats on every value received from `ticks` instead of every
+    /// interval, for tests that drive the monitor step by step
+    ///
+    /// Each value is the time the tick stands for, which rates and idle
+    /// detection use in place of the wall clock. The monitor stops once the
+    /// sender is dropped and every tick is handled, so awaiting the handle
+    /// from [`Self::start`] then means every event has been published.
+    #[must_use]
+    pub fn with_ticks(self, ticks: mpsc::Receiver<Duration>) -> Self {
+        *self
+            .ticks
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(ticks);
+        self
+    }
+
+    /// Get the polling interval
+    #[must_use]
+    pub const fn interval(&self) -> Duration {
+        self.interval
+    }
+
     /// Add event channel for emitting events
     ///
-    /// Events will be sent to this channel as they occur.
+    /// Events will be sent to this channel as they occur. Sending never
+    /// waits: if the channel is full the event is dropped and counted in
+    /// [`Self::dropped_events`], so a slow consumer cannot stall monitoring.
+    #[must_use]
+    pub fn with_events(self, tx: mpsc::Sender<ContainerEvent>) -> Self {
+        self.events.attach(tx);
+        self
+    }
+
+    /// Publish events to a shared bus instead of a private one
+    ///
+    /// Call this before [`Self::with_events`]: channels attached earlier stay
+    /// on the replaced bus.
     #[must_use]
-    pub fn with_events(mut self, tx: mpsc::Sender<ContainerEvent>) -> Self {
-        self.event_tx = Some(tx);
+    pub fn with_event_bus(mut self, bus: EventBus) -> Self {
+        self.events = bus;
         self
     }
 
+    /// Get the bus events are published to
+    #[must_use]
+    pub const fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Number of events dropped because a subscriber's channel was full
+    ///
+    /// This counts drops across the whole bus, which may be shared.
+    #[must_use]
+    pub fn dropped_events(&self) -> u64 {
+        self.events.dropped_events()
+    }
+
     /// Start monitoring in the background
     ///
     /// Returns a join handle that can be awaited to ensure the monitor completes.
     ///
     /// # Errors
-    /// Returns error if monitoring cannot be started
+    /// Returns [`Error::InvalidConfig`] if the interval is below [`MIN_INTERVAL`]
     pub async fn start(&self) -> Result<tokio::task::JoinHandle<()>> {
+        if self.interval < MIN_INTERVAL {
+            return Err(Error::InvalidConfig {
+                message: format!(
+                    "Monitor interval {:?} is too small (minimum {:?})",
+                    self.interval, MIN_INTERVAL
+                ),
+            });
+        }
+
+        let manual = self
+            .ticks
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .take();
+        let mut clock = manual.map_or_else(
+            || Clock::Every {
+                ticker: interval(self.interval),
+                last: Instant::now(),
+            },
+            Clock::Manual,
+        );
+
         *self.running.lock().await = true;
 
         let backend = Arc::clone(&self.backend);
         let running = Arc::clone(&self.running);
-        let interval_secs = self.interval_secs;
-        let event_tx = self.event_tx.clone();
+        let stopped = Arc::clone(&self.stopped);
+        let poll_interval = self.interval;
+        let events = self.events.clone();
         let container_id = self.container_id.clone();
-
-        let handle = tokio::spawn(async move {
-            let mut ticker = interval(Duration::from_secs(interval_secs));
-
+        let mut idle = self.idle.map(IdleDetector::new);
+        let (stats_icon, exit_icon) = if self.plain {
+            ("", "")
+        } else {
+            ("📊 ", "✅ ")
+        };
+
+        let span = tracing::info_span!("monitor", container_id = %container_id);
+        let task = async move {
             tracing::info!(
                 container_id = %container_id,
-                interval_secs,
+                interval_ms = poll_interval.as_millis(),
+                backend = %backend.kind(),
                 "Resource monitoring started"
             );
 
-            println!("\n📊 Resource Monitoring Started for {container_id}");
+            println!("\n{stats_icon}Resource Monitoring Started for {container_id}");
             println!("{:-<80}", "");
             println!(
                 "{:<10} {:<15} {:<15} {:<20} {:<20}",
@@ -109,48 +333,62 @@ impl ResourceMonitor {
             );
             println!("{:-<80}", "");
 
-            let start = std::time::Instant::now();
+            let mut elapsed = Duration::ZERO;
             let mut last_stats: Option<ResourceStats> = None;
 
             // Emit started event
-            if let Some(ref tx) = event_tx {
-                let event = ContainerEvent::Started {
-                    id: container_id.clone(),
-                    timestamp: std::time::SystemTime::now(),
-                };
-                event.emit_trace();
-                let _ = tx.send(event).await;
-            }
+            let event = ContainerEvent::Started {
+                id: container_id.clone(),
+                timestamp: std::time::SystemTime::now(),
+            };
+            event.emit_trace();
+            events.publish(&event);
 
             loop {
-                ticker.tick().await;
+                let tick = tokio::select! {
+                    tick = clock.tick() => tick,
+                    () = stopped.notified() => Some(Duration::ZERO),
+                };
 
                 // Check if we should stop
-                if !*running.lock().await {
-                    tracing::debug!("Monitor stopping");
-                    break;
-                }
+                let since_last = match tick {
+                    Some(since_last) if *running.lock().await => since_last,
+                    _ => {
+                        tracing::debug!("Monitor stopping");
+                        break;
+                    }
+                };
+                elapsed += since_last;
 
                 // Read stats
                 let stats = backend.stats().await;
 
                 match stats {
                     Ok(s) => {
-                        let elapsed = start.elapsed().as_secs();
-
                         // Check for CPU throttling
                         if let Some(ref prev) = last_stats {
                             let throttle_delta = s.cpu_throttled - prev.cpu_throttled;
-                            if throttle_delta > Duration::from_millis(100) {
-                                if let Some(ref tx) = event_tx {
-                                    let event = ContainerEvent::CpuThrottled {
-                                        id: container_id.clone(),
-                                        duration: throttle_delta,
-                                        timestamp: std::time::SystemTime::now(),
-                                    };
-                                    event.emit_trace();
-                                    let _ = tx.send(event).await;
-                                }
+                            if throttle_delta > THROTTLE_EVENT_THRESHOLD {
+                                let event = ContainerEvent::CpuThrottled {
+                                    id: container_id.clone(),
+                                    duration: throttle_delta,
+                                    timestamp: std::time::SystemTime::now(),
+                                };
+                                event.emit_trace();
+                                events.publish(&event);
+                            }
+
+                            // Check for throttling at memory.high
+                            let high_delta =
+                                s.memory_high_events.saturating_sub(prev.memory_high_events);
+                            if high_delta > 0 {
+                                let event = ContainerEvent::MemoryThrottled {
+                                    id: container_id.clone(),
+                                    events: high_delta,
+                                    timestamp: std::time::SystemTime::now(),
+                                };
+                                event.emit_trace();
+                                events.publish(&event);
                             }
 
                             // Check for memory pressure (>80%)
@@ -160,36 +398,45 @@ impl ResourceMonitor {
                                         (s.memory_current.as_bytes() as f64 / limit as f64) * 100.0;
 
                                     if percentage > 80.0 {
-                                        if let Some(ref tx) = event_tx {
-                                            let event = ContainerEvent::MemoryPressure {
-                                                id: container_id.clone(),
-                                                current: s.memory_current.as_bytes(),
-                                                limit,
-                                                percentage,
-                                                timestamp: std::time::SystemTime::now(),
-                                            };
-                                            event.emit_trace();
-                                            let _ = tx.send(event).await;
-                                        }
+                                        let event = ContainerEvent::MemoryPressure {
+                                            id: container_id.clone(),
+                                            current: s.memory_current.as_bytes(),
+                                            limit,
+                                            percentage,
+                                            timestamp: std::time::SystemTime::now(),
+                                        };
+                                        event.emit_trace();
+                                        events.publish(&event);
                                     }
                                 }
                             }
+
+                            // Check for a CPU-idle stretch
+                            if let Some(ref mut idle) = idle {
+                                let rates = ResourceRates::between(prev, &s, since_last);
+                                if let Some(duration) = idle.observe(&rates, since_last) {
+                                    let event = ContainerEvent::Idle {
+                                        id: container_id.clone(),
+                                        duration,
+                                        timestamp: std::time::SystemTime::now(),
+                                    };
+                                    event.emit_trace();
+                                    events.publish(&event);
+                                }
+                            }
                         }
 
                         // Emit stats update event
-                        if let Some(ref tx) = event_tx {
-                            let event = ContainerEvent::StatsUpdate {
-                                id: container_id.clone(),
-                                stats: s.clone(),
-                                timestamp: std::time::SystemTime::now(),
-                            };
-                            let _ = tx.send(event).await;
-                        }
+                        events.publish(&ContainerEvent::StatsUpdate {
+                            id: container_id.clone(),
+                            stats: s.clone(),
+                            timestamp: std::time::SystemTime::now(),
+                        });
 
                         // Print to console
                         println!(
                             "{:<10} {:<15.2} {:<15.2} {:<20} {:<20}",
-                            format!("{elapsed}s"),
+                            format!("{:.1}s", elapsed.as_secs_f64()),
                             s.cpu_usage.as_secs_f64(),
                             s.cpu_throttled.as_secs_f64(),
                             s.memory_current,
@@ -200,7 +447,7 @@ impl ResourceMonitor {
                     }
                     Err(e) => {
                         if format!("{e}").contains("No such file") {
-                            println!("\n✅ Container exited");
+                            println!("\n{exit_icon}Container exited");
                             tracing::info!("Container exited");
                             break;
                         }
@@ -211,14 +458,20 @@ impl ResourceMonitor {
             }
 
             tracing::info!(container_id = %container_id, "Monitoring stopped");
-        });
+        };
+
+        let handle = tokio::spawn(task.instrument(span));
 
         Ok(handle)
     }
 
     /// Stop monitoring
+    ///
+    /// The loop wakes up right away and reads no more stats; await the
+    /// handle from [`Self::start`] before removing the cgroup.
     pub async fn stop(&self) {
         *self.running.lock().await = false;
+        self.stopped.notify_one();
         tracing::debug!("Stopping monitor");
     }
 }
@@ -237,7 +490,7 @@ impl std::fmt::Debug for ResourceMonitor {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ResourceMonitor")
             .field("container_id", &self.container_id)
-            .field("interval_secs", &self.interval_secs)
+            .field("interval", &self.interval)
             .finish_non_exhaustive()
     }
 }
@@ -247,6 +500,33 @@ mod tests {
     use super::*;
     use crate::MockBackend;
 
+    /// Run `monitor` for one reading per entry of `ticks`, each standing for
+    /// that much time, and return every event it published in order
+    async fn drive(monitor: ResourceMonitor, ticks: &[Duration]) -> Vec<ContainerEvent> {
+        let (tick_tx, tick_rx) = mpsc::channel(ticks.len().max(1));
+        for &tick in ticks {
+            tick_tx.try_send(tick).unwrap();
+        }
+        drop(tick_tx);
+
+        let monitor = monitor.with_ticks(tick_rx);
+        let mut rx = monitor.events().subscribe(256);
+        monitor.start().await.unwrap().await.unwrap();
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+
+    fn monitor(mock: MockBackend) -> ResourceMonitor {
+        let backend = Arc::new(mock) as Arc<dyn ResourceBackend>;
+        ResourceMonitor::new(backend, ContainerId::new("test").unwrap(), 1)
+    }
+
+    const TICK: Duration = Duration::from_secs(1);
+
     #[tokio::test]
     async fn test_monitor_lifecycle() {
         let backend = Arc::new(MockBackend::new()) as Arc<dyn ResourceBackend>;
@@ -267,35 +547,201 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_monitor_with_events() {
+    async fn test_monitor_stops_without_waiting_for_interval() {
         let backend = Arc::new(MockBackend::new()) as Arc<dyn ResourceBackend>;
         let id = ContainerId::new("test").unwrap();
+        let monitor = ResourceMonitor::new(backend, id, 60);
+
+        let handle = monitor.start().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        monitor.stop().await;
+
+        let result = tokio::time::timeout(Duration::from_secs(1), handle).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_monitor_with_events() {
         let (tx, mut rx) = mpsc::channel(100);
+        let events = drive(monitor(MockBackend::new()).with_events(tx), &[TICK; 2]).await;
+
+        assert!(matches!(
+            events.as_slice(),
+            [
+                ContainerEvent::Started { .. },
+                ContainerEvent::StatsUpdate { .. },
+                ContainerEvent::StatsUpdate { .. },
+            ]
+        ));
+        // Attached channels get the same events
+        assert!(matches!(
+            rx.recv().await,
+            Some(ContainerEvent::Started { .. })
+        ));
+    }
 
-        let monitor = ResourceMonitor::new(backend, id, 1).with_events(tx);
+    #[tokio::test]
+    async fn test_monitor_reports_memory_high_throttling() {
+        let mock = MockBackend::new();
+        mock.set_memory_high_rate(2).await;
+
+        let events = drive(monitor(mock), &[TICK; 3]).await;
+        // None for the first reading, which has nothing to compare with
+        let throttled: Vec<u64> = events
+            .iter()
+            .filter_map(|e| match e {
+                ContainerEvent::MemoryThrottled { events, .. } => Some(*events),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(throttled, vec![2, 2]);
+    }
 
-        let handle = monitor.start().await.unwrap();
+    #[tokio::test]
+    async fn test_monitor_reports_cpu_throttling() {
+        let mock = MockBackend::new();
+        mock.set_throttle_rate(THROTTLE_EVENT_THRESHOLD * 2).await;
+
+        let events = drive(monitor(mock), &[TICK; 3]).await;
+        let throttled: Vec<Duration> = events
+            .iter()
+            .filter_map(|e| match e {
+                ContainerEvent::CpuThrottled { duration, .. } => Some(*duration),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(throttled, vec![THROTTLE_EVENT_THRESHOLD * 2; 2]);
+    }
+
+    #[tokio::test]
+    async fn test_monitor_ignores_light_throttling() {
+        let mock = MockBackend::new();
+        mock.set_throttle_rate(THROTTLE_EVENT_THRESHOLD / 2).await;
+
+        let events = drive(monitor(mock), &[TICK; 5]).await;
+        assert!(
+            !events
+                .iter()
+                .any(|e| matches!(e, ContainerEvent::CpuThrottled { .. }))
+        );
+    }
+
+    fn cpu(percent: f64) -> ResourceRates {
+        ResourceRates {
+            cpu_percent: percent,
+            ..ResourceRates::default()
+        }
+    }
+
+    #[test]
+    fn test_idle_detector() {
+        let config = IdleConfig::new(Duration::from_secs(3)).with_threshold(5.0);
+        let mut idle = IdleDetector::new(config);
+        let tick = Duration::from_secs(1);
+
+        assert_eq!(idle.observe(&cpu(1.0), tick), None);
+        assert_eq!(idle.observe(&cpu(4.9), tick), None);
+        // A busy reading starts the stretch over
+        assert_eq!(idle.observe(&cpu(50.0), tick), None);
+        assert_eq!(idle.observe(&cpu(0.0), tick), None);
+        assert_eq!(idle.observe(&cpu(0.0), tick), None);
+        assert_eq!(idle.observe(&cpu(0.0), tick), Some(Duration::from_secs(3)));
+        // Reported once per stretch
+        assert_eq!(idle.observe(&cpu(0.0), tick), None);
+
+        assert_eq!(idle.observe(&cpu(5.0), tick), None);
+        assert_eq!(
+            idle.observe(&cpu(0.0), Duration::from_secs(4)),
+            Some(Duration::from_secs(4))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_monitor_reports_idle_container() {
+        let mock = MockBackend::new();
+        mock.set_cpu_per_read(Duration::ZERO).await;
+        let monitor = monitor(mock).with_idle_detection(IdleConfig::new(Duration::from_secs(3)));
+
+        let events = drive(monitor, &[TICK; 6]).await;
+        // Three idle seconds after the baseline reading, reported once
+        let idle: Vec<Duration> = events
+            .iter()
+            .filter_map(|e| match e {
+                ContainerEvent::Idle { duration, .. } => Some(*duration),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(idle, vec![Duration::from_secs(3)]);
+    }
 
-        // Should receive started event
-        let event = tokio::time::timeout(Duration::from_secs(2), rx.recv())
-            .await
-            .expect("timeout")
-            .expect("event");
+    #[tokio::test]
+    async fn test_monitor_busy_container_is_not_idle() {
+        // The mock uses 100ms of CPU per reading, 10% of each tick
+        let monitor = monitor(MockBackend::new())
+            .with_idle_detection(IdleConfig::new(Duration::from_secs(2)));
+
+        let events = drive(monitor, &[TICK; 6]).await;
+        assert!(
+            !events
+                .iter()
+                .any(|e| matches!(e, ContainerEvent::Idle { .. }))
+        );
+    }
 
-        assert!(matches!(event, ContainerEvent::Started { .. }));
+    #[tokio::test]
+    async fn test_monitor_sub_second_interval() {
+        let backend = Arc::new(MockBackend::new()) as Arc<dyn ResourceBackend>;
+        let id = ContainerId::new("test").unwrap();
+        let (tx, mut rx) = mpsc::channel(100);
 
-        // Should receive stats update
-        let event = tokio::time::timeout(Duration::from_secs(2), rx.recv())
-            .await
-            .expect("timeout")
-            .expect("event");
+        let monitor = ResourceMonitor::new(backend, id, 1)
+            .with_interval(Duration::from_millis(50))
+            .with_events(tx);
+        let handle = monitor.start().await.unwrap();
 
-        assert!(matches!(event, ContainerEvent::StatsUpdate { .. }));
+        // Started plus at least three stats updates well within one second
+        let collect = async {
+            for _ in 0..4 {
+                rx.recv().await.expect("event");
+            }
+        };
+        assert!(
+            tokio::time::timeout(Duration::from_millis(500), collect)
+                .await
+                .is_ok()
+        );
 
         monitor.stop().await;
         let _ = handle.await;
     }
 
+    #[tokio::test]
+    async fn test_monitor_rejects_tiny_interval() {
+        let id = ContainerId::new("test").unwrap();
+
+        for interval in [Duration::ZERO, Duration::from_micros(100)] {
+            let backend = Arc::new(MockBackend::new()) as Arc<dyn ResourceBackend>;
+            let monitor = ResourceMonitor::new(backend, id.clone(), 1).with_interval(interval);
+            assert!(matches!(
+                monitor.start().await,
+                Err(Error::InvalidConfig { .. })
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_monitor_full_channel_does_not_stall() {
+        // Keep the receiver alive but never drain it
+        let (tx, _rx) = mpsc::channel(1);
+        let monitor = monitor(MockBackend::new()).with_events(tx);
+        let bus = monitor.events().clone();
+
+        // Started fills the channel; every stats update after it is dropped
+        let events = drive(monitor, &[TICK; 5]).await;
+        assert_eq!(events.len(), 6);
+        assert_eq!(bus.dropped_events(), 5);
+    }
+
     #[tokio::test]
     async fn test_monitor_stop_before_start() {
         let backend = Arc::new(MockBackend::new()) as Arc<dyn ResourceBackend>;
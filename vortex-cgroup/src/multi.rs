@@ -0,0 +1,316 @@
+//! Monitoring several containers from one background task
+//!
+//! [`MultiMonitor`] polls every registered backend on each tick and
+//! publishes per-container events on one [`EventBus`]. Containers can be
+//! added and removed while it is running.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::{Mutex, mpsc};
+use tokio::time::{Duration, interval};
+use tracing::Instrument;
+use vortex_core::{ContainerEvent, ContainerId, Error, EventBus, ResourceStats, Result};
+
+use crate::backend::ResourceBackend;
+use crate::monitor::{MIN_INTERVAL, THROTTLE_EVENT_THRESHOLD};
+
+type Backends = HashMap<ContainerId, Arc<dyn ResourceBackend>>;
+
+/// Resource monitor for a changing set of containers
+///
+/// # Example
+/// ```no_run
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use tokio::sync::mpsc;
+/// use vortex_cgroup::{MockBackend, MultiMonitor};
+/// use vortex_core::ContainerId;
+///
+/// # async fn example() {
+/// let (tx, mut rx) = mpsc::channel(100);
+/// let monitor = MultiMonitor::new(Duration::from_secs(1)).with_events(tx);
+///
+/// monitor
+///     .add(ContainerId::new("web").unwrap(), Arc::new(MockBackend::new()))
+///     .await;
+/// let handle = monitor.start().await.unwrap();
+///
+/// // Containers can join while the monitor runs
+/// monitor
+///     .add(ContainerId::new("db").unwrap(), Arc::new(MockBackend::new()))
+///     .await;
+///
+/// while let Some(event) = rx.recv().await {
+///     println!("{}: {}", event.container_id(), event);
+/// }
+///
+/// monitor.stop().await;
+/// handle.await.unwrap();
+/// # }
+/// ```
+pub struct MultiMonitor {
+    backends: Arc<Mutex<Backends>>,
+    interval: Duration,
+    running: Arc<Mutex<bool>>,
+    events: EventBus,
+}
+
+impl MultiMonitor {
+    /// Create a monitor with no containers
+    #[must_use]
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            backends: Arc::new(Mutex::new(HashMap::new())),
+            interval,
+            running: Arc::new(Mutex::new(false)),
+            events: EventBus::new(),
+        }
+    }
+
+    /// Add event channel shared by all containers
+    ///
+    /// Like [`ResourceMonitor`](crate::ResourceMonitor), events are dropped
+    /// rather than waited on when the channel is full.
+    #[must_use]
+    pub fn with_events(self, tx: mpsc::Sender<ContainerEvent>) -> Self {
+        self.events.attach(tx);
+        self
+    }
+
+    /// Publish events to a shared bus instead of a private one
+    #[must_use]
+    pub fn with_event_bus(mut self, bus: EventBus) -> Self {
+        self.events = bus;
+        self
+    }
+
+    /// Get the bus events are published to
+    #[must_use]
+    pub const fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Get the polling interval
+    #[must_use]
+    pub const fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Number of events dropped because a subscriber's channel was full
+    #[must_use]
+    pub fn dropped_events(&self) -> u64 {
+        self.events.dropped_events()
+    }
+
+    /// Start watching a container, replacing any backend already registered
+    /// for the same ID
+    pub async fn add(&self, container_id: ContainerId, backend: Arc<dyn ResourceBackend>) {
+        tracing::debug!(container_id = %container_id, "Adding container to monitor");
+        self.backends.lock().await.insert(container_id, backend);
+    }
+
+    /// Stop watching a container, returning whether it was being watched
+    pub async fn remove(&self, container_id: &ContainerId) -> bool {
+        tracing::debug!(container_id = %container_id, "Removing container from monitor");
+        self.backends.lock().await.remove(container_id).is_some()
+    }
+
+    /// IDs of the containers being watched, sorted
+    pub async fn containers(&self) -> Vec<ContainerId> {
+        let mut ids: Vec<ContainerId> = self.backends.lock().await.keys().cloned().collect();
+        ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        ids
+    }
+
+    /// Start monitoring in the background
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidConfig`] if the interval is below [`MIN_INTERVAL`]
+    pub async fn start(&self) -> Result<tokio::task::JoinHandle<()>> {
+        if self.interval < MIN_INTERVAL {
+            return Err(Error::InvalidConfig {
+                message: format!(
+                    "Monitor interval {:?} is too small (minimum {:?})",
+                    self.interval, MIN_INTERVAL
+                ),
+            });
+        }
+
+        *self.running.lock().await = true;
+
+        let backends = Arc::clone(&self.backends);
+        let running = Arc::clone(&self.running);
+        let poll_interval = self.interval;
+        let events = self.events.clone();
+
+        let span = tracing::info_span!("multi_monitor");
+        let task = async move {
+            let mut ticker = interval(poll_interval);
+            let mut last_stats: HashMap<ContainerId, ResourceStats> = HashMap::new();
+
+            tracing::info!(
+                interval_ms = poll_interval.as_millis(),
+                "Multi-container monitoring started"
+            );
+
+            loop {
+                ticker.tick().await;
+
+                if !*running.lock().await {
+                    tracing::debug!("Multi-container monitor stopping");
+                    break;
+                }
+
+                // Poll outside the lock so add/remove never wait on a slow backend
+                let snapshot: Vec<(ContainerId, Arc<dyn ResourceBackend>)> = backends
+                    .lock()
+                    .await
+                    .iter()
+                    .map(|(id, backend)| (id.clone(), Arc::clone(backend)))
+                    .collect();
+                last_stats.retain(|id, _| snapshot.iter().any(|(watched, _)| watched == id));
+
+                for (id, backend) in snapshot {
+                    let stats = match backend.stats().await {
+                        Ok(stats) => stats,
+                        Err(e) => {
+                            tracing::warn!(container_id = %id, error = %e, "Error reading stats");
+                            events.publish(&ContainerEvent::Error {
+                                id,
+                                message: e.to_string(),
+                                timestamp: SystemTime::now(),
+                            });
+                            continue;
+                        }
+                    };
+
+                    if let Some(prev) = last_stats.get(&id) {
+                        let throttle_delta = stats.cpu_throttled.saturating_sub(prev.cpu_throttled);
+                        if throttle_delta > THROTTLE_EVENT_THRESHOLD {
+                            let event = ContainerEvent::CpuThrottled {
+                                id: id.clone(),
+                                duration: throttle_delta,
+                                timestamp: SystemTime::now(),
+                            };
+                            event.emit_trace();
+                            events.publish(&event);
+                        }
+
+                        let high_delta = stats
+                            .memory_high_events
+                            .saturating_sub(prev.memory_high_events);
+                        if high_delta > 0 {
+                            let event = ContainerEvent::MemoryThrottled {
+                                id: id.clone(),
+                                events: high_delta,
+                                timestamp: SystemTime::now(),
+                            };
+                            event.emit_trace();
+                            events.publish(&event);
+                        }
+                    }
+
+                    events.publish(&ContainerEvent::StatsUpdate {
+                        id: id.clone(),
+                        stats: stats.clone(),
+                        timestamp: SystemTime::now(),
+                    });
+
+                    last_stats.insert(id, stats);
+                }
+            }
+
+            tracing::info!("Multi-container monitoring stopped");
+        };
+
+        Ok(tokio::spawn(task.instrument(span)))
+    }
+
+    /// Stop monitoring
+    pub async fn stop(&self) {
+        *self.running.lock().await = false;
+        tracing::debug!("Stopping multi-container monitor");
+    }
+}
+
+impl std::fmt::Debug for MultiMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MultiMonitor")
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockBackend;
+    use std::collections::HashSet;
+
+    #[tokio::test]
+    async fn test_events_tagged_per_container() {
+        let (tx, mut rx) = mpsc::channel(100);
+        let monitor = MultiMonitor::new(Duration::from_millis(20)).with_events(tx);
+
+        let web = ContainerId::new("web").unwrap();
+        let db = ContainerId::new("db").unwrap();
+        monitor.add(web.clone(), Arc::new(MockBackend::new())).await;
+        monitor.add(db.clone(), Arc::new(MockBackend::new())).await;
+
+        let handle = monitor.start().await.unwrap();
+
+        let mut seen = HashSet::new();
+        let collect = async {
+            while seen.len() < 2 {
+                if let Some(ContainerEvent::StatsUpdate { id, .. }) = rx.recv().await {
+                    seen.insert(id);
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(2), collect)
+            .await
+            .expect("updates from both containers");
+        assert!(seen.contains(&web));
+        assert!(seen.contains(&db));
+
+        monitor.stop().await;
+        let _ = handle.await;
+    }
+
+    #[tokio::test]
+    async fn test_add_and_remove_while_running() {
+        let (tx, mut rx) = mpsc::channel(100);
+        let monitor = MultiMonitor::new(Duration::from_millis(20)).with_events(tx);
+        let handle = monitor.start().await.unwrap();
+
+        let late = ContainerId::new("late").unwrap();
+        monitor
+            .add(late.clone(), Arc::new(MockBackend::new()))
+            .await;
+        assert_eq!(monitor.containers().await, vec![late.clone()]);
+
+        let event = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .expect("timeout")
+            .expect("event");
+        assert_eq!(event.container_id(), &late);
+
+        assert!(monitor.remove(&late).await);
+        assert!(!monitor.remove(&late).await);
+        assert!(monitor.containers().await.is_empty());
+
+        monitor.stop().await;
+        let result = tokio::time::timeout(Duration::from_secs(1), handle).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_tiny_interval() {
+        let monitor = MultiMonitor::new(Duration::ZERO);
+        assert!(matches!(
+            monitor.start().await,
+            Err(Error::InvalidConfig { .. })
+        ));
+    }
+}
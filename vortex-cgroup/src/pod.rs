@@ -0,0 +1,309 @@
+//! Pod-level cgroups grouping several containers
+//!
+//! A pod is a cgroup directly under the vortex root that holds container
+//! cgroups as children (`/sys/fs/cgroup/vortex/<pod>/<container>`). Limits set
+//! on the pod cap the aggregate usage of all its containers. A pod is
+//! marked with a `user.vortex.pod` attribute, so one without containers is
+//! not taken for a container.
+
+use std::ffi::{CStr, CString};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use vortex_core::{
+    ContainerId, CpuCores, CpuLimit, Error, MemoryLimit, MemorySize, ResourceLimits, Result,
+};
+
+use crate::backend::ResourceBackend;
+use crate::config::CGroupConfig;
+use crate::controller::{CGroupController, is_read_only_error, page_aligned_memory, page_size};
+
+/// Extended attribute marking a cgroup as a pod
+///
+/// cgroupfs refuses to create files, so the marker cannot be a file in the
+/// pod's directory, but it keeps `user.` attributes.
+const POD_MARKER: &CStr = c"user.vortex.pod";
+
+/// Controller for a pod cgroup and the containers inside it
+pub struct PodController {
+    name: ContainerId,
+    config: CGroupConfig,
+    path: PathBuf,
+    cpu: Option<CpuLimit>,
+    memory: Option<MemoryLimit>,
+}
+
+impl PodController {
+    /// Create (or open) a pod cgroup
+    ///
+    /// Pod names follow the same rules as container IDs. Opening an existing
+    /// pod picks up the limits already written to it.
+    pub async fn new(name: &str) -> Result<Self> {
+        Self::with_config(name, CGroupConfig::default()).await
+    }
+
+    /// Create (or open) a pod cgroup using a custom configuration
+    #[tracing::instrument(skip(config))]
+    pub async fn with_config(name: &str, config: CGroupConfig) -> Result<Self> {
+        let name = ContainerId::new(name)?;
+        let config = CGroupConfig {
+            parent: None,
+            ..config
+        };
+        let path = config.vortex_root().join(name.as_str());
+
+        CGroupController::check_writable(config.root())?;
+
+        fs::create_dir_all(&path).await.map_err(|e| {
+            if is_read_only_error(&e) {
+                return Error::CGroupReadOnly {
+                    path: config.root().to_path_buf(),
+                };
+            }
+            Error::CGroup {
+                message: format!(
+                    "Failed to create pod directory: {}\nPath: {}",
+                    e,
+                    path.display()
+                ),
+            }
+        })?;
+
+        // Without the marker a pod whose containers are gone looks like one
+        if let Err(e) = mark_pod(&path) {
+            tracing::warn!(path = %path.display(), error = %e, "Failed to mark pod cgroup");
+        }
+
+        // Children can only use controllers enabled at every level above them
+        CGroupController::enable_controllers_at(config.root()).await;
+        CGroupController::enable_controllers_at(&config.vortex_root()).await;
+        CGroupController::enable_controllers_at(&path).await;
+
+        let mut pod = Self {
+            name,
+            config,
+            path,
+            cpu: None,
+            memory: None,
+        };
+        pod.load_limits().await;
+
+        tracing::info!(pod = %pod.name, path = %pod.path.display(), "Pod ready");
+
+        Ok(pod)
+    }
+
+    /// Whether the cgroup at `path` was created as a pod
+    ///
+    /// This tells an empty pod from a container, which has no children
+    /// either.
+    #[must_use]
+    pub fn is_pod(path: &Path) -> bool {
+        let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+            return false;
+        };
+        unsafe { libc::getxattr(path.as_ptr(), POD_MARKER.as_ptr(), std::ptr::null_mut(), 0) >= 0 }
+    }
+
+    /// Get the pod name
+    #[must_use]
+    pub const fn name(&self) -> &ContainerId {
+        &self.name
+    }
+
+    /// Get the pod cgroup path
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the pod CPU cap, if any
+    #[must_use]
+    pub const fn cpu_limit(&self) -> Option<CpuLimit> {
+        self.cpu
+    }
+
+    /// Get the pod memory cap, if any
+    #[must_use]
+    pub const fn memory_limit(&self) -> Option<MemoryLimit> {
+        self.memory
+    }
+
+    /// Configuration for container cgroups created inside this pod
+    #[must_use]
+    pub fn child_config(&self) -> CGroupConfig {
+        self.config.clone().with_parent(self.name.as_str())
+    }
+
+    /// Set the aggregate CPU cap for the pod
+    pub async fn set_cpu_limit(&mut self, limit: CpuLimit) -> Result<()> {
+        CpuCores::try_new(limit.cores.as_f64())?;
+        let (quota, period) = limit.cores.to_quota();
+        self.write_file("cpu.max", &format!("{quota} {period}"))
+            .await?;
+        self.cpu = Some(limit);
+
+        tracing::info!(pod = %self.name, cores = limit.cores.as_f64(), "Set pod CPU limit");
+        Ok(())
+    }
+
+    /// Set the aggregate memory cap for the pod
+    ///
+    /// The limit is rounded up to whole pages, like a container's.
+    pub async fn set_memory_limit(&mut self, limit: MemoryLimit) -> Result<()> {
+        let limit = MemoryLimit {
+            limit: page_aligned_memory(limit.limit, page_size())?,
+            ..limit
+        };
+        self.write_file("memory.max", &limit.limit.as_bytes().to_string())
+            .await?;
+        if let Some(swap) = limit.swap {
+            self.write_file("memory.swap.max", &swap.as_bytes().to_string())
+                .await?;
+        }
+        self.memory = Some(limit);
+
+        tracing::info!(pod = %self.name, memory = %limit.limit, "Set pod memory limit");
+        Ok(())
+    }
+
+    /// Check that a container's limits fit within the pod caps
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidConfig`] if a limit exceeds the pod cap
+    pub fn check_fits(&self, cpu: Option<&CpuLimit>, memory: Option<&MemoryLimit>) -> Result<()> {
+        if let (Some(pod), Some(child)) = (self.cpu, cpu)
+            && child.cores.as_f64() > pod.cores.as_f64()
+        {
+            return Err(Error::InvalidConfig {
+                message: format!(
+                    "CPU limit of {} cores exceeds pod '{}' cap of {} cores",
+                    child.cores.as_f64(),
+                    self.name,
+                    pod.cores.as_f64()
+                ),
+            });
+        }
+
+        if let (Some(pod), Some(child)) = (self.memory, memory)
+            && child.limit > pod.limit
+        {
+            return Err(Error::InvalidConfig {
+                message: format!(
+                    "Memory limit of {} exceeds pod '{}' cap of {}",
+                    child.limit, self.name, pod.limit
+                ),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Create a container cgroup inside the pod and apply its limits
+    ///
+    /// # Errors
+    /// Returns error if the limits do not fit within the pod or the cgroup
+    /// cannot be created
+    pub async fn spawn(
+        &self,
+        container_id: ContainerId,
+        cpu: Option<CpuLimit>,
+        memory: Option<MemoryLimit>,
+    ) -> Result<CGroupController> {
+        self.check_fits(cpu.as_ref(), memory.as_ref())?;
+
+        let controller = CGroupController::with_config(container_id, self.child_config()).await?;
+
+        controller
+            .apply_limits(&ResourceLimits {
+                cpu,
+                memory,
+                ..ResourceLimits::default()
+            })
+            .await?;
+
+        Ok(controller)
+    }
+
+    /// List the container IDs currently in the pod
+    pub async fn containers(&self) -> Result<Vec<String>> {
+        let mut entries = fs::read_dir(&self.path).await?;
+        let mut containers = Vec::new();
+
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_type().await?.is_dir() {
+                containers.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+
+        containers.sort();
+        Ok(containers)
+    }
+
+    /// Remove the pod cgroup
+    ///
+    /// # Errors
+    /// Returns error if containers are still present in the pod
+    pub async fn cleanup(self) -> Result<()> {
+        let containers = self.containers().await?;
+        if !containers.is_empty() {
+            return Err(Error::CGroup {
+                message: format!(
+                    "Pod '{}' still has containers: {}",
+                    self.name,
+                    containers.join(", ")
+                ),
+            });
+        }
+
+        fs::remove_dir(&self.path)
+            .await
+            .map_err(|e| Error::CGroup {
+                message: format!("Failed to remove pod '{}': {e}", self.name),
+            })?;
+
+        tracing::info!(pod = %self.name, "Pod removed");
+        Ok(())
+    }
+
+    async fn write_file(&self, filename: &str, content: &str) -> Result<()> {
+        fs::write(self.path.join(filename), content)
+            .await
+            .map_err(|e| Error::CGroup {
+                message: format!("Failed to write pod {filename}: {e}"),
+            })
+    }
+
+    /// Read limits that were set on a previously created pod
+    async fn load_limits(&mut self) {
+        if let Ok(content) = fs::read_to_string(self.path.join("cpu.max")).await {
+            self.cpu = CpuLimit::from_cpu_max_str(&content).ok().flatten();
+        }
+        if let Ok(content) = fs::read_to_string(self.path.join("memory.max")).await {
+            self.memory = content
+                .trim()
+                .parse()
+                .ok()
+                .map(|bytes| MemoryLimit::new(MemorySize::from_bytes(bytes)));
+        }
+    }
+}
+
+/// Set the [`POD_MARKER`] attribute on a pod's directory
+fn mark_pod(path: &Path) -> std::io::Result<()> {
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    let value = b"1";
+    let set = unsafe {
+        libc::setxattr(
+            path.as_ptr(),
+            POD_MARKER.as_ptr(),
+            value.as_ptr().cast(),
+            value.len(),
+            0,
+        )
+    };
+    if set == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -238,3 +238,614 @@ async fn test_real_cgroup_controller() {
     // Cleanup
     controller.cleanup().await.unwrap();
 }
+
+#[tokio::test]
+#[ignore] // Requires root privileges
+async fn test_threaded_cgroup_takes_a_thread() {
+    if !is_root() {
+        println!("Skipping: requires root");
+        return;
+    }
+
+    // A pod without domain controllers enabled, so it can root a threaded
+    // subtree
+    let config = CGroupConfig::new().with_parent("threaded-test");
+    let pod = config.container_parent();
+    std::fs::create_dir_all(&pod).unwrap();
+    let worker = pod.join("worker");
+    std::fs::create_dir(&worker).unwrap();
+
+    let controller =
+        CGroupController::attach_with_config(ContainerId::new("worker").unwrap(), config).unwrap();
+    controller.set_threaded().await.unwrap();
+    assert_eq!(
+        std::fs::read_to_string(pod.join("cgroup.type"))
+            .unwrap()
+            .trim(),
+        "domain threaded"
+    );
+
+    // Threads can only move within the subtree, so the process joins the
+    // pod first and then sends its main thread on to the worker
+    let mut child = std::process::Command::new("sleep")
+        .arg("5")
+        .spawn()
+        .unwrap();
+    let tid = ProcessId::from_raw(i32::try_from(child.id()).unwrap());
+    std::fs::write(pod.join("cgroup.procs"), tid.as_raw().to_string()).unwrap();
+    controller.add_thread(tid).await.unwrap();
+    assert_eq!(controller.thread_ids().await.unwrap(), vec![tid]);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+    std::fs::remove_dir(&worker).unwrap();
+    std::fs::remove_dir(&pod).unwrap();
+}
+
+/// Create a unique scratch directory under the system temp dir
+fn temp_root(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("vortex-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[tokio::test]
+async fn test_controller_with_custom_root() {
+    let root = temp_root("custom-root");
+    let config = CGroupConfig::new().with_root(&root);
+
+    let controller = CGroupController::with_config(ContainerId::new("custom").unwrap(), config)
+        .await
+        .unwrap();
+
+    assert_eq!(controller.path(), root.join("vortex").join("custom"));
+    assert!(controller.path().is_dir());
+
+    // Call the inherent cleanup (the trait method needs only &self)
+    controller.cleanup().await.unwrap();
+    assert!(!root.join("vortex").join("custom").exists());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_cleanup_removes_nested_cgroups() {
+    let root = temp_root("nested");
+    let config = CGroupConfig::new().with_root(&root);
+
+    let controller = CGroupController::with_config(ContainerId::new("nested").unwrap(), config)
+        .await
+        .unwrap();
+
+    // Sub-cgroups created by the container itself
+    std::fs::create_dir_all(controller.path().join("a").join("b")).unwrap();
+    std::fs::create_dir_all(controller.path().join("c")).unwrap();
+
+    controller.cleanup_with(true).await.unwrap();
+    assert!(!root.join("vortex").join("nested").exists());
+
+    // Cleaning up again is a no-op
+    controller.cleanup_with(true).await.unwrap();
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_keep_on_exit_leaves_cgroup_until_removed() {
+    let root = temp_root("keep");
+    let config = CGroupConfig::new().with_root(&root);
+
+    let controller = CGroupController::with_config(ContainerId::new("kept").unwrap(), config)
+        .await
+        .unwrap()
+        .with_keep_on_exit(true);
+
+    controller.cleanup_with(true).await.unwrap();
+    assert!(controller.path().is_dir());
+    assert!(!controller.is_active());
+
+    controller.remove().await;
+    assert!(!controller.path().exists());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_list_processes() {
+    let root = temp_root("procs");
+    let config = CGroupConfig::new().with_root(&root);
+
+    let controller = CGroupController::with_config(ContainerId::new("procs").unwrap(), config)
+        .await
+        .unwrap();
+
+    std::fs::write(controller.path().join("cgroup.procs"), "").unwrap();
+    assert!(controller.processes().await.unwrap().is_empty());
+
+    std::fs::write(controller.path().join("cgroup.procs"), "12\n\n34\n 56 \n").unwrap();
+    let pids: Vec<i32> = controller
+        .processes()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(ProcessId::as_raw)
+        .collect();
+    assert_eq!(pids, vec![12, 34, 56]);
+
+    std::fs::write(controller.path().join("cgroup.threads"), "12\n13\n").unwrap();
+    assert_eq!(controller.thread_ids().await.unwrap().len(), 2);
+
+    // Mark as cleaned up without touching the fake pids
+    std::fs::write(controller.path().join("cgroup.procs"), "").unwrap();
+    controller.cleanup().await.unwrap();
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_current_cpu_limit() {
+    let root = temp_root("cpu-max");
+    let config = CGroupConfig::new().with_root(&root);
+
+    let controller = CGroupController::with_config(ContainerId::new("cpu-max").unwrap(), config)
+        .await
+        .unwrap();
+
+    controller
+        .set_cpu_limit(CpuLimit::new(CpuCores::new(0.5)))
+        .await
+        .unwrap();
+    let limit = controller.current_cpu_limit().await.unwrap().unwrap();
+    assert!((limit.cores.as_f64() - 0.5).abs() < f64::EPSILON);
+
+    std::fs::write(controller.path().join("cpu.max"), "max 100000\n").unwrap();
+    assert!(controller.current_cpu_limit().await.unwrap().is_none());
+
+    controller.cleanup().await.unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_apply_limits_writes_control_files() {
+    let root = temp_root("apply-limits");
+    let config = CGroupConfig::new().with_root(&root);
+
+    let controller =
+        CGroupController::with_config(ContainerId::new("apply-limits").unwrap(), config)
+            .await
+            .unwrap();
+    let read = |file: &str| std::fs::read_to_string(controller.path().join(file)).unwrap();
+
+    let limits = ResourceLimits {
+        cpu: Some(CpuLimit::new(CpuCores::new(0.5))),
+        cpu_weight: Some(50),
+        memory: Some(MemoryLimit::new(MemorySize::from_mb(64))),
+        pids: Some(32),
+        io: vec![IoLimit {
+            read_iops: Some(500),
+            ..IoLimit::new(259, 0)
+        }],
+    };
+    controller.apply_limits(&limits).await.unwrap();
+
+    assert_eq!(read("pids.max"), "32");
+    assert_eq!(read("cpu.weight"), "50");
+    assert_eq!(read("cpu.max"), "50000 100000");
+    assert_eq!(
+        read("io.max"),
+        "259:0 rbps=max wbps=max riops=500 wiops=max"
+    );
+    assert_eq!(read("memory.max"), (64 * 1024 * 1024).to_string());
+
+    // An out of range weight fails after pids went through
+    let limits = ResourceLimits {
+        pids: Some(16),
+        cpu_weight: Some(0),
+        ..ResourceLimits::default()
+    };
+    match controller.apply_limits(&limits).await {
+        Err(Error::LimitsPartiallyApplied {
+            applied, failed, ..
+        }) => {
+            assert_eq!(applied, vec!["pids"]);
+            assert_eq!(failed, "CPU weight");
+        }
+        other => panic!("Expected LimitsPartiallyApplied, got {other:?}"),
+    }
+    assert_eq!(read("pids.max"), "16");
+
+    controller.cleanup().await.unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_setters_write_what_apply_limits_writes() {
+    let root = temp_root("setters");
+    let config = CGroupConfig::new().with_root(&root);
+    let id = |name: &str| ContainerId::new(name).unwrap();
+    let batched = CGroupController::with_config(id("batched"), config.clone())
+        .await
+        .unwrap();
+    let single = CGroupController::with_config(id("single"), config)
+        .await
+        .unwrap();
+
+    let limits = ResourceLimits {
+        cpu: Some(CpuLimit::new(CpuCores::new(1.5))),
+        cpu_weight: Some(200),
+        memory: Some(MemoryLimit::with_swap(
+            MemorySize::from_bytes(10_000_000),
+            MemorySize::from_mb(8),
+        )),
+        pids: Some(64),
+        io: vec![IoLimit {
+            write_bps: Some(1 << 20),
+            ..IoLimit::new(8, 0)
+        }],
+    };
+    batched.apply_limits(&limits).await.unwrap();
+    single.set_pids_limit(64).await.unwrap();
+    single.set_cpu_weight(200).await.unwrap();
+    single.set_cpu_limit(limits.cpu.unwrap()).await.unwrap();
+    single.set_io_limit(limits.io[0]).await.unwrap();
+    single
+        .set_memory_limit(limits.memory.unwrap())
+        .await
+        .unwrap();
+
+    for file in [
+        "pids.max",
+        "cpu.weight",
+        "cpu.max",
+        "io.max",
+        "memory.max",
+        "memory.swap.max",
+    ] {
+        let read = |controller: &CGroupController| {
+            std::fs::read_to_string(controller.path().join(file)).unwrap()
+        };
+        assert_eq!(read(&batched), read(&single), "{file}");
+    }
+
+    batched.cleanup().await.unwrap();
+    single.cleanup().await.unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_memory_limit_is_checked() {
+    let root = temp_root("memory-max");
+    let config = CGroupConfig::new().with_root(&root);
+
+    let controller = CGroupController::with_config(ContainerId::new("memory-max").unwrap(), config)
+        .await
+        .unwrap();
+    let path = controller.path().to_path_buf();
+    let limit = MemoryLimit::new(MemorySize::from_mb(64));
+
+    // Page cache of an earlier run still charged to a reused cgroup
+    std::fs::write(
+        path.join("memory.current"),
+        format!("{}\n", 128 * 1024 * 1024),
+    )
+    .unwrap();
+    match controller.set_memory_limit_checked(limit, false).await {
+        Err(Error::MemoryBelowUsage { requested, usage }) => {
+            assert_eq!(requested, MemorySize::from_mb(64));
+            assert_eq!(usage, MemorySize::from_mb(128));
+        }
+        other => panic!("Expected MemoryBelowUsage, got {other:?}"),
+    }
+    assert!(!path.join("memory.max").exists());
+
+    // Forced, and kept as written
+    assert_eq!(
+        controller
+            .set_memory_limit_checked(limit, true)
+            .await
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        controller.current_memory_limit().await.unwrap(),
+        Some(MemorySize::from_mb(64))
+    );
+
+    // The kernel rounds down to whole pages
+    std::fs::write(path.join("memory.max"), "4096\n").unwrap();
+    assert_eq!(
+        controller
+            .verify_memory_limit(MemorySize::from_bytes(5000))
+            .await
+            .unwrap(),
+        Some(MemorySize::from_bytes(4096))
+    );
+
+    std::fs::write(path.join("memory.max"), "max\n").unwrap();
+    assert!(controller.current_memory_limit().await.unwrap().is_none());
+    assert!(
+        controller
+            .verify_memory_limit(MemorySize::from_mb(64))
+            .await
+            .is_err()
+    );
+
+    controller.cleanup().await.unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_stats_reads_swap() {
+    let root = temp_root("swap");
+    let config = CGroupConfig::new().with_root(&root);
+
+    let controller = CGroupController::with_config(ContainerId::new("swap").unwrap(), config)
+        .await
+        .unwrap();
+
+    let dir = controller.path().to_path_buf();
+    std::fs::write(dir.join("cpu.stat"), "usage_usec 1000\n").unwrap();
+    std::fs::write(dir.join("memory.current"), "8192\n").unwrap();
+    std::fs::write(dir.join("memory.peak"), "16384\n").unwrap();
+    std::fs::write(dir.join("memory.swap.current"), "2048\n").unwrap();
+    std::fs::write(dir.join("memory.swap.peak"), "4096\n").unwrap();
+
+    let stats = controller.stats().await.unwrap();
+    assert_eq!(stats.memory_peak.as_bytes(), 16384);
+    assert_eq!(stats.swap_current.as_bytes(), 2048);
+    assert_eq!(stats.swap_peak.as_bytes(), 4096);
+
+    controller.cleanup().await.unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_stats_without_peak_files() {
+    let root = temp_root("no-peak");
+    let config = CGroupConfig::new().with_root(&root);
+
+    let controller = CGroupController::with_config(ContainerId::new("no-peak").unwrap(), config)
+        .await
+        .unwrap();
+
+    // What a pre-5.19 kernel provides: no memory.peak or memory.swap.peak
+    std::fs::write(controller.path().join("cpu.stat"), "usage_usec 1000\n").unwrap();
+    std::fs::write(controller.path().join("memory.current"), "4096\n").unwrap();
+    std::fs::write(controller.path().join("memory.swap.current"), "512\n").unwrap();
+
+    let stats = controller.stats().await.unwrap();
+    assert_eq!(stats.memory_current.as_bytes(), 4096);
+    assert_eq!(stats.memory_peak, stats.memory_current);
+    assert_eq!(stats.swap_peak, stats.swap_current);
+
+    controller.cleanup().await.unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_set_oom_group() {
+    let root = temp_root("oom-group");
+    let config = CGroupConfig::new().with_root(&root);
+
+    let controller = CGroupController::with_config(ContainerId::new("oom-group").unwrap(), config)
+        .await
+        .unwrap();
+
+    controller.set_oom_group(true).await.unwrap();
+    let written = std::fs::read_to_string(controller.path().join("memory.oom.group")).unwrap();
+    assert_eq!(written, "1");
+
+    controller.cleanup().await.unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_running_container_id_conflict() {
+    let root = temp_root("conflict");
+    let config = CGroupConfig::new().with_root(&root);
+    let id = ContainerId::new("web").unwrap();
+
+    // A leftover directory without processes is reused
+    let existing = root.join("vortex").join("web");
+    std::fs::create_dir_all(&existing).unwrap();
+    let controller = CGroupController::with_config(id.clone(), config.clone())
+        .await
+        .unwrap();
+    controller.cleanup_with(false).await.unwrap();
+
+    // One with processes is a running container
+    std::fs::create_dir_all(&existing).unwrap();
+    std::fs::write(existing.join("cgroup.procs"), "4242\n").unwrap();
+    let err = CGroupController::with_config(id, config).await.unwrap_err();
+    assert!(matches!(err, Error::ContainerRunning { ref id } if id == "web"));
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_attach_is_read_only() {
+    let root = temp_root("attach");
+    let config = CGroupConfig::new().with_root(&root);
+    let id = ContainerId::new("attached").unwrap();
+
+    // Nothing to attach to yet
+    assert!(matches!(
+        CGroupController::attach_with_config(id.clone(), config.clone()),
+        Err(Error::ContainerNotFound { .. })
+    ));
+
+    let owner = CGroupController::with_config(id.clone(), config.clone())
+        .await
+        .unwrap();
+
+    let attached = CGroupController::attach_with_config(id, config).unwrap();
+    assert_eq!(attached.path(), owner.path());
+    assert!(!attached.is_active());
+
+    // Dropping the attached controller leaves the cgroup in place
+    drop(attached);
+    assert!(owner.path().is_dir());
+
+    owner.cleanup().await.unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_attached_controller_shares_cgroup() {
+    let root = temp_root("join");
+    let config = CGroupConfig::new().with_root(&root);
+    let id = ContainerId::new("shared").unwrap();
+
+    // Attaching never creates the cgroup
+    assert!(matches!(
+        CGroupController::attach_with_config(id.clone(), config.clone()),
+        Err(Error::ContainerNotFound { .. })
+    ));
+    assert!(!root.join("vortex").join("shared").exists());
+
+    let owner = CGroupController::with_config(id.clone(), config.clone())
+        .await
+        .unwrap();
+
+    let joined = CGroupController::attach_with_config(id, config).unwrap();
+    assert_eq!(joined.path(), owner.path());
+    joined.add_process(ProcessId::from_raw(4242)).await.unwrap();
+    assert_eq!(
+        std::fs::read_to_string(owner.path().join("cgroup.procs")).unwrap(),
+        "4242"
+    );
+
+    // Cleaning up or dropping the attached controller leaves the cgroup alone
+    joined.cleanup().await.unwrap();
+    drop(joined);
+    assert!(owner.path().is_dir());
+
+    std::fs::remove_file(owner.path().join("cgroup.procs")).unwrap();
+    owner.cleanup().await.unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_pod_hierarchy() {
+    let root = temp_root("pod");
+    let config = CGroupConfig::new().with_root(&root);
+
+    let mut pod = PodController::with_config("web", config).await.unwrap();
+    assert_eq!(pod.path(), root.join("vortex").join("web"));
+
+    pod.set_cpu_limit(CpuLimit::new(CpuCores::new(2.0)))
+        .await
+        .unwrap();
+    pod.set_memory_limit(MemoryLimit::new(MemorySize::from_mb(256)))
+        .await
+        .unwrap();
+
+    // Limits above the pod caps are rejected
+    let too_big = pod
+        .spawn(
+            ContainerId::new("big").unwrap(),
+            Some(CpuLimit::new(CpuCores::new(4.0))),
+            None,
+        )
+        .await;
+    assert!(matches!(too_big, Err(Error::InvalidConfig { .. })));
+
+    pod.check_fits(
+        Some(&CpuLimit::new(CpuCores::new(1.0))),
+        Some(&MemoryLimit::new(MemorySize::from_mb(128))),
+    )
+    .unwrap();
+
+    // No limits on the child so its directory stays empty and removable
+    // outside a real cgroup filesystem
+    let child = pod
+        .spawn(ContainerId::new("app").unwrap(), None, None)
+        .await
+        .unwrap();
+    assert_eq!(child.path(), pod.path().join("app"));
+    assert_eq!(pod.containers().await.unwrap(), vec!["app"]);
+    assert!(PodController::is_pod(pod.path()));
+    assert!(!PodController::is_pod(child.path()));
+
+    // Reopening the pod picks up its caps
+    let reopened = PodController::with_config("web", CGroupConfig::new().with_root(&root))
+        .await
+        .unwrap();
+    assert!((reopened.cpu_limit().unwrap().cores.as_f64() - 2.0).abs() < f64::EPSILON);
+    assert_eq!(
+        reopened.memory_limit().unwrap().limit,
+        MemorySize::from_mb(256)
+    );
+
+    // A pod with containers cannot be removed
+    assert!(reopened.cleanup().await.is_err());
+
+    child.cleanup().await.unwrap();
+    assert!(pod.containers().await.unwrap().is_empty());
+    // Still a pod once its last container is gone
+    assert!(PodController::is_pod(pod.path()));
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires root to mount a read-only tmpfs"]
+async fn test_read_only_cgroup_root() {
+    if !is_root() {
+        println!("Skipping: requires root");
+        return;
+    }
+
+    let root = temp_root("read-only");
+    let status = std::process::Command::new("mount")
+        .args(["-t", "tmpfs", "-o", "ro", "tmpfs"])
+        .arg(&root)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let config = CGroupConfig::new().with_root(&root);
+    let result = CGroupController::with_config(ContainerId::new("ro").unwrap(), config).await;
+
+    let _ = std::process::Command::new("umount").arg(&root).status();
+    std::fs::remove_dir_all(&root).unwrap();
+
+    match result {
+        Err(Error::CGroupReadOnly { path }) => assert_eq!(path, root),
+        other => panic!("Expected CGroupReadOnly, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+#[ignore] // Requires root privileges
+async fn test_device_filter_refuses_unlisted_devices() {
+    if !is_root() {
+        println!("Skipping: requires root");
+        return;
+    }
+
+    let config = CGroupConfig::new().with_parent("devices-test");
+    let cgroup = config.container_parent().join("filtered");
+    std::fs::create_dir_all(&cgroup).unwrap();
+    let controller =
+        CGroupController::attach_with_config(ContainerId::new("filtered").unwrap(), config)
+            .unwrap();
+
+    // The last rule wins, so /dev/zero is refused despite the defaults
+    let mut rules = DeviceRule::defaults();
+    rules.push("!c 1:5 rwm".parse().unwrap());
+    controller.set_device_access(&rules).await.unwrap();
+
+    let script = format!(
+        "echo $$ > {}; cat /dev/null && ! head -c1 /dev/zero",
+        cgroup.join("cgroup.procs").display()
+    );
+    let status = std::process::Command::new("/bin/sh")
+        .args(["-c", &script])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    std::fs::remove_dir(&cgroup).unwrap();
+    std::fs::remove_dir(cgroup.parent().unwrap()).unwrap();
+}
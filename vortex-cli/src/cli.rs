@@ -1,4 +1,9 @@
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use nix::sys::signal::Signal;
+use std::path::PathBuf;
+use std::time::Duration;
+use vortex_core::CpuCores;
+use vortex_namespace::{Hook, OomScoreAdj, Rlimit, SchedPolicy, TmpfsMount, User};
 
 /// Vortex container runtime
 #[derive(Parser, Debug)]
@@ -6,58 +11,233 @@ use clap::{Parser, Subcommand};
 #[command(about = "Lightweight container runtime", long_about = None)]
 #[command(version)]
 pub struct Cli {
+    /// Log output format
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    /// Shorthand for --log-format json
+    #[arg(long, global = true)]
+    pub json_logs: bool,
+
+    /// Log level filter (overrides RUST_LOG), e.g. "debug" or "vortex_cgroup=trace"
+    #[arg(long, global = true)]
+    pub log_level: Option<String>,
+
+    /// Print plain ASCII instead of emoji (also set by NO_COLOR or VORTEX_PLAIN)
+    #[arg(long, global = true, alias = "no-emoji")]
+    pub plain: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+/// Log output format
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable text
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// Output format for `list`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListFormat {
+    /// Aligned table with CPU and memory
+    Table,
+    /// JSON array of container objects
+    Json,
+    /// Table with state, PID, uptime and I/O as well
+    Wide,
+}
+
+/// Output format for `info`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InfoFormat {
+    /// One line per detected feature
+    Text,
+    /// JSON object
+    Json,
+}
+
+/// Output format for `run`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunFormat {
+    /// Progress and results for humans
+    Text,
+    /// Only the execution result as a JSON object, once the container exits
+    Json,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Run a container
-    Run {
+    Run(Box<RunArgs>),
+
+    /// Get container stats
+    Stats {
+        /// Container ID
+        #[arg(short, long, required_unless_present = "pid", conflicts_with = "pid")]
+        id: Option<String>,
+
+        /// Read the cgroup of any process instead, even one vortex did not start
+        #[arg(long, conflicts_with_all = ["pod", "follow"])]
+        pid: Option<i32>,
+
+        /// Pod the container belongs to
+        #[arg(long)]
+        pod: Option<String>,
+
+        /// Keep printing stats until interrupted or the container exits
+        #[arg(short, long)]
+        follow: bool,
+
+        /// Refresh interval for --follow, e.g. 500ms or 2s
+        #[arg(long, requires = "follow", value_parser = parse_duration, default_value = "1s")]
+        interval: Duration,
+
+        /// Start the Peak column of --follow over from the current usage
+        /// instead of the peak since the container started (Linux 6.12+)
+        #[arg(long, requires = "follow")]
+        reset_peak: bool,
+
+        /// Also show hugetlb and misc controller usage
+        #[arg(short, long, conflicts_with = "follow")]
+        verbose: bool,
+    },
+
+    /// Show a container's configuration and live state as JSON
+    Inspect {
         /// Container ID
         #[arg(short, long)]
         id: String,
+    },
 
-        /// CPU limit in cores (default: 1.0)
-        #[arg(long, default_value = "1.0")]
-        cpu: f64,
+    /// Print the output of a detached container
+    Logs {
+        /// Container ID
+        #[arg(short, long)]
+        id: String,
+    },
 
-        /// Memory limit in MB (default: 512)
-        #[arg(long, default_value = "512")]
-        memory: u64,
+    /// Show the processes in a container as a tree, with host and
+    /// container PIDs
+    Ps {
+        /// Container ID
+        #[arg(short, long)]
+        id: String,
 
-        /// Enable resource monitoring
+        /// Pod the container belongs to
         #[arg(long)]
-        monitor: bool,
+        pod: Option<String>,
+    },
 
-        /// Disable namespaces (no isolation)
-        #[arg(long)]
-        no_namespaces: bool,
+    /// List all containers
+    List {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ListFormat::Table)]
+        format: ListFormat,
+    },
 
-        /// Container hostname
+    /// Stop a container: send it a signal, then SIGKILL whatever is left
+    /// after the grace period
+    Stop {
+        /// Container ID
+        #[arg(short, long)]
+        id: String,
+
+        /// Pod the container belongs to
         #[arg(long)]
-        hostname: Option<String>,
+        pod: Option<String>,
+
+        /// Signal asking the processes to exit, e.g. INT or SIGTERM
+        #[arg(long, value_parser = parse_signal, default_value = "TERM")]
+        signal: Signal,
 
-        /// Command to run
-        #[arg(last = true, required = true)]
-        command: Vec<String>,
+        /// How long to wait for them before SIGKILL, e.g. 10s or 0
+        #[arg(long, value_parser = parse_duration, default_value = "10s")]
+        grace: Duration,
     },
 
-    /// Get container stats
-    Stats {
+    /// Stop a container and start it again in the background with the spec
+    /// it was run with
+    Restart {
         /// Container ID
         #[arg(short, long)]
         id: String,
+
+        /// How long to wait after the stop signal before SIGKILL, e.g. 10s or 0
+        #[arg(long, value_parser = parse_duration, default_value = "10s")]
+        timeout: Duration,
     },
 
-    /// List all containers
-    List,
+    /// Wait for a detached container to exit, print its exit code and exit
+    /// with it
+    Wait {
+        /// Container ID
+        #[arg(short, long)]
+        id: String,
 
-    /// Stop a container
-    Stop {
+        /// Give up after this long, e.g. 30s (default: wait forever)
+        #[arg(long, value_parser = parse_duration)]
+        timeout: Option<Duration>,
+    },
+
+    /// Change the limits of a running container; the limits from before the
+    /// first update are saved for --revert
+    Update {
         /// Container ID
         #[arg(short, long)]
         id: String,
+
+        /// Pod the container belongs to
+        #[arg(long)]
+        pod: Option<String>,
+
+        /// CPU limit in cores, e.g. 0.5, or in percent of one core, e.g.
+        /// 50% (1.0 and 100% are the same)
+        #[arg(long, value_name = "CORES", value_parser = parse_cpu)]
+        cpu: Option<CpuCores>,
+
+        /// Memory limit in MB
+        #[arg(long)]
+        memory: Option<u64>,
+
+        /// Maximum number of processes
+        #[arg(long, value_name = "N")]
+        pids_max: Option<u64>,
+
+        /// Set the memory limit even below current usage (processes may get
+        /// OOM-killed)
+        #[arg(long)]
+        force: bool,
+
+        /// Put back the limits saved by the first update
+        #[arg(
+            long,
+            conflicts_with_all = ["cpu", "memory", "pids_max", "force"],
+            required_unless_present_any = ["cpu", "memory", "pids_max"]
+        )]
+        revert: bool,
+    },
+
+    /// Remove a stopped container's cgroup, record and logs
+    Rm {
+        /// Container ID
+        #[arg(short, long, required_unless_present = "all", conflicts_with = "all")]
+        id: Option<String>,
+
+        /// Pod the container belongs to (default: the recorded pod)
+        #[arg(long)]
+        pod: Option<String>,
+
+        /// Kill the container first if it is still running
+        #[arg(short, long)]
+        force: bool,
+
+        /// Remove every stopped container
+        #[arg(long)]
+        all: bool,
     },
 
     /// Show namespace information
@@ -69,4 +249,341 @@ pub enum Commands {
 
     /// Check system health and requirements
     Health,
+
+    /// Show runtime version and detected system capabilities
+    Info {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = InfoFormat::Text)]
+        format: InfoFormat,
+    },
+}
+
+impl Commands {
+    /// Whether the command was asked for JSON output, so its errors are
+    /// reported as JSON too
+    pub fn json_output(&self) -> bool {
+        match self {
+            Self::Run(args) => args.format == RunFormat::Json,
+            Self::List { format } => *format == ListFormat::Json,
+            Self::Info { format } => *format == InfoFormat::Json,
+            _ => false,
+        }
+    }
+}
+
+/// Arguments for the `run` subcommand
+#[derive(Args, Debug)]
+#[command(group(ArgGroup::new("stop_trigger").args(["timeout", "idle_timeout"]).multiple(true)))]
+pub struct RunArgs {
+    /// Container ID (a random one is generated if omitted)
+    #[arg(short, long)]
+    pub id: Option<String>,
+
+    /// CPU limit in cores, e.g. 0.5, or in percent of one core, e.g. 50%
+    /// (1.0 and 100% are the same; default: 1.0)
+    #[arg(long, value_name = "CORES", value_parser = parse_cpu)]
+    pub cpu: Option<CpuCores>,
+
+    /// Memory limit in MB (default: 512)
+    #[arg(long)]
+    pub memory: Option<u64>,
+
+    /// Maximum number of processes in the container
+    #[arg(long, value_name = "N")]
+    pub pids_max: Option<u64>,
+
+    /// Preset limits and namespaces from a named profile; explicit flags
+    /// override it (built-in: small, medium, large)
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Enable resource monitoring
+    #[arg(long)]
+    pub monitor: bool,
+
+    /// Monitoring poll interval, e.g. 500ms or 2s (default: 2s)
+    #[arg(long, requires = "monitor", value_parser = parse_duration)]
+    pub monitor_interval: Option<Duration>,
+
+    /// Stop the container once its CPU usage has stayed below
+    /// --idle-threshold this long, e.g. 600s. Detection is CPU based: a
+    /// container waiting on I/O or the network counts as idle
+    #[arg(long, value_name = "DURATION", requires = "monitor", value_parser = parse_duration)]
+    pub idle_timeout: Option<Duration>,
+
+    /// CPU usage in percent of one core below which the container counts as
+    /// idle (default: 1)
+    #[arg(long, value_name = "PERCENT", requires = "idle_timeout", value_parser = parse_percent)]
+    pub idle_threshold: Option<f64>,
+
+    /// Disable namespaces (no isolation)
+    #[arg(long)]
+    pub no_namespaces: bool,
+
+    /// Container hostname
+    #[arg(long)]
+    pub hostname: Option<String>,
+
+    /// Let setuid binaries and file capabilities gain privileges
+    /// (no_new_privs is set by default)
+    #[arg(long)]
+    pub allow_new_privileges: bool,
+
+    /// Pass only this host variable, e.g. TERM, to the container and drop
+    /// the rest of the host environment; bundle and profile variables
+    /// still apply and win (repeatable)
+    #[arg(long, value_name = "KEY")]
+    pub env_keep: Vec<String>,
+
+    /// Process resource limit, e.g. nofile=1024:2048 (repeatable)
+    #[arg(long = "ulimit", value_name = "NAME=SOFT[:HARD]")]
+    pub ulimits: Vec<Rlimit>,
+
+    /// Numeric user and group to run as; the group defaults to the uid
+    #[arg(short, long, value_name = "UID[:GID]")]
+    pub user: Option<User>,
+
+    /// Supplementary group ID for the container process (repeatable)
+    #[arg(long = "group-add", value_name = "GID")]
+    pub groups: Vec<u32>,
+
+    /// OOM killer preference from -1000 (never kill) to 1000 (kill first)
+    #[arg(long, value_name = "SCORE", allow_negative_numbers = true)]
+    pub oom_score_adj: Option<OomScoreAdj>,
+
+    /// CPU scheduling policy: other, batch, idle, or the real-time fifo and
+    /// rr, which need --sched-priority and CAP_SYS_NICE
+    #[arg(long, value_name = "POLICY")]
+    pub sched: Option<SchedPolicy>,
+
+    /// Real-time priority from 1 to 99 for --sched fifo or rr
+    #[arg(long, value_name = "PRIORITY", requires = "sched")]
+    pub sched_priority: Option<i32>,
+
+    /// Nice value from -20 (highest precedence) to 19; lowering it needs
+    /// CAP_SYS_NICE
+    #[arg(long, value_name = "NICE", allow_negative_numbers = true)]
+    pub nice: Option<i32>,
+
+    /// Have the OOM killer kill the whole container at once
+    /// (memory.oom.group) instead of a single process
+    #[arg(long)]
+    pub oom_group: bool,
+
+    /// Attach the container to a pseudo-terminal for interactive use
+    /// (stdin is /dev/null otherwise)
+    #[arg(short, long, visible_alias = "interactive")]
+    pub tty: bool,
+
+    /// Capture the output and print it once the container exits, instead of
+    /// passing it through live (implied by --format json)
+    #[arg(long, conflicts_with_all = ["tty", "detach"])]
+    pub capture: bool,
+
+    /// Send stderr to stdout, keeping the order of their writes (2>&1)
+    #[arg(long, conflicts_with = "tty")]
+    pub merge_stderr: bool,
+
+    /// Stop the container once it has run this long, e.g. 30s
+    #[arg(long, value_parser = parse_duration, conflicts_with = "detach")]
+    pub timeout: Option<Duration>,
+
+    /// Signal sent on timeout or idle timeout, e.g. INT or SIGTERM
+    /// (default: TERM)
+    #[arg(long, value_name = "SIGNAL", requires = "stop_trigger", value_parser = parse_signal)]
+    pub stop_signal: Option<Signal>,
+
+    /// How long after the stop signal to send SIGKILL (default: 10s)
+    #[arg(long, value_name = "DURATION", requires = "stop_trigger", value_parser = parse_duration)]
+    pub stop_grace: Option<Duration>,
+
+    /// Run a minimal init as PID 1 that forwards signals and reaps zombies
+    #[arg(long, conflicts_with = "no_namespaces")]
+    pub init: bool,
+
+    /// Run in the background and print the container ID; output goes to
+    /// log files readable with `vortex logs`
+    #[arg(short, long, conflicts_with_all = ["tty", "monitor"])]
+    pub detach: bool,
+
+    /// Root filesystem directory (overrides root.path from the bundle)
+    #[arg(long, value_name = "DIR")]
+    pub rootfs: Option<PathBuf>,
+
+    /// Run on a throwaway writable overlay so the root filesystem itself is
+    /// never modified
+    #[arg(long)]
+    pub overlay: bool,
+
+    /// Mount a private tmpfs, e.g. /tmp:size=64m (repeatable); options are
+    /// size, mode, ro and noexec
+    #[arg(long, value_name = "TARGET[:OPTIONS]")]
+    pub tmpfs: Vec<TmpfsMount>,
+
+    /// Command run once the namespaces exist, before the container program
+    /// starts; it gets the container state (with the PID) as JSON on stdin
+    /// and a failure aborts the run (repeatable)
+    #[arg(long, value_name = "COMMAND")]
+    pub hook_prestart: Vec<Hook>,
+
+    /// Command run after the container is cleaned up; a failure is only
+    /// logged (repeatable)
+    #[arg(long, value_name = "COMMAND", conflicts_with = "detach")]
+    pub hook_poststop: Vec<Hook>,
+
+    /// Keep the cgroup after the container exits so its stats can still be
+    /// read; remove it with `vortex rm`
+    #[arg(long, conflicts_with = "detach")]
+    pub keep: bool,
+
+    /// Replace a running container with the same ID (its processes are killed)
+    #[arg(long)]
+    pub replace: bool,
+
+    /// Set the memory limit even when the cgroup already uses more, e.g. page
+    /// cache left in a kept cgroup (its processes get OOM-killed)
+    #[arg(long)]
+    pub force: bool,
+
+    /// Run the container inside a pod, creating the pod if needed
+    #[arg(long)]
+    pub pod: Option<String>,
+
+    /// Create the container cgroup in an existing cgroup instead of vortex/,
+    /// e.g. machine.slice (relative to /sys/fs/cgroup). It must be delegated:
+    /// writable by vortex and offering the cpu, memory and io controllers,
+    /// which for a systemd slice means Delegate=yes
+    #[arg(long, value_name = "PATH", value_parser = parse_cgroup_parent, conflicts_with = "pod")]
+    pub cgroup_parent: Option<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = RunFormat::Text, conflicts_with_all = ["monitor", "detach"])]
+    pub format: RunFormat,
+
+    /// OCI bundle directory containing a config.json
+    #[arg(long)]
+    pub bundle: Option<PathBuf>,
+
+    /// Command to run (overrides process.args from the bundle)
+    #[arg(last = true, required_unless_present = "bundle")]
+    pub command: Vec<String>,
+}
+
+/// Parse a duration like `250ms`, `2s` or `1.5s`; a bare number means seconds
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let (number, scale) = if let Some(ms) = value.strip_suffix("ms") {
+        (ms, 1e-3)
+    } else if let Some(secs) = value.strip_suffix('s') {
+        (secs, 1.0)
+    } else {
+        (value, 1.0)
+    };
+
+    let number: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid duration '{value}' (expected e.g. 500ms or 2s)"))?;
+
+    Duration::try_from_secs_f64(number * scale)
+        .map_err(|e| format!("invalid duration '{value}': {e}"))
+}
+
+/// Parse a CPU limit given in cores like `1.5` or in percent of one core
+/// like `150%`
+fn parse_cpu(value: &str) -> Result<CpuCores, String> {
+    let (number, scale) = match value.strip_suffix('%') {
+        Some(percent) => (percent, 0.01),
+        None => (value, 1.0),
+    };
+
+    let number: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid CPU limit '{value}' (expected e.g. 0.5 or 50%)"))?;
+
+    CpuCores::try_new(number * scale).map_err(|e| e.to_string())
+}
+
+/// Parse a non-negative percentage like `5` or `0.5`
+fn parse_percent(value: &str) -> Result<f64, String> {
+    match value.trim_end_matches('%').parse::<f64>() {
+        Ok(percent) if percent.is_finite() && percent >= 0.0 => Ok(percent),
+        _ => Err(format!(
+            "invalid percentage '{value}' (expected e.g. 5 or 0.5)"
+        )),
+    }
+}
+
+/// Parse a cgroup path like `machine.slice/vortex.slice`, relative to the
+/// cgroup root whether or not it starts with `/`
+fn parse_cgroup_parent(value: &str) -> Result<String, String> {
+    let relative = value.trim_matches('/');
+    let plain = relative
+        .split('/')
+        .all(|part| !matches!(part, "" | "." | ".."));
+    if !plain {
+        return Err(format!(
+            "invalid cgroup parent '{value}' (expected a path like machine.slice)"
+        ));
+    }
+    Ok(relative.to_string())
+}
+
+/// Parse a signal like `INT`, `SIGTERM` or `9`
+fn parse_signal(value: &str) -> Result<Signal, String> {
+    vortex_namespace::terminate::parse_signal(value)
+        .map_err(|_| format!("unknown signal '{value}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("2s").unwrap(), Duration::from_secs(2));
+        assert_eq!(parse_duration("1.5").unwrap(), Duration::from_millis(1500));
+        assert!(parse_duration("-1s").is_err());
+        assert!(parse_duration("fast").is_err());
+    }
+
+    #[test]
+    fn test_parse_cpu() {
+        let cores = |value| parse_cpu(value).unwrap().as_f64();
+        assert!((cores("50%") - 0.5).abs() < f64::EPSILON);
+        assert!((cores("100%") - cores("1.0")).abs() < f64::EPSILON);
+        assert!((cores("250%") - 2.5).abs() < f64::EPSILON);
+        assert!((cores("0.25") - 0.25).abs() < f64::EPSILON);
+
+        for invalid in ["abc%", "-50%", "0%", "%", "50%%", "abc", "-1"] {
+            assert!(parse_cpu(invalid).is_err(), "{invalid} was accepted");
+        }
+    }
+
+    #[test]
+    fn test_parse_percent() {
+        assert!((parse_percent("5").unwrap() - 5.0).abs() < f64::EPSILON);
+        assert!((parse_percent("0.5%").unwrap() - 0.5).abs() < f64::EPSILON);
+        assert!(parse_percent("-1").is_err());
+        assert!(parse_percent("NaN").is_err());
+    }
+
+    #[test]
+    fn test_parse_cgroup_parent() {
+        assert_eq!(
+            parse_cgroup_parent("machine.slice").unwrap(),
+            "machine.slice"
+        );
+        assert_eq!(
+            parse_cgroup_parent("/machine.slice/vortex.slice/").unwrap(),
+            "machine.slice/vortex.slice"
+        );
+        for invalid in ["", "/", "../etc", "a/./b", "a/../b", "a//b"] {
+            assert!(
+                parse_cgroup_parent(invalid).is_err(),
+                "{invalid} was accepted"
+            );
+        }
+    }
 }
@@ -1,100 +1,105 @@
 use anyhow::Result;
 use std::path::Path;
+use vortex_cgroup::{CGroupController, CGroupMode, controller, mode};
+
+use super::run;
+use crate::error::CliError;
+use crate::output::Icon;
 
 /// Execute health check command
 pub async fn execute() -> Result<()> {
-    println!("\n🏥 Vortex Health Check\n");
+    println!("\n{}Vortex Health Check\n", Icon::Health);
     println!("{:-<60}", "");
 
-    // Check 1: CGroup v2
+    // Check 1: CGroup mode (unified, hybrid or legacy), first so a hybrid
+    // host is explained before the v2 check fails
+    check_cgroup_mode();
+
+    // Check 2: CGroup v2
     check_cgroup_v2()?;
 
-    // Check 2: Permissions
+    // Check 3: CGroup writability
+    check_cgroup_writable()?;
+
+    // Check 4: Permissions
     check_permissions()?;
 
-    // Check 3: Namespace support
+    // Check 5: Capabilities
+    check_capabilities()?;
+
+    // Check 6: Namespace support
     check_namespace_support()?;
 
-    // Check 4: Required binaries
+    // Check 7: Required binaries
     check_binaries()?;
 
     println!("{:-<60}", "");
-    println!("\n✅ All systems operational!\n");
+    println!("\n{}All systems operational!\n", Icon::Ok);
 
     Ok(())
 }
 
+/// Cgroup v2 mount point
+pub(super) const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Namespace types vortex relies on
+pub(super) const NAMESPACES: [&str; 5] = ["pid", "mnt", "uts", "ipc", "net"];
+
 /// Check if running as root
-fn is_root() -> bool {
+pub(super) fn is_root() -> bool {
     unsafe { libc::getuid() == 0 }
 }
 
+/// Namespace types in `names` the kernel does not offer
+pub(super) fn missing_namespaces<'a>(names: &[&'a str]) -> Vec<&'a str> {
+    let ns_dir = Path::new("/proc/self/ns");
+    names
+        .iter()
+        .copied()
+        .filter(|name| !ns_dir.join(name).exists())
+        .collect()
+}
+
 /// Check if CGroup v2 is available and properly configured
 fn check_cgroup_v2() -> Result<()> {
     print!("Checking CGroup v2... ");
 
-    let cgroup_root = Path::new("/sys/fs/cgroup");
+    let cgroup_root = Path::new(CGROUP_ROOT);
 
     if !cgroup_root.exists() {
-        println!("❌ NOT FOUND");
-        anyhow::bail!(
-            "CGroup v2 not mounted at /sys/fs/cgroup\n\
-             \n\
-             To check your CGroup configuration:\n\
-             $ mount | grep cgroup2\n\
-             \n\
-             CGroup v2 is required for Vortex to function."
-        );
+        println!("{}NOT FOUND", Icon::Error);
+        return Err(CliError::CgroupV2Missing.into());
     }
 
     // Check if it's actually cgroup v2 (not v1)
-    let cgroup_controllers = cgroup_root.join("cgroup.controllers");
-    if !cgroup_controllers.exists() {
-        println!("❌ CGROUP v1 DETECTED");
-        anyhow::bail!(
-            "CGroup v1 detected, but Vortex requires CGroup v2\n\
-             \n\
-             You may need to:\n\
-             • Update your kernel (5.0+)\n\
-             • Change kernel boot parameters\n\
-             • Disable CGroup v1 in systemd"
-        );
+    let controllers_file = cgroup_root.join("cgroup.controllers");
+    if !controllers_file.exists() {
+        println!("{}CGROUP v1 DETECTED", Icon::Error);
+        return Err(CliError::CgroupV1Only.into());
     }
 
     // Check available controllers
-    match std::fs::read_to_string(&cgroup_controllers) {
+    match CGroupController::root_controllers(cgroup_root) {
         Ok(controllers) => {
-            let has_cpu = controllers.contains("cpu");
-            let has_memory = controllers.contains("memory");
-            let has_io = controllers.contains("io");
-
-            if !has_cpu || !has_memory || !has_io {
-                println!("⚠️  INCOMPLETE");
-                println!("   Available: {}", controllers.trim());
-                println!("   Missing: {}", {
-                    let mut missing = Vec::new();
-                    if !has_cpu {
-                        missing.push("cpu");
-                    }
-                    if !has_memory {
-                        missing.push("memory");
-                    }
-                    if !has_io {
-                        missing.push("io");
-                    }
-                    missing.join(", ")
-                });
-                anyhow::bail!(
-                    "Required controllers not available\n\
-                     \n\
-                     Vortex requires: cpu, memory, io"
-                );
+            let missing = controller::missing_controllers(&controllers);
+            if !missing.is_empty() {
+                println!("{}INCOMPLETE", Icon::Warning);
+                println!("   Available: {}", controllers.join(" "));
+                println!("   Missing: {}", missing.join(", "));
+                return Err(CliError::ControllersMissing {
+                    missing: missing.iter().map(ToString::to_string).collect(),
+                }
+                .into());
             }
 
-            println!("✅ OK (cpu, memory, io available)");
+            println!(
+                "{}OK ({} available)",
+                Icon::Ok,
+                controller::REQUIRED_CONTROLLERS.join(", ")
+            );
         }
         Err(e) => {
-            println!("❌ ERROR");
+            println!("{}ERROR", Icon::Error);
             anyhow::bail!("Could not read controllers: {}", e);
         }
     }
@@ -102,22 +107,58 @@ fn check_cgroup_v2() -> Result<()> {
     Ok(())
 }
 
+/// Report how cgroups are mounted, warning about controllers held by v1
+///
+/// Legacy hosts already fail the v2 check, so only hybrid ones warn here.
+fn check_cgroup_mode() {
+    print!("Checking CGroup mode... ");
+
+    match CGroupMode::detect() {
+        Some(CGroupMode::Hybrid) => {
+            println!("{}HYBRID", Icon::Warning);
+            let v1 = std::fs::read_to_string(mode::MOUNTINFO)
+                .map(|mountinfo| mode::v1_controllers(&mountinfo))
+                .unwrap_or_default();
+            if !v1.is_empty() {
+                println!("   Bound to cgroup v1: {}", v1.join(", "));
+            }
+            println!("   Controllers on v1 are unavailable to Vortex");
+            println!("   Boot with systemd.unified_cgroup_hierarchy=1 to move them to v2");
+        }
+        Some(mode) => println!("{}OK ({mode})", Icon::Ok),
+        None => println!("{}UNKNOWN", Icon::Warning),
+    }
+}
+
+/// Check that the cgroup filesystem is not mounted read-only
+fn check_cgroup_writable() -> Result<()> {
+    print!("Checking CGroup writability... ");
+
+    match CGroupController::check_writable(Path::new(CGROUP_ROOT)) {
+        Ok(()) => {
+            println!("{}OK (read-write)", Icon::Ok);
+            Ok(())
+        }
+        Err(e) => {
+            println!("{}READ-ONLY", Icon::Error);
+            Err(CliError::CgroupReadOnly {
+                message: e.to_string(),
+            }
+            .into())
+        }
+    }
+}
+
 /// Check if running with proper permissions
 fn check_permissions() -> Result<()> {
     print!("Checking permissions... ");
 
     if !is_root() {
-        println!("❌ NOT ROOT");
-        anyhow::bail!(
-            "Must run as root\n\
-             \n\
-             Vortex requires root permissions to:\n\
-             • Create and manage cgroups\n\
-             • Create namespaces\n\
-             • Access /sys/fs/cgroup\n\
-             \n\
-             Try: sudo vortex health"
-        );
+        println!("{}NOT ROOT", Icon::Error);
+        return Err(CliError::PermissionDenied {
+            operation: "check the host".to_string(),
+        }
+        .into());
     }
 
     // Check if we can write to cgroup root
@@ -126,10 +167,10 @@ fn check_permissions() -> Result<()> {
         Ok(()) => {
             // Clean up test directory
             let _ = std::fs::remove_dir(test_dir);
-            println!("✅ OK (root with write access)");
+            println!("{}OK (root with write access)", Icon::Ok);
         }
         Err(e) => {
-            println!("⚠️  LIMITED");
+            println!("{}LIMITED", Icon::Warning);
             println!("   Cannot write to /sys/fs/cgroup: {}", e);
             println!("   This may cause issues");
         }
@@ -138,6 +179,27 @@ fn check_permissions() -> Result<()> {
     Ok(())
 }
 
+/// Check the effective capabilities cover what a default `vortex run` needs
+fn check_capabilities() -> Result<()> {
+    print!("Checking capabilities... ");
+
+    let required = run::required_capabilities(&run::default_spec());
+    match vortex_security::require_capabilities(&required) {
+        Ok(()) => {
+            let names: Vec<String> = required.iter().map(ToString::to_string).collect();
+            println!("{}OK ({})", Icon::Ok, names.join(", "));
+            Ok(())
+        }
+        Err(e) => {
+            println!("{}MISSING", Icon::Error);
+            Err(CliError::CapabilitiesMissing {
+                message: e.to_string(),
+            }
+            .into())
+        }
+    }
+}
+
 /// Check if namespace support is available
 fn check_namespace_support() -> Result<()> {
     print!("Checking namespace support... ");
@@ -145,36 +207,25 @@ fn check_namespace_support() -> Result<()> {
     // Check /proc/self/ns/ exists
     let ns_dir = Path::new("/proc/self/ns");
     if !ns_dir.exists() {
-        println!("❌ NOT SUPPORTED");
-        anyhow::bail!(
-            "Kernel doesn't support namespaces\n\
-             \n\
-             Your kernel may be too old or compiled without namespace support."
-        );
-    }
-
-    // Check for required namespace types
-    let required = ["pid", "mnt", "uts", "ipc", "net"];
-    let mut missing = Vec::new();
-
-    for ns_type in &required {
-        let ns_file = ns_dir.join(ns_type);
-        if !ns_file.exists() {
-            missing.push(*ns_type);
+        println!("{}NOT SUPPORTED", Icon::Error);
+        return Err(CliError::NamespacesUnsupported {
+            missing: Vec::new(),
         }
+        .into());
     }
 
+    // Check for required namespace types
+    let missing = missing_namespaces(&NAMESPACES);
     if !missing.is_empty() {
-        println!("❌ INCOMPLETE");
+        println!("{}INCOMPLETE", Icon::Error);
         println!("   Missing: {}", missing.join(", "));
-        anyhow::bail!(
-            "Required namespace types not available\n\
-             \n\
-             Your kernel may need to be reconfigured."
-        );
+        return Err(CliError::NamespacesUnsupported {
+            missing: missing.iter().map(ToString::to_string).collect(),
+        }
+        .into());
     }
 
-    println!("✅ OK (all types available)");
+    println!("{}OK (all types available)", Icon::Ok);
     Ok(())
 }
 
@@ -193,11 +244,11 @@ fn check_binaries() -> Result<()> {
     }
 
     if !missing.is_empty() {
-        println!("⚠️  MISSING");
+        println!("{}MISSING", Icon::Warning);
         println!("   Not found: {}", missing.join(", "));
         println!("   Containers may not work properly");
     } else {
-        println!("✅ OK");
+        println!("{}OK", Icon::Ok);
     }
 
     Ok(())
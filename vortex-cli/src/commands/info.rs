@@ -0,0 +1,263 @@
+//! Info command implementation
+//!
+//! Unlike `health`, nothing here fails: every probe is reported as found or
+//! not, so the output can be pasted into a bug report as is.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+use vortex_cgroup::{CGroupController, CGroupMode};
+use vortex_security::{Capability, CapabilitySet};
+
+use super::health::{self, CGROUP_ROOT};
+use crate::cli::InfoFormat;
+use crate::output::Icon;
+
+/// Every namespace type the kernel may offer
+const ALL_NAMESPACES: [&str; 8] = ["pid", "mnt", "uts", "ipc", "net", "user", "cgroup", "time"];
+
+/// Capabilities the runtime may need for some container
+const CAPABILITIES: [Capability; 4] = [
+    Capability::SysAdmin,
+    Capability::SysResource,
+    Capability::SysNice,
+    Capability::SysTime,
+];
+
+#[derive(Debug, Serialize)]
+struct SystemInfo {
+    version: &'static str,
+    kernel: Option<String>,
+    root: bool,
+    cgroup: CgroupInfo,
+    namespaces: Vec<NamespaceInfo>,
+    features: Features,
+    missing_capabilities: Option<Vec<String>>,
+    rootless: Rootless,
+}
+
+#[derive(Debug, Serialize)]
+struct CgroupInfo {
+    /// `"v2"`, `"v1"` or `None` when nothing is mounted
+    version: Option<&'static str>,
+    /// How cgroups are mounted, `None` when unknown
+    mode: Option<CGroupMode>,
+    writable: bool,
+    controllers: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct NamespaceInfo {
+    name: &'static str,
+    available: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Features {
+    pidfd: bool,
+    overlayfs: bool,
+    seccomp: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Rootless {
+    /// Unprivileged users may create user namespaces
+    user_namespaces: bool,
+    /// `newuidmap` and `newgidmap` are installed to map subordinate IDs
+    id_map_helpers: bool,
+    viable: bool,
+}
+
+pub fn execute(format: InfoFormat) -> Result<()> {
+    let info = collect();
+
+    if format == InfoFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+
+    let yes_no = |value: bool| if value { "yes" } else { "no" };
+
+    println!("\n{}Vortex System Info", Icon::Info);
+    println!("{:-<60}", "");
+    println!("{:<22}{}", "Version:", info.version);
+    println!(
+        "{:<22}{}",
+        "Kernel:",
+        info.kernel.as_deref().unwrap_or("unknown")
+    );
+    println!("{:<22}{}", "Running as root:", yes_no(info.root));
+
+    println!(
+        "{:<22}{}",
+        "CGroup:",
+        match info.cgroup.version {
+            Some(version) if info.cgroup.writable => format!("{version} (read-write)"),
+            Some(version) => format!("{version} (read-only)"),
+            None => "not mounted".to_string(),
+        }
+    );
+    if let Some(mode) = info.cgroup.mode {
+        println!(
+            "{:<22}{}",
+            "CGroup mode:",
+            if mode == CGroupMode::Hybrid {
+                format!("{mode} (controllers on v1 are unavailable)")
+            } else {
+                mode.to_string()
+            }
+        );
+    }
+    if !info.cgroup.controllers.is_empty() {
+        println!(
+            "{:<22}{}",
+            "Controllers:",
+            info.cgroup.controllers.join(" ")
+        );
+    }
+
+    let (available, missing): (Vec<_>, Vec<_>) =
+        info.namespaces.iter().partition(|ns| ns.available);
+    let names =
+        |list: &[&NamespaceInfo]| list.iter().map(|ns| ns.name).collect::<Vec<_>>().join(" ");
+    println!("{:<22}{}", "Namespaces:", names(&available));
+    if !missing.is_empty() {
+        println!("{:<22}{}", "Missing namespaces:", names(&missing));
+    }
+
+    println!("{:<22}{}", "pidfd:", yes_no(info.features.pidfd));
+    println!("{:<22}{}", "overlayfs:", yes_no(info.features.overlayfs));
+    println!("{:<22}{}", "seccomp:", yes_no(info.features.seccomp));
+
+    match info.missing_capabilities {
+        Some(ref missing) if missing.is_empty() => println!("{:<22}all", "Capabilities:"),
+        Some(ref missing) => println!("{:<22}missing {}", "Capabilities:", missing.join(", ")),
+        None => println!("{:<22}unknown", "Capabilities:"),
+    }
+
+    println!(
+        "{:<22}{} (user namespaces: {}, newuidmap/newgidmap: {})",
+        "Rootless:",
+        if info.rootless.viable {
+            "possible"
+        } else {
+            "not possible"
+        },
+        yes_no(info.rootless.user_namespaces),
+        yes_no(info.rootless.id_map_helpers)
+    );
+    println!("{:-<60}", "");
+
+    Ok(())
+}
+
+fn collect() -> SystemInfo {
+    let controllers = CGroupController::root_controllers(Path::new(CGROUP_ROOT));
+    let version = if controllers.is_ok() {
+        Some("v2")
+    } else if Path::new(CGROUP_ROOT).is_dir() {
+        Some("v1")
+    } else {
+        None
+    };
+
+    let missing = health::missing_namespaces(&ALL_NAMESPACES);
+    let namespaces = ALL_NAMESPACES
+        .iter()
+        .map(|&name| NamespaceInfo {
+            name,
+            available: !missing.contains(&name),
+        })
+        .collect();
+
+    let user_namespaces = unprivileged_user_namespaces();
+    let id_map_helpers = ["newuidmap", "newgidmap"]
+        .iter()
+        .all(|helper| in_path(helper));
+
+    SystemInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        kernel: read_trimmed("/proc/sys/kernel/osrelease"),
+        root: health::is_root(),
+        cgroup: CgroupInfo {
+            version,
+            mode: CGroupMode::detect(),
+            writable: version.is_some()
+                && CGroupController::check_writable(Path::new(CGROUP_ROOT)).is_ok(),
+            controllers: controllers.unwrap_or_default(),
+        },
+        namespaces,
+        features: Features {
+            pidfd: pidfd_supported(),
+            overlayfs: std::fs::read_to_string("/proc/filesystems")
+                .is_ok_and(|filesystems| has_filesystem(&filesystems, "overlay")),
+            seccomp: std::fs::read_to_string("/proc/self/status")
+                .is_ok_and(|status| status.lines().any(|line| line.starts_with("Seccomp:"))),
+        },
+        missing_capabilities: CapabilitySet::effective().ok().map(|caps| {
+            caps.missing(&CAPABILITIES)
+                .iter()
+                .map(ToString::to_string)
+                .collect()
+        }),
+        rootless: Rootless {
+            user_namespaces,
+            id_map_helpers,
+            viable: user_namespaces && id_map_helpers,
+        },
+    }
+}
+
+fn read_trimmed(path: &str) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|content| content.trim().to_string())
+}
+
+/// Whether `/proc/filesystems` lists a filesystem type
+fn has_filesystem(filesystems: &str, name: &str) -> bool {
+    filesystems
+        .lines()
+        .any(|line| line.split_whitespace().last() == Some(name))
+}
+
+/// Whether the kernel has `pidfd_open(2)` (Linux 5.3)
+fn pidfd_supported() -> bool {
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, libc::getpid(), 0) };
+    if fd < 0 {
+        return false;
+    }
+    unsafe {
+        libc::close(fd as libc::c_int);
+    }
+    true
+}
+
+/// Whether unprivileged users may create user namespaces
+///
+/// `max_user_namespaces` is the upstream limit; Debian-based kernels also
+/// have an `unprivileged_userns_clone` switch.
+fn unprivileged_user_namespaces() -> bool {
+    let enabled = |path| read_trimmed(path).is_none_or(|value| value != "0");
+    Path::new("/proc/self/ns/user").exists()
+        && enabled("/proc/sys/user/max_user_namespaces")
+        && enabled("/proc/sys/kernel/unprivileged_userns_clone")
+}
+
+fn in_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_filesystem() {
+        let filesystems = "nodev\tsysfs\nnodev\ttmpfs\n\text4\nnodev\toverlay\n";
+        assert!(has_filesystem(filesystems, "overlay"));
+        assert!(has_filesystem(filesystems, "ext4"));
+        assert!(!has_filesystem(filesystems, "btrfs"));
+    }
+}
@@ -0,0 +1,158 @@
+//! Inspect command implementation
+
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use std::path::Path;
+use vortex_cgroup::{CGroupController, ResourceBackend};
+use vortex_core::{ContainerId, ContainerState, Registry};
+use vortex_namespace::{NamespaceId, NamespaceManager};
+
+use super::cgroup_config;
+use crate::error::CliError;
+use crate::registry;
+
+/// Print everything known about a container as pretty JSON
+///
+/// Combines the registry record (what was requested) with the live cgroup
+/// and `/proc` state (what is actually in effect).
+#[tracing::instrument(name = "inspect", skip_all, fields(container_id = %id))]
+pub async fn execute(id: &str) -> Result<()> {
+    let container_id = ContainerId::new(id).context("Invalid container ID")?;
+
+    let record = registry::open()
+        .get(id)?
+        .ok_or_else(|| CliError::ContainerNotFound { id: id.to_string() })?;
+
+    let controller = CGroupController::attach_with_config(
+        container_id,
+        cgroup_config(id, record.pod.as_deref()),
+    )
+    .ok();
+
+    // No cgroup left means the container has exited
+    let live = match controller {
+        Some(ref controller) => live_state(controller, record.state).await?,
+        None => LiveState::default(),
+    };
+
+    let report = json!({
+        "id": record.id,
+        "pod": record.pod,
+        "state": live.state,
+        "pid": live.pid,
+        "command": record.command,
+        "created": record.created,
+        "exit": record.exit,
+        "cgroup": controller.as_ref().map(|c| c.path().display().to_string()),
+        "backend": controller.as_ref().map(|c| c.kind().to_string()),
+        "limits": {
+            "configured": {
+                "cpu_cores": record.cpu.map(|c| c.cores.as_f64()),
+                "memory_bytes": record.memory.map(|m| m.limit.as_bytes()),
+                "swap_bytes": record.memory.and_then(|m| m.swap).map(|s| s.as_bytes()),
+            },
+            "actual": live.limits,
+        },
+        "namespaces": {
+            "configured": record.namespaces,
+            "ids": live.namespaces,
+        },
+        "stats": live.stats,
+        "descendants": live.descendants,
+    });
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+/// What is actually in effect for a container, read from the system
+struct LiveState {
+    state: &'static str,
+    pid: Option<i32>,
+    limits: Value,
+    stats: Value,
+    namespaces: Value,
+    descendants: Value,
+}
+
+impl Default for LiveState {
+    fn default() -> Self {
+        Self {
+            state: "exited",
+            pid: None,
+            limits: Value::Null,
+            stats: Value::Null,
+            namespaces: Value::Null,
+            descendants: Value::Null,
+        }
+    }
+}
+
+async fn live_state(controller: &CGroupController, recorded: ContainerState) -> Result<LiveState> {
+    let processes = controller
+        .processes()
+        .await
+        .context("Failed to read container processes")?;
+    let pid = processes.first().map(|p| p.as_raw());
+    let state = ContainerState::describe(Some(recorded), pid.is_some());
+
+    // Controller files may be missing when a controller isn't enabled
+    let cpu = controller.current_cpu_limit().await.ok().flatten();
+    let limits = json!({
+        "cpu_cores": cpu.map(|c| c.cores.as_f64()),
+        "memory_bytes": read_limit(controller.path(), "memory.max"),
+        "swap_bytes": read_limit(controller.path(), "memory.swap.max"),
+        "pids_max": read_limit(controller.path(), "pids.max"),
+    });
+
+    let stats = match controller.stats().await {
+        Ok(stats) => serde_json::to_value(stats)?,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to read stats");
+            Value::Null
+        }
+    };
+
+    let namespaces = match pid {
+        Some(pid) => {
+            let pid = u32::try_from(pid).with_context(|| format!("Invalid container PID {pid}"))?;
+            let info = NamespaceManager::namespaces_for_pid_async(pid)
+                .await
+                .context("Failed to read container namespaces")?;
+            let link = |id: Option<NamespaceId>| id.map(|id| id.to_string());
+            json!({
+                "pid": link(info.pid),
+                "net": link(info.net),
+                "mnt": link(info.mnt),
+                "uts": link(info.uts),
+                "ipc": link(info.ipc),
+                "user": link(info.user),
+                "cgroup": link(info.cgroup),
+                "time": link(info.time),
+            })
+        }
+        None => Value::Null,
+    };
+
+    // Dying descendants that pile up point at cleanup not keeping up
+    let descendants = json!({
+        "live": controller.nr_descendants().await.ok(),
+        "dying": controller.nr_dying_descendants().await.ok(),
+    });
+
+    Ok(LiveState {
+        state,
+        pid,
+        limits,
+        stats,
+        namespaces,
+        descendants,
+    })
+}
+
+/// Read a numeric limit file; `max` and missing files map to `null`
+fn read_limit(cgroup: &Path, file: &str) -> Option<u64> {
+    std::fs::read_to_string(cgroup.join(file))
+        .ok()
+        .and_then(|content| content.trim().parse().ok())
+}
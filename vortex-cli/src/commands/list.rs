@@ -1,54 +1,295 @@
 //! List command implementation
 
 use anyhow::{Context, Result};
-use vortex_cgroup::{CGroupController, ResourceBackend};
-use vortex_core::ContainerId;
+use serde::Serialize;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use vortex_cgroup::{CGroupConfig, CGroupController, PodController, ResourceBackend};
+use vortex_core::{ContainerId, ContainerState, MemorySize, Registry, ResourceStats};
 
-pub async fn execute() -> Result<()> {
+use crate::cli::ListFormat;
+use crate::output::Icon;
+use crate::registry;
+
+/// One row of `list` output
+#[derive(Debug, Serialize)]
+struct ContainerSummary {
+    id: String,
+    pod: Option<String>,
+    state: &'static str,
+    pid: Option<i32>,
+    command: Option<Vec<String>>,
+    created: Option<u64>,
+    uptime_secs: Option<u64>,
+    cpu_seconds: f64,
+    memory_bytes: u64,
+    io_read_bytes: u64,
+    io_write_bytes: u64,
+    /// Raw stats, for the pod totals
+    #[serde(skip)]
+    stats: ResourceStats,
+}
+
+pub async fn execute(format: ListFormat) -> Result<()> {
     tracing::info!("Listing containers");
 
-    println!("\n📋 Containers");
-    println!("{:-<60}", "");
+    let containers = collect().await?;
 
-    let vortex_path = std::path::Path::new("/sys/fs/cgroup/vortex");
+    if format == ListFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&containers)?);
+        return Ok(());
+    }
+
+    println!("\n{}Containers", Icon::List);
+    println!("{:-<60}", "");
 
-    if !vortex_path.exists() {
+    if containers.is_empty() {
         println!("No containers running");
         return Ok(());
     }
 
-    let mut entries = tokio::fs::read_dir(vortex_path)
+    let wide = format == ListFormat::Wide;
+    let mut headers = vec!["ID", "POD", "CPU", "MEMORY"];
+    if wide {
+        headers.extend(["STATE", "PID", "UPTIME", "READ", "WRITE", "COMMAND"]);
+    }
+
+    let mut rows: Vec<Vec<String>> = containers.iter().map(|c| row(c, wide)).collect();
+    rows.extend(pod_totals(&containers).iter().map(|c| row(c, wide)));
+    print_table(&headers, &rows);
+
+    println!("{:-<60}", "");
+    println!("Total: {} container(s)", containers.len());
+
+    Ok(())
+}
+
+/// Every container whose stats can be read, standalone ones first
+///
+/// Containers under a `--cgroup-parent` are outside the vortex namespace,
+/// so they are found through their records and come last.
+async fn collect() -> Result<Vec<ContainerSummary>> {
+    let mut containers = Vec::new();
+    let vortex_path = CGroupConfig::new().vortex_root();
+    if vortex_path.exists() {
+        containers.extend(scan(&vortex_path).await?);
+    }
+
+    // Like the records in `summarize`, these are extras the list can do without
+    let records = registry::open().list().unwrap_or_default();
+    for record in records.iter().filter(|r| r.cgroup_parent.is_some()) {
+        containers.extend(summarize(&record.id, None).await);
+    }
+
+    Ok(containers)
+}
+
+/// The containers in a vortex namespace directory
+async fn scan(vortex_path: &Path) -> Result<Vec<ContainerSummary>> {
+    let mut containers = Vec::new();
+    let mut pods = Vec::new();
+    for name in subdirectories(vortex_path).await? {
+        // Pods are marked as such; container cgroups have no children, so
+        // one with subdirectories is a pod too, marked or not
+        let path = vortex_path.join(&name);
+        let children = subdirectories(&path).await?;
+        if children.is_empty() && !PodController::is_pod(&path) {
+            containers.extend(summarize(&name, None).await);
+        } else {
+            pods.push((name, children));
+        }
+    }
+
+    for (pod, children) in &pods {
+        for id in children {
+            containers.extend(summarize(id, Some(pod)).await);
+        }
+    }
+
+    Ok(containers)
+}
+
+/// Combine the registry record with live cgroup state, `None` if the
+/// container's stats can't be read
+async fn summarize(id: &str, pod: Option<&str>) -> Option<ContainerSummary> {
+    let container_id = ContainerId::new(id).ok()?;
+    let controller =
+        CGroupController::attach_with_config(container_id, super::cgroup_config(id, pod)).ok()?;
+    let stats = controller.stats().await.ok()?;
+
+    let pid = controller
+        .processes()
         .await
-        .context("Failed to read vortex cgroup directory")?;
+        .ok()
+        .and_then(|procs| procs.first().map(|p| p.as_raw()));
+
+    let record = registry::open().get(id).ok().flatten();
+    let created = record.as_ref().map(|r| r.created);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
 
-    let mut count = 0;
+    Some(ContainerSummary {
+        id: id.to_string(),
+        pod: pod.map(str::to_string),
+        state: ContainerState::describe(record.as_ref().map(|r| r.state), pid.is_some()),
+        pid,
+        command: record.map(|r| r.command),
+        created,
+        uptime_secs: created.map(|created| now.saturating_sub(created)),
+        cpu_seconds: stats.cpu_usage.as_secs_f64(),
+        memory_bytes: stats.memory_current.as_bytes(),
+        io_read_bytes: stats.io_read_bytes,
+        io_write_bytes: stats.io_write_bytes,
+        stats,
+    })
+}
+
+/// One `total` row per pod, summing the stats of its containers
+///
+/// A pod with a single container gets none; that container's row already
+/// is the total.
+fn pod_totals(containers: &[ContainerSummary]) -> Vec<ContainerSummary> {
+    let mut pods: Vec<&str> = containers.iter().filter_map(|c| c.pod.as_deref()).collect();
+    pods.dedup();
+
+    pods.into_iter()
+        .filter_map(|pod| {
+            let members: Vec<&ContainerSummary> = containers
+                .iter()
+                .filter(|c| c.pod.as_deref() == Some(pod))
+                .collect();
+            if members.len() < 2 {
+                return None;
+            }
+            let stats = ResourceStats::aggregate(members.iter().map(|c| &c.stats));
+            Some(ContainerSummary {
+                id: "total".to_string(),
+                pod: Some(pod.to_string()),
+                state: "-",
+                pid: None,
+                command: Some(vec![format!("{} container(s)", members.len())]),
+                created: None,
+                uptime_secs: None,
+                cpu_seconds: stats.cpu_usage.as_secs_f64(),
+                memory_bytes: stats.memory_current.as_bytes(),
+                io_read_bytes: stats.io_read_bytes,
+                io_write_bytes: stats.io_write_bytes,
+                stats,
+            })
+        })
+        .collect()
+}
+
+fn row(c: &ContainerSummary, wide: bool) -> Vec<String> {
+    let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+
+    let mut row = vec![
+        c.id.clone(),
+        or_dash(c.pod.clone()),
+        format!("{:.2}s", c.cpu_seconds),
+        MemorySize::from_bytes(c.memory_bytes).to_string(),
+    ];
+    if wide {
+        row.extend([
+            c.state.to_string(),
+            or_dash(c.pid.map(|pid| pid.to_string())),
+            or_dash(c.uptime_secs.map(format_uptime)),
+            MemorySize::from_bytes(c.io_read_bytes).to_string(),
+            MemorySize::from_bytes(c.io_write_bytes).to_string(),
+            or_dash(c.command.as_ref().map(|args| args.join(" "))),
+        ]);
+    }
+    row
+}
+
+/// Print rows under headers with each column as wide as its widest cell
+pub(super) fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let line = |cells: Vec<&str>| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        println!("{}", padded.join("  ").trim_end());
+    };
+
+    line(headers.to_vec());
+    for row in rows {
+        line(row.iter().map(String::as_str).collect());
+    }
+}
+
+/// Format seconds as e.g. `45s`, `3m12s` or `2h05m`
+fn format_uptime(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// Sorted names of the directories directly under `path`
+async fn subdirectories(path: &Path) -> Result<Vec<String>> {
+    let mut entries = tokio::fs::read_dir(path)
+        .await
+        .with_context(|| format!("Failed to read cgroup directory {}", path.display()))?;
+
+    let mut names = Vec::new();
     while let Some(entry) = entries.next_entry().await? {
         if entry.file_type().await?.is_dir() {
-            let name = entry.file_name();
-            let id = name.to_string_lossy();
-
-            if let Ok(container_id) = ContainerId::new(id.as_ref()) {
-                if let Ok(controller) = CGroupController::new(container_id).await {
-                    if let Ok(stats) = controller.stats().await {
-                        println!(
-                            "  {} - CPU: {:.2}s, Memory: {}",
-                            id,
-                            stats.cpu_usage.as_secs_f64(),
-                            stats.memory_current
-                        );
-                        count += 1;
-                    }
-                }
-            }
+            names.push(entry.file_name().to_string_lossy().into_owned());
         }
     }
 
-    if count == 0 {
-        println!("No containers running");
-    } else {
-        println!("{:-<60}", "");
-        println!("Total: {} container(s)", count);
+    names.sort();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(id: &str, pod: Option<&str>, memory: u64) -> ContainerSummary {
+        let stats = ResourceStats {
+            memory_current: MemorySize::from_bytes(memory),
+            ..ResourceStats::default()
+        };
+        ContainerSummary {
+            id: id.to_string(),
+            pod: pod.map(str::to_string),
+            state: "running",
+            pid: None,
+            command: None,
+            created: None,
+            uptime_secs: None,
+            cpu_seconds: 0.0,
+            memory_bytes: memory,
+            io_read_bytes: 0,
+            io_write_bytes: 0,
+            stats,
+        }
     }
 
-    Ok(())
+    #[test]
+    fn test_pod_totals_only_for_several_containers() {
+        let containers = [
+            summary("solo", None, 1),
+            summary("web-1", Some("web"), 100),
+            summary("web-2", Some("web"), 200),
+            summary("db-1", Some("db"), 400),
+        ];
+
+        let totals = pod_totals(&containers);
+        assert_eq!(totals.len(), 1);
+        assert_eq!(totals[0].pod.as_deref(), Some("web"));
+        assert_eq!(totals[0].memory_bytes, 300);
+    }
 }
@@ -0,0 +1,33 @@
+//! Logs command implementation
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::path::Path;
+use vortex_core::ContainerId;
+
+use crate::registry::{self, LogStream};
+
+/// Print a detached container's stdout and stderr logs to our own
+pub fn execute(id: &str) -> Result<()> {
+    ContainerId::new(id).context("Invalid container ID")?;
+
+    let stdout_log = registry::log_path(id, LogStream::Stdout);
+    let stderr_log = registry::log_path(id, LogStream::Stderr);
+    if !stdout_log.exists() && !stderr_log.exists() {
+        anyhow::bail!("No logs for container '{id}' (only detached containers are logged)");
+    }
+
+    copy_log(&stdout_log, &mut std::io::stdout())?;
+    copy_log(&stderr_log, &mut std::io::stderr())?;
+    Ok(())
+}
+
+fn copy_log(path: &Path, out: &mut impl std::io::Write) -> Result<()> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to open {}", path.display())),
+    };
+    std::io::copy(&mut file, out).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(())
+}
@@ -1,31 +1,90 @@
 use crate::cli::Commands;
+use crate::registry;
 use anyhow::Result;
+use vortex_cgroup::{CGroupConfig, PodController};
+use vortex_core::Registry;
+use vortex_namespace::TerminationConfig;
 
 pub mod health;
+pub mod info;
+pub mod inspect;
 pub mod list;
+pub mod logs;
 pub mod namespaces;
+pub mod ps;
+pub mod restart;
+pub mod rm;
 pub mod run;
 pub mod stats;
 pub mod stop;
+pub mod update;
+pub mod wait;
 
 /// Dispatch command to appropriate handler
 pub async fn dispatch(command: Commands) -> Result<()> {
     match command {
-        Commands::Run {
+        Commands::Run(args) => run::execute(*args).await,
+
+        Commands::Stats {
             id,
-            cpu,
-            memory,
-            monitor,
-            no_namespaces,
-            hostname,
-            command,
-        } => run::execute(&id, cpu, memory, monitor, no_namespaces, hostname, &command).await,
+            pid,
+            pod,
+            follow,
+            interval,
+            reset_peak,
+            verbose,
+        } => match (pid, id.unwrap_or_default()) {
+            (Some(pid), _) => stats::execute_pid(pid, verbose).await,
+            // clap requires --id when --pid is missing
+            (None, id) if follow => stats::follow(&id, pod.as_deref(), interval, reset_peak).await,
+            (None, id) => stats::execute(&id, pod.as_deref(), verbose).await,
+        },
+
+        Commands::Inspect { id } => inspect::execute(&id).await,
+
+        Commands::Logs { id } => logs::execute(&id),
+
+        Commands::Ps { id, pod } => ps::execute(&id, pod.as_deref()).await,
 
-        Commands::Stats { id } => stats::execute(&id).await,
+        Commands::List { format } => list::execute(format).await,
 
-        Commands::List => list::execute().await,
+        Commands::Stop {
+            id,
+            pod,
+            signal,
+            grace,
+        } => stop::execute(&id, pod.as_deref(), TerminationConfig::new(signal, grace)).await,
+
+        Commands::Restart { id, timeout } => restart::execute(&id, timeout).await,
+
+        Commands::Wait { id, timeout } => wait::execute(&id, timeout).await,
+
+        Commands::Update {
+            id,
+            pod,
+            cpu,
+            memory,
+            pids_max,
+            force,
+            revert,
+        } => {
+            if revert {
+                update::revert(&id, pod.as_deref()).await
+            } else {
+                update::execute(&id, pod.as_deref(), cpu, memory, pids_max, force).await
+            }
+        }
 
-        Commands::Stop { id } => stop::execute(&id).await,
+        Commands::Rm {
+            id,
+            pod,
+            force,
+            all: _,
+        } => match id {
+            Some(id) => rm::execute(&id, pod.as_deref(), force).await,
+            // clap requires --all when --id is missing
+            None => rm::execute_all(force).await,
+        },
 
         Commands::Namespaces { pid } => {
             // Convert i32 to u32 for pid
@@ -34,5 +93,43 @@ pub async fn dispatch(command: Commands) -> Result<()> {
         }
 
         Commands::Health => health::execute().await,
+
+        Commands::Info { format } => info::execute(format),
+    }
+}
+
+/// `CGroup` configuration for a container, optionally inside a pod
+///
+/// A container started with `--cgroup-parent` is looked for under the
+/// parent in its record.
+fn cgroup_config(id: &str, pod: Option<&str>) -> CGroupConfig {
+    let config = match pod {
+        Some(pod) => CGroupConfig::new().with_parent(pod),
+        None => CGroupConfig::new(),
+    };
+    let parent = registry::open()
+        .get(id)
+        .ok()
+        .flatten()
+        .and_then(|record| record.cgroup_parent);
+    match parent {
+        Some(parent) => config.with_namespace(parent),
+        None => config,
+    }
+}
+
+/// Remove a pod once its last container is gone (best effort)
+async fn remove_pod_if_empty(name: &str) {
+    let Ok(pod) = PodController::new(name).await else {
+        return;
+    };
+
+    match pod.containers().await {
+        Ok(containers) if containers.is_empty() => {
+            if let Err(e) = pod.cleanup().await {
+                tracing::warn!(pod = name, error = %e, "Failed to remove empty pod");
+            }
+        }
+        _ => {}
     }
 }
@@ -1,31 +1,63 @@
 //! Namespaces command implementation
 
 use anyhow::{Context, Result};
+use vortex_namespace::{NamespaceKind, NamespaceManager};
+
+use crate::output::Icon;
 
 pub async fn execute(pid: Option<u32>) -> Result<()> {
     let target_pid = pid.unwrap_or_else(|| std::process::id());
 
-    println!("\n🔒 Namespace Information for PID {}", target_pid);
+    println!(
+        "\n{}Namespace Information for PID {}",
+        Icon::Lock,
+        target_pid
+    );
     println!("{:-<60}", "");
 
-    let ns_info = vortex_namespace::NamespaceManager::namespaces_for_pid(target_pid)
+    let ns_info = NamespaceManager::namespaces_for_pid_async(target_pid)
+        .await
         .context("Failed to get namespace information")?;
 
     print!("{}", ns_info);
 
     // Check if isolated
-    match ns_info.is_isolated() {
-        Ok(true) => println!("\n✅ Process is in isolated namespaces"),
-        Ok(false) => println!("\n⚠️  Process is in host namespaces"),
-        Err(e) => println!("\n❌ Failed to check isolation: {}", e),
+    match ns_info.is_isolated_async().await {
+        Ok(true) => println!("\n{}Process is in isolated namespaces", Icon::Ok),
+        Ok(false) => println!("\n{}Process is in host namespaces", Icon::Warning),
+        Err(e) => println!("\n{}Failed to check isolation: {}", Icon::Error, e),
+    }
+
+    if let Ok(host) = NamespaceManager::namespaces_for_pid_async(1).await {
+        let isolated = ns_info.diff(&host);
+        let shared: Vec<_> = NamespaceKind::ALL
+            .into_iter()
+            .filter(|&kind| ns_info.shares_namespace(&host, kind))
+            .collect();
+        println!(
+            "Isolated: {}; shared: {}",
+            join_kinds(&isolated),
+            join_kinds(&shared)
+        );
     }
 
     // Read hostname from /proc if UTS namespace is set
     if ns_info.uts.is_some() {
-        if let Ok(hostname) = std::fs::read_to_string("/proc/sys/kernel/hostname") {
+        if let Ok(hostname) = tokio::fs::read_to_string("/proc/sys/kernel/hostname").await {
             println!("Hostname: {}", hostname.trim());
         }
     }
 
     Ok(())
 }
+
+fn join_kinds(kinds: &[NamespaceKind]) -> String {
+    if kinds.is_empty() {
+        return "none".to_string();
+    }
+    kinds
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
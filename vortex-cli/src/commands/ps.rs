@@ -0,0 +1,271 @@
+//! Ps command implementation
+//!
+//! Lists the processes in a container's cgroup as a tree, built from the
+//! parent PIDs in `/proc/<pid>/stat`. PIDs are the host's; the PID inside
+//! the container comes from the `NSpid` line of `/proc/<pid>/status`.
+
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+use vortex_core::{MemorySize, ProcessId};
+
+use super::list::print_table;
+use super::stats::attach;
+use crate::output::{self, Icon};
+
+/// What one process looks like in `/proc`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProcessInfo {
+    pid: i32,
+    ppid: i32,
+    /// PID in the innermost PID namespace, if the kernel reports it
+    ns_pid: Option<i32>,
+    command: String,
+    state: char,
+    cpu: Duration,
+    memory: MemorySize,
+}
+
+#[tracing::instrument(name = "ps", skip_all, fields(container_id = %id, pod = pod))]
+pub async fn execute(id: &str, pod: Option<&str>) -> Result<()> {
+    tracing::info!(container_id = id, "Listing processes");
+
+    let controller = attach(id, pod)?;
+    let pids = controller
+        .processes()
+        .await
+        .context("Failed to read container processes")?;
+
+    let processes = tokio::task::spawn_blocking(move || {
+        pids.into_iter()
+            .filter_map(read_process)
+            .collect::<Vec<_>>()
+    })
+    .await?;
+
+    println!("\n{}Processes in '{}'", Icon::List, id);
+    println!("{:-<60}", "");
+
+    if processes.is_empty() {
+        println!("No processes running");
+        return Ok(());
+    }
+
+    let (branch, last, pipe) = if output::plain() {
+        ("|- ", "`- ", "|  ")
+    } else {
+        ("├─ ", "└─ ", "│  ")
+    };
+    let rows: Vec<Vec<String>> = tree(&processes)
+        .into_iter()
+        .map(|(prefix, process)| {
+            let indent: String = prefix
+                .iter()
+                .enumerate()
+                .map(|(depth, &more)| match (depth + 1 == prefix.len(), more) {
+                    (true, true) => branch,
+                    (true, false) => last,
+                    (false, true) => pipe,
+                    (false, false) => "   ",
+                })
+                .collect();
+            vec![
+                process.pid.to_string(),
+                process
+                    .ns_pid
+                    .map_or_else(|| "-".to_string(), |pid| pid.to_string()),
+                process.state.to_string(),
+                format!("{:.2}s", process.cpu.as_secs_f64()),
+                process.memory.to_string(),
+                format!("{indent}{}", process.command),
+            ]
+        })
+        .collect();
+    print_table(
+        &["PID", "NSPID", "STATE", "CPU", "MEMORY", "COMMAND"],
+        &rows,
+    );
+
+    println!("{:-<60}", "");
+    println!("Total: {} process(es)", processes.len());
+
+    Ok(())
+}
+
+/// Read a process from `/proc`, `None` if it exited during the scan
+fn read_process(pid: ProcessId) -> Option<ProcessInfo> {
+    let pid = pid.as_raw();
+    let read = |file: &str| match std::fs::read_to_string(format!("/proc/{pid}/{file}")) {
+        Ok(content) => Some(content),
+        Err(e) => {
+            tracing::debug!(pid, file, error = %e, "Process gone during scan");
+            None
+        }
+    };
+
+    let stat = parse_stat(&read("stat")?)?;
+    let status = read("status")?;
+    // comm holds the same name as stat, without the parsing pitfalls
+    let command = read("comm").map_or(stat.command, |comm| comm.trim_end().to_string());
+
+    Some(ProcessInfo {
+        pid,
+        ppid: stat.ppid,
+        ns_pid: status_field(&status, "NSpid")
+            .and_then(|pids| pids.split_whitespace().last()?.parse().ok()),
+        command,
+        state: stat.state,
+        cpu: stat.cpu,
+        memory: status_field(&status, "VmRSS")
+            .and_then(|rss| rss.trim_end_matches("kB").trim().parse().ok())
+            .map_or_else(|| MemorySize::from_bytes(0), MemorySize::from_kb),
+    })
+}
+
+/// The fields of `/proc/<pid>/stat` that `ps` shows
+#[derive(Debug, PartialEq, Eq)]
+struct Stat {
+    command: String,
+    state: char,
+    ppid: i32,
+    /// User plus system time
+    cpu: Duration,
+}
+
+/// Clock ticks per second in `/proc/<pid>/stat`, which Linux fixes at 100
+/// (`USER_HZ`) whatever the kernel's internal tick rate
+const CLOCK_TICKS: u64 = 100;
+
+/// Parse `/proc/<pid>/stat`
+///
+/// The command name is in parentheses and may itself contain spaces and
+/// parentheses, so the fields are counted from the last `)`.
+fn parse_stat(content: &str) -> Option<Stat> {
+    let (head, rest) = content.rsplit_once(')')?;
+    let (_, command) = head.split_once('(')?;
+    // After the command: state, ppid, ..., utime and stime at 12 and 13
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let ticks = |index: usize| fields.get(index)?.parse::<u64>().ok();
+    let cpu_ticks = ticks(11)? + ticks(12)?;
+
+    Some(Stat {
+        command: command.to_string(),
+        state: fields.first()?.chars().next()?,
+        ppid: fields.get(1)?.parse().ok()?,
+        cpu: Duration::from_millis(cpu_ticks * 1000 / CLOCK_TICKS),
+    })
+}
+
+/// The value of a `Name:\tvalue` line in `/proc/<pid>/status`
+fn status_field<'a>(status: &'a str, name: &str) -> Option<&'a str> {
+    status.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        (key == name).then(|| value.trim())
+    })
+}
+
+/// Order processes depth first, parents before their children
+///
+/// Each process comes with one flag per level of its indentation, telling
+/// whether more siblings follow at that level, which is all that is needed
+/// to draw the tree. Processes whose parent is outside the container are
+/// roots.
+fn tree(processes: &[ProcessInfo]) -> Vec<(Vec<bool>, &ProcessInfo)> {
+    let pids: HashSet<i32> = processes.iter().map(|p| p.pid).collect();
+    let mut children: BTreeMap<Option<i32>, Vec<&ProcessInfo>> = BTreeMap::new();
+    for process in processes {
+        let parent = pids.contains(&process.ppid).then_some(process.ppid);
+        children.entry(parent).or_default().push(process);
+    }
+    for siblings in children.values_mut() {
+        siblings.sort_by_key(|p| p.pid);
+    }
+
+    let mut ordered = Vec::with_capacity(processes.len());
+    // Roots get no tree lines; everything below them does
+    let mut stack: Vec<(Vec<bool>, &ProcessInfo)> = children
+        .get(&None)
+        .map(|roots| roots.iter().rev().map(|&p| (Vec::new(), p)).collect())
+        .unwrap_or_default();
+    while let Some((prefix, process)) = stack.pop() {
+        if let Some(kids) = children.get(&Some(process.pid)) {
+            for (i, &child) in kids.iter().enumerate().rev() {
+                let mut child_prefix = prefix.clone();
+                child_prefix.push(i + 1 < kids.len());
+                stack.push((child_prefix, child));
+            }
+        }
+        ordered.push((prefix, process));
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: i32, ppid: i32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            ppid,
+            ns_pid: None,
+            command: format!("p{pid}"),
+            state: 'S',
+            cpu: Duration::ZERO,
+            memory: MemorySize::from_bytes(0),
+        }
+    }
+
+    #[test]
+    fn test_parse_stat() {
+        let stat = "4242 (my (odd) cmd) S 4200 4242 4242 0 -1 4194560 100 0 0 0 \
+                    150 50 0 0 20 0 1 0 123 1000 200 18446744073709551615";
+        assert_eq!(
+            parse_stat(stat),
+            Some(Stat {
+                command: "my (odd) cmd".to_string(),
+                state: 'S',
+                ppid: 4200,
+                cpu: Duration::from_secs(2),
+            })
+        );
+
+        assert_eq!(parse_stat(""), None);
+        assert_eq!(parse_stat("4242 (sh) S 1"), None);
+    }
+
+    #[test]
+    fn test_status_field() {
+        let status = "Name:\tsh\nState:\tS (sleeping)\nNSpid:\t4242\t1\nVmRSS:\t    1536 kB\n";
+        assert_eq!(status_field(status, "NSpid"), Some("4242\t1"));
+        assert_eq!(status_field(status, "VmRSS"), Some("1536 kB"));
+        assert_eq!(status_field(status, "Pid"), None);
+    }
+
+    #[test]
+    fn test_tree_orders_children_under_parents() {
+        // 10 is outside the container, so 20 and 50 are roots
+        let processes = [
+            process(31, 30),
+            process(20, 10),
+            process(30, 20),
+            process(21, 20),
+            process(50, 10),
+        ];
+
+        let tree: Vec<_> = tree(&processes)
+            .into_iter()
+            .map(|(prefix, p)| (p.pid, prefix))
+            .collect();
+        assert_eq!(
+            tree,
+            vec![
+                (20, vec![]),
+                (21, vec![true]),
+                (30, vec![false]),
+                (31, vec![false, false]),
+                (50, vec![]),
+            ]
+        );
+    }
+}
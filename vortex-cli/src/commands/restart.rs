@@ -0,0 +1,81 @@
+//! Restart command implementation
+//!
+//! `run` saves the merged spec of every container next to its record, so a
+//! container can be started again without retyping its command line. The
+//! running instance is stopped like `stop` does, with the recorded stop
+//! signal, then a fresh one starts under the same ID in the background,
+//! like `run --detach`. A container that has exited is only started. An
+//! overlay root filesystem keeps its changes.
+
+use anyhow::{Context, Result};
+use std::time::Duration;
+use vortex_cgroup::CGroupController;
+use vortex_core::{ContainerId, ContainerState, Error, Registry};
+use vortex_namespace::{Termination, TerminationConfig};
+
+use super::{cgroup_config, run, stop, wait};
+use crate::error::CliError;
+use crate::output::Icon;
+use crate::registry;
+
+/// How long the supervisor of a stopped detached container gets to record
+/// its exit, which must not land on the new instance's record
+const EXIT_RECORD_WAIT: Duration = Duration::from_secs(2);
+
+#[tracing::instrument(name = "restart", skip_all, fields(container_id = %id))]
+pub async fn execute(id: &str, grace: Duration) -> Result<()> {
+    tracing::info!(container_id = id, "Restarting container");
+
+    let container_id = ContainerId::new(id).context("Invalid container ID")?;
+    let registry = registry::open();
+    let record = registry
+        .get(id)
+        .context("Failed to read container record")?
+        .ok_or_else(|| CliError::ContainerNotFound { id: id.to_string() })?;
+    let saved = registry::load_run(id)?.with_context(|| {
+        format!("Container '{id}' has no saved spec to restart from; run it again instead")
+    })?;
+
+    // Its `vortex run` would clean up after the new instance
+    if record.state == ContainerState::Running && record.pid.is_none() {
+        return Err(CliError::RunsInForeground { id: id.to_string() }.into());
+    }
+
+    let pod = record.pod.as_deref();
+    match CGroupController::adopt_with_config(container_id.clone(), cgroup_config(id, pod)) {
+        Ok(controller) => {
+            let termination = TerminationConfig::new(saved.spec.termination.signal, grace);
+            let stopped = stop::ask_to_exit(&controller, termination).await?;
+            controller
+                .cleanup_with(true)
+                .await
+                .context("Failed to cleanup container")?;
+
+            match stopped {
+                Some(stopped) => {
+                    if record.exit.is_none()
+                        && let Err(e) =
+                            wait::wait_for_exit(&registry, id, Some(EXIT_RECORD_WAIT)).await
+                    {
+                        tracing::warn!(error = %e, "Previous instance did not record its exit");
+                    }
+                    if stopped == Termination::Killed {
+                        println!(
+                            "{}Container '{id}' killed after {grace:?} grace period",
+                            Icon::Ok
+                        );
+                    } else {
+                        println!("{}Container '{id}' stopped", Icon::Ok);
+                    }
+                }
+                None => tracing::debug!("Container was not running"),
+            }
+        }
+        Err(Error::ContainerNotFound { .. }) => {
+            tracing::debug!("Container has no cgroup left, starting it");
+        }
+        Err(e) => return Err(e).context("Failed to access container"),
+    }
+
+    run::restart(&container_id, &saved, pod).await
+}
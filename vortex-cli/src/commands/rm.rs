@@ -0,0 +1,106 @@
+//! Rm command implementation
+
+use anyhow::{Context, Result};
+use vortex_cgroup::CGroupController;
+use vortex_core::{ContainerId, Registry};
+
+use super::cgroup_config;
+use crate::error::CliError;
+use crate::output::Icon;
+use crate::registry;
+
+/// Remove the cgroup, record and logs of a stopped container
+///
+/// A container that still has processes is refused unless `force` is set,
+/// in which case they are killed first.
+#[tracing::instrument(name = "rm", skip_all, fields(container_id = %id, pod = pod))]
+pub async fn execute(id: &str, pod: Option<&str>, force: bool) -> Result<()> {
+    tracing::info!(container_id = id, force, "Removing container");
+
+    remove(id, pod, force).await?;
+    println!("{}Container '{}' removed", Icon::Removed, id);
+    Ok(())
+}
+
+/// Remove every recorded container that is stopped, or every one with `force`
+///
+/// Records that cannot be read are reported and removed along with the
+/// cgroup they name, if it is stopped.
+#[tracing::instrument(name = "rm", skip_all)]
+pub async fn execute_all(force: bool) -> Result<()> {
+    let mut removed = 0;
+    let mut skipped = 0;
+
+    let store = registry::open();
+    let ids = store.ids().context("Failed to read container records")?;
+    for id in ids {
+        if let Err(e) = store.get(&id) {
+            println!("{}Record of '{}' is unreadable: {}", Icon::Warning, id, e);
+        }
+        match remove(&id, None, force).await {
+            Ok(()) => {
+                println!("{}Container '{}' removed", Icon::Removed, id);
+                removed += 1;
+            }
+            Err(e) => {
+                println!("{}Skipped '{}': {:#}", Icon::Skipped, id, e);
+                skipped += 1;
+            }
+        }
+    }
+
+    println!("Removed {} container(s), skipped {}", removed, skipped);
+    Ok(())
+}
+
+async fn remove(id: &str, pod: Option<&str>, force: bool) -> Result<()> {
+    let container_id = ContainerId::new(id).context("Invalid container ID")?;
+
+    // An unreadable record only leaves the cgroup without its pod and
+    // parent, which is no reason to keep it around
+    let (record, unreadable) = match registry::open().get(id) {
+        Ok(record) => (record, false),
+        Err(e) => {
+            tracing::warn!(container_id = id, error = %e, "Removing unreadable container record");
+            (None, true)
+        }
+    };
+    let pod = pod
+        .map(str::to_string)
+        .or_else(|| record.as_ref().and_then(|r| r.pod.clone()));
+    let config = cgroup_config(id, pod.as_deref());
+
+    match CGroupController::attach_with_config(container_id.clone(), config.clone()) {
+        Ok(attached) => {
+            // Look before taking ownership, so a refusal leaves the cgroup alone
+            let running = !attached
+                .processes()
+                .await
+                .context("Failed to read container processes")?
+                .is_empty();
+            if running && !force {
+                return Err(CliError::StillRunning { id: id.to_string() }.into());
+            }
+
+            let controller = CGroupController::adopt_with_config(container_id, config)
+                .context("Failed to access container")?;
+            controller
+                .cleanup_with(force)
+                .await
+                .context("Failed to remove container cgroup")?;
+        }
+        // The cgroup is already gone, only the record is left
+        Err(_) if record.is_some() || unreadable => {}
+        Err(_) => return Err(CliError::ContainerNotFound { id: id.to_string() }.into()),
+    }
+
+    registry::remove(id);
+    registry::remove_logs(id);
+    registry::remove_overlay(id);
+
+    if let Some(ref pod) = pod {
+        super::remove_pod_if_empty(pod).await;
+    }
+
+    Ok(())
+}
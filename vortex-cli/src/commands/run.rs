@@ -1,64 +1,263 @@
 use anyhow::{Context, Result};
+use std::path::Path;
 use std::sync::Arc;
-use vortex_cgroup::{CGroupController, ResourceBackend, ResourceMonitor};
-use vortex_core::{ContainerId, CpuCores, CpuLimit, MemoryLimit, MemorySize};
-use vortex_namespace::{NamespaceConfig, NamespaceExecutor};
+use std::time::Duration;
+use vortex_cgroup::{
+    CGroupConfig, CGroupController, IdleConfig, PodController, ResourceBackend, ResourceMonitor,
+    controller,
+};
+use vortex_core::{
+    ContainerEvent, ContainerId, ContainerState, CpuCores, CpuLimit, MemoryLimit, MemorySize,
+    Registry, ResourceLimits,
+};
+use vortex_namespace::{
+    Hook, HookState, NamespaceConfig, NamespaceExecutor, NamespaceKind, OomScoreAdj, RootfsSpec,
+    Scheduling, Stdio, Termination, TerminationConfig,
+};
+use vortex_security::{Capability, SecurityConfig, SecurityManager};
+
+use crate::cli::{RunArgs, RunFormat};
+use crate::error::CliError;
+use crate::oci::{self, RunSpec};
+use crate::output::{self, Icon};
+use crate::profile::{self, RunProfile};
+use crate::registry::{self, LogStream, SavedRun};
+
+/// Default CPU limit in cores
+const DEFAULT_CPU_CORES: f64 = 1.0;
+
+/// Default memory limit in MB
+const DEFAULT_MEMORY_MB: u64 = 512;
+
+/// Default monitoring poll interval
+const DEFAULT_MONITOR_INTERVAL: Duration = Duration::from_secs(2);
 
 /// Execute the run command
-pub async fn execute(
-    id: &str,
-    cpu: f64,
-    memory: u64,
-    enable_monitor: bool,
-    no_namespaces: bool,
-    hostname: Option<String>,
-    command: &[String],
-) -> Result<()> {
+///
+/// The whole lifecycle runs inside a `run` span so every nested event is
+/// tagged with the container ID.
+#[tracing::instrument(name = "run", skip_all, fields(container_id = tracing::field::Empty))]
+pub async fn execute(args: RunArgs) -> Result<()> {
+    // Load and validate config first so errors surface before any setup
+    let bundle = match args.bundle {
+        Some(ref dir) => Some(oci::load(dir).context("Failed to load OCI bundle")?),
+        None => None,
+    };
+    let profile = match args.profile {
+        Some(ref name) => Some(profile::load(name).context("Failed to load profile")?),
+        None => None,
+    };
+
+    // Create container ID
+    let container_id = create_container_id(args.id.as_deref())?;
+    tracing::Span::current().record("container_id", container_id.as_str());
+
+    // Merge CLI flags over the bundle spec
+    let monitor_interval = args
+        .monitor
+        .then(|| args.monitor_interval.unwrap_or(DEFAULT_MONITOR_INTERVAL));
+    let idle = args.idle_timeout.map(|window| {
+        IdleConfig::new(window)
+            .with_threshold(args.idle_threshold.unwrap_or(IdleConfig::DEFAULT_THRESHOLD))
+    });
+    let pod = args.pod.clone();
+    let replace = args.replace;
+    let detach = args.detach;
+    let keep = args.keep;
+    let overlay = args.overlay;
+    let oom_group = args.oom_group;
+    let force = args.force;
+    let json = args.format == RunFormat::Json;
+    // Live output by default; the result needs it captured
+    let stdio = if args.capture || json {
+        Stdio::Capture
+    } else {
+        Stdio::Inherit
+    };
+    let hook_state = hook_state(&container_id, args.bundle.as_deref());
+    let bundle_dir = args.bundle.clone();
+    let saved = SavedRun::new(
+        build_run_spec(args, bundle, profile)?,
+        bundle_dir.as_deref(),
+        overlay,
+        oom_group,
+    );
+    let spec = &saved.spec;
+
     // Validate environment
     validate_environment()?;
+    vortex_security::require_capabilities(&required_capabilities(spec))?;
 
-    // Create container ID
-    let container_id = create_container_id(id)?;
+    let rootfs = prepare_rootfs(&container_id, spec, overlay)?;
 
     // Setup CGroup controller with resource limits
-    let controller = setup_cgroup_controller(&container_id, cpu, memory).await?;
+    let controller = setup_cgroup_controller(
+        &container_id,
+        spec,
+        pod.as_deref(),
+        replace,
+        oom_group,
+        force,
+    )
+    .await;
+    let controller = match controller {
+        Ok(controller) => controller.with_keep_on_exit(keep),
+        Err(e) => {
+            if overlay {
+                registry::remove_overlay(container_id.as_str());
+            }
+            return Err(e);
+        }
+    };
 
-    // Setup namespace configuration
-    let ns_config = setup_namespace_config(no_namespaces, hostname)?;
+    if detach {
+        return run_detached(
+            &container_id,
+            &saved,
+            rootfs,
+            &hook_state,
+            pod.as_deref(),
+            controller,
+        )
+        .await;
+    }
+
+    // One controller serves the monitor and the cleanup at the end
+    let controller = Arc::new(controller);
 
     // Display configuration to user
-    display_configuration(id, cpu, memory, command, &ns_config);
+    if !json {
+        display_configuration(&container_id, spec, pod.as_deref());
+    }
 
     // Start monitoring if requested
-    let monitor_handle = if enable_monitor {
-        Some(start_monitoring(&container_id).await?)
+    let monitor_handle = if let Some(interval) = monitor_interval {
+        Some(start_monitoring(&container_id, controller.clone(), interval, idle).await?)
     } else {
         None
     };
+    let idle_stop = match (&monitor_handle, idle) {
+        (Some((monitor, _)), Some(_)) => Some(stop_when_idle(
+            monitor,
+            Arc::clone(&controller),
+            spec.termination,
+        )),
+        _ => None,
+    };
+
+    // Record the container so other commands can inspect it
+    let registry = registry::open();
+    let mut record = registry::record(container_id.as_str(), spec, pod.as_deref());
+    record.state = ContainerState::Running;
+    if let Err(e) = registry.insert(record) {
+        tracing::warn!(error = %e, "Failed to record container in registry");
+    }
+    registry::save_run(container_id.as_str(), &saved);
 
     // Execute command in isolated namespace
-    println!("\n🚀 Starting container...\n");
-    let result = execute_in_namespace(ns_config, command)?;
+    if !json {
+        println!("\n{}Starting container...\n", Icon::Start);
+    }
+    let result = execute_in_namespace(spec, rootfs, &hook_state, controller.path(), stdio);
+    if let Some(idle_stop) = idle_stop {
+        idle_stop.abort();
+    }
 
-    // Display execution results
-    display_execution_results(&result);
+    // Wait for the monitor to finish its last read before anything removes
+    // the cgroup under it
+    let monitor_stopped = match monitor_handle {
+        Some((monitor, handle)) => stop_monitoring(monitor, handle).await,
+        None => Ok(()),
+    };
 
-    // Stop monitoring if it was enabled
-    if let Some((monitor, handle)) = monitor_handle {
-        stop_monitoring(monitor, handle).await?;
+    if overlay {
+        registry::remove_overlay(container_id.as_str());
+    }
+    if keep {
+        if let Err(e) = registry.update_state(container_id.as_str(), ContainerState::Exited) {
+            tracing::warn!(error = %e, "Failed to record container in registry");
+        }
+    } else {
+        registry::remove(container_id.as_str());
     }
 
-    // Cleanup CGroup controller
-    controller
-        .cleanup()
-        .await
-        .context("Failed to cleanup controller")?;
+    // Nothing is left running, so don't leave the cgroup behind either
+    let cleaned_up = controller.cleanup().await;
+    if let Some(ref pod) = pod {
+        super::remove_pod_if_empty(pod).await;
+    }
 
-    println!("\n✅ Container stopped");
+    // Poststop hooks run however the container ended; it is gone by now, so
+    // a failing hook is only reported
+    if let Err(e) = vortex_namespace::hook::run_hooks(&spec.poststop, &hook_state.stopped()) {
+        tracing::warn!(error = %e, "Poststop hook failed");
+    }
+
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => {
+            if let Err(cleanup) = cleaned_up {
+                tracing::warn!(error = %cleanup, "Failed to clean up after failed start");
+            }
+            return Err(e);
+        }
+    };
+
+    // Display execution results
+    if json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        display_execution_results(&result, &spec.namespaces);
+    }
+
+    cleaned_up.context("Failed to cleanup controller")?;
+    monitor_stopped.context("Monitoring failed")?;
+
+    if json {
+        return Ok(());
+    }
+    println!("\n{}Container stopped", Icon::Ok);
+    if keep {
+        println!(
+            "   Cgroup kept at {}; remove it with `vortex rm --id {}`",
+            controller.path().display(),
+            container_id
+        );
+    }
 
     Ok(())
 }
 
+/// Start a container again from the run it was saved with
+///
+/// This is `run --detach` with the saved spec: the new instance runs in
+/// the background under the same ID. The previous one must be stopped and
+/// its cgroup removed already.
+pub(super) async fn restart(
+    container_id: &ContainerId,
+    saved: &SavedRun,
+    pod: Option<&str>,
+) -> Result<()> {
+    let spec = &saved.spec;
+    validate_environment()?;
+    vortex_security::require_capabilities(&required_capabilities(spec))?;
+
+    let rootfs = prepare_rootfs(container_id, spec, saved.overlay)?;
+    let controller =
+        setup_cgroup_controller(container_id, spec, pod, false, saved.oom_group, false).await?;
+    let hook_state = hook_state(container_id, saved.bundle.as_deref());
+
+    run_detached(container_id, saved, rootfs, &hook_state, pod, controller).await
+}
+
+/// State reported to the hooks of a container
+fn hook_state(container_id: &ContainerId, bundle: Option<&Path>) -> HookState {
+    match bundle {
+        Some(dir) => HookState::new(container_id.as_str()).with_bundle(dir),
+        None => HookState::new(container_id.as_str()),
+    }
+}
+
 /// Check if running as root
 fn is_root() -> bool {
     unsafe { libc::getuid() == 0 }
@@ -68,109 +267,439 @@ fn is_root() -> bool {
 fn validate_environment() -> Result<()> {
     // Check if running as root
     if !is_root() {
-        anyhow::bail!(
-            "🔒 Permission Denied\n\
-             \n\
-             Vortex needs root permissions to:\n\
-             • Create cgroups (resource limits)\n\
-             • Create namespaces (isolation)\n\
-             • Access kernel files\n\
-             \n\
-             Please run with sudo:\n\
-             $ sudo vortex run ..."
-        );
+        return Err(CliError::PermissionDenied {
+            operation: "run containers".to_string(),
+        }
+        .into());
     }
 
     // Check if CGroup v2 is available
     let cgroup_root = std::path::Path::new("/sys/fs/cgroup");
     if !cgroup_root.exists() {
-        anyhow::bail!(
-            "❌ CGroup v2 Not Found\n\
-             \n\
-             CGroup filesystem not mounted at /sys/fs/cgroup\n\
-             \n\
-             Check if CGroup v2 is enabled:\n\
-             $ mount | grep cgroup2\n\
-             \n\
-             On most modern Linux distributions, this should be automatic.\n\
-             If not, you may need to enable it in your kernel boot parameters."
-        );
+        return Err(CliError::CgroupV2Missing.into());
+    }
+
+    // Without a controller its limit silently does nothing, so say so up front
+    if let Ok(controllers) = CGroupController::root_controllers(cgroup_root) {
+        let missing = controller::missing_controllers(&controllers);
+        if !missing.is_empty() {
+            eprintln!(
+                "{}CGroup controllers not available: {}; their limits will not apply",
+                Icon::Warning,
+                missing.join(", ")
+            );
+        }
     }
 
     Ok(())
 }
 
-/// Create and validate container ID
-fn create_container_id(id: &str) -> Result<ContainerId> {
-    ContainerId::new(id).context("Invalid container ID")
+/// Capabilities the spec needs, checked before any setup so every missing
+/// one is reported together
+///
+/// An unprivileged caller may create a user namespace, so one on its own
+/// needs no `CAP_SYS_ADMIN`. A network namespace only gets a loopback
+/// device, which needs no `CAP_NET_ADMIN` to set up. Shifting the clocks
+/// of a time namespace needs `CAP_SYS_TIME` on top.
+pub(crate) fn required_capabilities(spec: &RunSpec) -> Vec<Capability> {
+    let mut required = Vec::new();
+    let other_than_user = spec
+        .namespaces
+        .enabled_namespaces()
+        .iter()
+        .any(|namespace| *namespace != "user");
+    if other_than_user || spec.root.is_some() {
+        required.push(Capability::SysAdmin);
+    }
+    // A limit that can't be read is left for the executor to report
+    let lowers_oom_score = spec
+        .oom_score_adj
+        .is_some_and(|adj| OomScoreAdj::current().is_ok_and(|current| adj < current));
+    if lowers_oom_score
+        || spec
+            .rlimits
+            .iter()
+            .any(|rlimit| rlimit.raises_hard_limit().unwrap_or(false))
+    {
+        required.push(Capability::SysResource);
+    }
+    if spec.scheduling.is_some_and(Scheduling::needs_privilege) {
+        required.push(Capability::SysNice);
+    }
+    if spec.namespaces.time && !spec.namespaces.time_offsets.is_zero() {
+        required.push(Capability::SysTime);
+    }
+    required
+}
+
+/// Create and validate container ID, generating one if not given
+fn create_container_id(id: Option<&str>) -> Result<ContainerId> {
+    match id {
+        Some(id) => ContainerId::new(id).context("Invalid container ID"),
+        None => Ok(ContainerId::generate()),
+    }
+}
+
+/// Spec of a run without a bundle, before the profile and flags apply
+pub(crate) fn default_spec() -> RunSpec {
+    RunSpec {
+        args: Vec::new(),
+        env: Vec::new(),
+        env_keep: Vec::new(),
+        cgroup_parent: None,
+        cwd: None,
+        rlimits: Vec::new(),
+        no_new_privs: true,
+        terminal: false,
+        init: false,
+        merge_stderr: false,
+        timeout: None,
+        termination: TerminationConfig::default(),
+        user: None,
+        groups: Vec::new(),
+        umask: None,
+        oom_score_adj: None,
+        scheduling: None,
+        root: None,
+        tmpfs: Vec::new(),
+        prestart: Vec::new(),
+        poststop: Vec::new(),
+        hostname: None,
+        cpu: None,
+        memory: None,
+        pids: None,
+        namespaces: NamespaceConfig::minimal(),
+    }
+}
+
+/// Build the run spec from CLI flags, falling back to the profile, the bundle
+/// and defaults
+///
+/// Precedence is: explicit CLI flag > profile > bundle config > built-in
+/// default.
+fn build_run_spec(
+    args: RunArgs,
+    bundle: Option<RunSpec>,
+    profile: Option<RunProfile>,
+) -> Result<RunSpec> {
+    let mut spec = bundle.unwrap_or_else(default_spec);
+
+    if let Some(profile) = profile {
+        apply_profile(&mut spec, profile)?;
+    }
+
+    if !args.command.is_empty() {
+        spec.args = args.command;
+    }
+    spec.env_keep = args.env_keep;
+    spec.cgroup_parent = args.cgroup_parent;
+
+    if let Some(pids) = args.pids_max {
+        spec.pids = Some(pids);
+    }
+
+    if let Some(cores) = args.cpu {
+        spec.cpu = Some(CpuLimit::new(cores));
+    }
+    spec.cpu
+        .get_or_insert(CpuLimit::new(CpuCores::new(DEFAULT_CPU_CORES)));
+
+    if let Some(memory) = args.memory {
+        let size = MemorySize::try_from_mb(memory).context("Invalid --memory value")?;
+        spec.memory = Some(MemoryLimit::new(size));
+    }
+    spec.memory
+        .get_or_insert(MemoryLimit::new(MemorySize::from_mb(DEFAULT_MEMORY_MB)));
+
+    // CLI ulimits override bundle rlimits for the same resource
+    for rlimit in args.ulimits {
+        spec.rlimits.retain(|r| r.resource != rlimit.resource);
+        spec.rlimits.push(rlimit);
+    }
+
+    if args.tty {
+        spec.terminal = true;
+    }
+    if args.init {
+        spec.init = true;
+    }
+    if args.merge_stderr {
+        spec.merge_stderr = true;
+    }
+    if let Some(timeout) = args.timeout {
+        spec.timeout = Some(timeout);
+    }
+    if let Some(signal) = args.stop_signal {
+        spec.termination.signal = signal;
+    }
+    if let Some(grace) = args.stop_grace {
+        spec.termination.grace = grace;
+    }
+
+    if let Some(user) = args.user {
+        spec.user = Some(user);
+    }
+    spec.groups.extend(args.groups);
+
+    if let Some(adj) = args.oom_score_adj {
+        spec.oom_score_adj = Some(adj);
+    }
+
+    if args.sched.is_some() || args.nice.is_some() {
+        let scheduling = Scheduling::new(args.sched, args.sched_priority, args.nice)
+            .context("Invalid scheduling options")?;
+        spec.scheduling = Some(scheduling);
+    }
+
+    if args.allow_new_privileges {
+        spec.no_new_privs = false;
+    }
+
+    if args.no_namespaces {
+        spec.namespaces = NamespaceConfig::none();
+    }
+
+    if let Some(hostname) = args.hostname {
+        spec.namespaces = spec.namespaces.with_hostname(hostname.as_str());
+        spec.hostname = Some(hostname);
+    }
+
+    if let Some(rootfs) = args.rootfs {
+        spec.root = Some(rootfs);
+    }
+
+    spec.tmpfs.extend(args.tmpfs);
+
+    spec.prestart.extend(args.hook_prestart);
+    // Like OCI poststop hooks, these cannot undo a finished run
+    spec.poststop
+        .extend(args.hook_poststop.into_iter().map(|hook| Hook {
+            abort_on_failure: false,
+            ..hook
+        }));
+
+    Ok(spec)
+}
+
+/// Lay a profile over the bundle spec
+fn apply_profile(spec: &mut RunSpec, profile: RunProfile) -> Result<()> {
+    let namespaces = profile.namespace_config()?;
+    if let Some(cpu) = profile.cpu {
+        let cores = CpuCores::try_new(cpu).context("Invalid cpu in profile")?;
+        spec.cpu = Some(CpuLimit::new(cores));
+    }
+    if let Some(memory) = profile.memory {
+        let size = MemorySize::try_from_mb(memory).context("Invalid memory in profile")?;
+        spec.memory = Some(MemoryLimit::new(size));
+    }
+    if let Some(pids) = profile.pids {
+        spec.pids = Some(pids);
+    }
+
+    // Profile variables replace bundle ones with the same name
+    for var in profile.env {
+        spec.env
+            .retain(|existing| env_name(existing) != env_name(&var));
+        spec.env.push(var);
+    }
+
+    if let Some(namespaces) = namespaces {
+        spec.namespaces = namespaces;
+    }
+    if let Some(hostname) = profile.hostname {
+        spec.namespaces = spec.namespaces.clone().with_hostname(hostname.as_str());
+        spec.hostname = Some(hostname);
+    }
+
+    Ok(())
+}
+
+/// Name part of a `KEY=VALUE` variable
+fn env_name(var: &str) -> &str {
+    var.split_once('=').map_or(var, |(name, _)| name)
+}
+
+/// Root filesystem for the container, creating the overlay's upper and
+/// work directories when `overlay` is set
+fn prepare_rootfs(
+    container_id: &ContainerId,
+    spec: &RunSpec,
+    overlay: bool,
+) -> Result<Option<RootfsSpec>> {
+    let Some(ref root) = spec.root else {
+        if overlay {
+            anyhow::bail!("--overlay needs a root filesystem (--rootfs or a bundle root.path)");
+        }
+        return Ok(None);
+    };
+
+    if !overlay {
+        return Ok(Some(RootfsSpec::Path(root.clone())));
+    }
+
+    let dir = registry::overlay_dir(container_id.as_str());
+    let upper = dir.join("upper");
+    let work = dir.join("work");
+    for scratch in [&upper, &work] {
+        std::fs::create_dir_all(scratch)
+            .with_context(|| format!("Failed to create {}", scratch.display()))?;
+    }
+
+    Ok(Some(RootfsSpec::Overlay {
+        lower: root.clone(),
+        upper,
+        work,
+    }))
 }
 
 /// Setup CGroup controller with resource limits
+///
+/// With a pod, the container cgroup is created inside it and its limits
+/// must fit within the pod caps. With `replace`, a running container with
+/// the same ID is killed instead of being reported as a conflict. With
+/// `oom_group`, an OOM kill takes down the whole container. With `force`,
+/// the memory limit is set even below what the cgroup already uses.
 async fn setup_cgroup_controller(
     container_id: &ContainerId,
-    cpu: f64,
-    memory: u64,
+    spec: &RunSpec,
+    pod: Option<&str>,
+    replace: bool,
+    oom_group: bool,
+    force: bool,
+) -> Result<CGroupController> {
+    let controller = create_cgroup_controller(container_id, spec, pod, replace, force).await?;
+
+    if oom_group {
+        controller
+            .set_oom_group(true)
+            .await
+            .context("Failed to set memory.oom.group")?;
+    }
+
+    Ok(controller)
+}
+
+async fn create_cgroup_controller(
+    container_id: &ContainerId,
+    spec: &RunSpec,
+    pod: Option<&str>,
+    replace: bool,
+    force: bool,
 ) -> Result<CGroupController> {
+    let mut config = CGroupConfig::new().with_replace(replace);
+    if let Some(ref parent) = spec.cgroup_parent {
+        config = config.with_namespace(parent);
+        CGroupController::check_delegated(&config.vortex_root())
+            .with_context(|| format!("Cannot create the container in cgroup parent '{parent}'"))?;
+    }
+
+    if let Some(pod) = pod {
+        let pod = PodController::with_config(pod, config)
+            .await
+            .context("Failed to create pod")?;
+
+        let controller = pod
+            .spawn(container_id.clone(), spec.cpu, spec.memory)
+            .await
+            .with_context(|| format!("Failed to create container in pod '{}'", pod.name()))?;
+        if let Some(pids) = spec.pids {
+            controller
+                .set_pids_limit(pids)
+                .await
+                .context("Failed to set process limit")?;
+        }
+        return Ok(controller);
+    }
+
     // Create controller
-    let controller = CGroupController::new(container_id.clone())
+    let controller = CGroupController::with_config(container_id.clone(), config)
         .await
         .context("Failed to create CGroup controller")?;
 
-    // Set CPU limit
-    let cpu_limit = CpuLimit::new(CpuCores::new(cpu));
-    controller
-        .set_cpu_limit(cpu_limit)
-        .await
-        .context("Failed to set CPU limit")?;
+    // Refuse an impossible memory limit before anything is written
+    if let Some(memory) = spec.memory {
+        controller
+            .check_memory_usage(memory.limit, force)
+            .await
+            .context("Failed to set memory limit")?;
+    }
 
-    // Set memory limit
-    let memory_limit = MemoryLimit::new(MemorySize::from_mb(memory));
+    let limits = ResourceLimits {
+        cpu: spec.cpu,
+        memory: spec.memory,
+        pids: spec.pids,
+        ..ResourceLimits::default()
+    };
     controller
-        .set_memory_limit(memory_limit)
+        .apply_limits(&limits)
         .await
-        .context("Failed to set memory limit")?;
+        .context("Failed to set resource limits")?;
+
+    if let Some(memory) = spec.memory
+        && let Some(effective) = controller.verify_memory_limit(memory.limit).await?
+    {
+        eprintln!(
+            "{}Memory limit is {} instead of the requested {}",
+            Icon::Warning,
+            effective,
+            memory.limit
+        );
+    }
 
     Ok(controller)
 }
 
-/// Setup namespace configuration
-fn setup_namespace_config(
-    no_namespaces: bool,
-    hostname: Option<String>,
-) -> Result<NamespaceConfig> {
-    if no_namespaces {
-        return Ok(NamespaceConfig::new());
+/// Display container configuration to user
+fn display_configuration(container_id: &ContainerId, spec: &RunSpec, pod: Option<&str>) {
+    println!("\n{}Container {} configured", Icon::Ok, container_id);
+    if let Some(pod) = pod {
+        println!("   Pod: {}", pod);
     }
-
-    let mut config = NamespaceConfig::minimal();
-
-    if let Some(h) = hostname {
-        config = config.with_hostname(h);
+    if let Some(cpu) = spec.cpu {
+        println!("   CPU limit: {cpu}");
+    }
+    if let Some(memory) = spec.memory {
+        println!("   Memory limit: {memory}");
+    }
+    if let Some(pids) = spec.pids {
+        println!("   Process limit: {pids}");
+    }
+    println!("   Command: {}", spec.args.join(" "));
+    if !spec.rlimits.is_empty() {
+        let rlimits: Vec<String> = spec.rlimits.iter().map(ToString::to_string).collect();
+        println!("   Ulimits: {}", rlimits.join(", "));
+    }
+    if let Some(user) = spec.user {
+        println!("   User: {}", user);
+    }
+    if !spec.groups.is_empty() {
+        let groups: Vec<String> = spec.groups.iter().map(ToString::to_string).collect();
+        println!("   Groups: {}", groups.join(", "));
+    }
+    if let Some(adj) = spec.oom_score_adj {
+        println!("   OOM score adjustment: {}", adj);
+    }
+    if let Some(scheduling) = spec.scheduling {
+        println!("   Scheduling: {scheduling}");
     }
 
-    Ok(config)
-}
-
-/// Display container configuration to user
-fn display_configuration(
-    id: &str,
-    cpu: f64,
-    memory: u64,
-    command: &[String],
-    ns_config: &NamespaceConfig,
-) {
-    println!("\n✅ Container {} configured", id);
-    println!("   CPU limit: {} cores", cpu);
-    println!("   Memory limit: {} MB", memory);
-    println!("   Command: {}", command.join(" "));
+    if let Some(ref root) = spec.root {
+        println!("   Root filesystem: {}", root.display());
+    }
+    for tmpfs in &spec.tmpfs {
+        match tmpfs.size {
+            Some(size) => println!("   Tmpfs: {} ({})", tmpfs.target.display(), size),
+            None => println!("   Tmpfs: {}", tmpfs.target.display()),
+        }
+    }
 
     // Access hostname field directly
-    if let Some(ref hostname) = ns_config.hostname {
+    if let Some(ref hostname) = spec.namespaces.hostname {
         println!("   Hostname: {}", hostname);
     }
 
-    if ns_config.has_any() {
-        let enabled = ns_config.enabled_namespaces();
+    if spec.namespaces.has_any() {
+        let enabled = spec.namespaces.enabled_namespaces();
         println!("   Namespaces: {}", enabled.join(", "));
     } else {
         println!("   Namespaces: disabled");
@@ -180,20 +709,20 @@ fn display_configuration(
 /// Start resource monitoring for the container
 async fn start_monitoring(
     container_id: &ContainerId,
+    backend: Arc<dyn ResourceBackend>,
+    interval: Duration,
+    idle: Option<IdleConfig>,
 ) -> Result<(ResourceMonitor, tokio::task::JoinHandle<()>)> {
-    // Create separate controller for monitoring
-    // (We can't use the main controller because it needs to be moved for cleanup)
-    let monitoring_controller = CGroupController::new(container_id.clone())
-        .await
-        .context("Failed to create monitoring controller")?;
-
-    let backend: Arc<dyn ResourceBackend> = Arc::new(monitoring_controller);
-
-    let monitor = ResourceMonitor::new(
+    let mut monitor = ResourceMonitor::new(
         backend,
         container_id.clone(),
-        2, // Poll every 2 seconds
-    );
+        DEFAULT_MONITOR_INTERVAL.as_secs(),
+    )
+    .with_interval(interval)
+    .with_plain_output(output::plain());
+    if let Some(idle) = idle {
+        monitor = monitor.with_idle_detection(idle);
+    }
 
     let handle = monitor
         .start()
@@ -203,39 +732,257 @@ async fn start_monitoring(
     Ok((monitor, handle))
 }
 
-/// Execute command in isolated namespace
-fn execute_in_namespace(
-    ns_config: NamespaceConfig,
-    command: &[String],
-) -> Result<vortex_namespace::ExecutionResult> {
-    if command.is_empty() {
-        anyhow::bail!("No command specified");
+/// Stop the container the first time the monitor reports it idle
+///
+/// The processes get the stop signal, then `SIGKILL` if they outlast the
+/// grace period; the executor sees the command exit as usual.
+fn stop_when_idle(
+    monitor: &ResourceMonitor,
+    controller: Arc<CGroupController>,
+    termination: TerminationConfig,
+) -> tokio::task::JoinHandle<()> {
+    let mut idle = monitor
+        .events()
+        .subscribe_filtered(1, |e| matches!(e, ContainerEvent::Idle { .. }));
+
+    tokio::spawn(async move {
+        let Some(event) = idle.recv().await else {
+            return;
+        };
+        println!("\n{}{event}, stopping it", Icon::Warning);
+        match super::stop::ask_to_exit(&controller, termination).await {
+            Ok(Some(Termination::Killed)) => controller.empty(true).await,
+            Ok(_) => {}
+            Err(e) => tracing::warn!(error = %e, "Failed to stop idle container"),
+        }
+    })
+}
+
+/// Start the container in the background and print its ID
+///
+/// The cgroup is left in place for `stop` to remove, and the container's
+/// output goes to log files in the registry.
+async fn run_detached(
+    container_id: &ContainerId,
+    saved: &SavedRun,
+    rootfs: Option<RootfsSpec>,
+    hook_state: &HookState,
+    pod: Option<&str>,
+    controller: CGroupController,
+) -> Result<()> {
+    let id = container_id.as_str();
+    let spec = &saved.spec;
+    if !spec.poststop.is_empty() {
+        tracing::warn!("Detached containers do not run poststop hooks, ignoring them");
     }
 
-    let program = &command[0];
-    let args = &command[1..];
+    // Recorded before the start, so the supervisor finds a record to
+    // update; it records the PID and the exit itself
+    let record = registry::record(id, spec, pod);
+    let started = registry::ensure_dir().and_then(|()| {
+        let (program, args) = split_command(spec)?;
+        if spec.terminal {
+            tracing::warn!("Detached containers have no terminal, ignoring it");
+        }
+        if let Err(e) = registry::open().insert(record) {
+            tracing::warn!(error = %e, "Failed to record container in registry");
+        }
+        registry::save_run(id, saved);
+        let (started, exited) = (id.to_string(), id.to_string());
+        build_executor(spec, rootfs, hook_state, controller.path())?
+            .with_tty(false)
+            .spawn_supervised(
+                program,
+                args,
+                &registry::log_path(id, LogStream::Stdout),
+                &registry::log_path(id, LogStream::Stderr),
+                move |pid| registry::record_start(&started, pid),
+                move |exit| registry::record_exit(&exited, exit),
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to start detached container: {}", e))
+    });
+
+    let pid = match started {
+        Ok(pid) => pid,
+        Err(e) => {
+            if let Err(cleanup) = controller.cleanup().await {
+                tracing::warn!(error = %cleanup, "Failed to clean up after failed start");
+            }
+            if let Some(pod) = pod {
+                super::remove_pod_if_empty(pod).await;
+            }
+            registry::remove(id);
+            registry::remove_overlay(id);
+            return Err(e);
+        }
+    };
+
+    // The container outlives us; `stop` removes the cgroup
+    controller.release();
 
-    let executor = NamespaceExecutor::new(ns_config)
-        .map_err(|e| anyhow::anyhow!("Failed to create executor: {}", e))?;
+    tracing::info!(pid, "Container running in the background");
+    println!("{}", container_id);
+    Ok(())
+}
+
+/// Split the command into program and arguments
+fn split_command(spec: &RunSpec) -> Result<(&str, &[String])> {
+    match spec.args.split_first() {
+        Some((program, args)) => Ok((program, args)),
+        None => anyhow::bail!("No command specified"),
+    }
+}
+
+/// Execute command in isolated namespace
+fn execute_in_namespace(
+    spec: &RunSpec,
+    rootfs: Option<RootfsSpec>,
+    hook_state: &HookState,
+    cgroup: &Path,
+    stdio: Stdio,
+) -> Result<vortex_namespace::ExecutionResult> {
+    let (program, args) = split_command(spec)?;
 
-    executor
+    build_executor(spec, rootfs, hook_state, cgroup)?
+        .with_stdio(stdio)
         .execute(program, args)
         .map_err(|e| anyhow::anyhow!("Failed to execute command: {}", e))
 }
 
+/// Configure an executor for the spec
+fn build_executor(
+    spec: &RunSpec,
+    rootfs: Option<RootfsSpec>,
+    hook_state: &HookState,
+    cgroup: &Path,
+) -> Result<NamespaceExecutor> {
+    let mut executor = NamespaceExecutor::new(spec.namespaces.clone())
+        .map_err(|e| anyhow::anyhow!("Failed to create executor: {}", e))?
+        .with_cgroup(cgroup);
+
+    if let Some(rootfs) = rootfs {
+        executor = executor.with_rootfs(rootfs);
+    }
+    if !spec.tmpfs.is_empty() {
+        executor = executor.with_tmpfs(spec.tmpfs.clone());
+    }
+    if !spec.prestart.is_empty() {
+        executor = executor.with_prestart_hooks(spec.prestart.clone(), hook_state.clone());
+    }
+
+    if !spec.env.is_empty() {
+        executor = executor.with_env(spec.env.clone());
+    }
+    if !spec.env_keep.is_empty() {
+        executor = executor.with_env_keep(spec.env_keep.clone());
+    }
+    if let Some(ref cwd) = spec.cwd {
+        executor = executor.with_cwd(cwd);
+    }
+    if !spec.rlimits.is_empty() {
+        executor = executor.with_rlimits(spec.rlimits.clone());
+    }
+    if let Some(user) = spec.user {
+        executor = executor.with_user(user);
+    }
+    if !spec.groups.is_empty() {
+        executor = executor.with_groups(spec.groups.clone());
+    }
+    if let Some(umask) = spec.umask {
+        executor = executor.with_umask(umask);
+    }
+    if let Some(adj) = spec.oom_score_adj {
+        executor = executor.with_oom_score_adj(adj);
+    }
+    if let Some(scheduling) = spec.scheduling {
+        executor = executor.with_scheduling(scheduling);
+    }
+    executor = executor
+        .with_tty(spec.terminal)
+        .with_merged_stderr(spec.merge_stderr)
+        .with_init(spec.init)
+        .with_termination(spec.termination);
+    if let Some(timeout) = spec.timeout {
+        executor = executor.with_timeout(timeout);
+    }
+    executor = executor.with_security(SecurityManager::with_config(SecurityConfig {
+        no_new_privs: spec.no_new_privs,
+    }));
+
+    Ok(executor)
+}
+
 /// Display execution results to user
-fn display_execution_results(result: &vortex_namespace::ExecutionResult) {
-    println!("\n📊 Execution completed");
+fn display_execution_results(
+    result: &vortex_namespace::ExecutionResult,
+    requested: &NamespaceConfig,
+) {
+    println!("\n{}Execution completed", Icon::Stats);
     println!("   Exit code: {}", result.exit_code);
+    if requested.has_any() {
+        display_isolation(&result.isolated, requested);
+    }
+    match result.stopped {
+        Some(Termination::Graceful) => println!("   Timed out: exited after the stop signal"),
+        Some(Termination::Killed) => println!("   Timed out: killed after the grace period"),
+        None => {}
+    }
 
     if !result.stdout.is_empty() {
         println!("\n--- STDOUT ---");
-        print!("{}", String::from_utf8_lossy(&result.stdout));
+        print!("{}", result.stdout_string());
     }
 
     if !result.stderr.is_empty() {
         println!("\n--- STDERR ---");
-        eprint!("{}", String::from_utf8_lossy(&result.stderr));
+        eprint!("{}", result.stderr_string());
+    }
+
+    if result.truncated {
+        println!(
+            "\n{}Output truncated to {} MB per stream",
+            Icon::Warning,
+            NamespaceExecutor::DEFAULT_MAX_CAPTURE / (1024 * 1024)
+        );
+    }
+}
+
+/// Show which namespaces the container really got, as read back from
+/// `/proc`, and warn about requested ones it shares with the host
+fn display_isolation(isolated: &[NamespaceKind], requested: &NamespaceConfig) {
+    let shared = NamespaceKind::ALL
+        .into_iter()
+        .filter(|kind| !isolated.contains(kind));
+    println!(
+        "   Isolated namespaces: {}",
+        join_kinds(isolated.iter().copied())
+    );
+    println!("   Shared with host: {}", join_kinds(shared));
+
+    let missing: Vec<&str> = requested
+        .enabled_namespaces()
+        .into_iter()
+        .filter(|&name| !isolated.iter().any(|kind| kind.name() == name))
+        .collect();
+    if !missing.is_empty() {
+        println!(
+            "{}Requested but not isolated: {}",
+            Icon::Warning,
+            missing.join(", ")
+        );
+        if missing.contains(&"pid") {
+            println!("   Only a program started with --init runs in the new PID namespace");
+        }
+    }
+}
+
+/// Comma-separated namespace names, or `none`
+fn join_kinds(kinds: impl Iterator<Item = NamespaceKind>) -> String {
+    let names: Vec<&str> = kinds.map(NamespaceKind::name).collect();
+    if names.is_empty() {
+        "none".to_string()
+    } else {
+        names.join(", ")
     }
 }
 
@@ -248,3 +995,115 @@ async fn stop_monitoring(
     handle.await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Commands};
+    use clap::Parser;
+    use vortex_namespace::TimeOffsets;
+
+    fn run_args(flags: &[&str]) -> RunArgs {
+        let cli = Cli::parse_from(["vortex", "run"].iter().chain(flags).chain(&["--", "true"]));
+        match cli.command {
+            Commands::Run(args) => *args,
+            other => panic!("not a run command: {other:?}"),
+        }
+    }
+
+    fn spec(flags: &[&str], bundle: Option<RunSpec>, profile: Option<RunProfile>) -> RunSpec {
+        build_run_spec(run_args(flags), bundle, profile).unwrap()
+    }
+
+    #[test]
+    fn test_defaults_without_profile() {
+        let spec = spec(&[], None, None);
+        assert_eq!(
+            spec.cpu,
+            Some(CpuLimit::new(CpuCores::new(DEFAULT_CPU_CORES)))
+        );
+        assert_eq!(
+            spec.memory,
+            Some(MemoryLimit::new(MemorySize::from_mb(DEFAULT_MEMORY_MB)))
+        );
+        assert!(spec.pids.is_none());
+    }
+
+    #[test]
+    fn test_flags_override_profile() {
+        let profile = RunProfile {
+            memory: Some(64),
+            namespaces: Some(vec!["pid".to_string(), "uts".to_string()]),
+            hostname: Some("preset".to_string()),
+            ..RunProfile::builtin("small").unwrap()
+        };
+
+        // The profile fills in what the flags leave open
+        let preset = spec(&[], None, Some(profile.clone()));
+        assert_eq!(preset.cpu, Some(CpuLimit::new(CpuCores::new(0.5))));
+        assert_eq!(
+            preset.memory,
+            Some(MemoryLimit::new(MemorySize::from_mb(64)))
+        );
+        assert_eq!(preset.pids, Some(128));
+        assert_eq!(preset.namespaces.enabled_namespaces(), vec!["pid", "uts"]);
+        assert_eq!(preset.hostname.as_deref(), Some("preset"));
+
+        let flags = ["--memory", "32", "--pids-max", "8", "--hostname", "flag"];
+        let spec = spec(&flags, None, Some(profile));
+        assert_eq!(spec.cpu, Some(CpuLimit::new(CpuCores::new(0.5))));
+        assert_eq!(spec.memory, Some(MemoryLimit::new(MemorySize::from_mb(32))));
+        assert_eq!(spec.pids, Some(8));
+        assert_eq!(spec.namespaces.hostname.as_deref(), Some("flag"));
+    }
+
+    #[test]
+    fn test_profile_overrides_bundle() {
+        let mut bundle = spec(&[], None, None);
+        bundle.env = vec!["A=bundle".to_string(), "B=bundle".to_string()];
+        bundle.cpu = Some(CpuLimit::new(CpuCores::new(3.0)));
+        let profile = RunProfile {
+            cpu: Some(0.25),
+            env: vec!["A=profile".to_string()],
+            namespaces: Some(Vec::new()),
+            ..RunProfile::default()
+        };
+
+        let spec = spec(&[], Some(bundle), Some(profile));
+        assert_eq!(spec.cpu, Some(CpuLimit::new(CpuCores::new(0.25))));
+        assert_eq!(spec.env, vec!["B=bundle", "A=profile"]);
+        assert!(!spec.namespaces.has_any());
+    }
+
+    #[test]
+    fn test_required_capabilities_follow_namespaces() {
+        let mut spec = default_spec();
+        assert_eq!(required_capabilities(&spec), vec![Capability::SysAdmin]);
+
+        // A user namespace alone is open to unprivileged callers
+        spec.namespaces = NamespaceConfig {
+            user: true,
+            ..NamespaceConfig::none()
+        };
+        assert!(required_capabilities(&spec).is_empty());
+
+        spec.namespaces.network = true;
+        assert_eq!(required_capabilities(&spec), vec![Capability::SysAdmin]);
+    }
+
+    #[test]
+    fn test_required_capabilities_for_time_offsets() {
+        let mut spec = default_spec();
+        spec.namespaces = NamespaceConfig::none().with_time(true);
+        assert_eq!(required_capabilities(&spec), vec![Capability::SysAdmin]);
+
+        spec.namespaces = spec.namespaces.with_time_offsets(TimeOffsets {
+            boottime_nanos: 1,
+            ..TimeOffsets::ZERO
+        });
+        assert_eq!(
+            required_capabilities(&spec),
+            vec![Capability::SysAdmin, Capability::SysTime]
+        );
+    }
+}
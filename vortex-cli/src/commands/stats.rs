@@ -1,21 +1,69 @@
 //! Stats command implementation
 
 use anyhow::{Context, Result};
-use vortex_cgroup::{CGroupController, ResourceBackend};
-use vortex_core::ContainerId;
+use std::time::{Duration, Instant};
+use vortex_cgroup::{CGroupConfig, CGroupController, ResourceBackend};
+use vortex_core::{ContainerId, MemorySize, ProcessId, ResourceRates, ResourceStats};
 
-pub async fn execute(id: &str) -> Result<()> {
-    tracing::info!(container_id = id, "Getting stats");
+use super::cgroup_config;
+use crate::output::Icon;
 
-    let container_id = ContainerId::new(id).context("Invalid container ID")?;
+#[tracing::instrument(name = "stats", skip_all, fields(container_id = %id, pod = pod))]
+pub async fn execute(id: &str, pod: Option<&str>, verbose: bool) -> Result<()> {
+    tracing::info!(container_id = id, "Getting stats");
 
-    let controller = CGroupController::new(container_id)
-        .await
-        .context("Failed to create controller (is container running?)")?;
+    let controller = attach(id, pod)?;
 
     let stats = controller.stats().await.context("Failed to read stats")?;
 
-    println!("\n📊 Container Stats for '{}'", id);
+    println!("\n{}Container Stats for '{}'", Icon::Stats, id);
+    print_stats(&controller, &stats, verbose).await;
+
+    Ok(())
+}
+
+/// Print stats of the cgroup a process is in, whoever started it
+#[tracing::instrument(name = "stats", skip_all, fields(pid = pid))]
+pub async fn execute_pid(pid: i32, verbose: bool) -> Result<()> {
+    tracing::info!(pid, "Getting stats");
+
+    let controller = CGroupController::attach_pid(ProcessId::from_raw(pid), CGroupConfig::new())
+        .with_context(|| format!("Failed to find the cgroup of process {pid}"))?;
+
+    let stats = match controller.stats().await {
+        Ok(stats) => stats,
+        Err(e) => {
+            // Usually a cgroup whose parent doesn't delegate memory
+            let available = controller
+                .available_controllers()
+                .await
+                .map(|controllers| controllers.join(" "))
+                .unwrap_or_default();
+            anyhow::bail!(
+                "Cannot read stats of {} (available controllers: {}): {}",
+                controller.path().display(),
+                if available.is_empty() {
+                    "none"
+                } else {
+                    &available
+                },
+                e
+            );
+        }
+    };
+
+    println!(
+        "\n{}Stats for process {} ({})",
+        Icon::Stats,
+        pid,
+        controller.path().display()
+    );
+    print_stats(&controller, &stats, verbose).await;
+
+    Ok(())
+}
+
+async fn print_stats(controller: &CGroupController, stats: &ResourceStats, verbose: bool) {
     println!("{:-<60}", "");
     println!("CPU Usage:       {:.2}s", stats.cpu_usage.as_secs_f64());
     println!("CPU Throttled:   {:.2}s", stats.cpu_throttled.as_secs_f64());
@@ -25,7 +73,143 @@ pub async fn execute(id: &str) -> Result<()> {
     println!("Swap Peak:       {}", stats.swap_peak);
     println!("I/O Read:        {} bytes", stats.io_read_bytes);
     println!("I/O Write:       {} bytes", stats.io_write_bytes);
+
+    if verbose {
+        println!(
+            "Throttled:       {}/{} periods",
+            stats.cpu_nr_throttled, stats.cpu_nr_periods
+        );
+
+        let extended = controller.extended_stats().await;
+        if !extended.is_empty() {
+            println!();
+        }
+        for (name, value) in extended {
+            // hugetlb usage is in bytes, misc resources are plain counts
+            if name.starts_with("hugetlb.") {
+                println!(
+                    "{:<17}{}",
+                    format!("{name}:"),
+                    MemorySize::from_bytes(value)
+                );
+            } else {
+                println!("{:<17}{}", format!("{name}:"), value);
+            }
+        }
+
+        if let (Ok(live), Ok(dying)) = (
+            controller.nr_descendants().await,
+            controller.nr_dying_descendants().await,
+        ) {
+            println!("Descendants:     {} ({} dying)", live, dying);
+        }
+    }
     println!("{:-<60}", "");
+}
+
+/// Print stats every `interval` until Ctrl+C or the container exits
+///
+/// With `reset_peak` the peak is tracked from now on, through the
+/// descriptors that reset it; other readers of `memory.peak` still see the
+/// peak since the container started.
+#[tracing::instrument(name = "stats", skip_all, fields(container_id = %id, pod = pod))]
+pub async fn follow(
+    id: &str,
+    pod: Option<&str>,
+    interval: Duration,
+    reset_peak: bool,
+) -> Result<()> {
+    tracing::info!(
+        container_id = id,
+        interval_ms = interval.as_millis(),
+        reset_peak,
+        "Following stats"
+    );
+
+    let controller = attach(id, pod)?;
+    let peaks = if reset_peak {
+        Some(
+            controller
+                .reset_peaks()
+                .await
+                .context("Failed to reset the memory peak")?,
+        )
+    } else {
+        None
+    };
+
+    println!(
+        "\n{}Following stats for '{}' (Ctrl+C to stop)",
+        Icon::Stats,
+        id
+    );
+    println!("{:-<96}", "");
+    println!(
+        "{:<10} {:<10} {:<12} {:<16} {:<16} {:<14} {:<14}",
+        "Time", "CPU %", "Throttled %", "Memory", "Peak", "Read/s", "Write/s"
+    );
+    println!("{:-<96}", "");
+
+    let start = Instant::now();
+    let mut ticker = tokio::time::interval(interval);
+    let mut previous: Option<(ResourceStats, Instant)> = None;
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => {
+                println!("{:-<96}", "");
+                break;
+            }
+        }
+
+        let stats = match controller.stats().await {
+            Ok(stats) => stats,
+            // The cgroup is removed when the container stops
+            Err(_) if !controller.path().exists() => {
+                println!("{:-<96}", "");
+                println!("{}Container '{}' exited", Icon::Ok, id);
+                break;
+            }
+            Err(e) => return Err(e).context("Failed to read stats"),
+        };
+        let now = Instant::now();
+        let peak = match peaks {
+            Some(ref peaks) => peaks.memory().context("Failed to read the memory peak")?,
+            None => stats.memory_peak,
+        };
+
+        let rates = previous
+            .as_ref()
+            .map(|(prev, at)| ResourceRates::between(prev, &stats, now - *at))
+            .unwrap_or_default();
+
+        println!(
+            "{:<10} {:<10.1} {:<12.1} {:<16} {:<16} {:<14} {:<14}",
+            format!("{:.1}s", start.elapsed().as_secs_f64()),
+            rates.cpu_percent,
+            rates.throttled_percent,
+            stats.memory_current.to_string(),
+            peak.to_string(),
+            format_rate(rates.io_read_per_sec),
+            format_rate(rates.io_write_per_sec),
+        );
+
+        previous = Some((stats, now));
+    }
 
     Ok(())
 }
+
+/// Attach read-only to a running container's cgroup
+pub(super) fn attach(id: &str, pod: Option<&str>) -> Result<CGroupController> {
+    let container_id = ContainerId::new(id).context("Invalid container ID")?;
+
+    CGroupController::attach_with_config(container_id, cgroup_config(id, pod))
+        .context("Failed to access container (is it running?)")
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn format_rate(bytes_per_sec: f64) -> String {
+    format!("{}/s", MemorySize::from_bytes(bytes_per_sec as u64))
+}
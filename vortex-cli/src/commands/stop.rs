@@ -1,24 +1,85 @@
 //! Stop command implementation
 
 use anyhow::{Context, Result};
+use std::time::{Duration, Instant};
 use vortex_cgroup::CGroupController;
-use vortex_core::ContainerId;
+use vortex_core::{ContainerId, Error};
+use vortex_namespace::{Termination, TerminationConfig};
 
-pub async fn execute(id: &str) -> Result<()> {
+use super::cgroup_config;
+use crate::output::Icon;
+use crate::registry;
+
+/// How often to check whether the processes have exited
+const STOP_POLL: Duration = Duration::from_millis(50);
+
+#[tracing::instrument(name = "stop", skip_all, fields(container_id = %id, pod = pod))]
+pub async fn execute(id: &str, pod: Option<&str>, termination: TerminationConfig) -> Result<()> {
     tracing::info!(container_id = id, "Stopping container");
 
     let container_id = ContainerId::new(id).context("Invalid container ID")?;
 
-    let mut controller = CGroupController::new(container_id)
-        .await
+    let controller = CGroupController::adopt_with_config(container_id, cgroup_config(id, pod))
         .context("Failed to access container (is it running?)")?;
 
+    let stopped = ask_to_exit(&controller, termination).await?;
+
+    // Kill what is still running, e.g. a detached container
     controller
-        .cleanup()
+        .cleanup_with(true)
         .await
         .context("Failed to cleanup container")?;
 
-    println!("✅ Container '{}' stopped", id);
+    registry::remove(id);
+    registry::remove_overlay(id);
+
+    match stopped {
+        Some(Termination::Killed) => println!(
+            "{}Container '{id}' killed after {:?} grace period",
+            Icon::Ok,
+            termination.grace
+        ),
+        _ => println!("{}Container '{}' stopped", Icon::Ok, id),
+    }
+
+    if let Some(pod) = pod {
+        super::remove_pod_if_empty(pod).await;
+    }
 
     Ok(())
 }
+
+/// Send the stop signal to every process in the container and wait up to
+/// the grace period for them to exit
+///
+/// Returns `None` if nothing was running.
+pub(super) async fn ask_to_exit(
+    controller: &CGroupController,
+    termination: TerminationConfig,
+) -> Result<Option<Termination>> {
+    let processes = controller
+        .processes()
+        .await
+        .context("Failed to read container processes")?;
+    if processes.is_empty() {
+        return Ok(None);
+    }
+
+    for pid in processes {
+        match pid.signal(termination.signal) {
+            Ok(()) | Err(Error::ProcessNotFound { .. }) => {}
+            Err(e) => tracing::warn!(pid = %pid, error = %e, "Failed to signal process"),
+        }
+    }
+
+    let deadline = Instant::now() + termination.grace;
+    loop {
+        if controller.processes().await.is_ok_and(|p| p.is_empty()) {
+            return Ok(Some(Termination::Graceful));
+        }
+        if Instant::now() >= deadline {
+            return Ok(Some(Termination::Killed));
+        }
+        tokio::time::sleep(STOP_POLL).await;
+    }
+}
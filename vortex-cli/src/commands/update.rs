@@ -0,0 +1,177 @@
+//! Update command implementation
+
+use anyhow::{Context, Result};
+use vortex_cgroup::{CGroupConfig, CGroupController, ResourceBackend};
+use vortex_core::{
+    ContainerId, CpuCores, CpuLimit, MemoryLimit, MemorySize, Registry, ResourceLimits,
+};
+
+use super::cgroup_config;
+use crate::error::CliError;
+use crate::output::Icon;
+use crate::registry;
+
+#[tracing::instrument(name = "update", skip_all, fields(container_id = %id, pod = pod))]
+pub async fn execute(
+    id: &str,
+    pod: Option<&str>,
+    cpu: Option<CpuCores>,
+    memory: Option<u64>,
+    pids: Option<u64>,
+    force: bool,
+) -> Result<()> {
+    let limits = ResourceLimits {
+        cpu: cpu.map(CpuLimit::new),
+        memory: memory
+            .map(|memory| MemorySize::try_from_mb(memory).context("Invalid --memory value"))
+            .transpose()?
+            .map(MemoryLimit::new),
+        pids,
+        ..ResourceLimits::default()
+    };
+
+    let controller = attach(id, cgroup_config(id, pod)).await?;
+
+    // Keep the limits from before the first update, so that --revert goes
+    // all the way back rather than undoing only the last change
+    let snapshot_path = registry::limits_snapshot_path(id);
+    if !snapshot_path.exists() {
+        let snapshot = controller
+            .snapshot_limits()
+            .await
+            .context("Failed to read current limits")?;
+        let json = serde_json::to_string_pretty(&snapshot)?;
+        registry::ensure_dir()?;
+        std::fs::write(&snapshot_path, json)
+            .with_context(|| format!("Failed to save limits to {}", snapshot_path.display()))?;
+    }
+
+    if let Some(memory) = limits.memory {
+        controller
+            .check_memory_usage(memory.limit, force)
+            .await
+            .context("Failed to set memory limit")?;
+    }
+    controller
+        .apply_limits(&limits)
+        .await
+        .context("Failed to set resource limits")?;
+
+    if let Some(memory) = limits.memory
+        && let Some(effective) = controller.verify_memory_limit(memory.limit).await?
+    {
+        eprintln!(
+            "{}Memory limit is {} instead of the requested {}",
+            Icon::Warning,
+            effective,
+            memory.limit
+        );
+    }
+
+    // Only the changed limits are recorded; the rest stay as they were
+    let store = registry::open();
+    if let Ok(Some(mut record)) = store.get(id) {
+        record.cpu = limits.cpu.or(record.cpu);
+        record.memory = limits.memory.or(record.memory);
+        if let Err(e) = store.insert(record) {
+            tracing::warn!(container_id = id, error = %e, "Failed to update container record");
+        }
+    }
+
+    println!(
+        "{}Updated limits of '{id}'; revert with `vortex update --id {id} --revert`",
+        Icon::Ok
+    );
+    Ok(())
+}
+
+#[tracing::instrument(name = "update", skip_all, fields(container_id = %id, pod = pod))]
+pub async fn revert(id: &str, pod: Option<&str>) -> Result<()> {
+    let snapshot_path = registry::limits_snapshot_path(id);
+    let json = match std::fs::read_to_string(&snapshot_path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            anyhow::bail!("No saved limits for '{id}' (it was never updated)");
+        }
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", snapshot_path.display()));
+        }
+    };
+    let snapshot: ResourceLimits = serde_json::from_str(&json)
+        .with_context(|| format!("Invalid saved limits in {}", snapshot_path.display()))?;
+
+    let controller = attach(id, cgroup_config(id, pod)).await?;
+    controller
+        .restore_limits(&snapshot)
+        .await
+        .context("Failed to restore limits")?;
+
+    let store = registry::open();
+    if let Ok(Some(mut record)) = store.get(id) {
+        record.cpu = snapshot.cpu;
+        record.memory = snapshot.memory;
+        if let Err(e) = store.insert(record) {
+            tracing::warn!(container_id = id, error = %e, "Failed to update container record");
+        }
+    }
+
+    registry::remove_limits_snapshot(id);
+
+    println!("{}Limits of '{id}' reverted", Icon::Ok);
+    Ok(())
+}
+
+/// Attach to the cgroup of a running container
+///
+/// The controller is inactive, so dropping it leaves the cgroup alone.
+async fn attach(id: &str, config: CGroupConfig) -> Result<CGroupController> {
+    let container_id = ContainerId::new(id).context("Invalid container ID")?;
+    let controller = CGroupController::attach_with_config(container_id, config)
+        .context("Failed to access container (is it running?)")?;
+
+    let processes = controller
+        .processes()
+        .await
+        .context("Failed to read container processes")?;
+    if processes.is_empty() {
+        return Err(CliError::NotRunning { id: id.to_string() }.into());
+    }
+
+    Ok(controller)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_attach_refuses_container_without_processes() {
+        let root = std::env::temp_dir().join(format!("vortex-update-{}", std::process::id()));
+        let config = CGroupConfig::new().with_root(&root);
+        let (idle, busy) = (
+            config.container_parent().join("idle"),
+            config.container_parent().join("busy"),
+        );
+        std::fs::create_dir_all(&idle).unwrap();
+        std::fs::create_dir_all(&busy).unwrap();
+        std::fs::write(idle.join("cgroup.procs"), "").unwrap();
+        std::fs::write(
+            busy.join("cgroup.procs"),
+            format!("{}\n", std::process::id()),
+        )
+        .unwrap();
+
+        let err = attach("idle", config.clone()).await.unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref::<CliError>(),
+                Some(CliError::NotRunning { id }) if id == "idle"
+            ),
+            "{err:#}"
+        );
+        assert!(attach("busy", config.clone()).await.is_ok());
+        assert!(attach("missing", config).await.is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
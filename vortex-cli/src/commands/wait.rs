@@ -0,0 +1,136 @@
+//! Wait command implementation
+//!
+//! A detached container is supervised by a process of its own, which
+//! records the exit in the registry. Waiting therefore only needs to watch
+//! the record, from any process, like `docker wait`.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use vortex_core::{ContainerExit, ContainerId, Registry};
+
+use crate::error::CliError;
+use crate::registry;
+
+/// How often the record is checked
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Checks the process may be gone without its exit recorded, so the
+/// supervisor has time to write it
+const GONE_POLLS: u32 = 3;
+
+/// Wait for the container to exit, print its exit code and exit with it
+#[tracing::instrument(name = "wait", skip_all, fields(container_id = %id))]
+pub async fn execute(id: &str, timeout: Option<Duration>) -> Result<()> {
+    ContainerId::new(id).context("Invalid container ID")?;
+
+    let exit = wait_for_exit(&registry::open(), id, timeout).await?;
+    if let Some(ref signal) = exit.signal {
+        tracing::info!(signal = %signal, "Container was killed by a signal");
+    }
+    println!("{}", exit.exit_code);
+    std::process::exit(exit.exit_code)
+}
+
+/// Poll the record of `id` until it holds the exit
+pub(super) async fn wait_for_exit(
+    registry: &impl Registry,
+    id: &str,
+    timeout: Option<Duration>,
+) -> Result<ContainerExit> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut seen = false;
+    let mut gone_polls = 0;
+
+    loop {
+        let record = registry
+            .get(id)
+            .context("Failed to read container record")?;
+        let pid = match record {
+            Some(record) => match record.exit {
+                Some(exit) => return Ok(exit),
+                None => record.pid,
+            },
+            None if seen => anyhow::bail!("Container '{id}' was removed before it exited"),
+            None => return Err(CliError::ContainerNotFound { id: id.to_string() }.into()),
+        };
+        seen = true;
+        let Some(pid) = pid else {
+            anyhow::bail!(
+                "Container '{id}' was not started with --detach, so its exit code is not recorded"
+            );
+        };
+
+        // Containers started before exits were recorded have no supervisor
+        if Path::new(&format!("/proc/{pid}")).exists() {
+            gone_polls = 0;
+        } else {
+            gone_polls += 1;
+            if gone_polls > GONE_POLLS {
+                anyhow::bail!("Container '{id}' exited without recording its exit code");
+            }
+        }
+
+        if let (Some(deadline), Some(timeout)) = (deadline, timeout)
+            && Instant::now() >= deadline
+        {
+            return Err(CliError::WaitTimeout {
+                id: id.to_string(),
+                timeout,
+            }
+            .into());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vortex_core::{ContainerRecord, ContainerState, InMemoryRegistry};
+
+    fn detached(pid: i32) -> ContainerRecord {
+        ContainerRecord {
+            state: ContainerState::Running,
+            pid: Some(pid),
+            ..ContainerRecord::new("web", vec!["sleep".to_string()])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_exit() {
+        let registry = InMemoryRegistry::new();
+        let error = wait_for_exit(&registry, "web", None).await.unwrap_err();
+        assert!(error.to_string().contains("not found"), "{error}");
+
+        // Our own process stands in for a running container
+        let own = i32::try_from(std::process::id()).unwrap();
+        registry.insert(detached(own)).unwrap();
+        let error = wait_for_exit(&registry, "web", Some(Duration::ZERO))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("still running"), "{error}");
+
+        let mut exited = detached(own);
+        exited.state = ContainerState::Exited;
+        exited.exit = Some(ContainerExit::now(3, None));
+        registry.insert(exited).unwrap();
+        let exit = wait_for_exit(&registry, "web", None).await.unwrap();
+        assert_eq!(exit.exit_code, 3);
+
+        // A foreground container never records its exit
+        registry
+            .insert(ContainerRecord::new("web", Vec::new()))
+            .unwrap();
+        let error = wait_for_exit(&registry, "web", None).await.unwrap_err();
+        assert!(error.to_string().contains("--detach"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn test_wait_gives_up_on_unsupervised_container() {
+        let registry = InMemoryRegistry::new();
+        registry.insert(detached(i32::MAX)).unwrap();
+        let error = wait_for_exit(&registry, "web", None).await.unwrap_err();
+        assert!(error.to_string().contains("without recording"), "{error}");
+    }
+}
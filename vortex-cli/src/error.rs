@@ -0,0 +1,313 @@
+//! Errors the CLI reports to users
+//!
+//! Commands return [`CliError`] for failures a user or script is expected to
+//! act on. Each has a stable [`code`](CliError::code), an
+//! [`exit code`](CliError::exit_code) and, kept apart from the message, the
+//! [`help`](CliError::help) on what to do about it. Everything else stays a
+//! plain `anyhow` error and exits with 1.
+//!
+//! Exit codes:
+//!
+//! | Code | Meaning                                                       |
+//! |------|---------------------------------------------------------------|
+//! | 1    | Any other error                                               |
+//! | 2    | Invalid command line (from clap)                              |
+//! | 3    | No such container                                             |
+//! | 4    | The container is running, or not, when it must not be         |
+//! | 5    | Gave up waiting                                               |
+//! | 69   | The host lacks something vortex needs (`EX_UNAVAILABLE`)      |
+//! | 77   | Not privileged enough (`EX_NOPERM`)                           |
+
+use serde_json::json;
+use std::fmt;
+use std::time::Duration;
+use vortex_cgroup::controller::REQUIRED_CONTROLLERS;
+
+use crate::output::Icon;
+
+/// A failure with its own code, exit code and advice
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliError {
+    /// The command needs root
+    PermissionDenied {
+        /// What root is needed for, e.g. "create cgroups"
+        operation: String,
+    },
+    /// Effective capabilities are missing, typically inside a container
+    CapabilitiesMissing {
+        /// The capability check's message
+        message: String,
+    },
+    /// Nothing is mounted at `/sys/fs/cgroup`
+    CgroupV2Missing,
+    /// `/sys/fs/cgroup` is a cgroup v1 hierarchy
+    CgroupV1Only,
+    /// The cgroup v2 root lacks controllers vortex needs
+    ControllersMissing {
+        /// Missing controller names
+        missing: Vec<String>,
+    },
+    /// The cgroup filesystem is mounted read-only
+    CgroupReadOnly {
+        /// The writability check's message
+        message: String,
+    },
+    /// The kernel lacks namespace types vortex needs
+    NamespacesUnsupported {
+        /// Missing namespace types, empty if `/proc/self/ns` is missing
+        missing: Vec<String>,
+    },
+    /// No container with this ID
+    ContainerNotFound {
+        /// Container ID
+        id: String,
+    },
+    /// `run` was given the ID of a running container
+    AlreadyRunning {
+        /// Container ID
+        id: String,
+    },
+    /// The container must be stopped first
+    StillRunning {
+        /// Container ID
+        id: String,
+    },
+    /// The container must be running
+    NotRunning {
+        /// Container ID
+        id: String,
+    },
+    /// The container belongs to a `vortex run` in the foreground
+    RunsInForeground {
+        /// Container ID
+        id: String,
+    },
+    /// `wait` ran into its timeout
+    WaitTimeout {
+        /// Container ID
+        id: String,
+        /// How long it waited
+        timeout: Duration,
+    },
+}
+
+impl CliError {
+    /// Stable identifier for scripts, e.g. `container_not_found`
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::PermissionDenied { .. } => "permission_denied",
+            Self::CapabilitiesMissing { .. } => "capabilities_missing",
+            Self::CgroupV2Missing => "cgroup_v2_missing",
+            Self::CgroupV1Only => "cgroup_v1_only",
+            Self::ControllersMissing { .. } => "controllers_missing",
+            Self::CgroupReadOnly { .. } => "cgroup_read_only",
+            Self::NamespacesUnsupported { .. } => "namespaces_unsupported",
+            Self::ContainerNotFound { .. } => "container_not_found",
+            Self::AlreadyRunning { .. } => "already_running",
+            Self::StillRunning { .. } => "still_running",
+            Self::NotRunning { .. } => "not_running",
+            Self::RunsInForeground { .. } => "runs_in_foreground",
+            Self::WaitTimeout { .. } => "timeout",
+        }
+    }
+
+    /// Process exit code, see the module docs
+    pub const fn exit_code(&self) -> i32 {
+        match self {
+            Self::ContainerNotFound { .. } => 3,
+            Self::AlreadyRunning { .. }
+            | Self::StillRunning { .. }
+            | Self::NotRunning { .. }
+            | Self::RunsInForeground { .. } => 4,
+            Self::WaitTimeout { .. } => 5,
+            Self::CgroupV2Missing
+            | Self::CgroupV1Only
+            | Self::ControllersMissing { .. }
+            | Self::CgroupReadOnly { .. }
+            | Self::NamespacesUnsupported { .. } => 69,
+            Self::PermissionDenied { .. } | Self::CapabilitiesMissing { .. } => 77,
+        }
+    }
+
+    /// What the user can do about it, if there is advice to give
+    pub fn help(&self) -> Option<String> {
+        let bullet = Icon::Bullet;
+        let help = match self {
+            Self::PermissionDenied { .. } => format!(
+                "Vortex needs root permissions to:\n\
+                 {bullet} Create cgroups (resource limits)\n\
+                 {bullet} Create namespaces (isolation)\n\
+                 {bullet} Access kernel files\n\
+                 \n\
+                 Please run with sudo:\n\
+                 $ sudo vortex ..."
+            ),
+            Self::CapabilitiesMissing { .. } => {
+                "Running as root inside another container often drops these.\n\
+                 Grant them to the outer container (e.g. --cap-add SYS_ADMIN)."
+                    .to_string()
+            }
+            Self::CgroupV2Missing => "Check if CGroup v2 is enabled:\n\
+                 $ mount | grep cgroup2\n\
+                 \n\
+                 On most modern Linux distributions, this should be automatic.\n\
+                 If not, you may need to enable it in your kernel boot parameters."
+                .to_string(),
+            Self::CgroupV1Only => format!(
+                "You may need to:\n\
+                 {bullet} Update your kernel (5.0+)\n\
+                 {bullet} Change kernel boot parameters\n\
+                 {bullet} Disable CGroup v1 in systemd"
+            ),
+            Self::ControllersMissing { .. } => format!(
+                "Vortex requires: {}\n\
+                 Enable them in the parent's cgroup.subtree_control.",
+                REQUIRED_CONTROLLERS.join(", ")
+            ),
+            Self::CgroupReadOnly { .. } => format!(
+                "This is common inside CI or nested containers. You may need to:\n\
+                 {bullet} Run the outer container with a writable cgroup mount\n\
+                 {bullet} Remount: mount -o remount,rw /sys/fs/cgroup"
+            ),
+            Self::NamespacesUnsupported { missing } if missing.is_empty() => {
+                "Your kernel may be too old or compiled without namespace support.".to_string()
+            }
+            Self::NamespacesUnsupported { .. } => {
+                "Your kernel may need to be reconfigured.".to_string()
+            }
+            Self::AlreadyRunning { id } => format!(
+                "Choose another --id, stop it first (vortex stop --id {id}) or pass --replace"
+            ),
+            Self::StillRunning { id } => {
+                format!("Stop it first (vortex stop --id {id}) or pass --force")
+            }
+            Self::RunsInForeground { .. } => {
+                "Stop it where it runs and start it again, or run it with --detach \
+                 to make it restartable"
+                    .to_string()
+            }
+            Self::ContainerNotFound { .. } | Self::NotRunning { .. } | Self::WaitTimeout { .. } => {
+                return None;
+            }
+        };
+        Some(help)
+    }
+
+    /// The CLI error behind `error`, if any
+    ///
+    /// Core errors with a matching kind are translated, so a container that
+    /// the cgroup layer could not find reports the same as one `inspect`
+    /// could not.
+    pub fn find(error: &anyhow::Error) -> Option<Self> {
+        error.chain().find_map(|cause| {
+            if let Some(cli) = cause.downcast_ref::<Self>() {
+                return Some(cli.clone());
+            }
+            match cause.downcast_ref::<vortex_core::Error>()? {
+                vortex_core::Error::ContainerNotFound { id } => {
+                    Some(Self::ContainerNotFound { id: id.clone() })
+                }
+                vortex_core::Error::ContainerRunning { id } => {
+                    Some(Self::AlreadyRunning { id: id.clone() })
+                }
+                e @ vortex_core::Error::CGroupReadOnly { .. } => Some(Self::CgroupReadOnly {
+                    message: e.to_string(),
+                }),
+                _ => None,
+            }
+        })
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PermissionDenied { operation } => {
+                write!(f, "Permission Denied: must run as root to {operation}")
+            }
+            Self::CapabilitiesMissing { message } | Self::CgroupReadOnly { message } => {
+                f.write_str(message)
+            }
+            Self::CgroupV2Missing => f.write_str("CGroup v2 not mounted at /sys/fs/cgroup"),
+            Self::CgroupV1Only => f.write_str("CGroup v1 detected, but Vortex requires CGroup v2"),
+            Self::ControllersMissing { missing } => write!(
+                f,
+                "Required CGroup controllers not available: {}",
+                missing.join(", ")
+            ),
+            Self::NamespacesUnsupported { missing } if missing.is_empty() => {
+                f.write_str("Kernel doesn't support namespaces")
+            }
+            Self::NamespacesUnsupported { missing } => write!(
+                f,
+                "Required namespace types not available: {}",
+                missing.join(", ")
+            ),
+            Self::ContainerNotFound { id } => write!(f, "Container '{id}' not found"),
+            Self::AlreadyRunning { id } => write!(f, "Container '{id}' is already running"),
+            Self::StillRunning { id } => write!(f, "Container '{id}' is still running"),
+            Self::NotRunning { id } => write!(f, "Container '{id}' is not running"),
+            Self::RunsInForeground { id } => {
+                write!(f, "Container '{id}' runs in the foreground of `vortex run`")
+            }
+            Self::WaitTimeout { id, timeout } => {
+                write!(f, "Container '{id}' still running after {timeout:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Report a failed command and return the exit code
+///
+/// With `json` the error goes to stdout as `{"error": ..., "code": ...}`,
+/// where a script reading the command's JSON output finds it; otherwise
+/// it goes to stderr with the help below it.
+pub fn report(error: &anyhow::Error, json: bool) -> i32 {
+    let cli = CliError::find(error);
+    if json {
+        let code = cli.as_ref().map_or("error", CliError::code);
+        println!("{}", json!({ "error": format!("{error:#}"), "code": code }));
+    } else {
+        eprintln!("Error: {error:?}");
+        if let Some(help) = cli.as_ref().and_then(CliError::help) {
+            eprintln!("\n{help}");
+        }
+    }
+    cli.as_ref().map_or(1, CliError::exit_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_find_through_context() {
+        let error = Err::<(), _>(CliError::StillRunning {
+            id: "web".to_string(),
+        })
+        .context("Failed to remove container")
+        .unwrap_err();
+        let cli = CliError::find(&error).unwrap();
+        assert_eq!(cli.code(), "still_running");
+        assert_eq!(cli.exit_code(), 4);
+        assert!(cli.help().unwrap().contains("--force"));
+
+        // Core errors of a known kind are translated
+        let error = Err::<(), _>(vortex_core::Error::ContainerNotFound {
+            id: "web".to_string(),
+        })
+        .context("Failed to access container")
+        .unwrap_err();
+        assert_eq!(
+            CliError::find(&error),
+            Some(CliError::ContainerNotFound {
+                id: "web".to_string()
+            })
+        );
+
+        assert_eq!(CliError::find(&anyhow::anyhow!("something else")), None);
+    }
+}
@@ -3,6 +3,11 @@ use tracing_subscriber::EnvFilter;
 
 mod cli;
 mod commands;
+mod error;
+mod oci;
+mod output;
+mod profile;
+mod registry;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -11,14 +16,49 @@ async fn main() -> Result<()> {
     let cli = cli::Cli::parse();
 
     // Setup logging
-    let log_level = "info";
-
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(log_level)),
-        )
-        .init();
+    init_logging(&cli)?;
+    output::init(cli.plain);
 
     // Dispatch command
-    commands::dispatch(cli.command).await
+    let json = cli.command.json_output();
+    if let Err(e) = commands::dispatch(cli.command).await {
+        std::process::exit(error::report(&e, json));
+    }
+
+    Ok(())
+}
+
+/// Install the tracing subscriber according to the global flags
+///
+/// `--log-level` takes precedence over `RUST_LOG`, which takes precedence
+/// over the default `info` level. Logs go to stderr so command output on
+/// stdout stays machine-readable.
+fn init_logging(cli: &cli::Cli) -> Result<()> {
+    let filter = match cli.log_level {
+        Some(ref level) => EnvFilter::try_new(level)
+            .map_err(|e| anyhow::anyhow!("Invalid --log-level '{}': {}", level, e))?,
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+    };
+
+    let format = if cli.json_logs {
+        cli::LogFormat::Json
+    } else {
+        cli.log_format
+    };
+
+    match format {
+        cli::LogFormat::Text => tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .with_env_filter(filter)
+            .init(),
+        cli::LogFormat::Json => tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .with_env_filter(filter)
+            .init(),
+    }
+
+    Ok(())
 }
@@ -0,0 +1,638 @@
+//! OCI runtime-spec `config.json` loader
+//!
+//! Only the subset of the spec that vortex can honor is translated:
+//! `process.args`, `process.env`, `process.cwd`, `process.rlimits`,
+//! `process.noNewPrivileges`, `process.terminal`, `process.user`,
+//! `process.oomScoreAdj`, `root.path`, `hostname`,
+//! `hooks.{prestart,poststop}`, `linux.resources.{cpu,memory}`,
+//! `linux.namespaces` and `linux.timeOffsets`. Everything else is
+//! ignored with a warning so that bundles produced by other tooling still run.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use vortex_core::{CpuCores, CpuLimit, MemoryLimit, MemorySize};
+use vortex_namespace::{
+    Hook, NamespaceConfig, OomScoreAdj, Rlimit, Scheduling, TerminationConfig, TimeOffsets,
+    TmpfsMount, User,
+};
+
+/// Name of the spec file inside a bundle directory
+pub const CONFIG_FILE: &str = "config.json";
+
+/// Container run specification translated from an OCI bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSpec {
+    /// Command and arguments
+    pub args: Vec<String>,
+    /// Environment in `KEY=VALUE` form
+    pub env: Vec<String>,
+    /// Host variables passed through to an otherwise empty environment,
+    /// under `env` (not part of the OCI config)
+    pub env_keep: Vec<String>,
+    /// Existing cgroup to create the container cgroup in instead of the
+    /// vortex namespace (not part of the OCI config)
+    pub cgroup_parent: Option<String>,
+    /// Working directory inside the container
+    pub cwd: Option<PathBuf>,
+    /// Process resource limits
+    pub rlimits: Vec<Rlimit>,
+    /// Set `no_new_privs` before exec; vortex defaults to on when the
+    /// bundle does not say
+    pub no_new_privs: bool,
+    /// Attach the process to a pseudo-terminal
+    pub terminal: bool,
+    /// Run a minimal init as PID 1 (not part of the OCI config)
+    pub init: bool,
+    /// Send stderr to stdout, like `2>&1` (not part of the OCI config)
+    pub merge_stderr: bool,
+    /// Stop the process after this long (not part of the OCI config)
+    pub timeout: Option<Duration>,
+    /// How to stop the process on timeout (not part of the OCI config)
+    pub termination: TerminationConfig,
+    /// User and primary group to run as (default: the caller's)
+    pub user: Option<User>,
+    /// Supplementary group IDs
+    pub groups: Vec<u32>,
+    /// File mode creation mask
+    pub umask: Option<u32>,
+    /// OOM killer preference of the process
+    pub oom_score_adj: Option<OomScoreAdj>,
+    /// Scheduling policy and nice value (not read from the OCI config)
+    pub scheduling: Option<Scheduling>,
+    /// Root filesystem path (resolved against the bundle directory)
+    pub root: Option<PathBuf>,
+    /// Tmpfs mounts inside the container
+    pub tmpfs: Vec<TmpfsMount>,
+    /// Hooks run once the namespaces exist, before the program starts
+    pub prestart: Vec<Hook>,
+    /// Hooks run after the container is cleaned up
+    pub poststop: Vec<Hook>,
+    /// Container hostname
+    pub hostname: Option<String>,
+    /// CPU limit
+    pub cpu: Option<CpuLimit>,
+    /// Memory limit
+    pub memory: Option<MemoryLimit>,
+    /// Maximum number of processes (not read from the OCI config)
+    pub pids: Option<u64>,
+    /// Namespaces to create
+    pub namespaces: NamespaceConfig,
+}
+
+/// Load `<bundle>/config.json`
+pub fn load(bundle: &Path) -> Result<RunSpec> {
+    let path = bundle.join(CONFIG_FILE);
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let (mut spec, ignored) =
+        parse(&content).with_context(|| format!("Invalid OCI config {}", path.display()))?;
+
+    for field in &ignored {
+        tracing::warn!(field = %field, "Ignoring unsupported OCI config field");
+    }
+
+    // root.path is relative to the bundle directory
+    if let Some(root) = spec.root.take() {
+        spec.root = Some(bundle.join(root));
+    }
+
+    Ok(spec)
+}
+
+/// Parse a `config.json` document
+///
+/// Returns the translated spec together with the paths of every field that
+/// was present but not supported.
+pub fn parse(json: &str) -> Result<(RunSpec, Vec<String>)> {
+    let config: OciConfig = serde_json::from_str(json).context("Failed to parse config.json")?;
+
+    let mut ignored = Vec::new();
+    collect_ignored(&mut ignored, "", &config.extra);
+
+    let process = config.process.unwrap_or_default();
+    collect_ignored(&mut ignored, "process.", &process.extra);
+
+    let rlimits = process
+        .rlimits
+        .iter()
+        .map(translate_rlimit)
+        .collect::<Result<Vec<_>>>()?;
+
+    let oom_score_adj = process
+        .oom_score_adj
+        .map(OomScoreAdj::new)
+        .transpose()
+        .context("Invalid process.oomScoreAdj")?;
+
+    if let Some(ref user) = process.user {
+        collect_ignored(&mut ignored, "process.user.", &user.extra);
+    }
+
+    let root = config.root.map(|root| {
+        collect_ignored(&mut ignored, "root.", &root.extra);
+        root.path
+    });
+
+    let hooks = config.hooks.unwrap_or_default();
+    collect_ignored(&mut ignored, "hooks.", &hooks.extra);
+    let prestart = translate_hooks(hooks.prestart, "prestart")?;
+    // Failing poststop hooks only cause a warning
+    let mut poststop = translate_hooks(hooks.poststop, "poststop")?;
+    for hook in &mut poststop {
+        hook.abort_on_failure = false;
+    }
+
+    let linux = config.linux.unwrap_or_default();
+    collect_ignored(&mut ignored, "linux.", &linux.extra);
+
+    let namespaces = match linux.namespaces {
+        Some(namespaces) => translate_namespaces(&namespaces, &mut ignored),
+        None => NamespaceConfig::none(),
+    };
+    let namespaces = match linux.time_offsets {
+        Some(offsets) => {
+            namespaces.with_time_offsets(translate_time_offsets(&offsets, &mut ignored)?)
+        }
+        None => namespaces,
+    };
+    let namespaces = match config.hostname {
+        Some(ref hostname) => namespaces.with_hostname(hostname.as_str()),
+        None => namespaces,
+    };
+
+    let resources = linux.resources.unwrap_or_default();
+    collect_ignored(&mut ignored, "linux.resources.", &resources.extra);
+
+    let cpu = resources.cpu.and_then(|cpu| {
+        collect_ignored(&mut ignored, "linux.resources.cpu.", &cpu.extra);
+        translate_cpu(&cpu)
+    });
+    let memory = resources.memory.and_then(|memory| {
+        collect_ignored(&mut ignored, "linux.resources.memory.", &memory.extra);
+        translate_memory(&memory)
+    });
+
+    let spec = RunSpec {
+        args: process.args,
+        env: process.env,
+        env_keep: Vec::new(),
+        cgroup_parent: None,
+        cwd: process.cwd,
+        rlimits,
+        no_new_privs: process.no_new_privileges.unwrap_or(true),
+        terminal: process.terminal,
+        init: false,
+        merge_stderr: false,
+        timeout: None,
+        termination: TerminationConfig::default(),
+        user: process.user.as_ref().map(|u| User::new(u.uid, u.gid)),
+        umask: process.user.as_ref().and_then(|u| u.umask),
+        groups: process.user.map(|u| u.additional_gids).unwrap_or_default(),
+        oom_score_adj,
+        scheduling: None,
+        root,
+        tmpfs: Vec::new(),
+        prestart,
+        poststop,
+        hostname: config.hostname,
+        cpu,
+        memory,
+        pids: None,
+        namespaces,
+    };
+
+    Ok((spec, ignored))
+}
+
+fn collect_ignored(ignored: &mut Vec<String>, prefix: &str, extra: &Map<String, Value>) {
+    ignored.extend(extra.keys().map(|key| format!("{prefix}{key}")));
+}
+
+fn translate_namespaces(namespaces: &[OciNamespace], ignored: &mut Vec<String>) -> NamespaceConfig {
+    let mut config = NamespaceConfig::none();
+
+    for ns in namespaces {
+        // Joining an existing namespace by path is not supported
+        if ns.path.is_some() {
+            ignored.push(format!("linux.namespaces[{}].path", ns.kind));
+            continue;
+        }
+
+        config = match ns.kind.as_str() {
+            "pid" => config.with_pid(true),
+            "network" => config.with_network(true),
+            "mount" => config.with_mount(true),
+            "uts" => config.with_uts(true),
+            "ipc" => config.with_ipc(true),
+            "user" => config.with_user(true),
+            "cgroup" => config.with_cgroup(true),
+            "time" => config.with_time(true),
+            other => {
+                ignored.push(format!("linux.namespaces[{other}]"));
+                config
+            }
+        };
+    }
+
+    config
+}
+
+fn translate_time_offsets(
+    offsets: &OciTimeOffsets,
+    ignored: &mut Vec<String>,
+) -> Result<TimeOffsets> {
+    collect_ignored(ignored, "linux.timeOffsets.", &offsets.extra);
+    let offset = |clock: &str, offset: Option<&OciTimeOffset>| {
+        let offset = offset.copied().unwrap_or_default();
+        if offset.nanosecs >= 1_000_000_000 {
+            anyhow::bail!(
+                "Invalid linux.timeOffsets.{clock}.nanosecs {} (must be below one second)",
+                offset.nanosecs
+            );
+        }
+        Ok(offset)
+    };
+    let monotonic = offset("monotonic", offsets.monotonic.as_ref())?;
+    let boottime = offset("boottime", offsets.boottime.as_ref())?;
+    Ok(TimeOffsets {
+        monotonic: monotonic.secs,
+        monotonic_nanos: monotonic.nanosecs,
+        boottime: boottime.secs,
+        boottime_nanos: boottime.nanosecs,
+    })
+}
+
+fn translate_hooks(hooks: Vec<OciHook>, kind: &str) -> Result<Vec<Hook>> {
+    hooks
+        .into_iter()
+        .map(|hook| {
+            let timeout = match hook.timeout {
+                Some(0) => anyhow::bail!("Invalid hooks.{kind} timeout 0 (must be positive)"),
+                Some(secs) => Duration::from_secs(secs),
+                None => Hook::DEFAULT_TIMEOUT,
+            };
+            Ok(Hook {
+                // args starts with the program name, like argv
+                args: hook.args.into_iter().skip(1).collect(),
+                env: hook.env,
+                timeout,
+                ..Hook::new(hook.path)
+            })
+        })
+        .collect()
+}
+
+fn translate_rlimit(rlimit: &OciRlimit) -> Result<Rlimit> {
+    let name = rlimit.kind.trim_start_matches("RLIMIT_").to_lowercase();
+    let resource = Rlimit::resource_from_name(&name)
+        .with_context(|| format!("Unsupported rlimit type {}", rlimit.kind))?;
+
+    Rlimit::new(resource, rlimit.soft, rlimit.hard)
+        .with_context(|| format!("Invalid rlimit {}", rlimit.kind))
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn translate_cpu(cpu: &OciCpu) -> Option<CpuLimit> {
+    let quota = cpu.quota?;
+    let period = cpu.period.filter(|p| *p > 0).unwrap_or(100_000);
+
+    CpuCores::try_new(quota as f64 / period as f64)
+        .ok()
+        .map(CpuLimit::new)
+}
+
+fn translate_memory(memory: &OciMemory) -> Option<MemoryLimit> {
+    let limit = memory.limit.and_then(|l| u64::try_from(l).ok())?;
+
+    // OCI expresses swap as memory+swap, cgroup v2 wants swap alone
+    let swap = memory
+        .swap
+        .and_then(|s| u64::try_from(s).ok())
+        .map(|s| s.saturating_sub(limit));
+
+    Some(match swap {
+        Some(swap) => {
+            MemoryLimit::with_swap(MemorySize::from_bytes(limit), MemorySize::from_bytes(swap))
+        }
+        None => MemoryLimit::new(MemorySize::from_bytes(limit)),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct OciConfig {
+    process: Option<OciProcess>,
+    root: Option<OciRoot>,
+    hostname: Option<String>,
+    hooks: Option<OciHooks>,
+    linux: Option<OciLinux>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OciHooks {
+    #[serde(default)]
+    prestart: Vec<OciHook>,
+    #[serde(default)]
+    poststop: Vec<OciHook>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OciHook {
+    path: PathBuf,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    env: Vec<String>,
+    timeout: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OciProcess {
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    env: Vec<String>,
+    cwd: Option<PathBuf>,
+    #[serde(default)]
+    rlimits: Vec<OciRlimit>,
+    #[serde(default)]
+    terminal: bool,
+    #[serde(rename = "noNewPrivileges")]
+    no_new_privileges: Option<bool>,
+    user: Option<OciUser>,
+    #[serde(rename = "oomScoreAdj")]
+    oom_score_adj: Option<i32>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OciUser {
+    uid: u32,
+    gid: u32,
+    umask: Option<u32>,
+    #[serde(rename = "additionalGids", default)]
+    additional_gids: Vec<u32>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OciRlimit {
+    #[serde(rename = "type")]
+    kind: String,
+    soft: u64,
+    hard: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OciRoot {
+    path: PathBuf,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OciLinux {
+    namespaces: Option<Vec<OciNamespace>>,
+    resources: Option<OciResources>,
+    #[serde(rename = "timeOffsets")]
+    time_offsets: Option<OciTimeOffsets>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OciTimeOffsets {
+    monotonic: Option<OciTimeOffset>,
+    boottime: Option<OciTimeOffset>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+struct OciTimeOffset {
+    #[serde(default)]
+    secs: i64,
+    #[serde(default)]
+    nanosecs: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct OciNamespace {
+    #[serde(rename = "type")]
+    kind: String,
+    path: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OciResources {
+    cpu: Option<OciCpu>,
+    memory: Option<OciMemory>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OciCpu {
+    quota: Option<i64>,
+    period: Option<u64>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OciMemory {
+    limit: Option<i64>,
+    swap: Option<i64>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"{
+        "ociVersion": "1.0.2",
+        "process": {
+            "terminal": false,
+            "args": ["/bin/sh", "-c", "echo hi"],
+            "env": ["PATH=/usr/bin:/bin", "TERM=xterm"],
+            "cwd": "/work",
+            "noNewPrivileges": false,
+            "oomScoreAdj": -500,
+            "user": { "uid": 1000, "gid": 100, "umask": 18, "additionalGids": [10, 20] },
+            "rlimits": [{ "type": "RLIMIT_NOFILE", "soft": 1024, "hard": 2048 }]
+        },
+        "root": { "path": "rootfs", "readonly": true },
+        "hostname": "oci-box",
+        "mounts": [],
+        "hooks": {
+            "prestart": [{ "path": "/usr/bin/netns-setup", "args": ["netns-setup", "br0"], "timeout": 5 }],
+            "poststop": [{ "path": "/usr/bin/cleanup", "env": ["MODE=full"] }],
+            "createRuntime": [{ "path": "/usr/bin/unused" }]
+        },
+        "linux": {
+            "namespaces": [
+                { "type": "pid" },
+                { "type": "mount" },
+                { "type": "uts" },
+                { "type": "network", "path": "/var/run/netns/foo" }
+            ],
+            "resources": {
+                "cpu": { "quota": 50000, "period": 100000, "shares": 1024 },
+                "memory": { "limit": 268435456, "swap": 536870912 }
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_parse_sample() {
+        let (spec, _) = parse(SAMPLE).unwrap();
+
+        assert_eq!(spec.args, vec!["/bin/sh", "-c", "echo hi"]);
+        assert_eq!(spec.env.len(), 2);
+        assert_eq!(spec.cwd, Some(PathBuf::from("/work")));
+        assert_eq!(spec.rlimits, vec!["nofile=1024:2048".parse().unwrap()]);
+        assert!(!spec.no_new_privs);
+        assert!(!spec.terminal);
+        assert_eq!(spec.user, Some(User::new(1000, 100)));
+        assert_eq!(spec.groups, vec![10, 20]);
+        assert_eq!(spec.umask, Some(0o022));
+        assert_eq!(spec.oom_score_adj, Some(OomScoreAdj::new(-500).unwrap()));
+        assert_eq!(spec.root, Some(PathBuf::from("rootfs")));
+        assert_eq!(spec.hostname.as_deref(), Some("oci-box"));
+        assert_eq!(spec.namespaces.hostname.as_deref(), Some("oci-box"));
+
+        assert_eq!(spec.cpu.unwrap().cores.as_f64(), 0.5);
+
+        let memory = spec.memory.unwrap();
+        assert_eq!(memory.limit, MemorySize::from_mb(256));
+        assert_eq!(memory.swap, Some(MemorySize::from_mb(256)));
+    }
+
+    #[test]
+    fn test_parse_hooks() {
+        let (spec, ignored) = parse(SAMPLE).unwrap();
+
+        let prestart = &spec.prestart[0];
+        assert_eq!(prestart.path, PathBuf::from("/usr/bin/netns-setup"));
+        assert_eq!(prestart.args, vec!["br0"]);
+        assert_eq!(prestart.timeout, Duration::from_secs(5));
+        assert!(prestart.abort_on_failure);
+
+        let poststop = &spec.poststop[0];
+        assert_eq!(poststop.env, vec!["MODE=full"]);
+        assert_eq!(poststop.timeout, Hook::DEFAULT_TIMEOUT);
+        assert!(!poststop.abort_on_failure);
+
+        assert!(ignored.contains(&"hooks.createRuntime".to_string()));
+
+        let json = r#"{ "hooks": { "prestart": [{ "path": "/bin/true", "timeout": 0 }] } }"#;
+        assert!(parse(json).is_err());
+    }
+
+    #[test]
+    fn test_parse_namespaces() {
+        let (spec, _) = parse(SAMPLE).unwrap();
+
+        assert_eq!(
+            spec.namespaces.enabled_namespaces(),
+            vec!["pid", "mnt", "uts"]
+        );
+    }
+
+    #[test]
+    fn test_parse_time_namespace() {
+        let json = r#"{ "linux": {
+            "namespaces": [{ "type": "time" }],
+            "timeOffsets": {
+                "monotonic": { "secs": -60 },
+                "boottime": { "secs": 86400, "nanosecs": 500 }
+            }
+        } }"#;
+        let (spec, ignored) = parse(json).unwrap();
+
+        assert_eq!(spec.namespaces.enabled_namespaces(), vec!["time"]);
+        assert_eq!(
+            spec.namespaces.time_offsets,
+            TimeOffsets {
+                monotonic: -60,
+                monotonic_nanos: 0,
+                boottime: 86_400,
+                boottime_nanos: 500,
+            }
+        );
+        assert!(ignored.is_empty(), "{ignored:?}");
+    }
+
+    #[test]
+    fn test_time_offset_nanosecs_below_one_second() {
+        let json = r#"{ "linux": {
+            "namespaces": [{ "type": "time" }],
+            "timeOffsets": { "monotonic": { "secs": 1, "nanosecs": 1000000000 } }
+        } }"#;
+        assert!(parse(json).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_fields_are_reported() {
+        let (_, ignored) = parse(SAMPLE).unwrap();
+
+        assert!(ignored.contains(&"ociVersion".to_string()));
+        assert!(ignored.contains(&"mounts".to_string()));
+        assert!(!ignored.contains(&"process.terminal".to_string()));
+        assert!(ignored.contains(&"root.readonly".to_string()));
+        assert!(ignored.contains(&"linux.resources.cpu.shares".to_string()));
+        assert!(ignored.contains(&"linux.namespaces[network].path".to_string()));
+    }
+
+    #[test]
+    fn test_parse_minimal() {
+        let (spec, ignored) = parse(r#"{ "process": { "args": ["true"] } }"#).unwrap();
+
+        assert_eq!(spec.args, vec!["true"]);
+        assert!(spec.no_new_privs);
+        assert!(spec.cpu.is_none());
+        assert!(spec.memory.is_none());
+        assert!(!spec.namespaces.has_any());
+        assert!(ignored.is_empty());
+    }
+
+    #[test]
+    fn test_unlimited_resources() {
+        let json = r#"{ "linux": { "resources": {
+            "cpu": { "quota": -1, "period": 100000 },
+            "memory": { "limit": -1 }
+        } } }"#;
+        let (spec, _) = parse(json).unwrap();
+
+        assert!(spec.cpu.is_none());
+        assert!(spec.memory.is_none());
+    }
+
+    #[test]
+    fn test_parse_invalid_json() {
+        assert!(parse("not json").is_err());
+    }
+
+    #[test]
+    fn test_load_resolves_root_against_bundle() {
+        let bundle = std::env::temp_dir().join(format!("vortex-oci-{}", std::process::id()));
+        std::fs::create_dir_all(&bundle).unwrap();
+        std::fs::write(bundle.join(CONFIG_FILE), SAMPLE).unwrap();
+
+        let spec = load(&bundle).unwrap();
+        assert_eq!(spec.root, Some(bundle.join("rootfs")));
+
+        std::fs::remove_dir_all(&bundle).unwrap();
+    }
+}
@@ -0,0 +1,113 @@
+//! Decorations for user-facing output
+//!
+//! Messages are prefixed with emoji unless plain output is selected with
+//! `--plain` (alias `--no-emoji`), a non-empty `NO_COLOR` or `VORTEX_PLAIN`
+//! set to anything but `0`. Plain output is ASCII only, which suits logs,
+//! CI and terminals without emoji fonts. Commands print [`Icon`]s instead of
+//! literal emoji so this choice is made in one place.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// Select the output style from the `--plain` flag and the environment
+pub fn init(plain_flag: bool) {
+    let plain = plain_flag
+        || std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || std::env::var_os("VORTEX_PLAIN").is_some_and(|value| !value.is_empty() && value != "0");
+
+    PLAIN.store(plain, Ordering::Relaxed);
+}
+
+/// Whether output is plain ASCII
+pub fn plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+/// A symbol prefixing a message
+///
+/// Displays as the emoji and the space after it, or as nothing in plain
+/// mode, so messages are written as `"{}Container stopped", Icon::Ok`.
+/// [`Icon::Bullet`] is the exception: it turns into `-` in plain mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Icon {
+    /// Something succeeded or is fine
+    Ok,
+    /// Something failed or is missing
+    Error,
+    /// Something works, but not fully
+    Warning,
+    /// Resource statistics
+    Stats,
+    /// A container was removed
+    Removed,
+    /// A container was left alone
+    Skipped,
+    /// Permissions and isolation
+    Lock,
+    /// A container is starting
+    Start,
+    /// A listing
+    List,
+    /// The health check
+    Health,
+    /// System information
+    Info,
+    /// An item of a list inside a message
+    Bullet,
+}
+
+impl Icon {
+    /// The emoji, padded so the message lines up behind it
+    const fn emoji(self) -> &'static str {
+        match self {
+            Self::Ok => "✅ ",
+            Self::Error => "❌ ",
+            // Emoji with a variation selector render narrower, hence two
+            Self::Warning => "⚠️  ",
+            Self::Stats => "📊 ",
+            Self::Removed => "🗑️  ",
+            Self::Skipped => "⏭️  ",
+            Self::Lock => "🔒 ",
+            Self::Start => "🚀 ",
+            Self::List => "📋 ",
+            Self::Health => "🏥 ",
+            Self::Info => "ℹ️  ",
+            Self::Bullet => "•",
+        }
+    }
+
+    /// What stands in for the emoji in plain mode
+    const fn ascii(self) -> &'static str {
+        match self {
+            Self::Bullet => "-",
+            _ => "",
+        }
+    }
+
+    fn as_str(self, plain: bool) -> &'static str {
+        if plain { self.ascii() } else { self.emoji() }
+    }
+}
+
+impl fmt::Display for Icon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str(plain()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_icons_are_ascii() {
+        for icon in [Icon::Ok, Icon::Warning, Icon::Removed, Icon::Bullet] {
+            assert!(icon.as_str(true).is_ascii(), "{icon:?}");
+            assert!(!icon.as_str(false).is_ascii(), "{icon:?}");
+        }
+        assert_eq!(Icon::Ok.as_str(true), "");
+        assert_eq!(Icon::Bullet.as_str(true), "-");
+    }
+}
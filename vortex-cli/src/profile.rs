@@ -0,0 +1,199 @@
+//! Named presets for `vortex run`
+//!
+//! A profile bundles limits and namespace settings under a name, so
+//! `--profile small` stands in for `--cpu 0.5 --memory 256 --pids-max 128`.
+//! Profiles are JSON files named `<name>.json`, looked up in
+//! `$XDG_CONFIG_HOME/vortex/profiles` (`~/.config/vortex/profiles`), then in
+//! [`SYSTEM_DIR`], then among the [built-in](BUILTIN) ones. The first match
+//! wins, so a file can replace a built-in profile of the same name.
+//!
+//! ```json
+//! {
+//!   "cpu": 0.5,
+//!   "memory": 256,
+//!   "pids": 128,
+//!   "namespaces": ["pid", "mount", "uts"],
+//!   "env": ["LANG=C.UTF-8"],
+//!   "hostname": "sandbox"
+//! }
+//! ```
+//!
+//! Every field is optional; explicit CLI flags override the profile.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use vortex_namespace::NamespaceConfig;
+
+/// System-wide profile directory
+pub const SYSTEM_DIR: &str = "/etc/vortex/profiles";
+
+/// Profiles available without any file: `(name, cpu cores, memory MB, pids)`
+pub const BUILTIN: [(&str, f64, u64, u64); 3] = [
+    ("small", 0.5, 256, 128),
+    ("medium", 1.0, 512, 512),
+    ("large", 2.0, 2048, 2048),
+];
+
+/// Settings a profile can preset
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RunProfile {
+    /// CPU limit in cores
+    pub cpu: Option<f64>,
+    /// Memory limit in MB
+    pub memory: Option<u64>,
+    /// Maximum number of processes
+    pub pids: Option<u64>,
+    /// Namespaces to create, by their OCI names (`pid`, `network`, `mount`,
+    /// `uts`, `ipc`, `user`, `cgroup`); an empty list disables isolation
+    pub namespaces: Option<Vec<String>>,
+    /// Environment in `KEY=VALUE` form
+    pub env: Vec<String>,
+    /// Container hostname
+    pub hostname: Option<String>,
+}
+
+impl RunProfile {
+    /// The built-in profile called `name`
+    pub fn builtin(name: &str) -> Option<Self> {
+        BUILTIN
+            .iter()
+            .find(|(builtin, ..)| *builtin == name)
+            .map(|&(_, cpu, memory, pids)| Self {
+                cpu: Some(cpu),
+                memory: Some(memory),
+                pids: Some(pids),
+                ..Self::default()
+            })
+    }
+
+    /// The namespaces to create, if the profile names them
+    pub fn namespace_config(&self) -> Result<Option<NamespaceConfig>> {
+        let Some(ref names) = self.namespaces else {
+            return Ok(None);
+        };
+
+        let mut config = NamespaceConfig::none();
+        for name in names {
+            config = match name.as_str() {
+                "pid" => config.with_pid(true),
+                "network" => config.with_network(true),
+                "mount" => config.with_mount(true),
+                "uts" => config.with_uts(true),
+                "ipc" => config.with_ipc(true),
+                "user" => config.with_user(true),
+                "cgroup" => config.with_cgroup(true),
+                other => anyhow::bail!("Unknown namespace '{other}' in profile"),
+            };
+        }
+
+        Ok(Some(config))
+    }
+}
+
+/// Load the profile called `name` from the profile directories, falling back
+/// to the built-in profiles
+pub fn load(name: &str) -> Result<RunProfile> {
+    load_from(name, &search_dirs())
+}
+
+/// Directories searched for profile files, most specific first
+fn search_dirs() -> Vec<PathBuf> {
+    let user = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+
+    user.map(|dir| dir.join("vortex").join("profiles"))
+        .into_iter()
+        .chain(std::iter::once(PathBuf::from(SYSTEM_DIR)))
+        .collect()
+}
+
+fn load_from(name: &str, dirs: &[PathBuf]) -> Result<RunProfile> {
+    // The name becomes a file name, so it must not walk out of the directory
+    if name.is_empty() || name.starts_with('.') || name.contains('/') {
+        anyhow::bail!("Invalid profile name '{name}'");
+    }
+
+    for dir in dirs {
+        let path = dir.join(format!("{name}.json"));
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        };
+
+        let profile: RunProfile = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid profile {}", path.display()))?;
+        profile
+            .namespace_config()
+            .with_context(|| format!("Invalid profile {}", path.display()))?;
+        return Ok(profile);
+    }
+
+    RunProfile::builtin(name).with_context(|| {
+        let builtin: Vec<&str> = BUILTIN.iter().map(|(name, ..)| *name).collect();
+        format!(
+            "Unknown profile '{name}' (built-in profiles: {})",
+            builtin.join(", ")
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_profiles() {
+        let small = RunProfile::builtin("small").unwrap();
+        assert_eq!(small.cpu, Some(0.5));
+        assert_eq!(small.memory, Some(256));
+        assert_eq!(small.pids, Some(128));
+        assert!(small.namespaces.is_none());
+        assert!(RunProfile::builtin("huge").is_none());
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        let profile: RunProfile = serde_json::from_str(
+            r#"{ "memory": 64, "namespaces": ["pid", "uts"], "env": ["A=1"] }"#,
+        )
+        .unwrap();
+        assert_eq!(profile.memory, Some(64));
+        assert!(profile.cpu.is_none());
+        assert_eq!(profile.env, vec!["A=1"]);
+
+        let namespaces = profile.namespace_config().unwrap().unwrap();
+        assert_eq!(namespaces.enabled_namespaces(), vec!["pid", "uts"]);
+
+        assert!(serde_json::from_str::<RunProfile>(r#"{ "cpus": 1 }"#).is_err());
+        let unknown: RunProfile = serde_json::from_str(r#"{ "namespaces": ["time"] }"#).unwrap();
+        assert!(unknown.namespace_config().is_err());
+    }
+
+    #[test]
+    fn test_load_prefers_files_over_builtin() {
+        let root = std::env::temp_dir().join(format!("vortex-profile-{}", std::process::id()));
+        let (user, system) = (root.join("user"), root.join("system"));
+        std::fs::create_dir_all(&user).unwrap();
+        std::fs::create_dir_all(&system).unwrap();
+        std::fs::write(user.join("small.json"), r#"{ "memory": 100 }"#).unwrap();
+        std::fs::write(system.join("small.json"), r#"{ "memory": 200 }"#).unwrap();
+        std::fs::write(system.join("broken.json"), "{").unwrap();
+        let dirs = [user, system.clone()];
+
+        assert_eq!(load_from("small", &dirs).unwrap().memory, Some(100));
+        assert_eq!(load_from("small", &dirs[1..]).unwrap().memory, Some(200));
+        assert_eq!(load_from("large", &dirs).unwrap().memory, Some(2048));
+        assert!(load_from("broken", &dirs).is_err());
+        assert!(load_from("missing", &dirs).is_err());
+        assert!(load_from("../system/small", &dirs).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
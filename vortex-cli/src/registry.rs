@@ -0,0 +1,249 @@
+//! On-disk record of running containers
+//!
+//! `run` writes one JSON file per container under [`REGISTRY_DIR`] through
+//! a [`FileRegistry`] and removes it once the container is cleaned up. It
+//! holds what the cgroup alone cannot tell other commands: the command
+//! line, the pod, when the container was created and which limits were
+//! requested. Detached containers also keep their output logs and overlay
+//! scratch directories here, and every container the [`SavedRun`] that
+//! `restart` starts it again from.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use vortex_core::{ContainerExit, ContainerRecord, ContainerState, FileRegistry, Registry};
+use vortex_namespace::DetachedExit;
+
+use crate::oci::RunSpec;
+
+/// Directory holding the container records
+pub const REGISTRY_DIR: &str = FileRegistry::DEFAULT_DIR;
+
+/// The registry every command reads and writes
+pub fn open() -> FileRegistry {
+    FileRegistry::new(REGISTRY_DIR)
+}
+
+/// Build a record for a container about to be started
+pub fn record(id: &str, spec: &RunSpec, pod: Option<&str>) -> ContainerRecord {
+    ContainerRecord {
+        pod: pod.map(str::to_string),
+        cgroup_parent: spec.cgroup_parent.clone(),
+        cpu: spec.cpu,
+        memory: spec.memory,
+        namespaces: spec
+            .namespaces
+            .enabled_namespaces()
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+        ..ContainerRecord::new(id, spec.args.clone())
+    }
+}
+
+/// Everything `run` was asked to do, so `restart` can do it again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedRun {
+    /// The spec merged from the bundle, profile and flags
+    pub spec: RunSpec,
+    /// Bundle directory, reported to hooks
+    pub bundle: Option<PathBuf>,
+    /// Whether the root filesystem is an overlay
+    pub overlay: bool,
+    /// Whether an OOM kill takes down the whole container
+    pub oom_group: bool,
+}
+
+impl SavedRun {
+    /// Save `spec` with the run options that are not part of it
+    ///
+    /// Relative bundle and root filesystem paths are made absolute, so a
+    /// restart from another directory finds them.
+    pub fn new(mut spec: RunSpec, bundle: Option<&Path>, overlay: bool, oom_group: bool) -> Self {
+        let absolute = |path: &Path| std::path::absolute(path).unwrap_or_else(|_| path.into());
+        spec.root = spec.root.as_deref().map(absolute);
+        Self {
+            spec,
+            bundle: bundle.map(absolute),
+            overlay,
+            oom_group,
+        }
+    }
+}
+
+/// Path of the run a container was started with
+///
+/// Not ending in `.json`, so the registry never takes it for a record.
+pub fn run_path(id: &str) -> PathBuf {
+    Path::new(REGISTRY_DIR).join(format!("{id}.run"))
+}
+
+/// Save the run of container `id` for `restart` (best effort)
+pub fn save_run(id: &str, run: &SavedRun) {
+    let path = run_path(id);
+    let saved = serde_json::to_string_pretty(run)
+        .map_err(std::io::Error::other)
+        .and_then(|json| std::fs::write(&path, json));
+    if let Err(e) = saved {
+        tracing::warn!(path = %path.display(), error = %e, "Failed to save container spec");
+    }
+}
+
+/// The run container `id` was started with, `None` if none was saved
+pub fn load_run(id: &str) -> Result<Option<SavedRun>> {
+    let path = run_path(id);
+    let json = match std::fs::read_to_string(&path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    serde_json::from_str(&json)
+        .map(Some)
+        .with_context(|| format!("Invalid saved spec {}", path.display()))
+}
+
+/// Record that the process of a detached container started as `pid`
+///
+/// Like [`record_exit`], this runs in the supervising process, before the
+/// PID reaches `run`, so nothing `run` does afterwards can race it.
+pub fn record_start(id: &str, pid: i32) {
+    let registry = open();
+    if let Ok(Some(mut record)) = registry.get(id) {
+        record.state = ContainerState::Running;
+        record.pid = Some(pid);
+        let _ = registry.insert(record);
+    }
+}
+
+/// Record how the process of a detached container ended
+///
+/// This runs in the process supervising the container, which has no
+/// terminal to warn on. A record that is gone was removed by `stop` or
+/// `rm` and stays gone, and one with another PID belongs to a later start
+/// of the same ID.
+pub fn record_exit(id: &str, exit: DetachedExit) {
+    let registry = open();
+    if let Ok(Some(mut record)) = registry.get(id)
+        && record.pid == Some(exit.pid)
+    {
+        record.state = ContainerState::Exited;
+        record.exit = Some(ContainerExit::now(
+            exit.exit_code,
+            exit.signal.map(|signal| signal.as_str().to_string()),
+        ));
+        let _ = registry.insert(record);
+    }
+}
+
+/// Output stream of a detached container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// Path of a detached container's log file for `stream`
+pub fn log_path(id: &str, stream: LogStream) -> PathBuf {
+    let suffix = match stream {
+        LogStream::Stdout => "stdout",
+        LogStream::Stderr => "stderr",
+    };
+    Path::new(REGISTRY_DIR).join(format!("{id}.{suffix}.log"))
+}
+
+/// Scratch directory holding a container's overlay upper and work dirs
+pub fn overlay_dir(id: &str) -> PathBuf {
+    Path::new(REGISTRY_DIR).join(format!("{id}.overlay"))
+}
+
+/// Discard a container's overlay changes (best effort)
+pub fn remove_overlay(id: &str) {
+    let path = overlay_dir(id);
+    if let Err(e) = std::fs::remove_dir_all(&path)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        tracing::warn!(path = %path.display(), error = %e, "Failed to remove overlay directory");
+    }
+}
+
+/// Where `update` saves the limits a container had before its first update
+pub fn limits_snapshot_path(id: &str) -> PathBuf {
+    Path::new(REGISTRY_DIR).join(format!("{id}.limits.json"))
+}
+
+/// Forget a container's saved limits (best effort)
+pub fn remove_limits_snapshot(id: &str) {
+    let path = limits_snapshot_path(id);
+    if let Err(e) = std::fs::remove_file(&path)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        tracing::warn!(path = %path.display(), error = %e, "Failed to remove saved limits");
+    }
+}
+
+/// Create the registry directory if needed
+pub fn ensure_dir() -> Result<()> {
+    std::fs::create_dir_all(REGISTRY_DIR)
+        .with_context(|| format!("Failed to create {}", REGISTRY_DIR))
+}
+
+/// Remove a detached container's log files (best effort)
+pub fn remove_logs(id: &str) {
+    for stream in [LogStream::Stdout, LogStream::Stderr] {
+        let path = log_path(id, stream);
+        if let Err(e) = std::fs::remove_file(&path)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            tracing::warn!(path = %path.display(), error = %e, "Failed to remove log file");
+        }
+    }
+}
+
+/// Remove a container record, its saved run and limits (best effort)
+pub fn remove(id: &str) {
+    if let Err(e) = open().remove(id) {
+        tracing::warn!(container_id = id, error = %e, "Failed to remove container record");
+    }
+    let path = run_path(id);
+    if let Err(e) = std::fs::remove_file(&path)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        tracing::warn!(path = %path.display(), error = %e, "Failed to remove saved spec");
+    }
+    remove_limits_snapshot(id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_run_round_trips() {
+        let json = r#"{
+            "process": {
+                "args": ["sh", "-c", "sleep 1"],
+                "rlimits": [{ "type": "RLIMIT_NOFILE", "soft": 1024, "hard": 2048 }]
+            },
+            "root": { "path": "rootfs" },
+            "linux": { "namespaces": [{ "type": "pid" }, { "type": "time" }] }
+        }"#;
+        let (spec, _) = crate::oci::parse(json).unwrap();
+        let saved = SavedRun::new(spec, Some(Path::new("bundle")), true, false);
+
+        // Relative paths would break a restart from another directory
+        assert!(saved.spec.root.as_deref().unwrap().is_absolute());
+        assert!(saved.bundle.as_deref().unwrap().ends_with("bundle"));
+        assert!(saved.bundle.as_deref().unwrap().is_absolute());
+
+        let restored: SavedRun =
+            serde_json::from_str(&serde_json::to_string(&saved).unwrap()).unwrap();
+        assert_eq!(restored.spec.args, saved.spec.args);
+        assert_eq!(restored.spec.rlimits, saved.spec.rlimits);
+        assert_eq!(restored.spec.termination, saved.spec.termination);
+        assert_eq!(
+            restored.spec.namespaces.enabled_namespaces(),
+            vec!["pid", "time"]
+        );
+        assert!(restored.overlay);
+    }
+}
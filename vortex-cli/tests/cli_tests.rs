@@ -42,14 +42,17 @@ fn test_invalid_command() {
 
 #[test]
 fn test_run_without_id() {
+    // The ID is generated, so the run gets as far as validating limits
     Command::new(env!("CARGO_BIN_EXE_vortex"))
         .arg("run")
+        .arg("--memory")
+        .arg("18446744073709551615")
         .arg("--")
         .arg("/bin/echo")
         .arg("test")
         .assert()
         .failure()
-        .stderr(predicate::str::contains("required"));
+        .stderr(predicate::str::contains("Invalid --memory value"));
 }
 
 #[test]
@@ -85,6 +88,38 @@ fn test_run_requires_root() {
         );
 }
 
+#[test]
+fn test_run_with_missing_bundle() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .arg("run")
+        .arg("--id")
+        .arg("test")
+        .arg("--bundle")
+        .arg("/nonexistent/vortex-bundle")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to load OCI bundle"));
+}
+
+#[test]
+fn test_run_json_error() {
+    let output = Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["run", "--id", "test", "--format", "json"])
+        .args(["--bundle", "/nonexistent/vortex-bundle"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    let error: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(error["code"], "error");
+    assert!(
+        error["error"]
+            .as_str()
+            .unwrap()
+            .contains("Failed to load OCI bundle")
+    );
+}
+
 #[test]
 fn test_stats_without_id() {
     Command::new(env!("CARGO_BIN_EXE_vortex"))
@@ -103,6 +138,15 @@ fn test_stop_without_id() {
         .stderr(predicate::str::contains("required"));
 }
 
+#[test]
+fn test_stop_rejects_unknown_signal() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["stop", "--id", "test", "--signal", "LOUD"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown signal 'LOUD'"));
+}
+
 #[test]
 fn test_list_command() {
     // List command should work (might show empty list or require root)
@@ -130,6 +174,21 @@ fn test_list_command() {
     }
 }
 
+#[test]
+fn test_list_json_is_an_array() {
+    let output = Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["list", "--format", "json"])
+        .output()
+        .expect("Failed to execute command");
+
+    if output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let parsed: serde_json::Value =
+            serde_json::from_str(&stdout).expect("list --format json should print JSON");
+        assert!(parsed.is_array(), "Expected a JSON array, got: {}", stdout);
+    }
+}
+
 #[test]
 fn test_namespaces_no_root_needed() {
     // Namespaces command should work without root
@@ -140,6 +199,35 @@ fn test_namespaces_no_root_needed() {
         .stdout(predicate::str::contains("Namespace"));
 }
 
+#[test]
+fn test_plain_output_is_ascii() {
+    for (flag, env) in [
+        (Some("--plain"), None),
+        (Some("--no-emoji"), None),
+        (None, Some(("NO_COLOR", "1"))),
+        (None, Some(("VORTEX_PLAIN", "1"))),
+    ] {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_vortex"));
+        command.env_remove("NO_COLOR").env_remove("VORTEX_PLAIN");
+        command.args(flag).arg("namespaces");
+        if let Some((key, value)) = env {
+            command.env(key, value);
+        }
+
+        let output = command.output().expect("Failed to execute command");
+        assert!(output.status.success());
+        assert!(output.stdout.is_ascii(), "{flag:?} {env:?}");
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .env_remove("NO_COLOR")
+        .env("VORTEX_PLAIN", "0")
+        .arg("namespaces")
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.stdout.is_ascii());
+}
+
 #[test]
 fn test_health_check_without_root() {
     // Skip if running as root
@@ -157,6 +245,360 @@ fn test_health_check_without_root() {
         );
 }
 
+#[test]
+fn test_info_json() {
+    let output = Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["info", "--format", "json"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let info: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+    assert!(info["namespaces"].is_array());
+    assert!(info["features"]["pidfd"].is_boolean());
+}
+
+#[test]
+fn test_json_logs() {
+    let output = Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args([
+            "--log-format",
+            "json",
+            "--log-level",
+            "info",
+            "stats",
+            "--id",
+            "bad id",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let line = stderr
+        .lines()
+        .find(|l| l.contains("Getting stats"))
+        .expect("Expected a log line");
+    assert!(line.starts_with('{'), "Expected JSON log line, got: {line}");
+    assert!(line.contains("\"level\":\"INFO\""));
+}
+
+#[test]
+fn test_run_invalid_ulimit() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args([
+            "run",
+            "--id",
+            "test",
+            "--ulimit",
+            "nofile=4096:1024",
+            "--",
+            "true",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("exceeds hard limit"));
+}
+
+#[test]
+fn test_run_invalid_tmpfs() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args([
+            "run",
+            "--id",
+            "test",
+            "--tmpfs",
+            "tmp:size=64m",
+            "--",
+            "true",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("must be an absolute path"));
+
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args([
+            "run",
+            "--id",
+            "test",
+            "--tmpfs",
+            "/tmp:size=0",
+            "--",
+            "true",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("must not be zero"));
+}
+
+#[test]
+fn test_run_memory_overflow() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args([
+            "run",
+            "--id",
+            "test",
+            "--memory",
+            "18446744073709551615",
+            "--",
+            "true",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("overflows"));
+}
+
+#[test]
+fn test_monitor_interval_requires_monitor() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args([
+            "run",
+            "--id",
+            "test",
+            "--monitor-interval",
+            "500ms",
+            "--",
+            "true",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--monitor"));
+}
+
+#[test]
+fn test_idle_timeout_requires_monitor() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["run", "--id", "test", "--idle-timeout", "60s", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--monitor"));
+}
+
+#[test]
+fn test_stop_signal_requires_a_stop_trigger() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["run", "--id", "test", "--stop-signal", "INT", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--timeout"));
+}
+
+#[test]
+fn test_detach_conflicts_with_tty() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["run", "--id", "test", "--detach", "--tty", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_init_conflicts_with_no_namespaces() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args([
+            "run",
+            "--id",
+            "test",
+            "--init",
+            "--no-namespaces",
+            "--",
+            "true",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_json_format_conflicts_with_monitor() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args([
+            "run",
+            "--id",
+            "test",
+            "--format",
+            "json",
+            "--monitor",
+            "--",
+            "true",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_update_needs_a_change() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["update", "--id", "test"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--revert"));
+
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["update", "--id", "test", "--revert", "--cpu", "2.0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_update_unknown_container() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["update", "--id", "no-such-container", "--cpu", "2.0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is it running?"));
+}
+
+#[test]
+fn test_unknown_profile() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args([
+            "run",
+            "--id",
+            "test",
+            "--profile",
+            "no-such-profile",
+            "--",
+            "true",
+        ])
+        .env("XDG_CONFIG_HOME", "/nonexistent")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unknown profile 'no-such-profile'",
+        ));
+}
+
+#[test]
+fn test_realtime_sched_needs_priority() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["run", "--id", "test", "--sched", "fifo", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Policy fifo needs a priority"));
+}
+
+#[test]
+fn test_keep_conflicts_with_detach() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["run", "--id", "test", "--keep", "--detach", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_rm_requires_id_or_all() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .arg("rm")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--id"));
+}
+
+#[test]
+fn test_rm_unknown_container() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["rm", "--id", "vortex-no-such-container"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Container 'vortex-no-such-container' not found",
+        ));
+}
+
+#[test]
+fn test_logs_of_unknown_container() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["logs", "--id", "vortex-no-such-container"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No logs"));
+}
+
+#[test]
+fn test_stats_of_missing_container() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["stats", "--id", "vortex-no-such-container", "--follow"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not found"));
+}
+
+#[test]
+fn test_wait_for_unknown_container() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["wait", "--id", "no-such-container", "--timeout", "1s"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("not found"));
+}
+
+#[test]
+fn test_restart_unknown_container() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["restart", "--id", "vortex-no-such-container"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("not found"));
+}
+
+#[test]
+fn test_ps_of_missing_container() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["ps", "--id", "vortex-no-such-container"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not found"));
+}
+
+#[test]
+fn test_stats_of_missing_pid() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["stats", "--pid", "2147483647"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Process 2147483647 not found"));
+}
+
+#[test]
+fn test_stats_pid_conflicts_with_id() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["stats", "--pid", "1", "--id", "test"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_stats_interval_requires_follow() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["stats", "--id", "test", "--interval", "2s"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--follow"));
+}
+
+#[test]
+fn test_stats_reset_peak_requires_follow() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["stats", "--id", "test", "--reset-peak"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--follow"));
+}
+
+#[test]
+fn test_invalid_log_format() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["--log-format", "xml", "list"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value"));
+}
+
 #[test]
 fn test_run_help() {
     Command::new(env!("CARGO_BIN_EXE_vortex"))
@@ -169,7 +611,10 @@ fn test_run_help() {
         .stdout(predicate::str::contains("--cpu"))
         .stdout(predicate::str::contains("--memory"))
         .stdout(predicate::str::contains("--monitor"))
-        .stdout(predicate::str::contains("--hostname"));
+        .stdout(predicate::str::contains("--hostname"))
+        .stdout(predicate::str::contains("--bundle"))
+        .stdout(predicate::str::contains("--pod"))
+        .stdout(predicate::str::contains("--ulimit"));
 }
 
 #[test]
@@ -219,6 +664,61 @@ fn test_negative_cpu_value() {
         .failure();
 }
 
+#[test]
+fn test_inspect_unknown_container() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .arg("inspect")
+        .arg("--id")
+        .arg("no-such-container")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Container 'no-such-container' not found",
+        ));
+}
+
+#[test]
+fn test_zero_cpu_value() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .arg("run")
+        .arg("--id")
+        .arg("test")
+        .arg("--cpu")
+        .arg("0%")
+        .arg("--")
+        .arg("/bin/echo")
+        .arg("test")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--cpu"))
+        .stderr(predicate::str::contains("greater than zero"));
+}
+
+#[test]
+#[ignore] // Requires root
+fn test_rm_all_removes_unreadable_records() {
+    // Skip if not root
+    if !is_root() {
+        return;
+    }
+
+    let record = std::path::Path::new("/run/vortex/test-rm-corrupt.json");
+    std::fs::create_dir_all("/run/vortex").unwrap();
+    std::fs::write(record, "{").unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["rm", "--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Record of 'test-rm-corrupt' is unreadable",
+        ))
+        .stdout(predicate::str::contains(
+            "Container 'test-rm-corrupt' removed",
+        ));
+    assert!(!record.exists());
+}
+
 #[test]
 #[ignore] // Requires root
 fn test_command_with_args() {
@@ -281,6 +781,147 @@ fn test_no_namespaces_flag() {
         .stdout(predicate::str::contains("Namespaces: disabled"));
 }
 
+#[test]
+#[ignore] // Requires root
+fn test_init_flag() {
+    // Skip if not root
+    if !is_root() {
+        return;
+    }
+
+    // The init takes PID 1, so the program is PID 2
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["run", "--id", "test-init", "--init", "--"])
+        .args(["/bin/sh", "-c", "echo pid=$$; exit 3"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("pid=2").and(predicate::str::contains("Exit code: 3")));
+}
+
+#[test]
+#[ignore] // Requires root
+fn test_env_keep() {
+    // Skip if not root
+    if !is_root() {
+        return;
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["run", "--id", "test-env-keep", "--env-keep", "VORTEX_KEPT"])
+        .args(["--format", "json", "--", "/usr/bin/env"])
+        .env("VORTEX_KEPT", "yes")
+        .env("VORTEX_DROPPED", "no")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["stdout"], "VORTEX_KEPT=yes\n");
+}
+
+#[test]
+#[ignore] // Requires root
+fn test_json_format() {
+    // Skip if not root
+    if !is_root() {
+        return;
+    }
+
+    // Stdout holds the result alone, so it parses as a whole
+    let output = Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["run", "--id", "test-json", "--format", "json", "--"])
+        .args(["/bin/sh", "-c", "echo out; echo err >&2; exit 4"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["exit_code"], 4);
+    assert_eq!(result["stdout"], "out\n");
+    // Setup logs from the child can precede the program's own stderr
+    assert!(result["stderr"].as_str().unwrap().ends_with("err\n"));
+    assert_eq!(result["binary"], false);
+}
+
+#[test]
+#[ignore] // Requires root
+fn test_output_is_live_unless_captured() {
+    // Skip if not root
+    if !is_root() {
+        return;
+    }
+
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["run", "--id", "test-live", "--", "echo", "live"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("live\n"))
+        .stdout(predicate::str::contains("--- STDOUT ---").not());
+
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args([
+            "run",
+            "--id",
+            "test-live",
+            "--capture",
+            "--",
+            "echo",
+            "live",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--- STDOUT ---\nlive\n"));
+}
+
+#[test]
+#[ignore] // Requires root
+fn test_timeout_sends_stop_signal() {
+    // Skip if not root
+    if !is_root() {
+        return;
+    }
+
+    let script = "trap 'echo flushed; exit 0' INT; while :; do sleep 0.1; done";
+    let output = Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["run", "--id", "test-timeout", "--format", "json"])
+        .args(["--timeout", "300ms", "--stop-signal", "INT"])
+        .args(["--", "/bin/sh", "-c", script])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["stopped"], "graceful");
+    assert!(result["stdout"].as_str().unwrap().ends_with("flushed\n"));
+}
+
+#[test]
+#[ignore] // Requires root
+fn test_merge_stderr() {
+    // Skip if not root
+    if !is_root() {
+        return;
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args([
+            "run",
+            "--id",
+            "test-merge",
+            "--format",
+            "json",
+            "--merge-stderr",
+        ])
+        .args(["--", "/bin/sh", "-c", "echo 1; echo 2 >&2; echo 3"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(result["stdout"].as_str().unwrap().ends_with("1\n2\n3\n"));
+    assert_eq!(result["stderr"], "");
+}
+
 #[test]
 #[ignore] // Requires root
 fn test_custom_hostname() {
@@ -301,3 +942,44 @@ fn test_custom_hostname() {
         .success()
         .stdout(predicate::str::contains("my-test-container"));
 }
+
+#[test]
+#[ignore] // Requires root
+fn test_hooks_write_marker_files() {
+    // Skip if not root
+    if !is_root() {
+        return;
+    }
+
+    let dir = std::env::temp_dir();
+    let prestart = dir.join(format!("vortex-prestart-{}", std::process::id()));
+    let poststop = dir.join(format!("vortex-poststop-{}", std::process::id()));
+
+    // The hook saves the state it gets on stdin before the program runs
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["run", "--id", "test-hooks", "--hook-prestart"])
+        .arg(format!("/usr/bin/tee {}", prestart.display()))
+        .arg("--hook-poststop")
+        .arg(format!("/usr/bin/touch {}", poststop.display()))
+        .arg("--")
+        .arg("/bin/cat")
+        .arg(&prestart)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#""id":"test-hooks","status":"created""#,
+        ));
+    assert!(poststop.exists());
+
+    let _ = std::fs::remove_file(&prestart);
+    let _ = std::fs::remove_file(&poststop);
+}
+
+#[test]
+fn test_run_empty_hook() {
+    Command::new(env!("CARGO_BIN_EXE_vortex"))
+        .args(["run", "--id", "test", "--hook-prestart", " ", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Hook command is empty"));
+}
@@ -0,0 +1,256 @@
+//! Publish/subscribe fan-out for container events
+//!
+//! An [`EventBus`] hands every published [`ContainerEvent`] to each
+//! subscriber whose filter accepts it. Each subscriber has its own bounded
+//! channel, so one slow consumer only loses its own events.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use crate::{ContainerEvent, ContainerId};
+
+type Filter = Box<dyn Fn(&ContainerEvent) -> bool + Send + Sync>;
+
+struct Subscriber {
+    tx: mpsc::Sender<ContainerEvent>,
+    filter: Option<Filter>,
+}
+
+impl Subscriber {
+    fn wants(&self, event: &ContainerEvent) -> bool {
+        self.filter.as_ref().is_none_or(|filter| filter(event))
+    }
+}
+
+/// Event bus with per-subscriber channels and filters
+///
+/// Cloning is cheap and clones share subscribers, so one bus can be handed
+/// to several monitors.
+///
+/// # Example
+/// ```
+/// use std::time::SystemTime;
+/// use vortex_core::{ContainerEvent, ContainerId, EventBus};
+///
+/// let bus = EventBus::new();
+/// let mut everything = bus.subscribe(16);
+/// let mut critical = bus.subscribe_critical(16);
+///
+/// bus.publish(&ContainerEvent::Started {
+///     id: ContainerId::new("web").unwrap(),
+///     timestamp: SystemTime::now(),
+/// });
+///
+/// assert!(everything.try_recv().is_ok());
+/// assert!(critical.try_recv().is_err());
+/// ```
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl EventBus {
+    /// Create a bus with no subscribers
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Receive every event on a new channel holding up to `capacity` events
+    #[must_use]
+    pub fn subscribe(&self, capacity: usize) -> mpsc::Receiver<ContainerEvent> {
+        let (tx, rx) = mpsc::channel(capacity);
+        self.add_subscriber(tx, None);
+        rx
+    }
+
+    /// Receive the events `filter` accepts on a new channel
+    #[must_use]
+    pub fn subscribe_filtered(
+        &self,
+        capacity: usize,
+        filter: impl Fn(&ContainerEvent) -> bool + Send + Sync + 'static,
+    ) -> mpsc::Receiver<ContainerEvent> {
+        let (tx, rx) = mpsc::channel(capacity);
+        self.add_subscriber(tx, Some(Box::new(filter)));
+        rx
+    }
+
+    /// Receive only critical events (see [`ContainerEvent::is_critical`])
+    #[must_use]
+    pub fn subscribe_critical(&self, capacity: usize) -> mpsc::Receiver<ContainerEvent> {
+        self.subscribe_filtered(capacity, ContainerEvent::is_critical)
+    }
+
+    /// Receive only the events of one container
+    #[must_use]
+    pub fn subscribe_container(
+        &self,
+        capacity: usize,
+        container_id: ContainerId,
+    ) -> mpsc::Receiver<ContainerEvent> {
+        self.subscribe_filtered(capacity, move |event| event.container_id() == &container_id)
+    }
+
+    /// Forward every event to an existing channel
+    pub fn attach(&self, tx: mpsc::Sender<ContainerEvent>) {
+        self.add_subscriber(tx, None);
+    }
+
+    fn add_subscriber(&self, tx: mpsc::Sender<ContainerEvent>, filter: Option<Filter>) {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Subscriber { tx, filter });
+    }
+
+    /// Deliver an event to every interested subscriber
+    ///
+    /// Never waits: a subscriber whose channel is full misses the event,
+    /// which is counted in [`Self::dropped_events`]. Subscribers whose
+    /// receiver was dropped are removed.
+    pub fn publish(&self, event: &ContainerEvent) {
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        subscribers.retain(|subscriber| {
+            if !subscriber.wants(event) {
+                return !subscriber.tx.is_closed();
+            }
+
+            match subscriber.tx.try_send(event.clone()) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    let total = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                    // Dropping a stats update is routine under load
+                    if matches!(event, ContainerEvent::StatsUpdate { .. }) {
+                        tracing::debug!(
+                            dropped = total,
+                            "Event channel full, dropping stats update"
+                        );
+                    } else {
+                        tracing::warn!(
+                            event = %event,
+                            dropped = total,
+                            "Event channel full, dropping event"
+                        );
+                    }
+                    true
+                }
+                Err(TrySendError::Closed(_)) => {
+                    tracing::trace!("Event receiver closed, unsubscribing");
+                    false
+                }
+            }
+        });
+    }
+
+    /// Number of live subscribers
+    #[must_use]
+    pub fn subscriber_count(&self) -> usize {
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        subscribers.retain(|subscriber| !subscriber.tx.is_closed());
+        subscribers.len()
+    }
+
+    /// Number of events dropped because a subscriber's channel was full
+    #[must_use]
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl std::fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventBus")
+            .field("subscribers", &self.subscriber_count())
+            .field("dropped", &self.dropped_events())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ResourceStats;
+    use std::time::SystemTime;
+
+    fn id(name: &str) -> ContainerId {
+        ContainerId::new(name).unwrap()
+    }
+
+    fn stats_update(name: &str) -> ContainerEvent {
+        ContainerEvent::StatsUpdate {
+            id: id(name),
+            stats: ResourceStats::default(),
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn test_critical_subscriber_skips_stats_updates() {
+        let bus = EventBus::new();
+        let mut critical = bus.subscribe_critical(16);
+
+        bus.publish(&stats_update("web"));
+        bus.publish(&ContainerEvent::Error {
+            id: id("web"),
+            message: "boom".to_string(),
+            timestamp: SystemTime::now(),
+        });
+        bus.publish(&ContainerEvent::MemoryPressure {
+            id: id("web"),
+            current: 90,
+            limit: 100,
+            percentage: 90.0,
+            timestamp: SystemTime::now(),
+        });
+
+        assert!(matches!(
+            critical.try_recv(),
+            Ok(ContainerEvent::Error { .. })
+        ));
+        assert!(matches!(
+            critical.try_recv(),
+            Ok(ContainerEvent::MemoryPressure { .. })
+        ));
+        assert!(critical.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_container_subscriber_and_fan_out() {
+        let bus = EventBus::new();
+        let mut all = bus.subscribe(16);
+        let mut db_only = bus.subscribe_container(16, id("db"));
+
+        bus.publish(&stats_update("web"));
+        bus.publish(&stats_update("db"));
+
+        assert_eq!(all.try_recv().unwrap().container_id(), &id("web"));
+        assert_eq!(all.try_recv().unwrap().container_id(), &id("db"));
+        assert_eq!(db_only.try_recv().unwrap().container_id(), &id("db"));
+        assert!(db_only.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_full_and_closed_subscribers() {
+        let bus = EventBus::new();
+        let mut small = bus.subscribe(1);
+        let closed = bus.subscribe(1);
+        drop(closed);
+
+        bus.publish(&stats_update("web"));
+        bus.publish(&stats_update("web"));
+
+        assert_eq!(bus.dropped_events(), 1);
+        assert_eq!(bus.subscriber_count(), 1);
+        assert!(small.try_recv().is_ok());
+    }
+}
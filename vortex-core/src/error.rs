@@ -2,6 +2,8 @@
 
 use thiserror::Error;
 
+use crate::MemorySize;
+
 /// Vortex error types
 #[derive(Error, Debug)]
 #[non_exhaustive]
@@ -17,6 +19,16 @@ pub enum Error {
         message: String,
     },
 
+    /// `CGroup` filesystem is mounted read-only
+    #[error(
+        "CGroup filesystem at {} is mounted read-only; vortex needs a writable cgroup v2 hierarchy (remount it read-write or run with a delegated cgroup)",
+        path.display()
+    )]
+    CGroupReadOnly {
+        /// Mount point that was found read-only
+        path: std::path::PathBuf,
+    },
+
     /// Namespace operation failed
     #[error("Namespace error: {message}")]
     Namespace {
@@ -24,6 +36,51 @@ pub enum Error {
         message: String,
     },
 
+    /// Namespaces were created but a later setup step failed
+    ///
+    /// Namespaces cannot be left again, so the calling process stays in the
+    /// half-configured ones and should exit rather than carry on.
+    #[error(
+        "Namespace setup failed after creating {}: {message}; the process is left in them and should exit",
+        created.join(", ")
+    )]
+    NamespaceSetup {
+        /// Namespaces the process is already in
+        created: Vec<String>,
+        /// Error message of the failed step
+        message: String,
+    },
+
+    /// The kernel does not offer a requested namespace type
+    #[error("The {namespace} namespace is not available: {reason}")]
+    NamespaceUnsupported {
+        /// Namespace as named in `/proc/<pid>/ns`, e.g. `user`
+        namespace: String,
+        /// Why it cannot be created
+        reason: String,
+    },
+
+    /// No container with the given ID
+    #[error("Container '{id}' not found")]
+    ContainerNotFound {
+        /// Container ID that was looked up
+        id: String,
+    },
+
+    /// A container with the given ID is already running
+    #[error("Container '{id}' already running")]
+    ContainerRunning {
+        /// Container ID that is in use
+        id: String,
+    },
+
+    /// No process with the given PID
+    #[error("Process {pid} not found")]
+    ProcessNotFound {
+        /// Process ID that was looked up
+        pid: i32,
+    },
+
     /// Permission denied
     #[error("Permission denied: {operation}")]
     PermissionDenied {
@@ -38,6 +95,40 @@ pub enum Error {
         message: String,
     },
 
+    /// CPU core count that cannot be turned into a quota
+    #[error("Invalid CPU cores value {value}: must be a finite number greater than zero")]
+    InvalidCpuCores {
+        /// Rejected value
+        value: f64,
+    },
+
+    /// Memory limit below what the cgroup already uses
+    #[error(
+        "Memory limit {requested} is below the current usage of {usage}; applying it would trigger the OOM killer right away"
+    )]
+    MemoryBelowUsage {
+        /// Limit that was asked for
+        requested: MemorySize,
+        /// Contents of `memory.current` at the time
+        usage: MemorySize,
+    },
+
+    /// Some resource limits were set before another one failed
+    ///
+    /// The ones in `applied` stay in place.
+    #[error(
+        "Failed to set the {failed} limit after setting {}: {message}",
+        applied.join(", ")
+    )]
+    LimitsPartiallyApplied {
+        /// Limits that were set
+        applied: Vec<String>,
+        /// Limit that failed
+        failed: String,
+        /// Error message of the failure
+        message: String,
+    },
+
     /// System error from nix
     #[error("System error: {0}")]
     System(#[from] nix::Error),
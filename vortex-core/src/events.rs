@@ -46,6 +46,29 @@ pub enum ContainerEvent {
         timestamp: SystemTime,
     },
 
+    /// Memory usage exceeded `memory.high` and was throttled
+    MemoryThrottled {
+        /// Container ID
+        id: ContainerId,
+        /// New `memory.high` events since the previous reading
+        events: u64,
+        /// Timestamp
+        #[serde(with = "systemtime_serde")]
+        timestamp: SystemTime,
+    },
+
+    /// CPU usage stayed below the idle threshold for the whole idle window
+    Idle {
+        /// Container ID
+        id: ContainerId,
+        /// How long CPU usage has been below the threshold
+        #[serde(with = "duration_serde")]
+        duration: Duration,
+        /// Timestamp
+        #[serde(with = "systemtime_serde")]
+        timestamp: SystemTime,
+    },
+
     /// Container exiting
     Exiting {
         /// Container ID
@@ -88,6 +111,8 @@ impl ContainerEvent {
             Self::Started { id, .. }
             | Self::CpuThrottled { id, .. }
             | Self::MemoryPressure { id, .. }
+            | Self::MemoryThrottled { id, .. }
+            | Self::Idle { id, .. }
             | Self::Exiting { id, .. }
             | Self::StatsUpdate { id, .. }
             | Self::Error { id, .. } => id,
@@ -101,6 +126,8 @@ impl ContainerEvent {
             Self::Started { timestamp, .. }
             | Self::CpuThrottled { timestamp, .. }
             | Self::MemoryPressure { timestamp, .. }
+            | Self::MemoryThrottled { timestamp, .. }
+            | Self::Idle { timestamp, .. }
             | Self::Exiting { timestamp, .. }
             | Self::StatsUpdate { timestamp, .. }
             | Self::Error { timestamp, .. } => *timestamp,
@@ -147,6 +174,22 @@ impl ContainerEvent {
                     "Memory pressure"
                 );
             }
+            Self::MemoryThrottled { id, events, .. } => {
+                tracing::warn!(
+                    container_id = %id,
+                    events,
+                    event = "memory_throttled",
+                    "Memory throttled at memory.high"
+                );
+            }
+            Self::Idle { id, duration, .. } => {
+                tracing::info!(
+                    container_id = %id,
+                    idle_ms = duration.as_millis(),
+                    event = "idle",
+                    "Container idle"
+                );
+            }
             Self::Exiting { id, exit_code, .. } => {
                 tracing::info!(
                     container_id = %id,
@@ -184,6 +227,15 @@ impl fmt::Display for ContainerEvent {
             Self::MemoryPressure { id, percentage, .. } => {
                 write!(f, "Container {} memory at {:.1}%", id, percentage)
             }
+            Self::MemoryThrottled { id, events, .. } => {
+                write!(
+                    f,
+                    "Container {id} memory throttled {events} time(s) at memory.high"
+                )
+            }
+            Self::Idle { id, duration, .. } => {
+                write!(f, "Container {id} idle for {duration:?}")
+            }
             Self::Exiting { id, exit_code, .. } => {
                 write!(f, "Container {} exiting with code {}", id, exit_code)
             }
@@ -276,6 +328,45 @@ mod tests {
         assert!(!event.is_critical());
     }
 
+    #[test]
+    fn test_memory_throttled_event() {
+        let id = ContainerId::new("test").unwrap();
+        let event = ContainerEvent::MemoryThrottled {
+            id: id.clone(),
+            events: 3,
+            timestamp: SystemTime::now(),
+        };
+
+        assert_eq!(event.container_id(), &id);
+        assert!(!event.is_critical());
+        assert!(event.to_string().contains("3 time(s)"));
+
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains(r#""type":"memory_throttled""#));
+        let back: ContainerEvent = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            back,
+            ContainerEvent::MemoryThrottled { events: 3, .. }
+        ));
+    }
+
+    #[test]
+    fn test_idle_event() {
+        let id = ContainerId::new("test").unwrap();
+        let event = ContainerEvent::Idle {
+            id,
+            duration: Duration::from_secs(30),
+            timestamp: SystemTime::now(),
+        };
+
+        assert!(!event.is_critical());
+        assert_eq!(event.to_string(), "Container test idle for 30s");
+
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains(r#""type":"idle""#));
+        assert!(json.contains(r#""duration":30000"#));
+    }
+
     #[test]
     fn test_event_serde() {
         let id = ContainerId::new("test").unwrap();
@@ -1,16 +1,26 @@
 //! Vortex Core - Foundation types, events, and utilities
 //!
-//! This crate provides the core abstractions used throughout Vortex.
+//! This crate provides the core abstractions used throughout Vortex,
+//! including the [`Registry`] of known containers.
 
 #![warn(missing_docs, clippy::all, clippy::pedantic, clippy::nursery)]
 #![allow(clippy::module_name_repetitions)]
 
+pub mod bus;
 pub mod error;
 pub mod events;
+pub mod registry;
 pub mod resources;
 pub mod types;
 
+pub use bus::EventBus;
 pub use error::{Error, Result};
 pub use events::ContainerEvent;
-pub use resources::{CpuCores, CpuLimit, MemoryLimit, MemorySize, ResourceStats};
+pub use registry::{
+    ContainerExit, ContainerRecord, ContainerState, FileRegistry, InMemoryRegistry, Registry,
+};
+pub use resources::{
+    CpuCores, CpuLimit, IoLimit, MemoryLimit, MemorySize, ResourceLimits, ResourceRates,
+    ResourceStats,
+};
 pub use types::{ContainerId, ProcessId};
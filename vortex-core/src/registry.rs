@@ -0,0 +1,442 @@
+//! Container registry: what the runtime remembers about each container
+//!
+//! The cgroup alone cannot tell which command a container runs, which pod
+//! it belongs to or when it was created, so `run` records that in a
+//! [`Registry`]. The storage is behind a trait: [`FileRegistry`] keeps one
+//! JSON file per container and is what the CLI uses, [`InMemoryRegistry`]
+//! lets registry-dependent code be tested without touching the filesystem.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{ContainerId, CpuLimit, Error, MemoryLimit, Result};
+
+/// Lifecycle state recorded for a container
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerState {
+    /// Cgroup set up, process not started yet
+    Created,
+    /// Process started
+    Running,
+    /// Process exited, cgroup kept for inspection (`run --keep`, or a
+    /// detached container until it is stopped or removed)
+    Exited,
+}
+
+impl ContainerState {
+    /// State shown to users, given whether the cgroup still has processes
+    #[must_use]
+    pub const fn describe(state: Option<Self>, has_processes: bool) -> &'static str {
+        match state {
+            _ if has_processes => "running",
+            Some(Self::Exited) => "exited (kept)",
+            _ => "created",
+        }
+    }
+}
+
+/// Registry entry for one container
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContainerRecord {
+    /// Container ID
+    pub id: String,
+    /// Pod the container belongs to
+    pub pod: Option<String>,
+    /// Existing cgroup the container was created under instead of the
+    /// vortex namespace, relative to the cgroup root
+    #[serde(default)]
+    pub cgroup_parent: Option<String>,
+    /// Recorded state
+    pub state: ContainerState,
+    /// Command and arguments
+    pub command: Vec<String>,
+    /// Creation time in seconds since the Unix epoch
+    pub created: u64,
+    /// Requested CPU limit
+    pub cpu: Option<CpuLimit>,
+    /// Requested memory limit
+    pub memory: Option<MemoryLimit>,
+    /// Namespaces the container was configured with
+    pub namespaces: Vec<String>,
+    /// PID of the container process, recorded for detached containers
+    #[serde(default)]
+    pub pid: Option<i32>,
+    /// How the process ended, recorded for detached containers
+    #[serde(default)]
+    pub exit: Option<ContainerExit>,
+}
+
+/// How the process of a container ended
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContainerExit {
+    /// Exit code, or 128 plus the signal number if a signal killed it
+    pub exit_code: i32,
+    /// Name of the signal that killed it, e.g. `SIGKILL`
+    pub signal: Option<String>,
+    /// Exit time in seconds since the Unix epoch
+    pub finished: u64,
+}
+
+impl ContainerExit {
+    /// An exit that happened now
+    #[must_use]
+    pub fn now(exit_code: i32, signal: Option<String>) -> Self {
+        Self {
+            exit_code,
+            signal,
+            finished: unix_now(),
+        }
+    }
+}
+
+/// Seconds since the Unix epoch
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+impl ContainerRecord {
+    /// A record for a container created now, without limits or namespaces
+    #[must_use]
+    pub fn new(id: impl Into<String>, command: Vec<String>) -> Self {
+        Self {
+            id: id.into(),
+            pod: None,
+            cgroup_parent: None,
+            state: ContainerState::Created,
+            command,
+            created: unix_now(),
+            cpu: None,
+            memory: None,
+            namespaces: Vec::new(),
+            pid: None,
+            exit: None,
+        }
+    }
+}
+
+/// Storage for container records, keyed by container ID
+pub trait Registry: Send + Sync {
+    /// Store a record, replacing any previous one with the same ID
+    ///
+    /// # Errors
+    /// Returns error if the record cannot be stored
+    fn insert(&self, record: ContainerRecord) -> Result<()>;
+
+    /// Look up a record, `None` if the container is unknown
+    ///
+    /// # Errors
+    /// Returns error if the record exists but cannot be read
+    fn get(&self, id: &str) -> Result<Option<ContainerRecord>>;
+
+    /// Every record, sorted by ID
+    ///
+    /// # Errors
+    /// Returns error if the records cannot be enumerated
+    fn list(&self) -> Result<Vec<ContainerRecord>>;
+
+    /// IDs of every record, sorted, including records that cannot be read
+    ///
+    /// # Errors
+    /// Returns error if the records cannot be enumerated
+    fn ids(&self) -> Result<Vec<String>> {
+        Ok(self.list()?.into_iter().map(|record| record.id).collect())
+    }
+
+    /// Forget a container; removing an unknown one is not an error
+    ///
+    /// # Errors
+    /// Returns error if the record exists but cannot be removed
+    fn remove(&self, id: &str) -> Result<()>;
+
+    /// Change the recorded state of a container
+    ///
+    /// # Errors
+    /// Returns [`Error::ContainerNotFound`] if there is no record for `id`
+    fn update_state(&self, id: &str, state: ContainerState) -> Result<()> {
+        let mut record = self
+            .get(id)?
+            .ok_or_else(|| Error::ContainerNotFound { id: id.to_string() })?;
+        record.state = state;
+        self.insert(record)
+    }
+}
+
+/// Registry keeping one `<id>.json` file per container in a directory
+#[derive(Debug, Clone)]
+pub struct FileRegistry {
+    dir: PathBuf,
+}
+
+impl FileRegistry {
+    /// Directory used by the CLI; it is on a tmpfs, so records do not
+    /// outlive a reboot, just like the containers
+    pub const DEFAULT_DIR: &'static str = "/run/vortex";
+
+    /// Registry in `dir`, which is created on the first insert
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Directory holding the records
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of the record for `id`; the ID is validated so it cannot
+    /// point outside the directory
+    fn record_path(&self, id: &str) -> Result<PathBuf> {
+        ContainerId::new(id)?;
+        Ok(self.dir.join(format!("{id}.json")))
+    }
+}
+
+impl Default for FileRegistry {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_DIR)
+    }
+}
+
+impl Registry for FileRegistry {
+    /// The record is written to a temporary file and renamed into place,
+    /// so other processes never read it half-written
+    fn insert(&self, record: ContainerRecord) -> Result<()> {
+        let path = self.record_path(&record.id)?;
+        std::fs::create_dir_all(&self.dir)?;
+
+        let json = serde_json::to_string_pretty(&record).map_err(std::io::Error::other)?;
+        // Not ending in `.json`, so `list` never picks it up
+        let temp = self
+            .dir
+            .join(format!(".{}.json.{}.tmp", record.id, std::process::id()));
+        std::fs::write(&temp, json)?;
+        std::fs::rename(&temp, path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&temp);
+        })?;
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<ContainerRecord>> {
+        let path = self.record_path(id)?;
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        serde_json::from_str(&content).map(Some).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Corrupt container record {}: {e}", path.display()),
+            )
+            .into()
+        })
+    }
+
+    /// Records that cannot be read are skipped with a warning, so one
+    /// corrupt file does not hide every other container
+    fn list(&self) -> Result<Vec<ContainerRecord>> {
+        let ids = self.ids()?;
+        let mut records = Vec::with_capacity(ids.len());
+        for id in ids {
+            match self.get(&id) {
+                Ok(Some(record)) => records.push(record),
+                // Removed since the directory was read
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!(container_id = %id, error = %e, "Skipping container record");
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    fn ids(&self) -> Result<Vec<String>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut ids = Vec::new();
+        for entry in entries {
+            let name = entry?.file_name();
+            if let Some(id) = name.to_str().and_then(|name| name.strip_suffix(".json")) {
+                ids.push(id.to_string());
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+
+    fn remove(&self, id: &str) -> Result<()> {
+        match std::fs::remove_file(self.record_path(id)?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Registry holding records in memory, for tests and embedding
+#[derive(Debug, Default)]
+pub struct InMemoryRegistry {
+    records: Mutex<BTreeMap<String, ContainerRecord>>,
+}
+
+impl InMemoryRegistry {
+    /// An empty registry
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn records(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, ContainerRecord>> {
+        // A panic while holding the lock cannot leave a map half-updated
+        self.records.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Registry for InMemoryRegistry {
+    fn insert(&self, record: ContainerRecord) -> Result<()> {
+        self.records().insert(record.id.clone(), record);
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<ContainerRecord>> {
+        Ok(self.records().get(id).cloned())
+    }
+
+    fn list(&self) -> Result<Vec<ContainerRecord>> {
+        Ok(self.records().values().cloned().collect())
+    }
+
+    fn remove(&self, id: &str) -> Result<()> {
+        self.records().remove(id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CpuCores, MemorySize};
+
+    fn record(id: &str) -> ContainerRecord {
+        ContainerRecord {
+            pod: Some("pod-a".to_string()),
+            cpu: Some(CpuLimit::new(CpuCores::new(0.5))),
+            memory: Some(MemoryLimit::new(MemorySize::from_mb(64))),
+            namespaces: vec!["pid".to_string(), "mnt".to_string()],
+            ..ContainerRecord::new(id, vec!["sleep".to_string(), "10".to_string()])
+        }
+    }
+
+    /// Behaviour every registry must have
+    fn check_registry(registry: &impl Registry) {
+        assert!(registry.list().unwrap().is_empty());
+        assert_eq!(registry.get("web").unwrap(), None);
+
+        registry.insert(record("web")).unwrap();
+        registry.insert(record("db")).unwrap();
+        assert_eq!(registry.get("web").unwrap(), Some(record("web")));
+
+        // Sorted by ID
+        let ids: Vec<String> = registry
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|record| record.id)
+            .collect();
+        assert_eq!(ids, vec!["db", "web"]);
+        assert_eq!(registry.ids().unwrap(), ids);
+
+        // Inserting again replaces
+        let mut running = record("web");
+        running.pid = Some(42);
+        registry.insert(running.clone()).unwrap();
+        assert_eq!(registry.get("web").unwrap(), Some(running));
+        assert_eq!(registry.list().unwrap().len(), 2);
+
+        registry
+            .update_state("web", ContainerState::Exited)
+            .unwrap();
+        let web = registry.get("web").unwrap().unwrap();
+        assert_eq!(web.state, ContainerState::Exited);
+        assert_eq!(web.pid, Some(42));
+        assert_eq!(web.exit, None);
+        assert!(matches!(
+            registry.update_state("cache", ContainerState::Running),
+            Err(Error::ContainerNotFound { .. })
+        ));
+
+        registry.remove("web").unwrap();
+        registry.remove("web").unwrap();
+        assert_eq!(registry.get("web").unwrap(), None);
+        assert_eq!(registry.list().unwrap(), vec![record("db")]);
+    }
+
+    #[test]
+    fn test_in_memory_registry() {
+        check_registry(&InMemoryRegistry::new());
+    }
+
+    #[test]
+    fn test_file_registry() {
+        let dir = std::env::temp_dir().join(format!("vortex-registry-{}", std::process::id()));
+        check_registry(&FileRegistry::new(&dir));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_record_without_exit_still_loads() {
+        // Records written before exits were recorded lack the field
+        let mut json = serde_json::to_value(record("web")).unwrap();
+        json.as_object_mut().unwrap().remove("exit");
+        let old: ContainerRecord = serde_json::from_value(json).unwrap();
+        assert_eq!(old.exit, None);
+
+        let mut exited = record("web");
+        exited.exit = Some(ContainerExit::now(137, Some("SIGKILL".to_string())));
+        let json = serde_json::to_string(&exited).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ContainerRecord>(&json).unwrap(),
+            exited
+        );
+    }
+
+    #[test]
+    fn test_file_registry_skips_corrupt_records() {
+        let dir = std::env::temp_dir().join(format!("vortex-corrupt-{}", std::process::id()));
+        let registry = FileRegistry::new(&dir);
+        registry.insert(record("web")).unwrap();
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+
+        assert!(registry.get("broken").is_err());
+        assert_eq!(registry.list().unwrap(), vec![record("web")]);
+        assert_eq!(registry.ids().unwrap(), vec!["broken", "web"]);
+        // IDs are file names, so they must not escape the directory
+        assert!(registry.get("../etc/passwd").is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_describe_state() {
+        assert_eq!(ContainerState::describe(None, true), "running");
+        assert_eq!(
+            ContainerState::describe(Some(ContainerState::Exited), false),
+            "exited (kept)"
+        );
+        assert_eq!(
+            ContainerState::describe(Some(ContainerState::Running), false),
+            "created"
+        );
+    }
+}
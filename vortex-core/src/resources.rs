@@ -2,9 +2,11 @@
 
 use serde::{Deserialize, Serialize};
 use std::fmt;
-use std::ops::{Add, Sub};
+use std::ops::{Add, AddAssign, Sub};
 use std::time::Duration;
 
+use crate::{Error, Result};
+
 /// Memory size value object with compile-time unit safety
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
 #[repr(transparent)]
@@ -40,6 +42,42 @@ impl MemorySize {
         )
     }
 
+    /// Create from kilobytes, failing on overflow
+    ///
+    /// Use this (rather than the saturating [`Self::from_kb`]) for values
+    /// coming from user input or config files.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidConfig`] if the size does not fit in `u64` bytes
+    pub fn try_from_kb(kb: u64) -> Result<Self> {
+        Self::checked_scale(kb, 1024, "KB")
+    }
+
+    /// Create from megabytes, failing on overflow
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidConfig`] if the size does not fit in `u64` bytes
+    pub fn try_from_mb(mb: u64) -> Result<Self> {
+        Self::checked_scale(mb, 1024 * 1024, "MB")
+    }
+
+    /// Create from gigabytes, failing on overflow
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidConfig`] if the size does not fit in `u64` bytes
+    pub fn try_from_gb(gb: u64) -> Result<Self> {
+        Self::checked_scale(gb, 1024 * 1024 * 1024, "GB")
+    }
+
+    fn checked_scale(value: u64, factor: u64, unit: &str) -> Result<Self> {
+        value
+            .checked_mul(factor)
+            .map(Self)
+            .ok_or_else(|| Error::InvalidConfig {
+                message: format!("Memory size {value} {unit} overflows"),
+            })
+    }
+
     /// Get value in bytes
     #[must_use]
     pub const fn as_bytes(self) -> u64 {
@@ -102,6 +140,9 @@ impl fmt::Display for MemorySize {
     }
 }
 
+/// `CGroup` CPU period in microseconds (100ms)
+const CPU_PERIOD_US: i64 = 100_000;
+
 /// CPU cores value object
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
 #[repr(transparent)]
@@ -110,11 +151,28 @@ pub struct CpuCores(f64);
 
 impl CpuCores {
     /// Create new CPU cores value
+    ///
+    /// The value is not checked: it must be finite and greater than zero
+    /// for [`to_quota`](Self::to_quota) to produce a quota the kernel
+    /// accepts. Use [`try_new`](Self::try_new) for untrusted input.
     #[must_use]
     pub const fn new(cores: f64) -> Self {
         Self(cores)
     }
 
+    /// Create a validated CPU cores value
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidCpuCores`] if the value is NaN, infinite,
+    /// zero or negative
+    pub fn try_new(cores: f64) -> Result<Self> {
+        if cores.is_finite() && cores > 0.0 {
+            Ok(Self(cores))
+        } else {
+            Err(Error::InvalidCpuCores { value: cores })
+        }
+    }
+
     /// Get value as f64
     #[must_use]
     pub const fn as_f64(self) -> f64 {
@@ -127,14 +185,13 @@ impl CpuCores {
     #[must_use]
     #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
     pub fn to_quota(self) -> (i64, i64) {
-        const PERIOD: i64 = 100_000; // 100ms = 100,000 microseconds
-        let quota = (self.0 * PERIOD as f64) as i64;
-        (quota, PERIOD)
+        let quota = (self.0 * CPU_PERIOD_US as f64) as i64;
+        (quota, CPU_PERIOD_US)
     }
 }
 
 /// CPU resource limit
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct CpuLimit {
     /// Number of CPU cores
     pub cores: CpuCores,
@@ -146,10 +203,48 @@ impl CpuLimit {
     pub const fn new(cores: CpuCores) -> Self {
         Self { cores }
     }
+
+    /// Parse the contents of a `cpu.max` file
+    ///
+    /// The format is `"<quota> <period>"`, with `max` as the quota meaning
+    /// unlimited. The period is optional and defaults to 100ms.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidConfig`] if the content is malformed
+    #[allow(clippy::cast_precision_loss)]
+    pub fn from_cpu_max_str(content: &str) -> Result<Option<Self>> {
+        let invalid = || Error::InvalidConfig {
+            message: format!("Invalid cpu.max value '{}'", content.trim()),
+        };
+
+        let mut parts = content.split_whitespace();
+        let quota = parts.next().ok_or_else(invalid)?;
+        let period: i64 = match parts.next() {
+            Some(period) => period.parse().map_err(|_| invalid())?,
+            None => CPU_PERIOD_US,
+        };
+        if parts.next().is_some() || period <= 0 {
+            return Err(invalid());
+        }
+
+        if quota == "max" {
+            return Ok(None);
+        }
+        let quota: i64 = quota.parse().map_err(|_| invalid())?;
+
+        let cores = CpuCores::try_new(quota as f64 / period as f64).map_err(|_| invalid())?;
+        Ok(Some(Self::new(cores)))
+    }
+}
+
+impl fmt::Display for CpuLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2} cores", self.cores.as_f64())
+    }
 }
 
 /// Memory resource limit
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MemoryLimit {
     /// Memory limit
     pub limit: MemorySize,
@@ -174,6 +269,135 @@ impl MemoryLimit {
     }
 }
 
+impl fmt::Display for MemoryLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.swap {
+            Some(swap) => write!(f, "{} (swap: {swap})", self.limit),
+            None => write!(f, "{} (no swap)", self.limit),
+        }
+    }
+}
+
+/// Bandwidth and IOPS caps for one block device, a line of `io.max`
+///
+/// `None` means unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IoLimit {
+    /// Device major number
+    pub major: u32,
+    /// Device minor number
+    pub minor: u32,
+    /// Read bytes per second
+    pub read_bps: Option<u64>,
+    /// Write bytes per second
+    pub write_bps: Option<u64>,
+    /// Read operations per second
+    pub read_iops: Option<u64>,
+    /// Write operations per second
+    pub write_iops: Option<u64>,
+}
+
+impl IoLimit {
+    /// Create an IO limit for device `major:minor` with nothing capped yet
+    #[must_use]
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self {
+            major,
+            minor,
+            read_bps: None,
+            write_bps: None,
+            read_iops: None,
+            write_iops: None,
+        }
+    }
+
+    /// Parse one line of `io.max`, e.g. `8:0 rbps=1048576 wbps=max`
+    ///
+    /// Keys that are left out, or set to `max`, are unlimited.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidConfig`] if the line is malformed
+    pub fn from_io_max_line(line: &str) -> Result<Self> {
+        let invalid = || Error::InvalidConfig {
+            message: format!("Invalid io.max line '{}'", line.trim()),
+        };
+
+        let mut parts = line.split_whitespace();
+        let (major, minor) = parts
+            .next()
+            .and_then(|device| device.split_once(':'))
+            .ok_or_else(invalid)?;
+        let mut limit = Self::new(
+            major.parse().map_err(|_| invalid())?,
+            minor.parse().map_err(|_| invalid())?,
+        );
+
+        for part in parts {
+            let (key, value) = part.split_once('=').ok_or_else(invalid)?;
+            let value = match value {
+                "max" => None,
+                value => Some(value.parse().map_err(|_| invalid())?),
+            };
+            match key {
+                "rbps" => limit.read_bps = value,
+                "wbps" => limit.write_bps = value,
+                "riops" => limit.read_iops = value,
+                "wiops" => limit.write_iops = value,
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(limit)
+    }
+}
+
+/// Formats as written to `io.max`, e.g. `8:0 rbps=1048576 wbps=max riops=max wiops=max`
+impl fmt::Display for IoLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = |v: Option<u64>| v.map_or_else(|| "max".to_string(), |v| v.to_string());
+        write!(
+            f,
+            "{}:{} rbps={} wbps={} riops={} wiops={}",
+            self.major,
+            self.minor,
+            value(self.read_bps),
+            value(self.write_bps),
+            value(self.read_iops),
+            value(self.write_iops)
+        )
+    }
+}
+
+/// Every resource limit of a container, for
+/// `ResourceBackend::apply_limits` to set in one go
+///
+/// Limits that are `None`, and `io` when empty, are left as they are.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    /// CPU bandwidth (`cpu.max`)
+    pub cpu: Option<CpuLimit>,
+    /// Relative CPU share from 1 to 10000, 100 by default (`cpu.weight`)
+    pub cpu_weight: Option<u16>,
+    /// Memory and swap (`memory.max`, `memory.swap.max`)
+    pub memory: Option<MemoryLimit>,
+    /// Maximum number of processes (`pids.max`)
+    pub pids: Option<u64>,
+    /// Per-device IO caps (`io.max`)
+    pub io: Vec<IoLimit>,
+}
+
+impl ResourceLimits {
+    /// Whether no limit is set at all
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.cpu.is_none()
+            && self.cpu_weight.is_none()
+            && self.memory.is_none()
+            && self.pids.is_none()
+            && self.io.is_empty()
+    }
+}
+
 /// Resource usage statistics snapshot
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ResourceStats {
@@ -185,6 +409,15 @@ pub struct ResourceStats {
     #[serde(with = "duration_serde")]
     pub cpu_throttled: Duration,
 
+    /// CPU enforcement periods elapsed under a quota (`nr_periods` of
+    /// `cpu.stat`)
+    #[serde(default)]
+    pub cpu_nr_periods: u64,
+
+    /// Periods in which the quota ran out (`nr_throttled` of `cpu.stat`)
+    #[serde(default)]
+    pub cpu_nr_throttled: u64,
+
     /// Current memory usage
     pub memory_current: MemorySize,
 
@@ -202,6 +435,104 @@ pub struct ResourceStats {
 
     /// Total bytes written to disk
     pub io_write_bytes: u64,
+
+    /// Times usage went over `memory.high` and was throttled
+    /// (the `high` counter of `memory.events`)
+    #[serde(default)]
+    pub memory_high_events: u64,
+}
+
+impl ResourceStats {
+    /// Combine the stats of several cgroups, e.g. the containers of a pod
+    ///
+    /// See the [`Add`] impl for how each field is combined.
+    #[must_use]
+    pub fn aggregate<'a>(stats: impl IntoIterator<Item = &'a Self>) -> Self {
+        stats
+            .into_iter()
+            .fold(Self::default(), |total, stats| total + stats.clone())
+    }
+}
+
+/// Sums usage and counters, but takes the larger of the peaks
+///
+/// The containers need not have peaked at the same time, so a sum of peaks
+/// would overstate the combined peak; the largest single peak is a lower
+/// bound for it instead.
+impl Add for ResourceStats {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            cpu_usage: self.cpu_usage.saturating_add(rhs.cpu_usage),
+            cpu_throttled: self.cpu_throttled.saturating_add(rhs.cpu_throttled),
+            cpu_nr_periods: self.cpu_nr_periods.saturating_add(rhs.cpu_nr_periods),
+            cpu_nr_throttled: self.cpu_nr_throttled.saturating_add(rhs.cpu_nr_throttled),
+            memory_current: self.memory_current + rhs.memory_current,
+            memory_peak: self.memory_peak.max(rhs.memory_peak),
+            swap_current: self.swap_current + rhs.swap_current,
+            swap_peak: self.swap_peak.max(rhs.swap_peak),
+            io_read_bytes: self.io_read_bytes.saturating_add(rhs.io_read_bytes),
+            io_write_bytes: self.io_write_bytes.saturating_add(rhs.io_write_bytes),
+            memory_high_events: self
+                .memory_high_events
+                .saturating_add(rhs.memory_high_events),
+        }
+    }
+}
+
+impl AddAssign for ResourceStats {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = std::mem::take(self) + rhs;
+    }
+}
+
+/// Rates of change between two [`ResourceStats`] snapshots
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceRates {
+    /// CPU usage as a percentage of one core (200.0 = two full cores)
+    pub cpu_percent: f64,
+
+    /// Share of the window spent throttled, as a percentage
+    pub throttled_percent: f64,
+
+    /// Change in current memory usage over the window, in bytes
+    pub memory_delta: i64,
+
+    /// Disk read throughput in bytes per second
+    pub io_read_per_sec: f64,
+
+    /// Disk write throughput in bytes per second
+    pub io_write_per_sec: f64,
+}
+
+impl ResourceRates {
+    /// Compute rates from two snapshots taken `elapsed` apart
+    ///
+    /// Counters that went backwards (e.g. the cgroup was recreated) and an
+    /// empty window yield zero rates rather than garbage.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_wrap)]
+    pub fn between(previous: &ResourceStats, current: &ResourceStats, elapsed: Duration) -> Self {
+        let window = elapsed.as_secs_f64();
+        if window <= 0.0 {
+            return Self::default();
+        }
+
+        let percent_of_window = |now: Duration, before: Duration| {
+            now.saturating_sub(before).as_secs_f64() / window * 100.0
+        };
+        let per_sec = |now: u64, before: u64| now.saturating_sub(before) as f64 / window;
+
+        Self {
+            cpu_percent: percent_of_window(current.cpu_usage, previous.cpu_usage),
+            throttled_percent: percent_of_window(current.cpu_throttled, previous.cpu_throttled),
+            memory_delta: current.memory_current.as_bytes() as i64
+                - previous.memory_current.as_bytes() as i64,
+            io_read_per_sec: per_sec(current.io_read_bytes, previous.io_read_bytes),
+            io_write_per_sec: per_sec(current.io_write_bytes, previous.io_write_bytes),
+        }
+    }
 }
 
 // Custom Duration serialization (serde_json doesn't handle Duration well)
@@ -237,6 +568,28 @@ mod tests {
         assert_eq!(size.as_mb(), 512.0);
     }
 
+    #[test]
+    fn memory_size_try_from_overflow() {
+        const MAX_MB: u64 = u64::MAX / (1024 * 1024);
+        const MAX_GB: u64 = u64::MAX / (1024 * 1024 * 1024);
+
+        assert_eq!(
+            MemorySize::try_from_mb(512).unwrap(),
+            MemorySize::from_mb(512)
+        );
+
+        assert!(MemorySize::try_from_kb(u64::MAX / 1024).is_ok());
+        assert!(MemorySize::try_from_kb(u64::MAX / 1024 + 1).is_err());
+        assert!(MemorySize::try_from_mb(MAX_MB).is_ok());
+        assert!(MemorySize::try_from_mb(MAX_MB + 1).is_err());
+        assert!(MemorySize::try_from_gb(MAX_GB).is_ok());
+        assert!(MemorySize::try_from_gb(MAX_GB + 1).is_err());
+        assert!(MemorySize::try_from_gb(u64::MAX).is_err());
+
+        // The saturating constructor keeps its behavior
+        assert_eq!(MemorySize::from_gb(u64::MAX).as_bytes(), u64::MAX);
+    }
+
     #[test]
     fn memory_size_arithmetic() {
         let a = MemorySize::from_mb(256);
@@ -252,6 +605,52 @@ mod tests {
         assert_eq!(format!("{}", MemorySize::from_bytes(100)), "100 bytes");
     }
 
+    #[test]
+    fn limit_display() {
+        assert_eq!(CpuLimit::new(CpuCores::new(1.5)).to_string(), "1.50 cores");
+        assert_eq!(
+            MemoryLimit::with_swap(MemorySize::from_mb(512), MemorySize::from_gb(1)).to_string(),
+            "512.00 MB (swap: 1.00 GB)"
+        );
+        assert_eq!(
+            MemoryLimit::new(MemorySize::from_mb(512)).to_string(),
+            "512.00 MB (no swap)"
+        );
+    }
+
+    #[test]
+    fn io_limit_display() {
+        let limit = IoLimit {
+            read_bps: Some(1024 * 1024),
+            write_iops: Some(100),
+            ..IoLimit::new(8, 0)
+        };
+        assert_eq!(
+            limit.to_string(),
+            "8:0 rbps=1048576 wbps=max riops=max wiops=100"
+        );
+        assert_eq!(
+            IoLimit::from_io_max_line(&limit.to_string()).unwrap(),
+            limit
+        );
+    }
+
+    #[test]
+    fn io_limit_from_io_max_line() {
+        let limit = IoLimit::from_io_max_line("259:0 wbps=2097152\n").unwrap();
+        assert_eq!(
+            limit,
+            IoLimit {
+                write_bps: Some(2 * 1024 * 1024),
+                ..IoLimit::new(259, 0)
+            }
+        );
+        assert!(IoLimit::from_io_max_line("").is_err());
+        assert!(IoLimit::from_io_max_line("8 rbps=1").is_err());
+        assert!(IoLimit::from_io_max_line("8:0 rbps=fast").is_err());
+        assert!(IoLimit::from_io_max_line("8:0 latency=1").is_err());
+    }
+
     #[test]
     fn cpu_quota_conversion() {
         let cores = CpuCores::new(1.0);
@@ -265,6 +664,110 @@ mod tests {
         assert_eq!(period, 100_000);
     }
 
+    #[test]
+    fn cpu_cores_validation() {
+        assert_eq!(CpuCores::try_new(1.5).unwrap(), CpuCores::new(1.5));
+        assert!(matches!(
+            CpuCores::try_new(f64::NAN),
+            Err(Error::InvalidCpuCores { .. })
+        ));
+        assert!(CpuCores::try_new(f64::INFINITY).is_err());
+        assert!(CpuCores::try_new(-1.0).is_err());
+        assert!(CpuCores::try_new(0.0).is_err());
+    }
+
+    #[test]
+    fn cpu_limit_from_cpu_max() {
+        let limit = CpuLimit::from_cpu_max_str("50000 100000\n")
+            .unwrap()
+            .unwrap();
+        assert!((limit.cores.as_f64() - 0.5).abs() < f64::EPSILON);
+
+        assert!(CpuLimit::from_cpu_max_str("max 100000").unwrap().is_none());
+
+        assert!(CpuLimit::from_cpu_max_str("").is_err());
+        assert!(CpuLimit::from_cpu_max_str("abc 100000").is_err());
+        assert!(CpuLimit::from_cpu_max_str("50000 0").is_err());
+        assert!(CpuLimit::from_cpu_max_str("50000 100000 1").is_err());
+    }
+
+    #[test]
+    fn resource_rates_between() {
+        let previous = ResourceStats {
+            cpu_usage: Duration::from_secs(1),
+            memory_current: MemorySize::from_mb(100),
+            io_read_bytes: 1000,
+            ..Default::default()
+        };
+        let current = ResourceStats {
+            cpu_usage: Duration::from_millis(2500),
+            cpu_throttled: Duration::from_millis(200),
+            memory_current: MemorySize::from_mb(90),
+            io_read_bytes: 3000,
+            ..Default::default()
+        };
+
+        let rates = ResourceRates::between(&previous, &current, Duration::from_secs(2));
+        assert!((rates.cpu_percent - 75.0).abs() < 1e-9);
+        assert!((rates.throttled_percent - 10.0).abs() < 1e-9);
+        assert_eq!(rates.memory_delta, -10 * 1024 * 1024);
+        assert!((rates.io_read_per_sec - 1000.0).abs() < 1e-9);
+        assert!(rates.io_write_per_sec.abs() < 1e-9);
+    }
+
+    #[test]
+    fn resource_rates_empty_window() {
+        let stats = ResourceStats::default();
+        let rates = ResourceRates::between(&stats, &stats, Duration::ZERO);
+        assert_eq!(rates.memory_delta, 0);
+        for rate in [
+            rates.cpu_percent,
+            rates.throttled_percent,
+            rates.io_read_per_sec,
+            rates.io_write_per_sec,
+        ] {
+            assert!(rate.abs() < f64::EPSILON, "{rates:?}");
+        }
+    }
+
+    #[test]
+    fn resource_stats_aggregate() {
+        let first = ResourceStats {
+            cpu_usage: Duration::from_secs(2),
+            cpu_nr_throttled: 3,
+            memory_current: MemorySize::from_mb(100),
+            memory_peak: MemorySize::from_mb(300),
+            swap_peak: MemorySize::from_mb(10),
+            io_read_bytes: 1000,
+            ..Default::default()
+        };
+        let second = ResourceStats {
+            cpu_usage: Duration::from_millis(500),
+            cpu_nr_throttled: 1,
+            memory_current: MemorySize::from_mb(50),
+            memory_peak: MemorySize::from_mb(200),
+            swap_peak: MemorySize::from_mb(20),
+            io_read_bytes: 24,
+            memory_high_events: 2,
+            ..Default::default()
+        };
+
+        let total = ResourceStats::aggregate([&first, &second]);
+        assert_eq!(total.cpu_usage, Duration::from_millis(2500));
+        assert_eq!(total.cpu_nr_throttled, 4);
+        assert_eq!(total.memory_current, MemorySize::from_mb(150));
+        assert_eq!(total.io_read_bytes, 1024);
+        assert_eq!(total.memory_high_events, 2);
+        // Peaks are the largest single peak, not the sum
+        assert_eq!(total.memory_peak, MemorySize::from_mb(300));
+        assert_eq!(total.swap_peak, MemorySize::from_mb(20));
+
+        let mut sum = first;
+        sum += second;
+        assert_eq!(sum.memory_current, total.memory_current);
+        assert_eq!(ResourceStats::aggregate([]).cpu_usage, Duration::ZERO);
+    }
+
     #[test]
     fn resource_stats_serde() {
         let stats = ResourceStats {
@@ -16,6 +16,9 @@ impl ContainerId {
     /// Maximum length for container IDs
     pub const MAX_LENGTH: usize = 64;
 
+    /// Length of IDs produced by [`Self::generate`]
+    pub const GENERATED_LENGTH: usize = 12;
+
     /// Create a new `ContainerId` with validation
     ///
     /// # Errors
@@ -26,6 +29,41 @@ impl ContainerId {
         Ok(Self(id))
     }
 
+    /// Generate a random ID
+    ///
+    /// IDs are [`Self::GENERATED_LENGTH`] lowercase base36 characters. They
+    /// are random, not checked against existing containers, so a collision
+    /// is unlikely but possible.
+    #[must_use]
+    pub fn generate() -> Self {
+        use std::collections::hash_map::RandomState;
+        use std::hash::{BuildHasher, Hasher};
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        const ALPHABET: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        // RandomState is seeded from the OS; the counter and clock keep
+        // successive calls within one process distinct
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        hasher.write_u32(std::process::id());
+        if let Ok(now) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+            hasher.write_u128(now.as_nanos());
+        }
+        let mut value = hasher.finish();
+
+        let mut id = String::with_capacity(Self::GENERATED_LENGTH);
+        for _ in 0..Self::GENERATED_LENGTH {
+            #[allow(clippy::cast_possible_truncation)]
+            id.push(char::from(ALPHABET[(value % 36) as usize]));
+            value /= 36;
+        }
+
+        debug_assert!(Self::validate(&id).is_ok());
+        Self(id)
+    }
+
     /// Validate a container ID
     fn validate(id: &str) -> Result<()> {
         if id.is_empty() {
@@ -119,6 +157,50 @@ impl ProcessId {
     pub const fn as_raw(self) -> i32 {
         self.0
     }
+
+    /// Check whether a process with this PID exists
+    ///
+    /// Uses `kill(pid, 0)`. Zombies that have not been reaped yet still exist;
+    /// use [`Self::is_alive`] to exclude them.
+    #[must_use]
+    pub fn exists(self) -> bool {
+        // EPERM means the process exists but belongs to someone else
+        matches!(
+            nix::sys::signal::kill(self.as_nix_pid(), None),
+            Ok(()) | Err(nix::errno::Errno::EPERM)
+        )
+    }
+
+    /// Check whether the process exists and is not a zombie
+    #[must_use]
+    pub fn is_alive(self) -> bool {
+        if !self.exists() {
+            return false;
+        }
+
+        // The state follows the parenthesized command name in /proc/<pid>/stat
+        std::fs::read_to_string(format!("/proc/{}/stat", self.0)).map_or(true, |stat| {
+            stat.rsplit_once(')')
+                .and_then(|(_, rest)| rest.split_whitespace().next())
+                .is_none_or(|state| state != "Z" && state != "X")
+        })
+    }
+
+    /// Send a signal to the process
+    ///
+    /// # Errors
+    /// Returns [`Error::ProcessNotFound`] if there is no such process,
+    /// [`Error::PermissionDenied`] if the caller may not signal it, or
+    /// [`Error::System`] for any other failure
+    pub fn signal(self, signal: nix::sys::signal::Signal) -> Result<()> {
+        nix::sys::signal::kill(self.as_nix_pid(), signal).map_err(|e| match e {
+            nix::errno::Errno::ESRCH => Error::ProcessNotFound { pid: self.0 },
+            nix::errno::Errno::EPERM => Error::PermissionDenied {
+                operation: format!("sending {signal} to process {}", self.0),
+            },
+            other => Error::System(other),
+        })
+    }
 }
 
 impl fmt::Display for ProcessId {
@@ -152,6 +234,18 @@ mod tests {
         assert!(ContainerId::new("invalid/id").is_err());
     }
 
+    #[test]
+    fn test_generated_ids_are_valid() {
+        let mut seen = std::collections::HashSet::new();
+        for _ in 0..1000 {
+            let id = ContainerId::generate();
+            assert_eq!(id.as_str().len(), ContainerId::GENERATED_LENGTH);
+            assert!(ContainerId::new(id.as_str()).is_ok());
+            seen.insert(id);
+        }
+        assert_eq!(seen.len(), 1000);
+    }
+
     #[test]
     fn test_container_id_serde() {
         let id = ContainerId::new("test-123").unwrap();
@@ -160,6 +254,39 @@ mod tests {
         assert_eq!(id, deserialized);
     }
 
+    /// PID of a child that has already been reaped
+    fn dead_pid() -> ProcessId {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        #[allow(clippy::cast_possible_wrap)]
+        let pid = ProcessId::from_raw(child.id() as i32);
+        child.wait().unwrap();
+        pid
+    }
+
+    #[test]
+    fn test_process_id_is_alive() {
+        let current = ProcessId::current();
+        assert!(current.exists());
+        assert!(current.is_alive());
+
+        let dead = dead_pid();
+        assert!(!dead.exists());
+        assert!(!dead.is_alive());
+    }
+
+    #[test]
+    fn test_process_id_signal() {
+        use nix::sys::signal::Signal;
+
+        // SIGCHLD is ignored by default, so this is harmless
+        assert!(ProcessId::current().signal(Signal::SIGCHLD).is_ok());
+
+        assert!(matches!(
+            dead_pid().signal(Signal::SIGTERM),
+            Err(Error::ProcessNotFound { .. })
+        ));
+    }
+
     #[test]
     fn test_process_id() {
         let pid = ProcessId::from_raw(123);
@@ -118,7 +118,7 @@ fn demo_command_execution() {
     match executor.execute("/bin/echo", &["Hello from isolated namespace!".to_string()]) {
         Ok(result) => {
             println!("    Exit code: {}", result.exit_code);
-            let stdout = String::from_utf8_lossy(&result.stdout);
+            let stdout = result.stdout_string();
             println!("    Output: {}", stdout.trim());
         }
         Err(e) => println!("    ❌ Failed: {}", e),
@@ -128,7 +128,7 @@ fn demo_command_execution() {
     println!("\n  Test 2: Show hostname");
     match executor.execute("/bin/hostname", &[]) {
         Ok(result) => {
-            let stdout = String::from_utf8_lossy(&result.stdout);
+            let stdout = result.stdout_string();
             println!("    Hostname: {}", stdout.trim());
         }
         Err(e) => println!("    ❌ Failed: {}", e),
@@ -138,7 +138,7 @@ fn demo_command_execution() {
     println!("\n  Test 3: Process info");
     match executor.execute("/bin/sh", &["-c".to_string(), "echo PID: $$".to_string()]) {
         Ok(result) => {
-            let stdout = String::from_utf8_lossy(&result.stdout);
+            let stdout = result.stdout_string();
             println!("    {}", stdout.trim());
         }
         Err(e) => println!("    ❌ Failed: {}", e),
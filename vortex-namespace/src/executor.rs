@@ -1,16 +1,43 @@
 //! Namespace executor - executes programs in isolated namespaces
 
-use nix::sys::wait::{waitpid, WaitStatus};
-use nix::unistd::{fork, ForkResult, Pid};
-use std::ffi::CString;
-use std::os::unix::io::FromRawFd;
+use nix::errno::Errno;
+use nix::fcntl::OFlag;
+use nix::sys::signal::{SigSet, SigmaskHow, Signal, killpg, pthread_sigmask};
+use nix::sys::wait::{Id, WaitPidFlag, WaitStatus, waitid, waitpid};
+use nix::unistd::{ForkResult, Pid, fork, getpgrp, pipe2, setsid, tcgetpgrp, tcsetpgrp};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::ffi::{CString, OsStr, OsString};
+use std::fs::{File, OpenOptions};
+use std::os::fd::{AsFd, IntoRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use vortex_core::{Error, Result};
+use vortex_security::SecurityManager;
 
 use crate::config::NamespaceConfig;
-use crate::manager::NamespaceManager;
+use crate::hook::{self, ChildGate, Hook, HookState, ParentGate};
+use crate::idmap::{self, IdKind, IdMap};
+use crate::init;
+use crate::manager::{NamespaceInfo, NamespaceKind, NamespaceManager};
+use crate::oom::OomScoreAdj;
+use crate::rlimit::Rlimit;
+use crate::rootfs::RootfsSpec;
+use crate::sched::Scheduling;
+use crate::terminate::{Termination, TerminationConfig, Watchdog};
+use crate::tmpfs::{self, TmpfsMount};
+use crate::tty;
+use crate::user::{self, User};
 
 /// Result of executing a command
-#[derive(Debug, Clone)]
+///
+/// Serializes with `stdout` and `stderr` as strings, decoded as lossy
+/// UTF-8, and a `binary` flag set when either was not valid UTF-8. Only
+/// output without that flag survives a round trip byte for byte.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "ExecutionRecord", from = "ExecutionRecord")]
 pub struct ExecutionResult {
     /// Exit code of the command
     pub exit_code: i32,
@@ -18,20 +45,417 @@ pub struct ExecutionResult {
     pub stdout: Vec<u8>,
     /// Standard error captured from the command
     pub stderr: Vec<u8>,
+    /// Whether `stdout` or `stderr` hit the capture limit and lost the rest
+    pub truncated: bool,
+    /// How the command was stopped if it ran past its timeout
+    pub stopped: Option<Termination>,
+    /// Namespaces the command was found in apart from the caller, read
+    /// from `/proc` before it started
+    pub isolated: Vec<NamespaceKind>,
+}
+
+impl ExecutionResult {
+    /// Standard output as UTF-8, with invalid sequences replaced by `U+FFFD`
+    ///
+    /// Borrows the output unless something had to be replaced; use
+    /// [`Self::stdout`] itself for binary output.
+    #[must_use]
+    pub fn stdout_string(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.stdout)
+    }
+
+    /// Standard error as UTF-8, with invalid sequences replaced by `U+FFFD`
+    #[must_use]
+    pub fn stderr_string(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.stderr)
+    }
+
+    /// Lines of standard output, decoded like [`Self::stdout_string`]
+    ///
+    /// Lines end at `\n` or `\r\n`, which are not included, as with
+    /// [`str::lines`].
+    pub fn stdout_lines(&self) -> impl Iterator<Item = Cow<'_, str>> {
+        self.stdout.split_inclusive(|&b| b == b'\n').map(|line| {
+            let line = line.strip_suffix(b"\n").unwrap_or(line);
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            String::from_utf8_lossy(line)
+        })
+    }
+}
+
+/// How a program started by [`NamespaceExecutor::spawn_supervised`] ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DetachedExit {
+    /// PID the program ran as
+    pub pid: i32,
+    /// Exit code, or 128 plus the signal number if a signal killed it
+    pub exit_code: i32,
+    /// Signal that killed the program
+    pub signal: Option<Signal>,
+}
+
+/// Where the child's standard streams go when it has no terminal
+///
+/// Capturing keeps everything in memory until the child exits, which suits
+/// tests and library callers that look at the output. Inheriting is cheaper
+/// and shows output live, but leaves nothing in the [`ExecutionResult`],
+/// and the child reads the caller's stdin rather than `/dev/null`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Stdio {
+    /// Read stdout and stderr through pipes into the result
+    #[default]
+    Capture,
+    /// Leave fds 0, 1 and 2 as the caller has them
+    Inherit,
+}
+
+/// Serialized form of [`ExecutionResult`]
+#[derive(Serialize, Deserialize)]
+struct ExecutionRecord {
+    exit_code: i32,
+    stdout: String,
+    stderr: String,
+    truncated: bool,
+    /// `stdout` or `stderr` lost bytes that were not valid UTF-8
+    #[serde(default)]
+    binary: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stopped: Option<Termination>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    isolated: Vec<NamespaceKind>,
+}
+
+impl From<ExecutionResult> for ExecutionRecord {
+    fn from(result: ExecutionResult) -> Self {
+        let binary = std::str::from_utf8(&result.stdout).is_err()
+            || std::str::from_utf8(&result.stderr).is_err();
+        Self {
+            exit_code: result.exit_code,
+            stdout: result.stdout_string().into_owned(),
+            stderr: result.stderr_string().into_owned(),
+            truncated: result.truncated,
+            binary,
+            stopped: result.stopped,
+            isolated: result.isolated,
+        }
+    }
+}
+
+impl From<ExecutionRecord> for ExecutionResult {
+    fn from(record: ExecutionRecord) -> Self {
+        Self {
+            exit_code: record.exit_code,
+            stdout: record.stdout.into_bytes(),
+            stderr: record.stderr.into_bytes(),
+            truncated: record.truncated,
+            stopped: record.stopped,
+            isolated: record.isolated,
+        }
+    }
 }
 
 /// Executor for running programs in isolated namespaces
+///
+/// The executor only holds configuration; pipes and child processes are
+/// created per [`NamespaceExecutor::execute`] call. Cloning is therefore cheap
+/// and never duplicates OS resources, so one configuration can spawn any
+/// number of children.
+#[derive(Clone)]
 pub struct NamespaceExecutor {
     config: NamespaceConfig,
+    env: Option<Vec<String>>,
+    env_keep: Vec<String>,
+    cwd: Option<PathBuf>,
+    rlimits: Vec<Rlimit>,
+    security: Option<SecurityManager>,
+    cgroup: Option<PathBuf>,
+    tty: bool,
+    stdio: Stdio,
+    merge_stderr: bool,
+    timeout: Option<Duration>,
+    termination: TerminationConfig,
+    init: bool,
+    user: Option<User>,
+    groups: Vec<u32>,
+    umask: Option<u32>,
+    rootfs: Option<RootfsSpec>,
+    tmpfs: Vec<TmpfsMount>,
+    oom_score_adj: Option<OomScoreAdj>,
+    scheduling: Option<Scheduling>,
+    prestart: Vec<Hook>,
+    hook_state: Option<HookState>,
+    uid_maps: Vec<IdMap>,
+    gid_maps: Vec<IdMap>,
+    max_capture: usize,
 }
 
 impl NamespaceExecutor {
+    /// Bytes of stdout and of stderr kept in memory unless set otherwise
+    pub const DEFAULT_MAX_CAPTURE: usize = 10 * 1024 * 1024;
+
+    /// Directories searched for a program without a `/` unless the
+    /// environment given to [`with_env`](Self::with_env) sets `PATH`
+    pub const DEFAULT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
     /// Create a new namespace executor
     ///
     /// # Errors
     /// Returns error if namespace creation fails
     pub fn new(config: NamespaceConfig) -> Result<Self> {
-        Ok(Self { config })
+        Ok(Self {
+            config,
+            env: None,
+            env_keep: Vec::new(),
+            cwd: None,
+            rlimits: Vec::new(),
+            security: None,
+            cgroup: None,
+            tty: false,
+            stdio: Stdio::Capture,
+            merge_stderr: false,
+            timeout: None,
+            termination: TerminationConfig::default(),
+            init: false,
+            user: None,
+            groups: Vec::new(),
+            umask: None,
+            rootfs: None,
+            tmpfs: Vec::new(),
+            oom_score_adj: None,
+            scheduling: None,
+            prestart: Vec::new(),
+            hook_state: None,
+            uid_maps: Vec::new(),
+            gid_maps: Vec::new(),
+            max_capture: Self::DEFAULT_MAX_CAPTURE,
+        })
+    }
+
+    /// Get the namespace configuration
+    #[must_use]
+    pub const fn config(&self) -> &NamespaceConfig {
+        &self.config
+    }
+
+    /// Replace the child's environment
+    ///
+    /// Entries use the `KEY=VALUE` form. Without this the child inherits
+    /// the environment of the calling process. A `PATH` entry here is where
+    /// the program is looked up; the inherited `PATH` never is.
+    #[must_use]
+    pub fn with_env(mut self, env: Vec<String>) -> Self {
+        self.env = Some(env);
+        self
+    }
+
+    /// Start the child with only the named variables of the calling process
+    ///
+    /// The child's environment is then empty but for these, if they are
+    /// set, and the entries of [`with_env`](Self::with_env), which replace
+    /// kept variables of the same name. `TERM` and `LANG` are typical.
+    #[must_use]
+    pub fn with_env_keep(mut self, names: Vec<String>) -> Self {
+        self.env_keep = names;
+        self
+    }
+
+    /// Set the working directory the child changes into before exec
+    #[must_use]
+    pub fn with_cwd(mut self, cwd: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(cwd.into());
+        self
+    }
+
+    /// Set resource limits applied in the child before exec
+    #[must_use]
+    pub fn with_rlimits(mut self, rlimits: Vec<Rlimit>) -> Self {
+        self.rlimits = rlimits;
+        self
+    }
+
+    /// Attach a security manager whose settings are applied right before exec
+    ///
+    /// With the default settings this sets `no_new_privs`.
+    #[must_use]
+    pub const fn with_security(mut self, security: SecurityManager) -> Self {
+        self.security = Some(security);
+        self
+    }
+
+    /// Move the child into the cgroup at `path` before anything else runs
+    ///
+    /// Joining happens before namespaces are created so a new cgroup
+    /// namespace is rooted at the container's cgroup.
+    #[must_use]
+    pub fn with_cgroup(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cgroup = Some(path.into());
+        self
+    }
+
+    /// Run the child as `user` instead of the caller's user
+    ///
+    /// Supplementary groups are cleared unless set with
+    /// [`Self::with_groups`].
+    #[must_use]
+    pub const fn with_user(mut self, user: User) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    /// Set the child's supplementary group IDs
+    #[must_use]
+    pub fn with_groups(mut self, groups: Vec<u32>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Set the child's file mode creation mask, e.g. `0o022`
+    #[must_use]
+    pub const fn with_umask(mut self, umask: u32) -> Self {
+        self.umask = Some(umask);
+        self
+    }
+
+    /// Switch the child to a root filesystem of its own
+    ///
+    /// Needs a mount namespace. The directories are checked before forking.
+    #[must_use]
+    pub fn with_rootfs(mut self, rootfs: RootfsSpec) -> Self {
+        self.rootfs = Some(rootfs);
+        self
+    }
+
+    /// Mount a tmpfs for each entry once the child is in its root filesystem
+    ///
+    /// Needs a mount namespace. The private `/tmp` of
+    /// [`NamespaceConfig::private_tmp`] is mounted first.
+    #[must_use]
+    pub fn with_tmpfs(mut self, mounts: Vec<TmpfsMount>) -> Self {
+        self.tmpfs = mounts;
+        self
+    }
+
+    /// Set the child's `oom_score_adj`, written right after it joins the
+    /// cgroup
+    ///
+    /// Lowering it below the caller's value needs `CAP_SYS_RESOURCE`.
+    #[must_use]
+    pub const fn with_oom_score_adj(mut self, adj: OomScoreAdj) -> Self {
+        self.oom_score_adj = Some(adj);
+        self
+    }
+
+    /// Set the child's scheduling policy and nice value, applied with its
+    /// resource limits
+    ///
+    /// Real-time policies and lowering the nice value need `CAP_SYS_NICE`
+    /// unless `RLIMIT_RTPRIO` or `RLIMIT_NICE` allow them.
+    #[must_use]
+    pub const fn with_scheduling(mut self, scheduling: Scheduling) -> Self {
+        self.scheduling = Some(scheduling);
+        self
+    }
+
+    /// Run `hooks` in the parent once the child has created its namespaces
+    /// and mounts, holding the child back from exec until they are done
+    ///
+    /// Each hook gets `state` with the child's PID. If one fails the child
+    /// exits without running the program.
+    #[must_use]
+    pub fn with_prestart_hooks(mut self, hooks: Vec<Hook>, state: HookState) -> Self {
+        self.prestart = hooks;
+        self.hook_state = Some(state);
+        self
+    }
+
+    /// Map user and group IDs of the new user namespace to the host
+    ///
+    /// The maps are checked against `/etc/subuid` and `/etc/subgid` before
+    /// the fork, see [`validate_id_maps`](crate::idmap::validate_id_maps),
+    /// and written once the namespace exists, before the child goes on.
+    #[must_use]
+    pub fn with_id_maps(mut self, uid_maps: Vec<IdMap>, gid_maps: Vec<IdMap>) -> Self {
+        self.uid_maps = uid_maps;
+        self.gid_maps = gid_maps;
+        self
+    }
+
+    /// Limit how many bytes of stdout and of stderr are kept in memory
+    ///
+    /// Both streams are read at the same time, and output past the limit is
+    /// read and discarded, so the child never blocks on a full pipe;
+    /// [`ExecutionResult::truncated`] is set then. Only
+    /// captured output is limited, not a terminal or detached log files.
+    #[must_use]
+    pub const fn with_max_capture(mut self, bytes: usize) -> Self {
+        self.max_capture = bytes;
+        self
+    }
+
+    /// Run the child on a new pty wired to the caller's terminal
+    ///
+    /// Without this the child's stdin is `/dev/null` and its output is
+    /// captured into the [`ExecutionResult`], unless [`Stdio::Inherit`] is
+    /// set.
+    #[must_use]
+    pub const fn with_tty(mut self, tty: bool) -> Self {
+        self.tty = tty;
+        self
+    }
+
+    /// Capture the child's output or let it inherit the caller's streams
+    ///
+    /// Ignored with a terminal, which always passes output straight through.
+    #[must_use]
+    pub const fn with_stdio(mut self, stdio: Stdio) -> Self {
+        self.stdio = stdio;
+        self
+    }
+
+    /// Send the child's stderr to where its stdout goes, like `2>&1`
+    ///
+    /// Both share one pipe, so their writes stay in the order they were
+    /// made, and [`ExecutionResult::stdout`] holds all of it while `stderr`
+    /// stays empty. A detached child writes both to its stdout log.
+    #[must_use]
+    pub const fn with_merged_stderr(mut self, merge: bool) -> Self {
+        self.merge_stderr = merge;
+        self
+    }
+
+    /// Stop the command once it has run for `timeout`
+    ///
+    /// The child gets a process group of its own, which is signalled as
+    /// [`with_termination`](Self::with_termination) says, so whatever it
+    /// started is stopped with it. If the caller's group is in the
+    /// foreground of its terminal, the child's group takes its place until
+    /// the child exits, so Ctrl-C reaches the child rather than the caller.
+    /// Not applied to [`spawn_detached`](Self::spawn_detached).
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// How to stop a command that runs past its timeout
+    ///
+    /// Defaults to [`TerminationConfig::default`]: `SIGTERM`, then `SIGKILL`
+    /// after ten seconds.
+    #[must_use]
+    pub const fn with_termination(mut self, termination: TerminationConfig) -> Self {
+        self.termination = termination;
+        self
+    }
+
+    /// Put a minimal init at PID 1 of the new PID namespace
+    ///
+    /// The init forwards signals to the program, which runs as PID 2, and
+    /// reaps orphans so no zombies pile up. The exit code is the program's.
+    /// Needs the PID namespace to be enabled.
+    #[must_use]
+    pub const fn with_init(mut self, init: bool) -> Self {
+        self.init = init;
+        self
     }
 
     /// Execute a program in the isolated namespace
@@ -42,8 +466,14 @@ impl NamespaceExecutor {
     /// 3. In child: Setup namespaces and execute program
     /// 4. In parent: Read output and wait for completion
     ///
+    /// Without namespaces and the settings only the forked child applies,
+    /// the program is started with [`std::process::Command`] instead; see
+    /// [`Self::runs_as_command`].
+    ///
     /// # Errors
-    /// Returns error if execution fails
+    /// Returns error if execution fails, including when the child fails to
+    /// set itself up and exits before exec
+    #[tracing::instrument(skip(self, args), fields(program = %program))]
     pub fn execute(&self, program: &str, args: &[String]) -> Result<ExecutionResult> {
         tracing::info!(
             program = %program,
@@ -51,19 +481,131 @@ impl NamespaceExecutor {
             "Executing in isolated namespace"
         );
 
-        // Create pipes for stdout and stderr using raw pipe() call
+        check_command(program, args)?;
+        self.check_before_fork()?;
+
+        let result = if self.tty {
+            self.execute_tty(program, args)
+        } else if self.runs_as_command() {
+            self.execute_command(program, args)
+        } else if self.stdio == Stdio::Inherit {
+            self.execute_inherited(program, args)
+        } else {
+            self.execute_piped(program, args)
+        };
+
+        if let Some(ref rootfs) = self.rootfs {
+            rootfs.cleanup();
+        }
+        result
+    }
+
+    /// Report problems the child could only hit after forking
+    fn check_before_fork(&self) -> Result<()> {
+        // Unprivileged hard limit raises
+        for rlimit in &self.rlimits {
+            rlimit.check()?;
+        }
+
+        if self.init && !self.config.pid {
+            return Err(Error::InvalidConfig {
+                message: "An init needs a PID namespace".to_string(),
+            });
+        }
+
+        if let Some(ref rootfs) = self.rootfs {
+            if !self.config.mount {
+                return Err(Error::InvalidConfig {
+                    message: "A root filesystem needs a mount namespace".to_string(),
+                });
+            }
+            rootfs.validate()?;
+        }
+
+        if !self.uid_maps.is_empty() || !self.gid_maps.is_empty() {
+            if !self.config.user {
+                return Err(Error::InvalidConfig {
+                    message: "ID maps need a user namespace".to_string(),
+                });
+            }
+            idmap::validate_id_maps(IdKind::Uid, &self.uid_maps)?;
+            idmap::validate_id_maps(IdKind::Gid, &self.gid_maps)?;
+        }
+
+        if !self.config.time_offsets.is_zero() && !self.config.time {
+            return Err(Error::InvalidConfig {
+                message: "Time offsets need a time namespace".to_string(),
+            });
+        }
+
+        let mounts = self.tmpfs_mounts();
+        if !mounts.is_empty() && !self.config.mount {
+            return Err(Error::InvalidConfig {
+                message: "A tmpfs mount needs a mount namespace".to_string(),
+            });
+        }
+        for (i, mount) in mounts.iter().enumerate() {
+            mount.validate()?;
+            if mounts[..i].iter().any(|other| other.target == mount.target) {
+                return Err(Error::InvalidConfig {
+                    message: format!("Duplicate tmpfs mount on {}", mount.target.display()),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The private `/tmp`, if any, followed by the other tmpfs mounts
+    fn tmpfs_mounts(&self) -> Vec<TmpfsMount> {
+        self.config
+            .private_tmp
+            .map(TmpfsMount::private_tmp)
+            .into_iter()
+            .chain(self.tmpfs.iter().cloned())
+            .collect()
+    }
+
+    /// Run the program with its output captured through pipes
+    fn execute_piped(&self, program: &str, args: &[String]) -> Result<ExecutionResult> {
+        // Create pipes for stdout and stderr using raw pipe() call; merged
+        // output shares the stdout pipe
         let stdout_pipe = self.create_pipe()?;
-        let stderr_pipe = self.create_pipe()?;
+        let stderr_pipe = if self.merge_stderr {
+            None
+        } else {
+            Some(self.create_pipe()?)
+        };
+        let (parent_gate, child_gate) = self.hook_gate()?.unzip();
+        let (report_read, report_write) = report_pipe()?;
 
         // Fork process
         match unsafe { fork() } {
             Ok(ForkResult::Parent { child }) => {
+                drop(child_gate);
+                drop(report_write);
                 // Parent process
-                self.handle_parent(child, stdout_pipe, stderr_pipe)
+                let watchdog = self.watch(child);
+                let prepared = self.prepare_child(parent_gate, child);
+                let result = self.handle_parent(child, watchdog, stdout_pipe, stderr_pipe);
+                prepared.and_then(|isolated| {
+                    read_report(report_read)?;
+                    result.map(|result| ExecutionResult { isolated, ..result })
+                })
             }
             Ok(ForkResult::Child) => {
+                drop(parent_gate);
+                drop(report_read);
+                self.enter_own_group();
                 // Child process - this never returns
-                self.handle_child(program, args, stdout_pipe, stderr_pipe);
+                self.handle_child(
+                    program,
+                    args,
+                    stdout_pipe,
+                    stderr_pipe,
+                    child_gate,
+                    report_write,
+                );
             }
             Err(e) => Err(Error::Namespace {
                 message: format!("Failed to fork: {}", e),
@@ -71,159 +613,928 @@ impl NamespaceExecutor {
         }
     }
 
-    /// Create a pipe for IPC using libc directly
-    fn create_pipe(&self) -> Result<[i32; 2]> {
-        let mut fds = [0i32; 2];
-        unsafe {
-            if libc::pipe(fds.as_mut_ptr()) == -1 {
-                return Err(Error::Namespace {
-                    message: format!("Failed to create pipe: {}", std::io::Error::last_os_error()),
-                });
+    /// Run the program on the caller's own stdin, stdout and stderr
+    fn execute_inherited(&self, program: &str, args: &[String]) -> Result<ExecutionResult> {
+        let (parent_gate, child_gate) = self.hook_gate()?.unzip();
+        let (report_read, report_write) = report_pipe()?;
+
+        match unsafe { fork() } {
+            Ok(ForkResult::Parent { child }) => {
+                drop(child_gate);
+                drop(report_write);
+                let watchdog = self.watch(child);
+
+                let prepared = self.prepare_child(parent_gate, child);
+                let (exit_code, stopped) = self.wait_watched(child, watchdog)?;
+                let isolated = prepared?;
+                read_report(report_read)?;
+
+                Ok(ExecutionResult {
+                    exit_code,
+                    stdout: Vec::new(),
+                    stderr: Vec::new(),
+                    truncated: false,
+                    stopped,
+                    isolated,
+                })
+            }
+            Ok(ForkResult::Child) => {
+                drop(parent_gate);
+                drop(report_read);
+                self.enter_own_group();
+                if self.merge_stderr && unsafe { libc::dup2(1, 2) } == -1 {
+                    eprintln!("Failed to redirect stderr");
+                    unsafe {
+                        libc::_exit(1);
+                    }
+                }
+                self.setup_and_exec(program, args, child_gate, report_write);
             }
+            Err(e) => Err(Error::Namespace {
+                message: format!("Failed to fork: {e}"),
+            }),
         }
-        Ok(fds)
     }
 
-    /// Handle parent process after fork
-    fn handle_parent(
-        &self,
-        child: Pid,
-        stdout_pipe: [i32; 2],
-        stderr_pipe: [i32; 2],
-    ) -> Result<ExecutionResult> {
-        // Close write ends in parent
-        unsafe {
-            libc::close(stdout_pipe[1]);
-            libc::close(stderr_pipe[1]);
+    /// Whether [`Self::execute`] can leave fork and exec to
+    /// [`std::process::Command`]
+    ///
+    /// That needs no namespaces, prestart hooks or terminal, and none of
+    /// the resource limits, scheduling, OOM score or credentials that the
+    /// forked child sets up itself. Joining the cgroup, the working
+    /// directory and the security settings are then applied in a
+    /// `pre_exec` closure.
+    fn runs_as_command(&self) -> bool {
+        !self.uses_gate()
+            && !self.tty
+            && self.rlimits.is_empty()
+            && self.scheduling.is_none()
+            && self.oom_score_adj.is_none()
+            && self.user.is_none()
+            && self.groups.is_empty()
+            && self.umask.is_none()
+    }
+
+    /// Run the program with [`std::process::Command`]
+    ///
+    /// Behaves like the forked paths: the program is looked up in the same
+    /// `PATH`, a failed setup step is an error, and a program that cannot be
+    /// executed exits with code 127.
+    fn execute_command(&self, program: &str, args: &[String]) -> Result<ExecutionResult> {
+        use std::os::unix::process::CommandExt;
+        use std::process::Command;
+
+        let env = self.environment();
+        let candidates = program_candidates(program, Self::search_path(env.as_deref()));
+        let candidates: Vec<&Path> = candidates
+            .iter()
+            .map(|candidate| Path::new(OsStr::from_bytes(candidate.as_bytes())))
+            .collect();
+        // The first candidate that can be executed; failing that, the first
+        // one, so exec reports why not
+        let path = candidates
+            .iter()
+            .find(|path| is_executable(path))
+            .or_else(|| candidates.first())
+            .copied()
+            .unwrap_or_else(|| Path::new(program));
+
+        let mut command = Command::new(path);
+        command.arg0(program).args(args);
+        if let Some(ref env) = env {
+            command
+                .env_clear()
+                .envs(env.iter().filter_map(|var| var.split_once('=')));
+        }
+        if self.timeout.is_some() {
+            command.process_group(0);
         }
 
-        // Read from pipes
-        let stdout = self.read_from_fd(stdout_pipe[0])?;
-        let stderr = self.read_from_fd(stderr_pipe[0])?;
+        let merged = self.command_stdio(&mut command)?;
 
-        // Close read ends
+        let (report_read, report_write) = report_pipe()?;
+        let cgroup = self.cgroup.clone();
+        let cwd = self.cwd.clone();
+        let security = self.security.clone();
+        let mut report = report_write;
+        // Only the child runs this, between fork and exec
         unsafe {
-            libc::close(stdout_pipe[0]);
-            libc::close(stderr_pipe[0]);
+            command.pre_exec(move || {
+                use std::io::Write;
+
+                let failure = command_setup(cgroup.as_deref(), cwd.as_deref(), security.as_ref());
+                failure.map_or(Ok(()), |message| {
+                    let _ = report.write_all(message.as_bytes());
+                    Err(std::io::Error::from_raw_os_error(libc::ECANCELED))
+                })
+            });
         }
 
-        // Wait for child
-        let exit_code = self.wait_for_child(child)?;
+        let spawned = command.spawn();
+        // Close the parent's copies of the report and output pipes
+        drop(command);
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(e) => {
+                read_report(report_read)?;
+                return Ok(self.exec_failure(program, &e));
+            }
+        };
+        read_report(report_read)?;
+
+        let pid = Pid::from_raw(i32::try_from(child.id()).map_err(|_| Error::Namespace {
+            message: format!("Invalid child PID {}", child.id()),
+        })?);
+        let watchdog = self.watch(pid);
+
+        let (stdout, stderr, truncated) = match (merged, child.stdout.take(), child.stderr.take()) {
+            (Some(merged), ..) => self.capture(OwnedFd::from(merged).into_raw_fd(), None)?,
+            (None, Some(stdout), Some(stderr)) => self.capture(
+                OwnedFd::from(stdout).into_raw_fd(),
+                Some(OwnedFd::from(stderr).into_raw_fd()),
+            )?,
+            _ => (Vec::new(), Vec::new(), false),
+        };
+
+        let (exit_code, stopped) = self.wait_watched(pid, watchdog)?;
 
         Ok(ExecutionResult {
             exit_code,
             stdout,
             stderr,
+            truncated,
+            stopped,
+            isolated: Vec::new(),
         })
     }
 
-    /// Handle child process after fork
-    fn handle_child(
+    /// Point the command's standard streams where [`Self::stdio`] says
+    ///
+    /// Returns the read end of the pipe stdout and stderr share when they
+    /// are captured merged, as with `2>&1`.
+    fn command_stdio(
         &self,
-        program: &str,
-        args: &[String],
-        stdout_pipe: [i32; 2],
-        stderr_pipe: [i32; 2],
-    ) -> ! {
-        // Close read ends in child
-        unsafe {
-            libc::close(stdout_pipe[0]);
-            libc::close(stderr_pipe[0]);
-        }
+        command: &mut std::process::Command,
+    ) -> Result<Option<std::io::PipeReader>> {
+        use std::process::Stdio as ChildStdio;
 
-        // Redirect stdout and stderr using libc directly
-        unsafe {
-            if libc::dup2(stdout_pipe[1], 1) == -1 {
-                eprintln!("Failed to redirect stdout");
-                libc::_exit(1);
+        let pipe_error = |e: std::io::Error| Error::Namespace {
+            message: format!("Failed to create pipe: {e}"),
+        };
+        match (self.stdio, self.merge_stderr) {
+            (Stdio::Capture, false) => {
+                command
+                    .stdin(ChildStdio::null())
+                    .stdout(ChildStdio::piped())
+                    .stderr(ChildStdio::piped());
+                Ok(None)
             }
-
-            if libc::dup2(stderr_pipe[1], 2) == -1 {
-                eprintln!("Failed to redirect stderr");
-                libc::_exit(1);
+            (Stdio::Capture, true) => {
+                let (read, write) = std::io::pipe().map_err(pipe_error)?;
+                let stderr = write.try_clone().map_err(pipe_error)?;
+                command
+                    .stdin(ChildStdio::null())
+                    .stdout(write)
+                    .stderr(stderr);
+                Ok(Some(read))
             }
-
-            // Close original file descriptors
-            libc::close(stdout_pipe[1]);
-            libc::close(stderr_pipe[1]);
+            (Stdio::Inherit, true) => {
+                let stdout = std::io::stdout()
+                    .as_fd()
+                    .try_clone_to_owned()
+                    .map_err(|e| Error::Namespace {
+                        message: format!("Failed to redirect stderr: {e}"),
+                    })?;
+                command.stderr(stdout);
+                Ok(None)
+            }
+            (Stdio::Inherit, false) => Ok(None),
         }
+    }
 
-        // Setup namespaces
-        let mut manager = NamespaceManager::new(self.config.clone());
-        if let Err(e) = manager.create() {
-            eprintln!("Failed to create namespaces: {}", e);
-            unsafe {
-                libc::_exit(1);
+    /// What the forked paths report when exec fails: the error on the
+    /// program's stderr and exit code 127
+    fn exec_failure(&self, program: &str, error: &std::io::Error) -> ExecutionResult {
+        let message = format!("Failed to execute {program}: {error}\n").into_bytes();
+        let (stdout, stderr) = match (self.stdio, self.merge_stderr) {
+            (Stdio::Capture, true) => (message, Vec::new()),
+            (Stdio::Capture, false) => (Vec::new(), message),
+            (Stdio::Inherit, _) => {
+                eprint!("{}", String::from_utf8_lossy(&message));
+                (Vec::new(), Vec::new())
             }
+        };
+        ExecutionResult {
+            exit_code: 127,
+            stdout,
+            stderr,
+            truncated: false,
+            stopped: None,
+            isolated: Vec::new(),
         }
-
-        // Execute program
-        self.execute_child(program, args);
     }
 
-    /// Read all data from a file descriptor
-    fn read_from_fd(&self, fd: i32) -> Result<Vec<u8>> {
-        use std::io::Read;
+    /// Start the program in the background and return its PID
+    ///
+    /// The program is double-forked into a session of its own, so it keeps
+    /// running after the caller exits. Its stdin is `/dev/null` and its
+    /// stdout and stderr are appended to the given files. Joining the cgroup
+    /// and creating namespaces happen in the background process itself.
+    ///
+    /// # Errors
+    /// Returns error if a log file cannot be opened, the fork fails or the
+    /// background process fails to set itself up before exec
+    #[tracing::instrument(skip(self, args, stdout, stderr), fields(program = %program))]
+    pub fn spawn_detached(
+        &self,
+        program: &str,
+        args: &[String],
+        stdout: &Path,
+        stderr: &Path,
+    ) -> Result<i32> {
+        self.start_detached(
+            program,
+            args,
+            stdout,
+            stderr,
+            None::<(fn(i32), fn(DetachedExit))>,
+        )
+    }
 
-        let mut file = unsafe { std::fs::File::from_raw_fd(fd) };
-        let mut buffer = Vec::new();
+    /// [`Self::spawn_detached`], calling `on_start` with the program's PID
+    /// and `on_exit` once the program ends
+    ///
+    /// A supervisor process stays behind as the program's parent and waits
+    /// for it. It keeps nothing of the caller's open but the log files, so
+    /// the caller may exit first. Both callbacks run in that forked
+    /// process, so they can report through files, not the caller's memory.
+    /// `on_start` has returned before this does, and `on_exit` never runs
+    /// before it.
+    ///
+    /// # Errors
+    /// Returns error if a log file cannot be opened, the fork fails or the
+    /// background process fails to set itself up before exec
+    #[tracing::instrument(skip(self, args, stdout, stderr, on_start, on_exit), fields(program = %program))]
+    pub fn spawn_supervised(
+        &self,
+        program: &str,
+        args: &[String],
+        stdout: &Path,
+        stderr: &Path,
+        on_start: impl FnOnce(i32),
+        on_exit: impl FnOnce(DetachedExit),
+    ) -> Result<i32> {
+        self.start_detached(program, args, stdout, stderr, Some((on_start, on_exit)))
+    }
 
-        file.read_to_end(&mut buffer)
-            .map_err(|e| Error::Namespace {
-                message: format!("Failed to read from pipe: {}", e),
-            })?;
+    /// Fork the intermediate process of a detached start and return the
+    /// program's PID
+    fn start_detached(
+        &self,
+        program: &str,
+        args: &[String],
+        stdout: &Path,
+        stderr: &Path,
+        supervisor: Option<(impl FnOnce(i32), impl FnOnce(DetachedExit))>,
+    ) -> Result<i32> {
+        check_command(program, args)?;
+        self.check_before_fork()?;
 
-        Ok(buffer)
-    }
+        let stdout = open_log(stdout)?;
+        let stderr = open_log(stderr)?;
+        // The intermediate child reports the final PID through this pipe
+        let pid_pipe = self.create_pipe()?;
+        let (parent_gate, child_gate) = self.hook_gate()?.unzip();
+        let (report_read, report_write) = report_pipe()?;
 
-    /// Execute the child program (does not return)
-    fn execute_child(&self, program: &str, args: &[String]) -> ! {
-        // Convert program and args to C strings
-        let program_c = match CString::new(program) {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("Invalid program path: {}", e);
+        match unsafe { fork() } {
+            Ok(ForkResult::Parent { child }) => {
+                drop(child_gate);
+                drop(report_write);
                 unsafe {
-                    libc::_exit(1);
+                    libc::close(pid_pipe[1]);
                 }
-            }
-        };
+                let reported = self.read_from_fd(pid_pipe[0])?;
+                let _ = waitpid_retrying(child);
 
-        let mut args_c: Vec<CString> = Vec::new();
-        args_c.push(program_c.clone()); // First arg is program name
+                let pid = <[u8; 4]>::try_from(reported.as_slice())
+                    .map(i32::from_ne_bytes)
+                    .map_err(|_| Error::Namespace {
+                        message: "Detached process failed to start".to_string(),
+                    })?;
+                self.prepare_child(parent_gate, Pid::from_raw(pid))?;
+                // Returns once the program runs, so its setup errors are ours
+                read_report(report_read)?;
 
-        for arg in args {
-            match CString::new(arg.as_str()) {
-                Ok(s) => args_c.push(s),
-                Err(e) => {
-                    eprintln!("Invalid argument: {}", e);
-                    unsafe {
-                        libc::_exit(1);
-                    }
+                tracing::info!(pid, "Started detached process");
+                Ok(pid)
+            }
+            Ok(ForkResult::Child) => {
+                drop(parent_gate);
+                drop(report_read);
+                unsafe {
+                    libc::close(pid_pipe[0]);
                 }
+                self.detach_child(
+                    program,
+                    args,
+                    pid_pipe[1],
+                    &stdout,
+                    &stderr,
+                    child_gate,
+                    report_write,
+                    supervisor,
+                );
             }
+            Err(e) => Err(Error::Namespace {
+                message: format!("Failed to fork: {e}"),
+            }),
         }
-
-        // Convert to pointers
-        let mut args_ptr: Vec<*const libc::c_char> = args_c.iter().map(|s| s.as_ptr()).collect();
-        args_ptr.push(std::ptr::null()); // Null-terminated array
-
-        // Execute
-        unsafe {
-            libc::execvp(program_c.as_ptr(), args_ptr.as_ptr());
-        }
-
-        // If we get here, exec failed
-        let error = std::io::Error::last_os_error();
-        eprintln!("Failed to execute {}: {}", program, error);
-        unsafe {
-            libc::_exit(127);
-        } // Command not found
     }
 
-    /// Wait for child process and get exit code
-    fn wait_for_child(&self, child: Pid) -> Result<i32> {
-        match waitpid(child, None) {
+    /// Intermediate child of [`Self::spawn_detached`]: start a new session,
+    /// fork the real process, report its PID and exit, or with the
+    /// supervisor callbacks stay behind as a supervisor that waits for the
+    /// program
+    #[allow(clippy::too_many_arguments)]
+    fn detach_child(
+        &self,
+        program: &str,
+        args: &[String],
+        pid_fd: i32,
+        stdout: &File,
+        stderr: &File,
+        gate: Option<ChildGate>,
+        report: File,
+        supervisor: Option<(impl FnOnce(i32), impl FnOnce(DetachedExit))>,
+    ) -> ! {
+        if setsid().is_err() {
+            unsafe {
+                libc::_exit(1);
+            }
+        }
+        // The caller waits for this process; a supervisor has to be one
+        // further down, which then belongs to init
+        if supervisor.is_some() {
+            match unsafe { fork() } {
+                Ok(ForkResult::Child) => {}
+                Ok(ForkResult::Parent { .. }) => unsafe { libc::_exit(0) },
+                Err(_) => unsafe { libc::_exit(1) },
+            }
+        }
+
+        match unsafe { fork() } {
+            Ok(ForkResult::Parent { child }) => {
+                // Before the caller learns the PID, so whatever `on_start`
+                // records is in place once the caller returns
+                let on_exit = supervisor.map(|(on_start, on_exit)| {
+                    on_start(child.as_raw());
+                    on_exit
+                });
+                let pid = child.as_raw().to_ne_bytes();
+                unsafe {
+                    libc::write(pid_fd, pid.as_ptr().cast(), pid.len());
+                }
+                let Some(on_exit) = on_exit else {
+                    unsafe { libc::_exit(0) }
+                };
+
+                // The caller reads these pipes and maybe our stdio to the
+                // end, which must not wait for the program to exit
+                drop(gate);
+                drop(report);
+                unsafe {
+                    libc::close(pid_fd);
+                    let devnull = libc::open(c"/dev/null".as_ptr(), libc::O_RDWR);
+                    if devnull != -1 {
+                        for fd in 0..=2 {
+                            libc::dup2(devnull, fd);
+                        }
+                        if devnull > 2 {
+                            libc::close(devnull);
+                        }
+                    }
+                }
+                on_exit(wait_detached(child));
+                unsafe { libc::_exit(0) }
+            }
+            Ok(ForkResult::Child) => {
+                unsafe {
+                    libc::close(pid_fd);
+
+                    let devnull = libc::open(c"/dev/null".as_ptr(), libc::O_RDONLY);
+                    if devnull == -1
+                        || libc::dup2(devnull, 0) == -1
+                        || libc::dup2(stdout.as_raw_fd(), 1) == -1
+                        || libc::dup2(
+                            if self.merge_stderr {
+                                stdout.as_raw_fd()
+                            } else {
+                                stderr.as_raw_fd()
+                            },
+                            2,
+                        ) == -1
+                    {
+                        libc::_exit(1);
+                    }
+                    if devnull > 2 {
+                        libc::close(devnull);
+                    }
+                }
+                self.setup_and_exec(program, args, gate, report);
+            }
+            Err(_) => unsafe {
+                libc::_exit(1);
+            },
+        }
+    }
+
+    /// Whether the child waits for the parent once its namespaces exist,
+    /// to have them checked and the prestart hooks run
+    fn uses_gate(&self) -> bool {
+        self.config.has_any() || !self.prestart.is_empty()
+    }
+
+    /// Pipes holding the child back for the parent, if it needs to wait
+    fn hook_gate(&self) -> Result<Option<(ParentGate, ChildGate)>> {
+        if self.uses_gate() {
+            hook::gate().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Once `child` is ready, check its namespaces and run the prestart
+    /// hooks, then let it exec
+    ///
+    /// Returns the namespaces the child was found to be in apart from the
+    /// caller.
+    fn prepare_child(&self, gate: Option<ParentGate>, child: Pid) -> Result<Vec<NamespaceKind>> {
+        let Some(gate) = gate else {
+            return Ok(Vec::new());
+        };
+        gate.release_after(|| {
+            idmap::write_id_maps(IdKind::Uid, child.as_raw(), &self.uid_maps)?;
+            idmap::write_id_maps(IdKind::Gid, child.as_raw(), &self.gid_maps)?;
+            let isolated = self.isolated_namespaces(child);
+            if let Some(ref state) = self.hook_state {
+                hook::run_hooks(&self.prestart, &state.created(child.as_raw()))?;
+            }
+            Ok(isolated)
+        })
+    }
+
+    /// Compare the child's namespaces with the caller's
+    ///
+    /// A requested namespace the child still shares with the caller was not
+    /// created after all, which is worth a warning. Nothing is isolated when
+    /// `/proc` cannot be read.
+    fn isolated_namespaces(&self, child: Pid) -> Vec<NamespaceKind> {
+        let read = || -> Result<(NamespaceInfo, NamespaceInfo)> {
+            let own = NamespaceManager::namespaces_for_pid(std::process::id())?;
+            let raw = u32::try_from(child.as_raw()).unwrap_or_default();
+            Ok((own, NamespaceManager::namespaces_for_children(raw)?))
+        };
+        let (own, theirs) = match read() {
+            Ok(namespaces) => namespaces,
+            Err(e) => {
+                tracing::warn!(error = %e, "Could not check the container's namespaces");
+                return Vec::new();
+            }
+        };
+
+        let isolated: Vec<NamespaceKind> = NamespaceKind::ALL
+            .into_iter()
+            .filter(|&kind| theirs.get(kind).is_some() && !theirs.shares_namespace(&own, kind))
+            .collect();
+        for name in self.config.enabled_namespaces() {
+            if !isolated.iter().any(|kind| kind.name() == name) {
+                tracing::warn!(
+                    namespace = name,
+                    "Requested namespace is shared with the host"
+                );
+            }
+        }
+        isolated
+    }
+
+    /// Create a pipe for IPC using libc directly
+    fn create_pipe(&self) -> Result<[i32; 2]> {
+        let mut fds = [0i32; 2];
+        unsafe {
+            if libc::pipe(fds.as_mut_ptr()) == -1 {
+                return Err(Error::Namespace {
+                    message: format!("Failed to create pipe: {}", std::io::Error::last_os_error()),
+                });
+            }
+        }
+        Ok(fds)
+    }
+
+    /// Handle parent process after fork
+    fn handle_parent(
+        &self,
+        child: Pid,
+        watchdog: Option<Watchdog>,
+        stdout_pipe: [i32; 2],
+        stderr_pipe: Option<[i32; 2]>,
+    ) -> Result<ExecutionResult> {
+        // Close write ends in parent
+        unsafe {
+            libc::close(stdout_pipe[1]);
+            if let Some(stderr_pipe) = stderr_pipe {
+                libc::close(stderr_pipe[1]);
+            }
+        }
+
+        let (stdout, stderr, truncated) =
+            self.capture(stdout_pipe[0], stderr_pipe.map(|pipe| pipe[0]))?;
+
+        // Wait for child
+        let (exit_code, stopped) = self.wait_watched(child, watchdog)?;
+
+        Ok(ExecutionResult {
+            exit_code,
+            stdout,
+            stderr,
+            truncated,
+            stopped,
+            isolated: Vec::new(),
+        })
+    }
+
+    /// Read the child's stdout and stderr pipes, which also closes them
+    ///
+    /// Returns both outputs and whether either hit the capture limit.
+    /// stderr is read on a thread of its own: reading one pipe to the end
+    /// before the other would leave a child that fills the other one
+    /// blocked forever.
+    fn capture(&self, stdout_fd: i32, stderr_fd: Option<i32>) -> Result<(Vec<u8>, Vec<u8>, bool)> {
+        let limit = self.max_capture;
+        let (stdout, stderr) = std::thread::scope(|scope| {
+            let stderr = stderr_fd.map(|fd| scope.spawn(move || capture_fd(fd, limit)));
+            let stdout = capture_fd(stdout_fd, limit);
+            let stderr = stderr.map_or_else(
+                || Ok((Vec::new(), false)),
+                |reader| {
+                    reader
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                },
+            );
+            (stdout, stderr)
+        });
+        let (stdout, stdout_truncated) = stdout?;
+        let (stderr, stderr_truncated) = stderr?;
+        let truncated = stdout_truncated || stderr_truncated;
+        if truncated {
+            tracing::warn!(
+                max_bytes = self.max_capture,
+                "Output exceeded the capture limit and was truncated"
+            );
+        }
+        Ok((stdout, stderr, truncated))
+    }
+
+    /// Handle child process after fork
+    fn handle_child(
+        &self,
+        program: &str,
+        args: &[String],
+        stdout_pipe: [i32; 2],
+        stderr_pipe: Option<[i32; 2]>,
+        gate: Option<ChildGate>,
+        report: File,
+    ) -> ! {
+        // Close read ends in child
+        unsafe {
+            libc::close(stdout_pipe[0]);
+            if let Some(stderr_pipe) = stderr_pipe {
+                libc::close(stderr_pipe[0]);
+            }
+        }
+        let stderr_fd = stderr_pipe.map_or(stdout_pipe[1], |stderr_pipe| stderr_pipe[1]);
+
+        // Redirect stdout and stderr using libc directly
+        unsafe {
+            if libc::dup2(stdout_pipe[1], 1) == -1 {
+                eprintln!("Failed to redirect stdout");
+                libc::_exit(1);
+            }
+
+            if libc::dup2(stderr_fd, 2) == -1 {
+                eprintln!("Failed to redirect stderr");
+                libc::_exit(1);
+            }
+
+            // Close original file descriptors
+            libc::close(stdout_pipe[1]);
+            if stderr_fd != stdout_pipe[1] {
+                libc::close(stderr_fd);
+            }
+
+            // Never share the caller's stdin with the container
+            let devnull = libc::open(c"/dev/null".as_ptr(), libc::O_RDONLY);
+            if devnull == -1 || libc::dup2(devnull, 0) == -1 {
+                eprintln!("Failed to redirect stdin from /dev/null");
+                libc::_exit(1);
+            }
+            if devnull != 0 {
+                libc::close(devnull);
+            }
+        }
+
+        self.setup_and_exec(program, args, gate, report);
+    }
+
+    /// Run the program on a pty connected to the caller's terminal
+    ///
+    /// Output goes straight to the terminal, so the returned result has
+    /// empty `stdout` and `stderr`.
+    fn execute_tty(&self, program: &str, args: &[String]) -> Result<ExecutionResult> {
+        let pty = tty::open_pty()?;
+        let (parent_gate, child_gate) = self.hook_gate()?.unzip();
+        let (report_read, report_write) = report_pipe()?;
+
+        match unsafe { fork() } {
+            Ok(ForkResult::Parent { child }) => {
+                drop(child_gate);
+                drop(report_write);
+                // The master sees EOF only once every slave fd is closed
+                drop(pty.slave);
+                // The child's new session is its process group
+                let watchdog = self.watch(child);
+
+                let prepared = self.prepare_child(parent_gate, child);
+                if prepared.is_ok()
+                    && let Err(e) = tty::proxy(&pty.master)
+                {
+                    tracing::warn!(error = %e, "Terminal proxy stopped");
+                }
+                let (exit_code, stopped) = self.wait_watched(child, watchdog)?;
+                let isolated = prepared?;
+                read_report(report_read)?;
+
+                Ok(ExecutionResult {
+                    exit_code,
+                    stdout: Vec::new(),
+                    stderr: Vec::new(),
+                    truncated: false,
+                    stopped,
+                    isolated,
+                })
+            }
+            Ok(ForkResult::Child) => {
+                drop(parent_gate);
+                drop(report_read);
+                drop(pty.master);
+                if let Err(e) = tty::attach_slave(pty.slave) {
+                    eprintln!("{e}");
+                    unsafe {
+                        libc::_exit(1);
+                    }
+                }
+                self.setup_and_exec(program, args, child_gate, report_write);
+            }
+            Err(e) => Err(Error::Namespace {
+                message: format!("Failed to fork: {e}"),
+            }),
+        }
+    }
+
+    /// Child-side setup shared by the piped, pty and detached paths, then exec
+    ///
+    /// A failed setup step is written to `report` for the parent to return
+    /// as an error; the child then exits, never running the program in a
+    /// half-configured container. A failed exec is the program's own
+    /// failure, printed to stderr with exit code 127 like a shell does.
+    fn setup_and_exec(
+        &self,
+        program: &str,
+        args: &[String],
+        gate: Option<ChildGate>,
+        mut report: File,
+    ) -> ! {
+        use std::io::Write;
+
+        let failure = self.run_child(&mut Host { gate }, program, args);
+        if !failure.setup || report.write_all(failure.message.as_bytes()).is_err() {
+            eprintln!("{}", failure.message);
+        }
+        unsafe {
+            libc::_exit(failure.exit_code);
+        }
+    }
+
+    /// Prepare the child with `ops` and exec the program
+    ///
+    /// Only returns if a step fails; later steps are skipped. The order is
+    /// fixed:
+    /// 1. Join the cgroup, before namespaces so a new cgroup namespace is
+    ///    rooted at it
+    /// 2. Set the OOM score adjustment, while the host's `/proc` is still
+    ///    mounted
+    /// 3. Create namespaces and start the init, which leaves the rest to
+    ///    the program process, then switch to the root filesystem and mount
+    ///    tmpfs on top of it
+    /// 4. Wait for the parent to check the namespaces and run prestart hooks
+    /// 5. Change the working directory, inside the new root
+    /// 6. Apply resource limits, then the scheduling policy, which
+    ///    `RLIMIT_RTPRIO` may permit
+    /// 7. Apply security settings
+    /// 8. Switch user, groups and umask, once nothing privileged is left
+    /// 9. Exec
+    fn run_child(&self, ops: &mut impl ChildOps, program: &str, args: &[String]) -> ChildFailure {
+        if let Some(ref cgroup) = self.cgroup
+            && let Err(e) = ops.join_cgroup(cgroup)
+        {
+            return ChildFailure::setup(format!(
+                "Failed to join cgroup {}: {}",
+                cgroup.display(),
+                e
+            ));
+        }
+
+        if let Some(adj) = self.oom_score_adj
+            && let Err(e) = ops.set_oom_score_adj(adj)
+        {
+            return ChildFailure::setup(e.to_string());
+        }
+
+        if let Err(e) = ops.create_namespaces(&self.config) {
+            return ChildFailure::setup(format!("Failed to create namespaces: {e}"));
+        }
+
+        if self.init
+            && let Err(e) = ops.start_init()
+        {
+            return ChildFailure::setup(e.to_string());
+        }
+
+        if let Some(ref rootfs) = self.rootfs
+            && let Err(e) = ops.enter_rootfs(rootfs)
+        {
+            return ChildFailure::setup(format!("Failed to switch root filesystem: {e}"));
+        }
+
+        let mounts = self.tmpfs_mounts();
+        if !mounts.is_empty()
+            && let Err(e) = ops.mount_tmpfs(&mounts)
+        {
+            return ChildFailure::setup(e.to_string());
+        }
+
+        if self.uses_gate()
+            && let Err(e) = ops.wait_for_hooks()
+        {
+            return ChildFailure::setup(e.to_string());
+        }
+
+        if let Some(ref cwd) = self.cwd
+            && let Err(e) = ops.change_dir(cwd)
+        {
+            return ChildFailure::setup(format!(
+                "Failed to change directory to {}: {}",
+                cwd.display(),
+                e
+            ));
+        }
+
+        for rlimit in &self.rlimits {
+            if let Err(e) = ops.apply_rlimit(rlimit) {
+                return ChildFailure::setup(e.to_string());
+            }
+        }
+
+        if let Some(scheduling) = self.scheduling
+            && let Err(e) = ops.set_scheduling(scheduling)
+        {
+            return ChildFailure::setup(e.to_string());
+        }
+
+        // Apply security settings late so nothing above is affected
+        if let Some(ref security) = self.security
+            && let Err(e) = ops.apply_security(security)
+        {
+            return ChildFailure::setup(format!("Failed to apply security settings: {e}"));
+        }
+
+        if let Err(e) = ops.switch_user(self.user, &self.groups, self.umask) {
+            return ChildFailure::setup(e.to_string());
+        }
+
+        let command = match exec_args(program, args) {
+            Ok(command) => command,
+            Err(message) => return ChildFailure::setup(message),
+        };
+        let environment = self.environment();
+        let env = environment.as_ref().map(|env| {
+            env.iter()
+                .filter_map(|var| CString::new(var.as_str()).ok())
+                .collect::<Vec<_>>()
+        });
+
+        let candidates = program_candidates(program, Self::search_path(environment.as_deref()));
+        let error = ops.exec(&candidates, &command, env.as_deref());
+        ChildFailure {
+            message: format!("Failed to execute {program}: {error}"),
+            // Command not found
+            exit_code: 127,
+            setup: false,
+        }
+    }
+
+    /// The child's environment, `None` if it inherits the caller's
+    fn environment(&self) -> Option<Vec<String>> {
+        if self.env.is_none() && self.env_keep.is_empty() {
+            return None;
+        }
+        Some(assemble_env(
+            &self.env_keep,
+            std::env::vars_os(),
+            self.env.as_deref().unwrap_or_default(),
+        ))
+    }
+
+    /// `PATH` of the child's environment `env`, or [`Self::DEFAULT_PATH`]
+    ///
+    /// The first entry wins, as with `getenv`.
+    fn search_path(env: Option<&[String]>) -> &str {
+        env.into_iter()
+            .flatten()
+            .find_map(|var| var.strip_prefix("PATH="))
+            .unwrap_or(Self::DEFAULT_PATH)
+    }
+
+    /// Read all data from a file descriptor
+    ///
+    /// `read_to_end` retries reads interrupted by a signal itself.
+    fn read_from_fd(&self, fd: i32) -> Result<Vec<u8>> {
+        use std::io::Read;
+
+        let mut file = unsafe { std::fs::File::from_raw_fd(fd) };
+        let mut buffer = Vec::new();
+
+        file.read_to_end(&mut buffer)
+            .map_err(|e| Error::Namespace {
+                message: format!("Failed to read from pipe: {}", e),
+            })?;
+
+        Ok(buffer)
+    }
+
+    /// Put the child in a process group of its own if it has a timeout
+    ///
+    /// Called on both sides of the fork, so the group exists whichever runs
+    /// first; the parent's call fails harmlessly once the child has done it
+    /// or exec'd.
+    fn own_group(&self, pid: Pid) {
+        if self.timeout.is_some() {
+            let _ = nix::unistd::setpgid(pid, pid);
+        }
+    }
+
+    /// Child side of [`Self::own_group`]
+    fn enter_own_group(&self) {
+        self.own_group(Pid::from_raw(0));
+    }
+
+    /// Start a watchdog for `child` if the command has a timeout
+    ///
+    /// In a group of its own the child would miss what the caller's
+    /// terminal sends, Ctrl-C in particular, so it is also given the
+    /// terminal if the caller has it.
+    fn watch(&self, child: Pid) -> Option<Watchdog> {
+        if !self.tty {
+            self.own_group(child);
+            if self.timeout.is_some() {
+                give_terminal(child);
+            }
+        }
+        self.timeout
+            .map(|timeout| Watchdog::start(child, timeout, self.termination))
+    }
+
+    /// Wait for the child and learn whether its watchdog had to stop it
+    fn wait_watched(
+        &self,
+        child: Pid,
+        watchdog: Option<Watchdog>,
+    ) -> Result<(i32, Option<Termination>)> {
+        let stopped = watchdog.and_then(|watchdog| {
+            // Leave the child a zombie until the watchdog is done with its PID
+            while waitid(Id::Pid(child), WaitPidFlag::WEXITED | WaitPidFlag::WNOWAIT)
+                == Err(Errno::EINTR)
+            {}
+            take_terminal(child);
+            watchdog.finish()
+        });
+        Ok((self.wait_for_child(child)?, stopped))
+    }
+
+    /// Wait for child process and get exit code
+    fn wait_for_child(&self, child: Pid) -> Result<i32> {
+        match waitpid_retrying(child) {
             Ok(WaitStatus::Exited(_, code)) => {
                 tracing::info!(
                     program = "command",
@@ -253,37 +1564,476 @@ impl NamespaceExecutor {
     }
 }
 
-impl std::fmt::Debug for NamespaceExecutor {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("NamespaceExecutor")
-            .field("config", &self.config)
-            .finish()
-    }
+/// Steps the child takes between fork and exec
+///
+/// [`Host`] makes the real system calls. Keeping them behind a trait lets
+/// tests check the sequencing and error handling of
+/// [`NamespaceExecutor::run_child`] without forking or root.
+trait ChildOps {
+    fn join_cgroup(&mut self, cgroup: &Path) -> std::io::Result<()>;
+    fn set_oom_score_adj(&mut self, adj: OomScoreAdj) -> Result<()>;
+    fn create_namespaces(&mut self, config: &NamespaceConfig) -> Result<()>;
+    /// Returns in the program process only
+    fn start_init(&mut self) -> Result<()>;
+    fn enter_rootfs(&mut self, rootfs: &RootfsSpec) -> Result<()>;
+    fn mount_tmpfs(&mut self, mounts: &[TmpfsMount]) -> Result<()>;
+    fn wait_for_hooks(&mut self) -> Result<()>;
+    fn change_dir(&mut self, dir: &Path) -> std::io::Result<()>;
+    fn apply_rlimit(&mut self, rlimit: &Rlimit) -> Result<()>;
+    fn set_scheduling(&mut self, scheduling: Scheduling) -> Result<()>;
+    fn apply_security(&mut self, security: &SecurityManager) -> Result<()>;
+    fn switch_user(&mut self, user: Option<User>, groups: &[u32], umask: Option<u32>)
+    -> Result<()>;
+    /// Replace the process image with the first of `candidates` that can be
+    /// executed, returning only on failure
+    fn exec(
+        &mut self,
+        candidates: &[CString],
+        argv: &[CString],
+        env: Option<&[CString]>,
+    ) -> std::io::Error;
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// The calling process
+struct Host {
+    /// Set when prestart hooks hold the process back before exec
+    gate: Option<ChildGate>,
+}
 
-    #[test]
-    fn test_execution_result_creation() {
-        let result = ExecutionResult {
-            exit_code: 0,
-            stdout: b"hello".to_vec(),
-            stderr: vec![],
-        };
+impl ChildOps for Host {
+    fn join_cgroup(&mut self, cgroup: &Path) -> std::io::Result<()> {
+        // "0" means the writing process
+        std::fs::write(cgroup.join("cgroup.procs"), "0")
+    }
 
-        assert_eq!(result.exit_code, 0);
-        assert_eq!(result.stdout, b"hello");
-        assert!(result.stderr.is_empty());
+    fn set_oom_score_adj(&mut self, adj: OomScoreAdj) -> Result<()> {
+        adj.apply()
     }
 
-    #[test]
-    fn test_execution_result_clone() {
-        let result1 = ExecutionResult {
-            exit_code: 0,
-            stdout: b"test".to_vec(),
+    fn create_namespaces(&mut self, config: &NamespaceConfig) -> Result<()> {
+        NamespaceManager::new(config.clone()).create()
+    }
+
+    fn start_init(&mut self) -> Result<()> {
+        init::start()
+    }
+
+    fn enter_rootfs(&mut self, rootfs: &RootfsSpec) -> Result<()> {
+        rootfs.enter()
+    }
+
+    fn mount_tmpfs(&mut self, mounts: &[TmpfsMount]) -> Result<()> {
+        tmpfs::mount_all(mounts)
+    }
+
+    fn wait_for_hooks(&mut self) -> Result<()> {
+        self.gate.take().map_or(Ok(()), ChildGate::wait)
+    }
+
+    fn change_dir(&mut self, dir: &Path) -> std::io::Result<()> {
+        std::env::set_current_dir(dir)
+    }
+
+    fn apply_rlimit(&mut self, rlimit: &Rlimit) -> Result<()> {
+        rlimit.apply()
+    }
+
+    fn set_scheduling(&mut self, scheduling: Scheduling) -> Result<()> {
+        scheduling.apply()
+    }
+
+    fn apply_security(&mut self, security: &SecurityManager) -> Result<()> {
+        security.apply()
+    }
+
+    fn switch_user(
+        &mut self,
+        user: Option<User>,
+        groups: &[u32],
+        umask: Option<u32>,
+    ) -> Result<()> {
+        user::apply_credentials(user, groups, umask)
+    }
+
+    fn exec(
+        &mut self,
+        candidates: &[CString],
+        argv: &[CString],
+        env: Option<&[CString]>,
+    ) -> std::io::Error {
+        let mut argv_ptr: Vec<*const libc::c_char> = argv.iter().map(|s| s.as_ptr()).collect();
+        argv_ptr.push(std::ptr::null()); // Null-terminated array
+        let env_ptr = env.map(|env| {
+            let mut env_ptr: Vec<*const libc::c_char> = env.iter().map(|s| s.as_ptr()).collect();
+            env_ptr.push(std::ptr::null());
+            env_ptr
+        });
+
+        let exec = |path: &CString, argv_ptr: &[*const libc::c_char]| match &env_ptr {
+            Some(env_ptr) => unsafe {
+                libc::execve(path.as_ptr(), argv_ptr.as_ptr(), env_ptr.as_ptr());
+            },
+            None => unsafe {
+                libc::execv(path.as_ptr(), argv_ptr.as_ptr());
+            },
+        };
+
+        // Like execvp: keep looking past directories that lack the program
+        // or deny access, but report the denial if nothing else turned up
+        let mut error = std::io::Error::from_raw_os_error(libc::ENOENT);
+        for candidate in candidates {
+            exec(candidate, &argv_ptr);
+
+            let last = std::io::Error::last_os_error();
+            match last.raw_os_error() {
+                Some(libc::EACCES) => error = last,
+                Some(libc::ENOENT | libc::ENOTDIR) => {}
+                // Also like execvp: a file without a `#!` line is a shell script
+                Some(libc::ENOEXEC) => {
+                    let shell = c"/bin/sh";
+                    // argv[0] is the program, replaced by the script's path
+                    let mut shell_argv = vec![shell.as_ptr(), candidate.as_ptr()];
+                    shell_argv.extend_from_slice(&argv_ptr[1..]);
+                    exec(&shell.to_owned(), &shell_argv);
+                    return last;
+                }
+                _ => return last,
+            }
+        }
+
+        error
+    }
+}
+
+/// Make the process group of `leader` the foreground group of the caller's
+/// terminal, if the caller's group has it now
+///
+/// The group also gets `SIGCONT`, in case the child was stopped for reading
+/// the terminal before it was handed over.
+fn give_terminal(leader: Pid) {
+    let stdin = std::io::stdin();
+    if tcgetpgrp(&stdin) == Ok(getpgrp()) && tcsetpgrp(&stdin, leader).is_ok() {
+        let _ = killpg(leader, Signal::SIGCONT);
+    }
+}
+
+/// Give the caller's terminal back to the caller if the process group of
+/// `leader` still has it
+///
+/// `SIGTTOU` is blocked meanwhile, as the caller is in the background
+/// until it is done.
+fn take_terminal(leader: Pid) {
+    let stdin = std::io::stdin();
+    if tcgetpgrp(&stdin) != Ok(leader) {
+        return;
+    }
+    let mut ttou = SigSet::empty();
+    ttou.add(Signal::SIGTTOU);
+    let mut old = SigSet::empty();
+    if pthread_sigmask(SigmaskHow::SIG_BLOCK, Some(&ttou), Some(&mut old)).is_ok() {
+        let _ = tcsetpgrp(&stdin, getpgrp());
+        let _ = pthread_sigmask(SigmaskHow::SIG_SETMASK, Some(&old), None);
+    }
+}
+
+/// Why the child gave up before or at exec
+#[derive(Debug)]
+struct ChildFailure {
+    message: String,
+    exit_code: i32,
+    /// Failed before exec, so the program never ran
+    setup: bool,
+}
+
+impl ChildFailure {
+    /// A failed setup step, reported with exit code 1
+    const fn setup(message: String) -> Self {
+        Self {
+            message,
+            exit_code: 1,
+            setup: true,
+        }
+    }
+}
+
+/// Wait for a detached program and learn how it ended
+fn wait_detached(child: Pid) -> DetachedExit {
+    match waitpid_retrying(child) {
+        Ok(WaitStatus::Exited(_, code)) => DetachedExit {
+            pid: child.as_raw(),
+            exit_code: code,
+            signal: None,
+        },
+        Ok(WaitStatus::Signaled(_, signal, _)) => DetachedExit {
+            pid: child.as_raw(),
+            exit_code: 128 + signal as i32,
+            signal: Some(signal),
+        },
+        // Like wait_for_child, anything else is a plain failure
+        _ => DetachedExit {
+            pid: child.as_raw(),
+            exit_code: 1,
+            signal: None,
+        },
+    }
+}
+
+/// Wait for `child` to change state, retrying when a signal interrupts
+///
+/// A handled signal cuts `waitpid` short with `EINTR` while the child is
+/// still running; giving up then would lose its exit status.
+fn waitpid_retrying(child: Pid) -> nix::Result<WaitStatus> {
+    loop {
+        match waitpid(child, None) {
+            Err(Errno::EINTR) => {}
+            status => return status,
+        }
+    }
+}
+
+/// Build an environment from the `keep` variables of `host`, in that order,
+/// followed by the `KEY=VALUE` entries of `overrides`
+///
+/// A kept variable is left out if `overrides` sets it too, or if it is
+/// unset or not UTF-8 on the host.
+fn assemble_env(
+    keep: &[String],
+    host: impl IntoIterator<Item = (OsString, OsString)>,
+    overrides: &[String],
+) -> Vec<String> {
+    let host: Vec<(OsString, OsString)> = host.into_iter().collect();
+    let overridden = |name: &str| {
+        overrides
+            .iter()
+            .any(|var| var.split_once('=').map_or(var.as_str(), |(key, _)| key) == name)
+    };
+    keep.iter()
+        .filter(|name| !overridden(name))
+        .filter_map(|name| {
+            let (_, value) = host
+                .iter()
+                .find(|(key, _)| key.as_os_str() == name.as_str())?;
+            Some(format!("{name}={}", value.to_str()?))
+        })
+        .chain(overrides.iter().cloned())
+        .collect()
+}
+
+/// Refuse an empty program, or a program or argument exec cannot take
+fn check_command(program: &str, args: &[String]) -> Result<()> {
+    let message = if program.is_empty() {
+        "The program to execute is empty".to_string()
+    } else if program.contains('\0') {
+        format!("Program {program:?} contains a NUL byte")
+    } else if let Some(arg) = args.iter().find(|arg| arg.contains('\0')) {
+        format!("Argument {arg:?} contains a NUL byte")
+    } else {
+        return Ok(());
+    };
+    Err(Error::InvalidConfig { message })
+}
+
+/// Build the argument vector for exec, the program name first
+fn exec_args(program: &str, args: &[String]) -> std::result::Result<Vec<CString>, String> {
+    let mut command =
+        vec![CString::new(program).map_err(|e| format!("Invalid program path: {e}"))?];
+    for arg in args {
+        command.push(CString::new(arg.as_str()).map_err(|e| format!("Invalid argument: {e}"))?);
+    }
+    Ok(command)
+}
+
+/// Paths to try, in order, when executing `program`
+///
+/// A program containing `/` is used as is. Anything else is looked up in
+/// each directory of `path`, so it resolves against the container's `PATH`
+/// inside its root filesystem rather than against the host's. Empty
+/// entries are skipped instead of meaning the current directory.
+fn program_candidates(program: &str, path: &str) -> Vec<CString> {
+    if program.contains('/') {
+        return CString::new(program).into_iter().collect();
+    }
+
+    path.split(':')
+        .filter(|dir| !dir.is_empty())
+        .filter_map(|dir| CString::new(format!("{}/{program}", dir.trim_end_matches('/'))).ok())
+        .collect()
+}
+
+/// Whether `path` is a file someone may execute
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::metadata(path)
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+/// The child's setup in [`NamespaceExecutor::execute_command`], in the
+/// order [`NamespaceExecutor::run_child`] uses
+///
+/// Returns why a step failed.
+fn command_setup(
+    cgroup: Option<&Path>,
+    cwd: Option<&Path>,
+    security: Option<&SecurityManager>,
+) -> Option<String> {
+    let mut host = Host { gate: None };
+    if let Some(cgroup) = cgroup
+        && let Err(e) = host.join_cgroup(cgroup)
+    {
+        return Some(format!("Failed to join cgroup {}: {e}", cgroup.display()));
+    }
+    if let Some(cwd) = cwd
+        && let Err(e) = host.change_dir(cwd)
+    {
+        return Some(format!(
+            "Failed to change directory to {}: {e}",
+            cwd.display()
+        ));
+    }
+    if let Some(security) = security
+        && let Err(e) = host.apply_security(security)
+    {
+        return Some(format!("Failed to apply security settings: {e}"));
+    }
+    None
+}
+
+/// Pipe the child reports a failed setup step through
+///
+/// Both ends are closed on exec, so the parent reads either the child's
+/// error message or, once the program runs, end of file.
+fn report_pipe() -> Result<(File, File)> {
+    let (read, write) = pipe2(OFlag::O_CLOEXEC).map_err(|e| Error::Namespace {
+        message: format!("Failed to create pipe: {e}"),
+    })?;
+    Ok((read.into(), write.into()))
+}
+
+/// Wait until the child execs or reports why it could not
+fn read_report(mut report: File) -> Result<()> {
+    use std::io::Read;
+
+    let mut message = String::new();
+    report
+        .read_to_string(&mut message)
+        .map_err(|e| Error::Namespace {
+            message: format!("Failed to read from pipe: {e}"),
+        })?;
+
+    if message.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Namespace { message })
+    }
+}
+
+/// Read a pipe to end of file, keeping at most `limit` bytes
+///
+/// Returns the kept bytes and whether anything was dropped. Takes ownership
+/// of `fd` and closes it.
+fn capture_fd(fd: i32, limit: usize) -> Result<(Vec<u8>, bool)> {
+    use std::io::Read;
+
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let mut truncated = false;
+
+    loop {
+        let read = match file.read(&mut chunk) {
+            Ok(0) => return Ok((buffer, truncated)),
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                return Err(Error::Namespace {
+                    message: format!("Failed to read from pipe: {e}"),
+                });
+            }
+        };
+
+        let keep = read.min(limit - buffer.len());
+        buffer.extend_from_slice(&chunk[..keep]);
+        truncated |= keep < read;
+    }
+}
+
+/// Open a log file for appending, creating it if needed
+fn open_log(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| Error::Namespace {
+            message: format!("Failed to open log file {}: {e}", path.display()),
+        })
+}
+
+impl std::fmt::Debug for NamespaceExecutor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NamespaceExecutor")
+            .field("config", &self.config)
+            .field("env", &self.env)
+            .field("env_keep", &self.env_keep)
+            .field("cwd", &self.cwd)
+            .field("rlimits", &self.rlimits)
+            .field("security", &self.security)
+            .field("cgroup", &self.cgroup)
+            .field("tty", &self.tty)
+            .field("stdio", &self.stdio)
+            .field("merge_stderr", &self.merge_stderr)
+            .field("timeout", &self.timeout)
+            .field("termination", &self.termination)
+            .field("init", &self.init)
+            .field("user", &self.user)
+            .field("groups", &self.groups)
+            .field("umask", &self.umask)
+            .field("rootfs", &self.rootfs)
+            .field("tmpfs", &self.tmpfs)
+            .field("oom_score_adj", &self.oom_score_adj)
+            .field("scheduling", &self.scheduling)
+            .field("prestart", &self.prestart)
+            .field("hook_state", &self.hook_state)
+            .field("uid_maps", &self.uid_maps)
+            .field("gid_maps", &self.gid_maps)
+            .field("max_capture", &self.max_capture)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TimeOffsets;
+    use crate::sched::SchedPolicy;
+    use nix::sys::signal::Signal;
+    use vortex_core::MemorySize;
+
+    #[test]
+    fn test_execution_result_creation() {
+        let result = ExecutionResult {
+            exit_code: 0,
+            stdout: b"hello".to_vec(),
+            stderr: vec![],
+            truncated: false,
+            stopped: None,
+            isolated: Vec::new(),
+        };
+
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout, b"hello");
+        assert!(result.stderr.is_empty());
+    }
+
+    #[test]
+    fn test_execution_result_clone() {
+        let result1 = ExecutionResult {
+            exit_code: 0,
+            stdout: b"test".to_vec(),
             stderr: b"error".to_vec(),
+            truncated: false,
+            stopped: None,
+            isolated: Vec::new(),
         };
 
         let result2 = result1.clone();
@@ -293,6 +2043,422 @@ mod tests {
         assert_eq!(result1.stderr, result2.stderr);
     }
 
+    #[test]
+    fn test_execution_result_serde_round_trip() {
+        let result = ExecutionResult {
+            exit_code: 3,
+            stdout: "héllo\n".as_bytes().to_vec(),
+            stderr: b"warning\n".to_vec(),
+            truncated: true,
+            stopped: None,
+            isolated: Vec::new(),
+        };
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "exit_code": 3,
+                "stdout": "héllo\n",
+                "stderr": "warning\n",
+                "truncated": true,
+                "binary": false,
+            })
+        );
+        let back: ExecutionResult = serde_json::from_value(json).unwrap();
+        assert_eq!(back, result);
+    }
+
+    #[test]
+    fn test_execution_result_flags_binary_output() {
+        let result = ExecutionResult {
+            exit_code: 0,
+            stdout: vec![b'a', 0xff, b'b'],
+            stderr: Vec::new(),
+            truncated: false,
+            stopped: None,
+            isolated: Vec::new(),
+        };
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["binary"], true);
+        assert_eq!(json["stdout"], "a\u{fffd}b");
+    }
+
+    #[test]
+    fn test_execution_result_lossy_strings() {
+        let result = ExecutionResult {
+            exit_code: 0,
+            stdout: b"one\r\ntw\xffo\n\nthree".to_vec(),
+            stderr: b"warning\n".to_vec(),
+            truncated: false,
+            stopped: None,
+            isolated: Vec::new(),
+        };
+
+        // Valid output is borrowed, invalid bytes are replaced
+        assert!(matches!(result.stderr_string(), Cow::Borrowed("warning\n")));
+        assert!(matches!(result.stdout_string(), Cow::Owned(_)));
+        assert_eq!(result.stdout_string(), "one\r\ntw\u{fffd}o\n\nthree");
+
+        let lines: Vec<_> = result.stdout_lines().collect();
+        assert_eq!(lines, ["one", "tw\u{fffd}o", "", "three"]);
+
+        let empty = ExecutionResult {
+            stdout: Vec::new(),
+            ..result
+        };
+        assert_eq!(empty.stdout_lines().count(), 0);
+    }
+
+    /// Records the child steps and fails the one named `fail_at`
+    #[derive(Default)]
+    struct FakeChild {
+        steps: Vec<&'static str>,
+        fail_at: Option<&'static str>,
+        exec_env: Option<Vec<CString>>,
+        exec_candidates: Vec<CString>,
+    }
+
+    impl FakeChild {
+        fn failing_at(step: &'static str) -> Self {
+            Self {
+                fail_at: Some(step),
+                ..Self::default()
+            }
+        }
+
+        fn step(&mut self, name: &'static str) -> Result<()> {
+            self.steps.push(name);
+            if self.fail_at == Some(name) {
+                return Err(Error::Namespace {
+                    message: format!("{name} failed"),
+                });
+            }
+            Ok(())
+        }
+
+        fn io_step(&mut self, name: &'static str) -> std::io::Result<()> {
+            self.step(name)
+                .map_err(|_| std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+        }
+    }
+
+    impl ChildOps for FakeChild {
+        fn join_cgroup(&mut self, _: &Path) -> std::io::Result<()> {
+            self.io_step("cgroup")
+        }
+
+        fn set_oom_score_adj(&mut self, _: OomScoreAdj) -> Result<()> {
+            self.step("oom")
+        }
+
+        fn create_namespaces(&mut self, _: &NamespaceConfig) -> Result<()> {
+            self.step("namespaces")
+        }
+
+        fn start_init(&mut self) -> Result<()> {
+            self.step("init")
+        }
+
+        fn enter_rootfs(&mut self, _: &RootfsSpec) -> Result<()> {
+            self.step("rootfs")
+        }
+
+        fn mount_tmpfs(&mut self, _: &[TmpfsMount]) -> Result<()> {
+            self.step("tmpfs")
+        }
+
+        fn wait_for_hooks(&mut self) -> Result<()> {
+            self.step("hooks")
+        }
+
+        fn change_dir(&mut self, _: &Path) -> std::io::Result<()> {
+            self.io_step("cwd")
+        }
+
+        fn apply_rlimit(&mut self, _: &Rlimit) -> Result<()> {
+            self.step("rlimit")
+        }
+
+        fn set_scheduling(&mut self, _: Scheduling) -> Result<()> {
+            self.step("sched")
+        }
+
+        fn apply_security(&mut self, _: &SecurityManager) -> Result<()> {
+            self.step("security")
+        }
+
+        fn switch_user(&mut self, _: Option<User>, _: &[u32], _: Option<u32>) -> Result<()> {
+            self.step("user")
+        }
+
+        fn exec(
+            &mut self,
+            candidates: &[CString],
+            _: &[CString],
+            env: Option<&[CString]>,
+        ) -> std::io::Error {
+            self.steps.push("exec");
+            self.exec_env = env.map(<[CString]>::to_vec);
+            self.exec_candidates = candidates.to_vec();
+            std::io::Error::from(std::io::ErrorKind::NotFound)
+        }
+    }
+
+    fn configured_executor() -> NamespaceExecutor {
+        NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_cgroup("/sys/fs/cgroup/vortex/test")
+            .with_oom_score_adj(OomScoreAdj::new(500).unwrap())
+            .with_rootfs(RootfsSpec::Path("/srv/root".into()))
+            .with_tmpfs(vec![TmpfsMount::new("/run")])
+            .with_prestart_hooks(vec![Hook::new("true")], HookState::new("test"))
+            .with_init(true)
+            .with_cwd("/tmp")
+            .with_rlimits(vec![
+                "nofile=64".parse().unwrap(),
+                "nproc=64".parse().unwrap(),
+            ])
+            .with_scheduling(Scheduling::new(Some(SchedPolicy::Batch), None, Some(5)).unwrap())
+            .with_security(SecurityManager::new())
+            .with_user(User::new(65534, 65534))
+            .with_env(vec!["A=1".to_string(), "BAD=\0".to_string()])
+    }
+
+    #[test]
+    fn test_child_steps_run_in_order() {
+        let mut fake = FakeChild::default();
+        let failure = configured_executor().run_child(&mut fake, "true", &[]);
+
+        assert_eq!(
+            fake.steps,
+            vec![
+                "cgroup",
+                "oom",
+                "namespaces",
+                "init",
+                "rootfs",
+                "tmpfs",
+                "hooks",
+                "cwd",
+                "rlimit",
+                "rlimit",
+                "sched",
+                "security",
+                "user",
+                "exec"
+            ]
+        );
+        // Exec only returns on failure
+        assert_eq!(failure.exit_code, 127);
+        assert!(failure.message.starts_with("Failed to execute true"));
+        // Entries that can't be passed to exec are dropped
+        assert_eq!(fake.exec_env, Some(vec![CString::new("A=1").unwrap()]));
+    }
+
+    #[test]
+    fn test_child_stops_at_first_failure() {
+        for (step, message) in [
+            ("cgroup", "Failed to join cgroup"),
+            ("oom", "oom failed"),
+            ("namespaces", "Failed to create namespaces"),
+            ("init", "init failed"),
+            ("rootfs", "Failed to switch root filesystem"),
+            ("tmpfs", "tmpfs failed"),
+            ("hooks", "hooks failed"),
+            ("sched", "sched failed"),
+            ("cwd", "Failed to change directory"),
+            ("rlimit", "rlimit failed"),
+            ("security", "Failed to apply security settings"),
+            ("user", "user failed"),
+        ] {
+            let mut fake = FakeChild::failing_at(step);
+            let failure = configured_executor().run_child(&mut fake, "true", &[]);
+
+            assert_eq!(fake.steps.last(), Some(&step));
+            assert!(!fake.steps.contains(&"exec"), "exec after failed {step}");
+            assert_eq!(failure.exit_code, 1);
+            assert!(failure.message.contains(message), "{}", failure.message);
+        }
+    }
+
+    #[test]
+    fn test_child_skips_unconfigured_steps() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none()).unwrap();
+        let mut fake = FakeChild::default();
+        executor.run_child(&mut fake, "true", &[]);
+
+        assert_eq!(fake.steps, vec!["namespaces", "user", "exec"]);
+        assert_eq!(fake.exec_env, None);
+    }
+
+    #[test]
+    fn test_assemble_env() {
+        let host = || {
+            [
+                ("TERM", "xterm"),
+                ("LANG", "C.UTF-8"),
+                ("SECRET", "hunter2"),
+            ]
+            .map(|(key, value)| (OsString::from(key), OsString::from(value)))
+        };
+        let keep = [
+            "LANG".to_string(),
+            "TERM".to_string(),
+            "DISPLAY".to_string(),
+        ];
+
+        // Only kept variables that are set, in the order asked for
+        assert_eq!(
+            assemble_env(&keep, host(), &[]),
+            vec!["LANG=C.UTF-8", "TERM=xterm"]
+        );
+        // Explicit entries win over kept ones
+        assert_eq!(
+            assemble_env(&keep, host(), &["TERM=dumb".to_string(), "A=1".to_string()]),
+            vec!["LANG=C.UTF-8", "TERM=dumb", "A=1"]
+        );
+        assert_eq!(assemble_env(&[], host(), &["A=1".to_string()]), vec!["A=1"]);
+    }
+
+    #[test]
+    fn test_env_keep_clears_the_rest() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none()).unwrap();
+        assert_eq!(executor.environment(), None);
+
+        // PATH is set wherever the tests run
+        let path = std::env::var("PATH").unwrap();
+        let executor = executor
+            .with_env_keep(vec!["PATH".to_string(), "VORTEX_UNSET_VAR".to_string()])
+            .with_env(vec!["A=1".to_string()]);
+        let mut fake = FakeChild::default();
+        executor.run_child(&mut fake, "true", &[]);
+
+        assert_eq!(
+            fake.exec_env,
+            Some(vec![
+                CString::new(format!("PATH={path}")).unwrap(),
+                CString::new("A=1").unwrap()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_child_rejects_nul_in_arguments() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none()).unwrap();
+        let mut fake = FakeChild::default();
+        let failure = executor.run_child(&mut fake, "echo", &["a\0b".to_string()]);
+
+        assert!(!fake.steps.contains(&"exec"));
+        assert_eq!(failure.exit_code, 1);
+        assert!(failure.message.starts_with("Invalid argument"));
+    }
+
+    #[test]
+    fn test_program_candidates() {
+        let strings = |candidates: Vec<CString>| -> Vec<String> {
+            candidates
+                .into_iter()
+                .map(|c| c.into_string().unwrap())
+                .collect()
+        };
+
+        assert_eq!(
+            strings(program_candidates("ls", "/usr/bin:/bin/:")),
+            vec!["/usr/bin/ls", "/bin/ls"]
+        );
+        // A path is never looked up
+        assert_eq!(
+            strings(program_candidates("./run", "/usr/bin")),
+            vec!["./run"]
+        );
+        assert_eq!(
+            strings(program_candidates("/bin/true", "/usr/bin")),
+            vec!["/bin/true"]
+        );
+        assert!(program_candidates("ls", "").is_empty());
+    }
+
+    #[test]
+    fn test_child_resolves_against_configured_path() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none()).unwrap();
+        let mut fake = FakeChild::default();
+        executor.run_child(&mut fake, "true", &[]);
+        assert_eq!(
+            fake.exec_candidates.len(),
+            NamespaceExecutor::DEFAULT_PATH.split(':').count()
+        );
+        assert_eq!(
+            fake.exec_candidates.first(),
+            Some(&CString::new("/usr/local/sbin/true").unwrap())
+        );
+
+        let executor = executor.with_env(vec![
+            "HOME=/root".to_string(),
+            "PATH=/opt/bin".to_string(),
+            "PATH=/ignored".to_string(),
+        ]);
+        let mut fake = FakeChild::default();
+        executor.run_child(&mut fake, "true", &[]);
+        assert_eq!(
+            fake.exec_candidates,
+            vec![CString::new("/opt/bin/true").unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_oom_score_adj_round_trips() {
+        // Raising the value needs no privilege
+        let adj = OomScoreAdj::new(OomScoreAdj::current().unwrap().value().max(700)).unwrap();
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_oom_score_adj(adj);
+
+        let result = executor
+            .execute("/bin/cat", &["/proc/self/oom_score_adj".to_string()])
+            .unwrap();
+
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(String::from_utf8_lossy(&result.stdout), format!("{adj}\n"));
+    }
+
+    #[test]
+    fn test_scheduling_is_applied() {
+        // Moving to SCHED_BATCH and raising the nice value need no privilege
+        let nice = 19;
+        let scheduling = Scheduling::new(Some(SchedPolicy::Batch), None, Some(nice)).unwrap();
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_scheduling(scheduling);
+
+        let result = executor
+            .execute("/bin/cat", &["/proc/self/stat".to_string()])
+            .unwrap();
+        assert_eq!(result.exit_code, 0);
+
+        // Fields after the command name, which may contain spaces; nice is
+        // field 19 and the policy field 41 of proc(5)
+        let stat = String::from_utf8_lossy(&result.stdout);
+        let fields: Vec<&str> = stat.rsplit_once(") ").unwrap().1.split(' ').collect();
+        assert_eq!(fields[19 - 3], nice.to_string());
+        assert_eq!(fields[41 - 3], libc::SCHED_BATCH.to_string());
+    }
+
+    #[test]
+    fn test_executor_clone_keeps_config() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::minimal())
+            .unwrap()
+            .with_cwd("/tmp");
+        let clone = executor.clone();
+
+        assert_eq!(
+            clone.config().enabled_namespaces(),
+            executor.config().enabled_namespaces()
+        );
+        assert_eq!(clone.cwd, executor.cwd);
+    }
+
     #[test]
     #[ignore] // Requires root privileges
     fn test_simple_execution() {
@@ -322,6 +2488,957 @@ mod tests {
         assert!(String::from_utf8_lossy(&result.stderr).contains("error"));
     }
 
+    #[test]
+    fn test_id_maps_need_user_namespace() {
+        let result = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_id_maps(vec![IdMap::new(0, 100_000, 1)], Vec::new())
+            .execute("/bin/true", &[]);
+        assert!(matches!(result, Err(Error::InvalidConfig { .. })));
+    }
+
+    #[test]
+    fn test_id_maps_outside_subuid_are_refused() {
+        // Root may map anything
+        if nix::unistd::Uid::effective().is_root() {
+            return;
+        }
+
+        let config = NamespaceConfig {
+            user: true,
+            ..NamespaceConfig::none()
+        };
+        let err = NamespaceExecutor::new(config)
+            .unwrap()
+            .with_id_maps(vec![IdMap::new(0, 4_000_000_000, 10)], Vec::new())
+            .execute("/bin/true", &[])
+            .unwrap_err();
+
+        assert!(matches!(err, Error::InvalidConfig { .. }));
+        let user = nix::unistd::User::from_uid(nix::unistd::Uid::effective())
+            .unwrap()
+            .map_or_else(
+                || nix::unistd::Uid::effective().to_string(),
+                |user| user.name,
+            );
+        assert!(
+            err.to_string().contains(&format!(
+                "uid 4000000000-4000000009 not in /etc/subuid for user {user}"
+            )),
+            "{err}"
+        );
+    }
+
+    #[test]
+    #[ignore = "requires root privileges"]
+    fn test_id_maps_are_written() {
+        let config = NamespaceConfig {
+            user: true,
+            ..NamespaceConfig::none()
+        };
+        let result = NamespaceExecutor::new(config)
+            .unwrap()
+            .with_id_maps(
+                vec![IdMap::new(0, 100_000, 65536)],
+                vec![IdMap::new(0, 100_000, 65536)],
+            )
+            .execute(
+                "/bin/sh",
+                &[
+                    "-c".to_string(),
+                    "cat /proc/self/uid_map /proc/self/gid_map".to_string(),
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(result.exit_code, 0, "{}", result.stderr_string());
+        let maps: Vec<Vec<String>> = result
+            .stdout_lines()
+            .map(|line| line.split_whitespace().map(str::to_string).collect())
+            .collect();
+        assert_eq!(maps, vec![vec!["0", "100000", "65536"]; 2]);
+    }
+
+    #[test]
+    fn test_merged_stderr_keeps_order() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_merged_stderr(true);
+        // A umask makes the executor fork the child itself
+        for executor in [executor.clone(), executor.with_umask(0o022)] {
+            let script = "echo 1; echo 2 >&2; echo 3; echo 4 >&2";
+            let result = executor
+                .execute("/bin/sh", &["-c".to_string(), script.to_string()])
+                .unwrap();
+
+            assert_eq!(result.exit_code, 0);
+            assert_eq!(String::from_utf8_lossy(&result.stdout), "1\n2\n3\n4\n");
+            assert!(result.stderr.is_empty());
+        }
+    }
+
+    #[test]
+    #[ignore = "requires root privileges"]
+    fn test_execution_with_env_and_cwd() {
+        let config = NamespaceConfig::new();
+        let executor = NamespaceExecutor::new(config)
+            .unwrap()
+            .with_env(vec!["VORTEX_TEST=value".to_string()])
+            .with_cwd("/tmp");
+
+        let result = executor
+            .execute(
+                "/bin/sh",
+                &["-c".to_string(), "echo $VORTEX_TEST; pwd".to_string()],
+            )
+            .unwrap();
+
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(String::from_utf8_lossy(&result.stdout), "value\n/tmp\n");
+    }
+
+    #[test]
+    fn test_stdin_is_dev_null() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none()).unwrap();
+        let result = executor
+            .execute(
+                "/bin/sh",
+                &["-c".to_string(), "readlink /proc/self/fd/0".to_string()],
+            )
+            .unwrap();
+
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(String::from_utf8_lossy(&result.stdout), "/dev/null\n");
+    }
+
+    #[test]
+    fn test_inherited_stdio_is_not_captured() {
+        let stdout = std::fs::read_link("/proc/self/fd/1").unwrap();
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_stdio(Stdio::Inherit);
+
+        // The child's stdout is the very file ours is
+        let script = format!(
+            "test \"$(readlink /proc/$$/fd/1)\" = '{}' || exit 3",
+            stdout.display()
+        );
+        let result = executor
+            .execute("/bin/sh", &["-c".to_string(), script])
+            .unwrap();
+
+        assert_eq!(result.exit_code, 0);
+        assert!(result.stdout.is_empty() && result.stderr.is_empty());
+    }
+
+    #[test]
+    fn test_timeout_signal_lets_child_exit_cleanly() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_timeout(Duration::from_millis(300))
+            .with_termination(TerminationConfig::new(
+                Signal::SIGINT,
+                Duration::from_secs(10),
+            ));
+
+        let script = "trap 'echo flushed; exit 0' INT; while :; do sleep 0.1; done";
+        let result = executor
+            .execute("/bin/sh", &["-c".to_string(), script.to_string()])
+            .unwrap();
+
+        assert_eq!(result.stopped, Some(Termination::Graceful));
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(String::from_utf8_lossy(&result.stdout), "flushed\n");
+    }
+
+    #[test]
+    fn test_timeout_kills_child_after_grace() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_timeout(Duration::from_millis(200))
+            .with_termination(TerminationConfig::new(
+                Signal::SIGTERM,
+                Duration::from_millis(200),
+            ));
+
+        // The ignored SIGTERM is inherited by sleep as well
+        let script = "trap '' TERM; while :; do sleep 0.1; done";
+        let result = executor
+            .execute("/bin/sh", &["-c".to_string(), script.to_string()])
+            .unwrap();
+
+        assert_eq!(result.stopped, Some(Termination::Killed));
+        assert_eq!(result.exit_code, 128 + Signal::SIGKILL as i32);
+    }
+
+    #[test]
+    fn test_timeout_child_gets_terminal_interrupt() {
+        let pty = tty::open_pty().unwrap();
+        let caller = match unsafe { fork() }.unwrap() {
+            ForkResult::Child => {
+                drop(pty.master);
+                let code = tty::attach_slave(pty.slave).map_or(101, |()| {
+                    NamespaceExecutor::new(NamespaceConfig::none())
+                        .unwrap()
+                        .with_timeout(Duration::from_secs(30))
+                        .execute("sleep", &["30".to_string()])
+                        .map_or(102, |result| result.exit_code)
+                });
+                unsafe { libc::_exit(code) }
+            }
+            ForkResult::Parent { child } => child,
+        };
+        drop(pty.slave);
+
+        // Wait for the command's group to get the terminal, then press
+        // Ctrl-C; the terminal has no foreground group, 0, until the caller
+        // takes it
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+        while !tcgetpgrp(&pty.master)
+            .is_ok_and(|group| ![caller, Pid::from_raw(0)].contains(&group))
+        {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "terminal not handed over"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        nix::unistd::write(&pty.master, b"\x03").unwrap();
+
+        // The caller survives and reports the interrupted command
+        assert_eq!(
+            waitpid(caller, None).unwrap(),
+            WaitStatus::Exited(caller, 128 + Signal::SIGINT as i32)
+        );
+    }
+
+    #[test]
+    fn test_command_within_timeout_is_not_stopped() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_timeout(Duration::from_secs(30));
+
+        let started = std::time::Instant::now();
+        let result = executor.execute("/bin/true", &[]).unwrap();
+
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stopped, None);
+        assert!(started.elapsed() < Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_wait_survives_interrupting_signal() {
+        use nix::sys::signal::{SaFlags, SigAction, SigHandler, SigSet, sigaction};
+
+        extern "C" fn ignore(_: libc::c_int) {}
+
+        // A handler without SA_RESTART makes a blocked waitpid fail with EINTR
+        let action = SigAction::new(
+            SigHandler::Handler(ignore),
+            SaFlags::empty(),
+            SigSet::empty(),
+        );
+        unsafe { sigaction(Signal::SIGUSR1, &action) }.unwrap();
+
+        // The umask takes the forked path, where the parent calls waitpid
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_umask(0o022);
+        let waiter = unsafe { libc::pthread_self() };
+        let interrupter = std::thread::spawn(move || {
+            for _ in 0..3 {
+                std::thread::sleep(Duration::from_millis(100));
+                unsafe { libc::pthread_kill(waiter, libc::SIGUSR1) };
+            }
+        });
+
+        // Closing the pipes first leaves the parent blocked in waitpid
+        let script = "exec >/dev/null 2>&1; sleep 0.5; exit 7";
+        let result = executor
+            .execute("/bin/sh", &["-c".to_string(), script.to_string()])
+            .unwrap();
+        interrupter.join().unwrap();
+
+        assert_eq!(result.exit_code, 7);
+    }
+
+    #[test]
+    fn test_tty_gives_child_a_terminal() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_tty(true);
+        let result = executor
+            .execute(
+                "/bin/sh",
+                &["-c".to_string(), "test -t 0 && test -t 1".to_string()],
+            )
+            .unwrap();
+
+        assert_eq!(result.exit_code, 0);
+    }
+
+    #[test]
+    fn test_spawn_detached_logs_output() {
+        let dir = std::env::temp_dir().join(format!("vortex-detach-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (out, err) = (dir.join("out.log"), dir.join("err.log"));
+
+        let executor = NamespaceExecutor::new(NamespaceConfig::none()).unwrap();
+        let pid = executor
+            .spawn_detached(
+                "/bin/sh",
+                &["-c".to_string(), "echo out; echo err >&2".to_string()],
+                &out,
+                &err,
+            )
+            .unwrap();
+        assert!(pid > 0);
+
+        // Reparented away from us, so it can't be waited on here
+        assert!(waitpid(Pid::from_raw(pid), None).is_err());
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while std::fs::read_to_string(&err).unwrap_or_default().is_empty()
+            && std::time::Instant::now() < deadline
+        {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "out\n");
+        assert_eq!(std::fs::read_to_string(&err).unwrap(), "err\n");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_spawn_supervised_reports_exit() {
+        let dir = std::env::temp_dir().join(format!("vortex-supervised-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (out, err) = (dir.join("out.log"), dir.join("err.log"));
+        let executor = NamespaceExecutor::new(NamespaceConfig::none()).unwrap();
+
+        for (script, expected) in [("exit 3", "3 None"), ("kill -9 $$", "137 Some(SIGKILL)")] {
+            let (start_file, exit_file) = (dir.join("start"), dir.join("exit"));
+            let _ = std::fs::remove_file(&exit_file);
+            let (started, report) = (start_file.clone(), exit_file.clone());
+            let pid = executor
+                .spawn_supervised(
+                    "/bin/sh",
+                    &["-c".to_string(), script.to_string()],
+                    &out,
+                    &err,
+                    move |pid| {
+                        let _ = std::fs::write(&started, pid.to_string());
+                    },
+                    move |exit| {
+                        let _ = std::fs::write(
+                            &report,
+                            format!("{} {} {:?}", exit.pid, exit.exit_code, exit.signal),
+                        );
+                    },
+                )
+                .unwrap();
+
+            // Recorded before the PID was returned
+            assert_eq!(
+                std::fs::read_to_string(&start_file).unwrap(),
+                pid.to_string()
+            );
+
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+            while !exit_file.exists() && std::time::Instant::now() < deadline {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            assert_eq!(
+                std::fs::read_to_string(&exit_file).unwrap(),
+                format!("{pid} {expected}")
+            );
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_capture_is_truncated_at_limit() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_max_capture(1024);
+
+        // Far more than a pipe buffer, so the child would block if the
+        // excess were not drained
+        let result = executor
+            .execute(
+                "/bin/sh",
+                &[
+                    "-c".to_string(),
+                    "head -c 1048576 /dev/zero; echo done >&2".to_string(),
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout, vec![0; 1024]);
+        assert_eq!(result.stderr, b"done\n");
+        assert!(result.truncated);
+    }
+
+    /// Far more than a pipe buffer on stderr before stdout is closed; the
+    /// timeout only keeps a regression from hanging the test run
+    fn assert_stderr_read_while_stdout_is_open(config: NamespaceConfig) {
+        let result = NamespaceExecutor::new(config)
+            .unwrap()
+            .with_timeout(Duration::from_secs(10))
+            .execute(
+                "/bin/sh",
+                &[
+                    "-c".to_string(),
+                    "head -c 1048576 /dev/zero >&2; echo done".to_string(),
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stderr.len(), 1024 * 1024);
+        assert_eq!(result.stdout, b"done\n");
+        assert!(!result.truncated);
+    }
+
+    #[test]
+    fn test_capture_reads_stderr_while_stdout_is_open() {
+        assert_stderr_read_while_stdout_is_open(NamespaceConfig::none());
+    }
+
+    #[test]
+    #[ignore = "requires root privileges"]
+    fn test_capture_in_namespaces_reads_stderr_while_stdout_is_open() {
+        assert_stderr_read_while_stdout_is_open(NamespaceConfig::new());
+    }
+
+    #[test]
+    fn test_capture_within_limit_is_complete() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none()).unwrap();
+        let result = executor
+            .execute("/bin/echo", &["hello".to_string()])
+            .unwrap();
+
+        assert_eq!(result.stdout, b"hello\n");
+        assert!(!result.truncated);
+    }
+
+    #[test]
+    fn test_plain_runs_use_command() {
+        let plain = NamespaceExecutor::new(NamespaceConfig::none()).unwrap();
+        assert!(plain.runs_as_command());
+        assert!(plain.clone().with_cwd("/tmp").runs_as_command());
+
+        assert!(!plain.clone().with_tty(true).runs_as_command());
+        assert!(
+            !plain
+                .with_rlimits(vec!["nofile=512".parse().unwrap()])
+                .runs_as_command()
+        );
+        let isolated = NamespaceExecutor::new(NamespaceConfig::none().with_uts(true)).unwrap();
+        assert!(!isolated.runs_as_command());
+    }
+
+    #[test]
+    fn test_command_without_namespaces_captures_output() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_env(vec!["GREETING=hello".to_string()])
+            .with_cwd("/tmp")
+            .with_security(SecurityManager::new());
+
+        let result = executor
+            .execute(
+                "sh",
+                &[
+                    "-c".to_string(),
+                    "echo $GREETING from $(pwd); echo oops >&2; exit 3".to_string(),
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(result.exit_code, 3);
+        assert_eq!(result.stdout, b"hello from /tmp\n");
+        assert_eq!(result.stderr, b"oops\n");
+    }
+
+    #[test]
+    fn test_command_without_namespaces_reports_missing_program() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none()).unwrap();
+        let result = executor.execute("vortex-no-such-program", &[]).unwrap();
+
+        assert_eq!(result.exit_code, 127);
+        assert!(
+            String::from_utf8_lossy(&result.stderr)
+                .starts_with("Failed to execute vortex-no-such-program"),
+        );
+
+        // A setup step failing is an error instead
+        let err = executor
+            .with_cwd("/nonexistent/vortex-dir")
+            .execute("/bin/true", &[])
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("Failed to change directory"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_missing_cgroup_fails_before_exec() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_cgroup("/nonexistent/vortex-cgroup");
+
+        // Reported as an error rather than as the exit code of /bin/true
+        let err = executor.execute("/bin/true", &[]).unwrap_err();
+        assert!(err.to_string().contains("Failed to join cgroup"), "{err}");
+    }
+
+    #[test]
+    fn test_detached_setup_failure_is_reported() {
+        let dir = std::env::temp_dir().join(format!("vortex-detach-fail-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_cgroup("/nonexistent/vortex-cgroup");
+
+        let err = executor
+            .spawn_detached("/bin/true", &[], &dir.join("out.log"), &dir.join("err.log"))
+            .unwrap_err();
+        assert!(err.to_string().contains("Failed to join cgroup"), "{err}");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    #[ignore = "requires root privileges"]
+    fn test_execution_with_rlimit() {
+        let config = NamespaceConfig::new();
+        let executor = NamespaceExecutor::new(config)
+            .unwrap()
+            .with_rlimits(vec!["nofile=512:1024".parse().unwrap()]);
+
+        let result = executor
+            .execute(
+                "/bin/sh",
+                &["-c".to_string(), "ulimit -n; ulimit -Hn".to_string()],
+            )
+            .unwrap();
+
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(String::from_utf8_lossy(&result.stdout), "512\n1024\n");
+    }
+
+    #[test]
+    #[ignore = "requires root privileges"]
+    fn test_execution_with_no_new_privs() {
+        let script = "grep NoNewPrivs /proc/self/status".to_string();
+
+        let executor = NamespaceExecutor::new(NamespaceConfig::new()).unwrap();
+        let result = executor.execute("/bin/sh", &["-c".to_string(), script.clone()]);
+        assert!(String::from_utf8_lossy(&result.unwrap().stdout).contains("NoNewPrivs:\t0"));
+
+        let executor = NamespaceExecutor::new(NamespaceConfig::new())
+            .unwrap()
+            .with_security(SecurityManager::new());
+        let result = executor.execute("/bin/sh", &["-c".to_string(), script]);
+        assert!(String::from_utf8_lossy(&result.unwrap().stdout).contains("NoNewPrivs:\t1"));
+    }
+
+    #[test]
+    #[ignore = "requires root privileges"]
+    fn test_no_new_privs_blocks_setuid() {
+        // A setuid-root copy of `id` run as nobody must not report euid 0
+        let helper = std::env::temp_dir().join(format!("vortex-setuid-{}", std::process::id()));
+        std::fs::copy("/usr/bin/id", &helper).unwrap();
+        std::fs::set_permissions(
+            &helper,
+            std::os::unix::fs::PermissionsExt::from_mode(0o4755),
+        )
+        .unwrap();
+
+        let script = format!(
+            "exec setpriv --reuid=65534 --regid=65534 --clear-groups {} -u",
+            helper.display()
+        );
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_security(SecurityManager::new());
+        let result = executor
+            .execute("/bin/sh", &["-c".to_string(), script])
+            .unwrap();
+
+        std::fs::remove_file(&helper).unwrap();
+        assert_eq!(
+            result.exit_code,
+            0,
+            "{}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+        assert_eq!(String::from_utf8_lossy(&result.stdout).trim(), "65534");
+    }
+
+    #[test]
+    #[ignore = "requires root privileges"]
+    fn test_execution_with_user_and_groups() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_user(User::new(65534, 65534))
+            .with_groups(vec![100, 65534])
+            .with_umask(0o077);
+
+        let result = executor
+            .execute(
+                "/bin/sh",
+                &["-c".to_string(), "id -u; id -g; id -G; umask".to_string()],
+            )
+            .unwrap();
+
+        assert_eq!(
+            result.exit_code,
+            0,
+            "{}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&result.stdout),
+            "65534\n65534\n65534 100\n0077\n"
+        );
+    }
+
+    #[test]
+    #[ignore = "requires root privileges"]
+    fn test_overlay_rootfs_keeps_base_untouched() {
+        // The host root is the base; upper and work live on another fs
+        let dir = PathBuf::from(format!("/dev/shm/vortex-overlay-{}", std::process::id()));
+        let upper = dir.join("upper");
+        let work = dir.join("work");
+        std::fs::create_dir_all(&upper).unwrap();
+        std::fs::create_dir_all(&work).unwrap();
+
+        let marker = format!("vortex-overlay-marker-{}", std::process::id());
+        let executor = NamespaceExecutor::new(NamespaceConfig::none().with_mount(true))
+            .unwrap()
+            .with_rootfs(RootfsSpec::Overlay {
+                lower: "/".into(),
+                upper: upper.clone(),
+                work,
+            });
+        let result = executor
+            .execute(
+                "/bin/sh",
+                &["-c".to_string(), format!("echo hi > /{marker}")],
+            )
+            .unwrap();
+
+        assert_eq!(
+            result.exit_code,
+            0,
+            "{}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+        assert!(upper.join(&marker).exists());
+        assert!(!Path::new("/").join(&marker).exists());
+        assert!(!dir.join(crate::rootfs::MERGED_DIR).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_script_without_interpreter_runs_in_shell() {
+        let dir = std::env::temp_dir().join(format!("vortex-noexec-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("greet");
+        std::fs::write(&script, "echo \"hello $1\"\n").unwrap();
+        std::fs::set_permissions(&script, std::os::unix::fs::PermissionsExt::from_mode(0o755))
+            .unwrap();
+
+        let result = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .execute(script.to_str().unwrap(), &["world".to_string()])
+            .unwrap();
+
+        assert_eq!(
+            result.exit_code,
+            0,
+            "{}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+        assert_eq!(String::from_utf8_lossy(&result.stdout), "hello world\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[ignore = "requires root privileges"]
+    fn test_program_resolves_against_container_path() {
+        // The program only exists in the container's root, on its PATH
+        let dir = PathBuf::from(format!("/dev/shm/vortex-path-{}", std::process::id()));
+        let upper = dir.join("upper");
+        let work = dir.join("work");
+        let bin = upper.join("vortex-bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::create_dir_all(&work).unwrap();
+        std::fs::write(bin.join("greet"), "#!/bin/sh\necho from the rootfs\n").unwrap();
+        std::fs::set_permissions(
+            bin.join("greet"),
+            std::os::unix::fs::PermissionsExt::from_mode(0o755),
+        )
+        .unwrap();
+
+        let executor = NamespaceExecutor::new(NamespaceConfig::none().with_mount(true))
+            .unwrap()
+            .with_rootfs(RootfsSpec::Overlay {
+                lower: "/".into(),
+                upper,
+                work,
+            })
+            .with_env(vec!["PATH=/nonexistent:/vortex-bin".to_string()]);
+        let result = executor.execute("greet", &[]).unwrap();
+
+        assert_eq!(
+            result.exit_code,
+            0,
+            "{}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+        assert_eq!(String::from_utf8_lossy(&result.stdout), "from the rootfs\n");
+
+        // Only the container's PATH is searched, not the host's
+        let result = executor.execute("sh", &[]).unwrap();
+        assert_eq!(result.exit_code, 127);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rootfs_needs_mount_namespace() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_rootfs(RootfsSpec::Path("/".into()));
+        assert!(matches!(
+            executor.execute("true", &[]),
+            Err(Error::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_program_fails_before_fork() {
+        // A forked child would report a failed exec as an exit code instead
+        let executor = NamespaceExecutor::new(NamespaceConfig::none()).unwrap();
+        for (program, args) in [
+            ("", vec![]),
+            ("tr\0ue", vec![]),
+            ("true", vec!["a\0".to_string()]),
+        ] {
+            let Err(Error::InvalidConfig { message }) = executor.execute(program, &args) else {
+                panic!("{program:?} {args:?} should be refused");
+            };
+            assert!(
+                message.contains("empty") || message.contains("NUL"),
+                "{message}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_init_needs_pid_namespace() {
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_init(true);
+        assert!(matches!(
+            executor.execute("true", &[]),
+            Err(Error::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    #[ignore = "requires root privileges"]
+    fn test_init_reaps_orphans() {
+        let executor =
+            NamespaceExecutor::new(NamespaceConfig::none().with_pid(true).with_mount(true))
+                .unwrap()
+                .with_init(true);
+        // A private /proc shows only the new PID namespace; the orphaned
+        // sleep is reparented to the init and must not linger as a zombie
+        let script = "mount --make-rprivate / && mount -t proc proc /proc || exit 99
+            sh -c 'sleep 0.1 &'
+            sleep 0.5
+            echo $$
+            grep -l '^State:.*Z' /proc/[0-9]*/status | wc -l";
+        let result = executor
+            .execute("/bin/sh", &["-c".to_string(), script.to_string()])
+            .unwrap();
+
+        assert_eq!(
+            result.exit_code,
+            0,
+            "{}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+        let stdout = String::from_utf8_lossy(&result.stdout);
+        let lines: Vec<&str> = stdout.lines().map(str::trim).collect();
+        // The init is PID 1, the program runs as PID 2
+        assert_eq!(lines, vec!["2", "0"]);
+
+        // The program's exit status comes through the init
+        let result = executor
+            .execute("/bin/sh", &["-c".to_string(), "exit 7".to_string()])
+            .unwrap();
+        assert_eq!(result.exit_code, 7);
+        let result = executor
+            .execute("/bin/sh", &["-c".to_string(), "kill -TERM $$".to_string()])
+            .unwrap();
+        assert_eq!(result.exit_code, 128 + libc::SIGTERM);
+    }
+
+    #[test]
+    fn test_tmpfs_needs_mount_namespace() {
+        let config = NamespaceConfig::none().with_private_tmp(MemorySize::from_mb(16));
+        let executor = NamespaceExecutor::new(config).unwrap();
+        assert!(matches!(
+            executor.execute("true", &[]),
+            Err(Error::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn test_time_offsets_need_time_namespace() {
+        let offsets = TimeOffsets {
+            boottime: 3600,
+            ..TimeOffsets::ZERO
+        };
+        let config = NamespaceConfig::none().with_time_offsets(offsets);
+        let executor = NamespaceExecutor::new(config).unwrap();
+        assert!(matches!(
+            executor.execute("true", &[]),
+            Err(Error::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    #[ignore = "requires root privileges"]
+    fn test_time_namespace_shifts_uptime() {
+        let host_uptime = || -> f64 {
+            let uptime = std::fs::read_to_string("/proc/uptime").unwrap();
+            uptime.split_whitespace().next().unwrap().parse().unwrap()
+        };
+        let day = 86_400.0;
+        let config = NamespaceConfig::none()
+            .with_time(true)
+            .with_time_offsets(TimeOffsets {
+                boottime: 86_400,
+                ..TimeOffsets::ZERO
+            });
+        let before = host_uptime();
+        let result = NamespaceExecutor::new(config)
+            .unwrap()
+            .execute("cat", &["/proc/uptime".to_string()])
+            .unwrap();
+
+        assert_eq!(
+            result.exit_code,
+            0,
+            "{}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+        let stdout = String::from_utf8_lossy(&result.stdout);
+        let uptime: f64 = stdout.split_whitespace().next().unwrap().parse().unwrap();
+        assert!(uptime >= before + day, "{stdout}");
+        assert!(uptime < host_uptime() + day + 1.0, "{stdout}");
+    }
+
+    #[test]
+    fn test_duplicate_tmpfs_target() {
+        let config = NamespaceConfig::none()
+            .with_mount(true)
+            .with_private_tmp(MemorySize::from_mb(16));
+        let executor = NamespaceExecutor::new(config)
+            .unwrap()
+            .with_tmpfs(vec!["/tmp:size=1m".parse().unwrap()]);
+        let err = executor.execute("true", &[]).unwrap_err();
+        assert!(err.to_string().contains("Duplicate tmpfs"), "{err}");
+    }
+
+    #[test]
+    #[ignore = "requires root privileges"]
+    fn test_private_tmp_is_invisible_on_host() {
+        let marker = format!("vortex-tmpfs-marker-{}", std::process::id());
+        let config = NamespaceConfig::none()
+            .with_mount(true)
+            .with_private_tmp(MemorySize::from_mb(1));
+        let executor = NamespaceExecutor::new(config).unwrap();
+        let result = executor
+            .execute(
+                "/bin/sh",
+                &[
+                    "-c".to_string(),
+                    format!("echo hi > /tmp/{marker} && cat /tmp/{marker} && df -k /tmp"),
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(
+            result.exit_code,
+            0,
+            "{}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+        let stdout = String::from_utf8_lossy(&result.stdout);
+        assert!(stdout.starts_with("hi\n"), "{stdout}");
+        // 1 MB tmpfs reported in KB blocks
+        assert!(stdout.contains(" 1024 "), "{stdout}");
+        assert!(!Path::new("/tmp").join(&marker).exists());
+    }
+
+    #[test]
+    #[ignore = "requires root privileges"]
+    fn test_prestart_hook_runs_before_exec() {
+        let marker = std::env::temp_dir().join(format!("vortex-hook-{}", std::process::id()));
+        let hook = Hook {
+            args: vec!["-c".to_string(), format!("cat > {}", marker.display())],
+            ..Hook::new("/bin/sh")
+        };
+        let executor = NamespaceExecutor::new(NamespaceConfig::minimal())
+            .unwrap()
+            .with_prestart_hooks(vec![hook], HookState::new("hooked"));
+
+        // The program only starts once the hook has written the marker
+        let result = executor
+            .execute("/bin/cat", &[marker.display().to_string()])
+            .unwrap();
+
+        assert_eq!(
+            result.exit_code,
+            0,
+            "{}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+        let state = String::from_utf8_lossy(&result.stdout);
+        assert!(
+            state.contains(r#""id":"hooked","status":"created","pid":"#),
+            "{state}"
+        );
+
+        std::fs::remove_file(&marker).unwrap();
+    }
+
+    #[test]
+    fn test_failing_prestart_hook_stops_the_child() {
+        let marker = std::env::temp_dir().join(format!("vortex-no-exec-{}", std::process::id()));
+        let executor = NamespaceExecutor::new(NamespaceConfig::none())
+            .unwrap()
+            .with_prestart_hooks(vec![Hook::new("false")], HookState::new("hooked"));
+
+        let err = executor
+            .execute("/bin/touch", &[marker.display().to_string()])
+            .unwrap_err();
+        assert!(err.to_string().contains("Hook false failed"), "{err}");
+        assert!(!marker.exists());
+    }
+
     #[test]
     #[ignore] // Requires root privileges
     fn test_execution_failure() {
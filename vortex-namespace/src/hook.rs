@@ -0,0 +1,364 @@
+//! Lifecycle hooks: external commands run around the container
+//!
+//! Hooks follow the OCI runtime model: each is a program that gets the
+//! container state as JSON on stdin. `prestart` hooks run in the parent once
+//! the child has created its namespaces, while the child waits before exec,
+//! so they can for example move a network interface into the new network
+//! namespace of `pid`. `poststop` hooks run after the container is cleaned
+//! up. This keeps integrations such as networking or volume provisioning
+//! out of the runtime itself.
+
+use nix::fcntl::OFlag;
+use nix::unistd::pipe2;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use vortex_core::{Error, Result};
+
+/// OCI runtime spec version reported in [`HookState`]
+const OCI_VERSION: &str = "1.0.2";
+
+/// How often a running hook is checked for exit
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// An external command run at a point of the container lifecycle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hook {
+    /// Program to run
+    pub path: PathBuf,
+    /// Arguments after the program name
+    pub args: Vec<String>,
+    /// Environment in `KEY=VALUE` form (default: the caller's environment)
+    pub env: Vec<String>,
+    /// How long the hook may run before it is killed
+    pub timeout: Duration,
+    /// Whether a failing hook fails the run, instead of only being logged
+    pub abort_on_failure: bool,
+}
+
+impl Hook {
+    /// Time a hook may run unless set otherwise
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// A hook running `path` without arguments that aborts the run on
+    /// failure
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            args: Vec::new(),
+            env: Vec::new(),
+            timeout: Self::DEFAULT_TIMEOUT,
+            abort_on_failure: true,
+        }
+    }
+
+    /// Run the hook with `state` on stdin and wait for it to exit
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidConfig`] if the hook cannot be started, exits
+    /// unsuccessfully or runs longer than its timeout
+    pub fn run(&self, state: &HookState) -> Result<()> {
+        let fail = |reason: String| Error::InvalidConfig {
+            message: format!("Hook {} {reason}", self.path.display()),
+        };
+
+        let mut command = Command::new(&self.path);
+        command.args(&self.args).stdin(Stdio::piped());
+        if !self.env.is_empty() {
+            command.env_clear().envs(
+                self.env
+                    .iter()
+                    .map(|var| var.split_once('=').unwrap_or((var.as_str(), ""))),
+            );
+        }
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| fail(format!("failed to start: {e}")))?;
+
+        // A hook that ignores its stdin makes the write fail with EPIPE
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(state.to_json().as_bytes());
+        }
+
+        let deadline = Instant::now() + self.timeout;
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if Instant::now() >= deadline => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(fail(format!("timed out after {:?}", self.timeout)));
+                }
+                Ok(None) => std::thread::sleep(POLL_INTERVAL),
+                Err(e) => return Err(fail(format!("could not be waited for: {e}"))),
+            }
+        };
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(fail(format!("failed with {status}")))
+        }
+    }
+}
+
+/// Parse a command line split at whitespace, e.g.
+/// `/usr/libexec/setup-net --bridge br0`
+impl FromStr for Hook {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut words = s.split_whitespace();
+        let Some(path) = words.next() else {
+            return Err(Error::InvalidConfig {
+                message: "Hook command is empty".to_string(),
+            });
+        };
+
+        Ok(Self {
+            args: words.map(str::to_string).collect(),
+            ..Self::new(path)
+        })
+    }
+}
+
+/// Container state passed to hooks on stdin, in the OCI state format
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookState {
+    oci_version: &'static str,
+    /// Container ID
+    pub id: String,
+    /// `created` for prestart hooks, `stopped` for poststop hooks
+    pub status: &'static str,
+    /// Container process, while it exists
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<i32>,
+    /// Bundle directory the container was started from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bundle: Option<PathBuf>,
+}
+
+impl HookState {
+    /// State of a container that has not started yet
+    #[must_use]
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            oci_version: OCI_VERSION,
+            id: id.into(),
+            status: "creating",
+            pid: None,
+            bundle: None,
+        }
+    }
+
+    /// Set the bundle directory
+    #[must_use]
+    pub fn with_bundle(mut self, bundle: impl Into<PathBuf>) -> Self {
+        self.bundle = Some(bundle.into());
+        self
+    }
+
+    /// The state once the container process exists and waits before exec
+    #[must_use]
+    pub fn created(&self, pid: i32) -> Self {
+        Self {
+            status: "created",
+            pid: Some(pid),
+            ..self.clone()
+        }
+    }
+
+    /// The state once the container is gone
+    #[must_use]
+    pub fn stopped(&self) -> Self {
+        Self {
+            status: "stopped",
+            pid: None,
+            ..self.clone()
+        }
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Run hooks in order
+///
+/// A failing hook with [`Hook::abort_on_failure`] stops the remaining ones;
+/// other failures are logged.
+///
+/// # Errors
+/// Returns the error of the first failing hook that aborts on failure
+pub fn run_hooks(hooks: &[Hook], state: &HookState) -> Result<()> {
+    for hook in hooks {
+        tracing::debug!(hook = %hook.path.display(), status = state.status, "Running hook");
+        match hook.run(state) {
+            Ok(()) => {}
+            Err(e) if hook.abort_on_failure => return Err(e),
+            Err(e) => tracing::warn!(error = %e, "Ignoring failed hook"),
+        }
+    }
+    Ok(())
+}
+
+/// Parent end of the pipes that hold the child before exec until the parent
+/// has checked its namespaces and run the prestart hooks
+pub(crate) struct ParentGate {
+    ready: File,
+    release: File,
+}
+
+/// Child end of the pipes, see [`ParentGate`]
+pub(crate) struct ChildGate {
+    ready: File,
+    release: File,
+}
+
+/// Create both ends before fork; the pipes are closed on exec so they never
+/// leak into the container or a hook
+pub(crate) fn gate() -> Result<(ParentGate, ChildGate)> {
+    let pipe = || {
+        pipe2(OFlag::O_CLOEXEC).map_err(|e| Error::Namespace {
+            message: format!("Failed to create pipe: {e}"),
+        })
+    };
+    let (ready_read, ready_write) = pipe()?;
+    let (release_read, release_write) = pipe()?;
+
+    Ok((
+        ParentGate {
+            ready: ready_read.into(),
+            release: release_write.into(),
+        },
+        ChildGate {
+            ready: ready_write.into(),
+            release: release_read.into(),
+        },
+    ))
+}
+
+impl ParentGate {
+    /// Wait for the child to be ready, run `prepare` and let it continue
+    ///
+    /// If the child gives up before it is ready nothing runs, and its own
+    /// error is reported when it is waited for. If `prepare` fails, e.g.
+    /// because a hook did, the child is not released and exits on its own.
+    pub(crate) fn release_after<T: Default>(
+        self,
+        prepare: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        let Self {
+            mut ready,
+            mut release,
+        } = self;
+
+        // read_exact retries reads interrupted by a signal
+        let mut byte = [0u8; 1];
+        if ready.read_exact(&mut byte).is_err() {
+            return Ok(T::default());
+        }
+
+        let prepared = prepare()?;
+        release.write_all(&byte).map_err(|e| Error::Namespace {
+            message: format!("Failed to release container process: {e}"),
+        })?;
+        Ok(prepared)
+    }
+}
+
+impl ChildGate {
+    /// Tell the parent the namespaces exist and wait for its hooks
+    pub(crate) fn wait(self) -> Result<()> {
+        let Self {
+            mut ready,
+            mut release,
+        } = self;
+
+        let mut byte = [0u8; 1];
+        ready.write_all(&byte)?;
+        drop(ready);
+
+        match release.read_exact(&mut byte) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(Error::Namespace {
+                message: "A prestart hook failed".to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hook() {
+        let hook: Hook = "/usr/libexec/setup-net --bridge br0".parse().unwrap();
+        assert_eq!(hook.path, PathBuf::from("/usr/libexec/setup-net"));
+        assert_eq!(hook.args, vec!["--bridge", "br0"]);
+        assert_eq!(hook.timeout, Hook::DEFAULT_TIMEOUT);
+        assert!(hook.abort_on_failure);
+
+        assert!("  ".parse::<Hook>().is_err());
+    }
+
+    #[test]
+    fn test_state_json() {
+        let state = HookState::new("web").with_bundle("/srv/web");
+        assert_eq!(
+            state.created(42).to_json(),
+            r#"{"ociVersion":"1.0.2","id":"web","status":"created","pid":42,"bundle":"/srv/web"}"#
+        );
+        assert_eq!(
+            HookState::new("web").stopped().to_json(),
+            r#"{"ociVersion":"1.0.2","id":"web","status":"stopped"}"#
+        );
+    }
+
+    #[test]
+    fn test_hook_gets_state_on_stdin() {
+        let hook = Hook {
+            args: vec![
+                "-c".to_string(),
+                "if grep -q '\"pid\":7'; then exit 3; fi".to_string(),
+            ],
+            ..Hook::new("/bin/sh")
+        };
+        let err = hook.run(&HookState::new("web").created(7)).unwrap_err();
+        assert!(err.to_string().contains("exit status: 3"), "{err}");
+        assert!(hook.run(&HookState::new("web").created(8)).is_ok());
+    }
+
+    #[test]
+    fn test_hook_timeout() {
+        let hook = Hook {
+            args: vec!["5".to_string()],
+            timeout: Duration::from_millis(50),
+            ..Hook::new("sleep")
+        };
+        let started = Instant::now();
+        let err = hook.run(&HookState::new("web")).unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_failing_hook_aborts_unless_optional() {
+        let state = HookState::new("web");
+        let optional = Hook {
+            abort_on_failure: false,
+            ..Hook::new("false")
+        };
+        assert!(run_hooks(&[optional, Hook::new("true")], &state).is_ok());
+        assert!(run_hooks(&[Hook::new("false"), Hook::new("true")], &state).is_err());
+        assert!(run_hooks(&[Hook::new("/nonexistent/hook")], &state).is_err());
+    }
+}
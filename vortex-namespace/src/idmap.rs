@@ -0,0 +1,320 @@
+//! UID/GID mappings for user namespaces, checked before they are written
+//!
+//! Only a privileged process may write arbitrary ranges to
+//! `/proc/<pid>/uid_map` and `gid_map`. Anyone else may map their own ID,
+//! and larger ranges go through the setuid `newuidmap`/`newgidmap` helpers,
+//! which only allow what `/etc/subuid` and `/etc/subgid` delegate to the
+//! user. A mapping outside those ranges fails with a bare `EPERM`, so
+//! [`validate_id_maps`] checks it up front and says which range is missing,
+//! before [`write_id_maps`] writes it.
+
+use nix::unistd::{Gid, Uid, User as Passwd};
+use std::fmt;
+use std::str::FromStr;
+use vortex_core::{Error, Result};
+
+/// Whether a mapping is for user or group IDs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdKind {
+    /// User IDs (`uid_map`, `/etc/subuid`)
+    Uid,
+    /// Group IDs (`gid_map`, `/etc/subgid`)
+    Gid,
+}
+
+impl IdKind {
+    /// File listing the subordinate IDs delegated to each user
+    #[must_use]
+    pub const fn subid_file(self) -> &'static str {
+        match self {
+            Self::Uid => "/etc/subuid",
+            Self::Gid => "/etc/subgid",
+        }
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Uid => "uid",
+            Self::Gid => "gid",
+        }
+    }
+
+    /// Setuid helper writing the map for an unprivileged caller
+    const fn helper(self) -> &'static str {
+        match self {
+            Self::Uid => "newuidmap",
+            Self::Gid => "newgidmap",
+        }
+    }
+}
+
+/// One line of a `uid_map` or `gid_map`: `size` IDs starting at
+/// `container_id` inside the namespace map to those starting at `host_id`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdMap {
+    /// First ID inside the namespace
+    pub container_id: u32,
+    /// First ID on the host
+    pub host_id: u32,
+    /// Number of IDs mapped
+    pub size: u32,
+}
+
+impl IdMap {
+    /// Create a mapping
+    #[must_use]
+    pub const fn new(container_id: u32, host_id: u32, size: u32) -> Self {
+        Self {
+            container_id,
+            host_id,
+            size,
+        }
+    }
+
+    /// Last host ID of the mapping, `None` for an empty one
+    fn host_end(self) -> Option<u64> {
+        (self.size > 0).then(|| u64::from(self.host_id) + u64::from(self.size) - 1)
+    }
+}
+
+impl fmt::Display for IdMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.container_id, self.host_id, self.size)
+    }
+}
+
+/// Parse `container_id:host_id:size`, e.g. `0:100000:65536`
+impl FromStr for IdMap {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidConfig {
+            message: format!("Invalid ID mapping '{s}', expected container_id:host_id:size"),
+        };
+
+        let mut parts = s
+            .split(':')
+            .map(|part| part.parse::<u32>().map_err(|_| invalid()));
+        let (Some(container_id), Some(host_id), Some(size), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        Ok(Self::new(container_id?, host_id?, size?))
+    }
+}
+
+/// A range of subordinate IDs delegated to a user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubIdRange {
+    /// First delegated ID
+    pub start: u32,
+    /// Number of delegated IDs
+    pub count: u32,
+}
+
+impl SubIdRange {
+    fn contains(self, map: &IdMap) -> bool {
+        let range = u64::from(self.start)..u64::from(self.start) + u64::from(self.count);
+        map.host_end()
+            .is_none_or(|last| range.contains(&u64::from(map.host_id)) && range.contains(&last))
+    }
+}
+
+/// Ranges delegated to a user in the contents of `/etc/subuid` or
+/// `/etc/subgid`
+///
+/// Lines are `owner:start:count`, where the owner is a user name or a
+/// numeric ID. Comments, blank and malformed lines are skipped.
+#[must_use]
+pub fn parse_subids(content: &str, user: &str, id: u32) -> Vec<SubIdRange> {
+    let id = id.to_string();
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.split(':');
+            let owner = parts.next()?;
+            let start = parts.next()?.parse().ok()?;
+            let count = parts.next()?.parse().ok()?;
+            (parts.next().is_none() && (owner == user || owner == id))
+                .then_some(SubIdRange { start, count })
+        })
+        .collect()
+}
+
+/// Check that the calling user may write `maps` for a new user namespace
+///
+/// Root may map anything. Other users may map their own effective ID, one
+/// ID at a time, and ranges delegated to them in [`IdKind::subid_file`].
+///
+/// # Errors
+/// Returns [`Error::InvalidConfig`] naming the first mapping that is not
+/// allowed, or the subid file if it cannot be read
+pub fn validate_id_maps(kind: IdKind, maps: &[IdMap]) -> Result<()> {
+    let uid = Uid::effective();
+    if uid.is_root() {
+        return Ok(());
+    }
+
+    let own_id = match kind {
+        IdKind::Uid => uid.as_raw(),
+        IdKind::Gid => Gid::effective().as_raw(),
+    };
+    let user = Passwd::from_uid(uid)
+        .ok()
+        .flatten()
+        .map_or_else(|| uid.to_string(), |user| user.name);
+
+    // Without the file only the caller's own ID can be mapped
+    let content = match std::fs::read_to_string(kind.subid_file()) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(Error::InvalidConfig {
+                message: format!("Failed to read {}: {e}", kind.subid_file()),
+            });
+        }
+    };
+
+    check_id_maps(kind, maps, own_id, &user, &content)
+}
+
+/// Write `maps` into the user namespace of process `pid`
+///
+/// Root writes `/proc/<pid>/uid_map` or `gid_map` itself; anyone else goes
+/// through [`IdKind::helper`]. Check the maps with [`validate_id_maps`]
+/// first for a clear error. Nothing is written for no maps.
+///
+/// # Errors
+/// Returns [`Error::Namespace`] if the map cannot be written or the helper
+/// fails
+pub(crate) fn write_id_maps(kind: IdKind, pid: i32, maps: &[IdMap]) -> Result<()> {
+    if maps.is_empty() {
+        return Ok(());
+    }
+
+    if Uid::effective().is_root() {
+        use std::fmt::Write;
+
+        // The kernel takes the whole map in a single write
+        let content = maps.iter().fold(String::new(), |mut content, map| {
+            let _ = writeln!(content, "{} {} {}", map.container_id, map.host_id, map.size);
+            content
+        });
+        let path = format!("/proc/{pid}/{}_map", kind.name());
+        return std::fs::write(&path, content).map_err(|e| Error::Namespace {
+            message: format!("Failed to write {path}: {e}"),
+        });
+    }
+
+    let output = std::process::Command::new(kind.helper())
+        .arg(pid.to_string())
+        .args(
+            maps.iter()
+                .flat_map(|map| [map.container_id, map.host_id, map.size].map(|id| id.to_string())),
+        )
+        .output()
+        .map_err(|e| Error::Namespace {
+            message: format!("Failed to run {}: {e}", kind.helper()),
+        })?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::Namespace {
+            message: format!(
+                "{} failed: {}",
+                kind.helper(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        })
+    }
+}
+
+fn check_id_maps(
+    kind: IdKind,
+    maps: &[IdMap],
+    own_id: u32,
+    user: &str,
+    subids: &str,
+) -> Result<()> {
+    let ranges = parse_subids(subids, user, own_id);
+
+    for map in maps {
+        let own = map.size == 1 && map.host_id == own_id;
+        if own || ranges.iter().any(|range| range.contains(map)) {
+            continue;
+        }
+
+        let last = map.host_end().unwrap_or_else(|| u64::from(map.host_id));
+        return Err(Error::InvalidConfig {
+            message: format!(
+                "{} {}-{} not in {} for user {user}",
+                kind.name(),
+                map.host_id,
+                last,
+                kind.subid_file()
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUBUID: &str = "# delegated ranges\n\
+                          alice:100000:65536\n\
+                          1001:300000:65536\n\
+                          bob:200000:65536\n\
+                          broken:line\n";
+
+    #[test]
+    fn test_parse_subids_by_name_and_id() {
+        assert_eq!(
+            parse_subids(SUBUID, "alice", 1000),
+            vec![SubIdRange {
+                start: 100_000,
+                count: 65536
+            }]
+        );
+        assert_eq!(parse_subids(SUBUID, "carol", 1001)[0].start, 300_000);
+        assert!(parse_subids(SUBUID, "dave", 1002).is_empty());
+    }
+
+    #[test]
+    fn test_parse_id_map() {
+        let map: IdMap = "0:100000:65536".parse().unwrap();
+        assert_eq!(map, IdMap::new(0, 100_000, 65536));
+        assert_eq!(map.to_string(), "0:100000:65536");
+        assert!("0:100000".parse::<IdMap>().is_err());
+        assert!("0:100000:1:2".parse::<IdMap>().is_err());
+        assert!("root:0:1".parse::<IdMap>().is_err());
+    }
+
+    #[test]
+    fn test_maps_within_delegated_ranges() {
+        let maps = [IdMap::new(0, 1000, 1), IdMap::new(1, 100_000, 65536)];
+        assert!(check_id_maps(IdKind::Uid, &maps, 1000, "alice", SUBUID).is_ok());
+    }
+
+    #[test]
+    fn test_map_outside_delegated_ranges() {
+        // One past the end of alice's range
+        let maps = [IdMap::new(0, 100_001, 65536)];
+        let err = check_id_maps(IdKind::Uid, &maps, 1000, "alice", SUBUID).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid configuration: uid 100001-165536 not in /etc/subuid for user alice"
+        );
+
+        // Bob's range is not alice's, and only the own ID maps without one
+        let maps = [IdMap::new(0, 200_000, 10)];
+        assert!(check_id_maps(IdKind::Gid, &maps, 1000, "alice", SUBUID).is_err());
+        let maps = [IdMap::new(0, 1000, 2)];
+        assert!(check_id_maps(IdKind::Uid, &maps, 1000, "alice", "").is_err());
+    }
+}
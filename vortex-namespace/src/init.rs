@@ -0,0 +1,132 @@
+//! Minimal init for containers with a PID namespace
+//!
+//! The first process in a new PID namespace is PID 1: orphans are
+//! reparented to it, so it has to reap them, and the kernel drops every
+//! signal it has no handler for. Most programs expect neither. Like tini or
+//! Docker's `--init`, a small init sits at PID 1 instead, forwards signals
+//! to the program and reaps everything else.
+
+use nix::errno::Errno;
+use nix::sched::{CloneFlags, unshare};
+use nix::sys::signal::{SaFlags, SigAction, SigHandler, SigSet, SigmaskHow, Signal};
+use nix::sys::signal::{sigaction, sigprocmask};
+use nix::sys::wait::{WaitStatus, waitpid};
+use nix::unistd::{ForkResult, Pid, fork};
+use std::sync::atomic::{AtomicI32, Ordering};
+use vortex_core::{Error, Result};
+
+/// Signals passed on to the supervised child
+const FORWARDED: [Signal; 7] = [
+    Signal::SIGHUP,
+    Signal::SIGINT,
+    Signal::SIGQUIT,
+    Signal::SIGTERM,
+    Signal::SIGUSR1,
+    Signal::SIGUSR2,
+    Signal::SIGWINCH,
+];
+
+/// PID the signal handler forwards to, 0 until the child exists
+static FORWARD_TO: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn forward(signal: libc::c_int) {
+    let pid = FORWARD_TO.load(Ordering::Relaxed);
+    if pid > 0 {
+        unsafe {
+            libc::kill(pid, signal);
+        }
+    }
+}
+
+/// Create a PID namespace with an init at PID 1 and return in the program
+/// process, PID 2 of the namespace
+///
+/// `unshare` only moves later children into the namespace, so the calling
+/// process stays outside it and waits for the init, which in turn waits for
+/// the program. Both forward signals to their child and exit with the
+/// program's status, `128 + signal` if it was killed.
+pub fn start() -> Result<()> {
+    unshare(CloneFlags::CLONE_NEWPID).map_err(|e| Error::Namespace {
+        message: format!("Failed to create PID namespace: {e}"),
+    })?;
+
+    // The first child is PID 1, the second the program
+    fork_supervised()?;
+    fork_supervised()
+}
+
+/// Fork and return in the child; the parent supervises it and never returns
+fn fork_supervised() -> Result<()> {
+    let fail = |e: Errno| Error::Namespace {
+        message: format!("Failed to start init: {e}"),
+    };
+
+    // Hold signals back until the handler knows where to send them
+    let forwarded: SigSet = FORWARDED.into_iter().collect();
+    let mut previous = SigSet::empty();
+    sigprocmask(SigmaskHow::SIG_BLOCK, Some(&forwarded), Some(&mut previous)).map_err(fail)?;
+
+    match unsafe { fork() } {
+        Ok(ForkResult::Child) => {
+            sigprocmask(SigmaskHow::SIG_SETMASK, Some(&previous), None).map_err(fail)?;
+            Ok(())
+        }
+        Ok(ForkResult::Parent { child }) => {
+            // Setup pipes stay with the child alone, so the parent sees
+            // them close when the program execs
+            close_fds_from(3);
+
+            FORWARD_TO.store(child.as_raw(), Ordering::Relaxed);
+            let action = SigAction::new(
+                SigHandler::Handler(forward),
+                SaFlags::SA_RESTART,
+                SigSet::empty(),
+            );
+            for signal in FORWARDED {
+                let _ = unsafe { sigaction(signal, &action) };
+            }
+            let _ = sigprocmask(SigmaskHow::SIG_SETMASK, Some(&previous), None);
+
+            let code = reap_until(child);
+            unsafe { libc::_exit(code) }
+        }
+        Err(e) => {
+            let _ = sigprocmask(SigmaskHow::SIG_SETMASK, Some(&previous), None);
+            Err(fail(e))
+        }
+    }
+}
+
+/// Reap every child until `main` exits and return its exit code
+fn reap_until(main: Pid) -> i32 {
+    loop {
+        match waitpid(Pid::from_raw(-1), None) {
+            Ok(WaitStatus::Exited(pid, code)) if pid == main => return code,
+            Ok(WaitStatus::Signaled(pid, signal, _)) if pid == main => return 128 + signal as i32,
+            // An orphan reparented to us, or a stop
+            Ok(_) | Err(Errno::EINTR) => {}
+            // No children left without `main` having been seen
+            Err(_) => return 1,
+        }
+    }
+}
+
+/// Close every file descriptor from `first` up
+fn close_fds_from(first: libc::c_int) {
+    let closed = unsafe {
+        libc::syscall(
+            libc::SYS_close_range,
+            first.unsigned_abs(),
+            libc::c_uint::MAX,
+            0,
+        )
+    };
+    if closed != 0 {
+        // Kernels before 5.9 lack close_range
+        for fd in first..1024 {
+            unsafe {
+                libc::close(fd);
+            }
+        }
+    }
+}
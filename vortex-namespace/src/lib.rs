@@ -7,14 +7,45 @@
 //! - UTS namespace - Hostname isolation
 //! - IPC namespace - Inter-process communication isolation
 //! - User namespace - UID/GID mapping
+//! - Time namespace - Monotonic and boot clock offsets
+//!
+//! It also switches the child to its own root filesystem (optionally a
+//! writable overlay), mounts private tmpfs such as a size-capped `/tmp`,
+//! applies per-process resource limits (`setrlimit`), the scheduling policy
+//! and nice value, the OOM score adjustment and the user, groups and umask, can put a minimal init at
+//! PID 1 to reap zombies, and can move the calling process into a running
+//! container with [`NamespaceManager::join`].
+//! Lifecycle [`Hook`]s let external programs take part in container setup.
+//! A command that runs past its timeout is stopped as a
+//! [`TerminationConfig`] says.
 
 #![warn(missing_docs, clippy::all, clippy::pedantic, clippy::nursery)]
 #![allow(clippy::module_name_repetitions, clippy::missing_errors_doc)]
 
 pub mod config;
 pub mod executor;
+pub mod hook;
+pub mod idmap;
+mod init;
 pub mod manager;
+pub mod oom;
+pub mod rlimit;
+pub mod rootfs;
+pub mod sched;
+pub mod terminate;
+pub mod tmpfs;
+mod tty;
+pub mod user;
 
-pub use config::{NamespaceConfig, NamespaceFlags};
-pub use executor::{ExecutionResult, NamespaceExecutor};
-pub use manager::NamespaceManager;
+pub use config::{NamespaceConfig, NamespaceFlags, TimeOffsets};
+pub use executor::{DetachedExit, ExecutionResult, NamespaceExecutor, Stdio};
+pub use hook::{Hook, HookState};
+pub use idmap::{IdKind, IdMap};
+pub use manager::{NamespaceId, NamespaceKind, NamespaceManager};
+pub use oom::OomScoreAdj;
+pub use rlimit::Rlimit;
+pub use rootfs::RootfsSpec;
+pub use sched::{SchedPolicy, Scheduling};
+pub use terminate::{Termination, TerminationConfig};
+pub use tmpfs::TmpfsMount;
+pub use user::User;
@@ -1,10 +1,29 @@
 //! Namespace lifecycle management
 
-use nix::sched::{unshare, CloneFlags};
+use nix::sched::{CloneFlags, setns, unshare};
 use nix::unistd::sethostname;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
 use vortex_core::{Error, Result};
 
-use crate::config::NamespaceConfig;
+use crate::config::{NamespaceConfig, TimeOffsets};
+
+/// Namespaces in the order [`NamespaceManager::enter`] joins them
+///
+/// User comes first so the rest are joined with the capabilities it grants;
+/// mount comes last because it changes what the others' paths resolve to.
+const JOIN_ORDER: &[(&str, CloneFlags)] = &[
+    ("user", CloneFlags::CLONE_NEWUSER),
+    ("ipc", CloneFlags::CLONE_NEWIPC),
+    ("uts", CloneFlags::CLONE_NEWUTS),
+    ("net", CloneFlags::CLONE_NEWNET),
+    ("pid", CloneFlags::CLONE_NEWPID),
+    ("cgroup", CloneFlags::CLONE_NEWCGROUP),
+    ("time", CloneFlags::from_bits_retain(libc::CLONE_NEWTIME)),
+    ("mnt", CloneFlags::CLONE_NEWNS),
+];
 
 /// Namespace manager for creating and managing namespaces
 #[derive(Debug)]
@@ -45,9 +64,30 @@ impl NamespaceManager {
     ///
     /// This calls unshare(2) to create new namespaces for the current process.
     /// Note: PID namespace isolation requires forking - current process won't have PID 1.
+    /// The time namespace is similar: the current process stays in the old
+    /// one, and only children forked afterwards, or the process itself once
+    /// it calls execve(2), see the shifted clocks. Its
+    /// [offsets](NamespaceConfig::with_time_offsets) are set right after
+    /// unsharing, since the kernel refuses them once a process is inside.
+    ///
+    /// unshare(2) creates all namespaces or none, but a namespace cannot be
+    /// left once entered. If a step after it fails, such as setting the
+    /// hostname, the process stays in the half-configured namespaces and
+    /// [`Error::NamespaceSetup`] lists them; the caller should exit instead
+    /// of carrying on. [`crate::NamespaceExecutor`] only calls this in the
+    /// forked child, which does exactly that.
+    ///
+    /// Before unsharing, each requested namespace is checked against
+    /// `/proc/self/ns`, so a kernel built without one is reported by name
+    /// rather than as a bare `EINVAL`.
     ///
     /// # Errors
-    /// Returns error if namespace creation fails (typically due to permissions)
+    /// Returns [`Error::NamespaceUnsupported`] if the kernel does not offer
+    /// a requested namespace, [`Error::Namespace`] if namespace creation
+    /// fails (typically due to permissions) and [`Error::NamespaceSetup`]
+    /// if a later step, such as setting the hostname or the time offsets,
+    /// does
+    #[tracing::instrument(skip(self), fields(namespaces = ?self.config.enabled_namespaces()))]
     pub fn create(&mut self) -> Result<()> {
         if self.created {
             tracing::warn!("Namespaces already created");
@@ -59,6 +99,8 @@ impl NamespaceManager {
             return Ok(());
         }
 
+        self.check_supported(Path::new("/proc"), nix::unistd::Uid::effective().is_root())?;
+
         // If PID namespace is requested, we need special handling
         // because you can't unshare PID namespace for current process
         // Only child processes will have new PID namespace
@@ -93,14 +135,38 @@ impl NamespaceManager {
         }
 
         tracing::debug!("Namespaces created successfully");
+        self.created = true;
 
-        // Configure UTS namespace if enabled
-        if self.config.uts {
-            self.setup_uts()?;
+        // Configure UTS namespace if enabled, and shift the clocks of the
+        // time namespace before anything enters it
+        let setup = if self.config.uts {
+            self.setup_uts()
+        } else {
+            Ok(())
+        };
+        let setup = setup.and_then(|()| {
+            let offsets = &self.config.time_offsets;
+            if self.config.time && !offsets.is_zero() {
+                Self::set_time_offsets(std::process::id(), offsets)
+            } else {
+                Ok(())
+            }
+        });
+        if let Err(e) = setup {
+            // The PID and time namespaces are only entered by children
+            return Err(Error::NamespaceSetup {
+                created: enabled
+                    .iter()
+                    .filter(|&&ns| ns != "pid" && ns != "time")
+                    .map(|&ns| ns.to_string())
+                    .collect(),
+                message: match e {
+                    Error::Namespace { message } => message,
+                    e => e.to_string(),
+                },
+            });
         }
 
-        self.created = true;
-
         if has_pid_ns {
             tracing::info!(
                 namespaces = ?enabled,
@@ -115,6 +181,82 @@ impl NamespaceManager {
 
         Ok(())
     }
+
+    /// Check that the kernel, with procfs mounted at `proc`, can create
+    /// every configured namespace
+    ///
+    /// A kernel lists the namespace types it was built with as links in
+    /// `/proc/self/ns`. Debian-derived kernels can also forbid user
+    /// namespaces to everyone but root through the
+    /// `kernel.unprivileged_userns_clone` sysctl, which only matters when
+    /// not `privileged`. Without procfs nothing can be checked, and
+    /// unshare(2) is left to report what it finds.
+    fn check_supported(&self, proc: &Path, privileged: bool) -> Result<()> {
+        if !proc.join("self").exists() {
+            tracing::debug!(proc = %proc.display(), "procfs not mounted, skipping namespace checks");
+            return Ok(());
+        }
+
+        let ns_dir = proc.join("self").join("ns");
+        for name in self.config.enabled_namespaces() {
+            let link = ns_dir.join(name);
+            if link.symlink_metadata().is_err() {
+                return Err(Error::NamespaceUnsupported {
+                    namespace: name.to_string(),
+                    reason: format!(
+                        "the kernel has no {}; it was built without this namespace type",
+                        link.display()
+                    ),
+                });
+            }
+        }
+
+        if self.config.user && !privileged {
+            let sysctl = proc.join("sys/kernel/unprivileged_userns_clone");
+            // Kernels without the patch have no such file and allow it
+            if std::fs::read_to_string(&sysctl).is_ok_and(|value| value.trim() == "0") {
+                return Err(Error::NamespaceUnsupported {
+                    namespace: "user".to_string(),
+                    reason: "unprivileged user namespaces are disabled by \
+                             kernel.unprivileged_userns_clone; set it to 1 or run as root"
+                        .to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Shift the clocks of the time namespace process `pid` gives its
+    /// children, by writing `/proc/<pid>/timens_offsets`
+    ///
+    /// This only works between unshare(2) of a time namespace and the first
+    /// process entering it, and needs `CAP_SYS_TIME`.
+    ///
+    /// # Errors
+    /// Returns [`Error::Namespace`] if the kernel refuses the offsets
+    pub fn set_time_offsets(pid: u32, offsets: &TimeOffsets) -> Result<()> {
+        tracing::debug!(
+            pid,
+            monotonic = offsets.monotonic,
+            boottime = offsets.boottime,
+            "Setting time namespace offsets"
+        );
+
+        let path = format!("/proc/{pid}/timens_offsets");
+        std::fs::write(&path, offsets.to_proc_format()).map_err(|e| {
+            let hint = match e.raw_os_error() {
+                Some(libc::EPERM) => "; it needs CAP_SYS_TIME",
+                Some(libc::EACCES) => "; a process already entered the time namespace",
+                Some(libc::EINVAL) => "; an offset may take a clock below zero",
+                _ => "",
+            };
+            Error::Namespace {
+                message: format!("Failed to write {path}: {e}{hint}"),
+            }
+        })
+    }
+
     fn setup_uts(&self) -> Result<()> {
         // Set hostname if configured
         if let Some(ref hostname) = self.config.hostname {
@@ -159,16 +301,72 @@ impl NamespaceManager {
 
         Ok(())
     }
-    /// Enter existing namespaces (for joining a container)
+    /// Enter the namespaces of process `pid` (for joining a container)
+    ///
+    /// Only the namespaces enabled in the config are entered, and ones the
+    /// caller already shares with `pid` are skipped. Joining the PID
+    /// namespace only affects children forked afterwards: the caller keeps
+    /// its own PID. Joining a mount, user or time namespace fails in a
+    /// multithreaded process.
     ///
     /// # Errors
-    /// Returns error if setns fails
-    pub fn enter(&self, _pid: i32) -> Result<()> {
-        // TODO: Implement namespace entering with setns(2)
-        tracing::warn!("Namespace entering not yet implemented");
+    /// Returns error if a namespace cannot be opened or setns fails
+    pub fn enter(&self, pid: i32) -> Result<()> {
+        let wanted = self.config.to_clone_flags();
+
+        // Open everything first; after switching the mount namespace the
+        // target's /proc entries may no longer be reachable
+        let mut targets = Vec::new();
+        for &(name, flag) in JOIN_ORDER {
+            if !wanted.contains(flag) {
+                continue;
+            }
+
+            let path = format!("/proc/{pid}/ns/{name}");
+            let target = std::fs::read_link(&path).map_err(|e| Error::Namespace {
+                message: format!("Failed to read {name} namespace of process {pid}: {e}"),
+            })?;
+            let current = std::fs::read_link(format!("/proc/self/ns/{name}")).ok();
+            if current.as_ref() == Some(&target) {
+                continue;
+            }
+
+            let file = std::fs::File::open(&path).map_err(|e| Error::Namespace {
+                message: format!("Failed to open {path}: {e}"),
+            })?;
+            targets.push((name, flag, file));
+        }
+
+        for (name, flag, file) in targets {
+            setns(&file, flag).map_err(|e| Error::Namespace {
+                message: format!("Failed to enter {name} namespace of process {pid}: {e}"),
+            })?;
+            tracing::debug!(pid, namespace = name, "Entered namespace");
+        }
+
         Ok(())
     }
 
+    /// Move the calling process into a running container
+    ///
+    /// `pid` is any process of the container and `cgroup` its cgroup
+    /// directory. This is `exec` without the fork: the caller joins the
+    /// cgroup, then the namespaces via [`Self::enter`], whose caveats apply.
+    /// In particular, only children spawned afterwards land in the
+    /// container's PID namespace, so call this before starting any threads
+    /// and run the actual work in a child process.
+    ///
+    /// # Errors
+    /// Returns error if the cgroup or a namespace cannot be joined
+    pub fn join(&self, pid: i32, cgroup: &Path) -> Result<()> {
+        // The cgroup path must be resolved before the mount namespace changes
+        std::fs::write(cgroup.join("cgroup.procs"), "0").map_err(|e| Error::Namespace {
+            message: format!("Failed to join cgroup {}: {e}", cgroup.display()),
+        })?;
+
+        self.enter(pid)
+    }
+
     /// Get current namespace IDs
     ///
     /// # Errors
@@ -180,54 +378,234 @@ impl NamespaceManager {
 
     /// Get namespace IDs for a specific PID
     ///
+    /// Namespaces whose link cannot be read, for example because the process
+    /// is gone, are `None`. This blocks on `/proc`; async callers should use
+    /// [`Self::namespaces_for_pid_async`].
+    ///
     /// # Errors
-    /// Returns error if reading namespace IDs fails
+    /// Returns error if procfs is not mounted at `/proc`, where no link
+    /// could be read for any process
     pub fn namespaces_for_pid(pid: u32) -> Result<NamespaceInfo> {
+        Self::namespaces_in(Path::new("/proc"), pid, false)
+    }
+
+    /// Get the namespace IDs a process gives its children
+    ///
+    /// Like [`Self::namespaces_for_pid`], but the PID and time namespaces
+    /// are read from `pid_for_children` and `time_for_children`: unsharing
+    /// them only moves later children, so a process that just did so is
+    /// still in the old ones itself.
+    ///
+    /// # Errors
+    /// Returns error if procfs is not mounted at `/proc`
+    pub fn namespaces_for_children(pid: u32) -> Result<NamespaceInfo> {
+        Self::namespaces_in(Path::new("/proc"), pid, true)
+    }
+
+    /// [`Self::namespaces_for_pid`] with procfs mounted at `proc`, reading
+    /// the PID and time namespaces of the process' children if
+    /// `for_children`
+    fn namespaces_in(proc: &Path, pid: u32, for_children: bool) -> Result<NamespaceInfo> {
         use std::fs;
 
-        let base_path = format!("/proc/{pid}/ns");
+        // procfs always has `self`, whatever process reads it
+        if !proc.join("self").exists() {
+            return Err(Error::Namespace {
+                message: format!(
+                    "procfs not mounted at {}; cannot inspect namespaces",
+                    proc.display()
+                ),
+            });
+        }
+
+        let base_path = proc.join(pid.to_string()).join("ns");
 
-        let read_ns = |name: &str| -> Result<String> {
-            let path = format!("{base_path}/{name}");
+        let read_ns = |name: &str| -> Result<NamespaceId> {
+            let path = base_path.join(name);
             fs::read_link(&path)
-                .map(|p| p.to_string_lossy().into_owned())
                 .map_err(|e| Error::Namespace {
                     message: format!("Failed to read {name} namespace: {e}"),
-                })
+                })?
+                .to_string_lossy()
+                .parse()
+        };
+
+        let (pid_link, time_link) = if for_children {
+            ("pid_for_children", "time_for_children")
+        } else {
+            ("pid", "time")
         };
 
         Ok(NamespaceInfo {
-            pid: read_ns("pid").ok(),
+            pid: read_ns(pid_link).ok(),
             net: read_ns("net").ok(),
             mnt: read_ns("mnt").ok(),
             uts: read_ns("uts").ok(),
             ipc: read_ns("ipc").ok(),
             user: read_ns("user").ok(),
             cgroup: read_ns("cgroup").ok(),
+            time: read_ns(time_link).ok(),
         })
     }
+
+    /// [`Self::namespaces_for_pid`] on the blocking thread pool, so it does
+    /// not stall the async runtime
+    ///
+    /// # Errors
+    /// Returns error if reading namespace IDs fails
+    pub async fn namespaces_for_pid_async(pid: u32) -> Result<NamespaceInfo> {
+        tokio::task::spawn_blocking(move || Self::namespaces_for_pid(pid)).await?
+    }
+}
+
+/// A namespace as named by a `/proc/<pid>/ns` link, e.g. `net:[4026531905]`
+///
+/// Two processes share a namespace exactly when their IDs are equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NamespaceId {
+    /// Namespace type, e.g. `net`
+    pub kind: String,
+    /// Inode number of the namespace
+    pub inode: u64,
+}
+
+impl fmt::Display for NamespaceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:[{}]", self.kind, self.inode)
+    }
+}
+
+/// Parse the target of a namespace link, `kind:[inode]`
+impl FromStr for NamespaceId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.split_once(":[")
+            .and_then(|(kind, rest)| {
+                let inode = rest.strip_suffix(']')?.parse().ok()?;
+                Some(Self {
+                    kind: kind.to_string(),
+                    inode,
+                })
+            })
+            .ok_or_else(|| Error::Namespace {
+                message: format!("Invalid namespace link '{s}'"),
+            })
+    }
+}
+
+/// The eight namespace types a process can be in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NamespaceKind {
+    /// Process IDs
+    Pid,
+    /// Network stack
+    Net,
+    /// Mount table
+    Mnt,
+    /// Hostname and domain name
+    Uts,
+    /// System V IPC and POSIX message queues
+    Ipc,
+    /// User and group IDs
+    User,
+    /// Cgroup root
+    Cgroup,
+    /// Monotonic and boot clock offsets
+    Time,
+}
+
+impl NamespaceKind {
+    /// Every namespace type, in the order [`NamespaceInfo`] lists them
+    pub const ALL: [Self; 8] = [
+        Self::Pid,
+        Self::Net,
+        Self::Mnt,
+        Self::Uts,
+        Self::Ipc,
+        Self::User,
+        Self::Cgroup,
+        Self::Time,
+    ];
+
+    /// Name of the namespace link in `/proc/<pid>/ns`
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Pid => "pid",
+            Self::Net => "net",
+            Self::Mnt => "mnt",
+            Self::Uts => "uts",
+            Self::Ipc => "ipc",
+            Self::User => "user",
+            Self::Cgroup => "cgroup",
+            Self::Time => "time",
+        }
+    }
+}
+
+impl fmt::Display for NamespaceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 /// Information about current namespaces
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct NamespaceInfo {
     /// PID namespace ID
-    pub pid: Option<String>,
+    pub pid: Option<NamespaceId>,
     /// Network namespace ID
-    pub net: Option<String>,
+    pub net: Option<NamespaceId>,
     /// Mount namespace ID
-    pub mnt: Option<String>,
+    pub mnt: Option<NamespaceId>,
     /// UTS namespace ID
-    pub uts: Option<String>,
+    pub uts: Option<NamespaceId>,
     /// IPC namespace ID
-    pub ipc: Option<String>,
+    pub ipc: Option<NamespaceId>,
     /// User namespace ID
-    pub user: Option<String>,
+    pub user: Option<NamespaceId>,
     /// CGroup namespace ID
-    pub cgroup: Option<String>,
+    pub cgroup: Option<NamespaceId>,
+    /// Time namespace ID, `None` before Linux 5.6
+    pub time: Option<NamespaceId>,
 }
 
 impl NamespaceInfo {
+    /// ID of one namespace, `None` if its link could not be read
+    #[must_use]
+    pub const fn get(&self, kind: NamespaceKind) -> Option<&NamespaceId> {
+        match kind {
+            NamespaceKind::Pid => self.pid.as_ref(),
+            NamespaceKind::Net => self.net.as_ref(),
+            NamespaceKind::Mnt => self.mnt.as_ref(),
+            NamespaceKind::Uts => self.uts.as_ref(),
+            NamespaceKind::Ipc => self.ipc.as_ref(),
+            NamespaceKind::User => self.user.as_ref(),
+            NamespaceKind::Cgroup => self.cgroup.as_ref(),
+            NamespaceKind::Time => self.time.as_ref(),
+        }
+    }
+
+    /// Namespace types in which `self` and `other` differ
+    ///
+    /// A namespace known on one side only counts as different; one unknown
+    /// on both sides does not.
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> Vec<NamespaceKind> {
+        NamespaceKind::ALL
+            .into_iter()
+            .filter(|&kind| self.get(kind) != other.get(kind))
+            .collect()
+    }
+
+    /// Whether both are known to be in the same namespace of type `kind`
+    #[must_use]
+    pub fn shares_namespace(&self, other: &Self, kind: NamespaceKind) -> bool {
+        self.get(kind).is_some_and(|id| other.get(kind) == Some(id))
+    }
+
     /// Check if in different namespace than init (PID 1)
     ///
     /// # Errors
@@ -235,7 +613,26 @@ impl NamespaceInfo {
     pub fn is_isolated(&self) -> Result<bool> {
         let init_ns = NamespaceManager::namespaces_for_pid(1)?;
 
-        Ok(self.pid != init_ns.pid || self.net != init_ns.net || self.mnt != init_ns.mnt)
+        Ok(self.isolated_from(&init_ns))
+    }
+
+    /// [`Self::is_isolated`] without blocking the async runtime
+    ///
+    /// # Errors
+    /// Returns error if cannot read namespaces
+    pub async fn is_isolated_async(&self) -> Result<bool> {
+        let init_ns = NamespaceManager::namespaces_for_pid_async(1).await?;
+
+        Ok(self.isolated_from(&init_ns))
+    }
+
+    fn isolated_from(&self, init_ns: &Self) -> bool {
+        self.diff(init_ns).iter().any(|kind| {
+            matches!(
+                kind,
+                NamespaceKind::Pid | NamespaceKind::Net | NamespaceKind::Mnt
+            )
+        })
     }
 }
 
@@ -263,6 +660,9 @@ impl std::fmt::Display for NamespaceInfo {
         if let Some(ref cgroup) = self.cgroup {
             writeln!(f, "  CGROUP: {cgroup}")?;
         }
+        if let Some(ref time) = self.time {
+            writeln!(f, "  TIME:   {time}")?;
+        }
         Ok(())
     }
 }
@@ -290,16 +690,252 @@ mod tests {
         assert!(info.pid.is_some());
     }
 
+    #[test]
+    fn test_enter_own_namespaces_is_noop() {
+        let manager = NamespaceManager::new(NamespaceConfig::all());
+        let pid = i32::try_from(std::process::id()).unwrap();
+
+        assert!(manager.enter(pid).is_ok());
+    }
+
+    #[test]
+    fn test_enter_missing_process_fails() {
+        let manager = NamespaceManager::with_defaults();
+
+        assert!(matches!(
+            manager.enter(i32::MAX),
+            Err(Error::Namespace { .. })
+        ));
+    }
+
+    #[test]
+    #[ignore = "requires root privileges"]
+    fn test_failed_setup_names_created_namespaces() {
+        // unshare(2) of a UTS namespace only moves this test's thread, and
+        // a hostname longer than 64 bytes makes sethostname(2) fail after it
+        let config = NamespaceConfig::none()
+            .with_uts(true)
+            .with_hostname("h".repeat(65));
+        let mut manager = NamespaceManager::new(config);
+
+        match manager.create() {
+            Err(Error::NamespaceSetup { created, message }) => {
+                assert_eq!(created, vec!["uts"]);
+                assert!(message.contains("hostname"), "{message}");
+            }
+            other => panic!("expected NamespaceSetup, got {other:?}"),
+        }
+        assert!(manager.is_created());
+    }
+
     #[test]
     fn test_namespace_info_display() {
         let info = NamespaceInfo {
-            pid: Some("pid:[4026531836]".to_string()),
-            net: Some("net:[4026531905]".to_string()),
+            pid: Some("pid:[4026531836]".parse().unwrap()),
+            net: Some("net:[4026531905]".parse().unwrap()),
             ..Default::default()
         };
 
         let display = format!("{info}");
-        assert!(display.contains("PID:"));
+        assert!(display.contains("PID:    pid:[4026531836]"));
         assert!(display.contains("NET:"));
     }
+
+    #[test]
+    fn test_namespace_info_diff() {
+        let id = |link: &str| Some(link.parse::<NamespaceId>().unwrap());
+        let host = NamespaceInfo {
+            pid: id("pid:[1]"),
+            net: id("net:[2]"),
+            mnt: id("mnt:[3]"),
+            uts: id("uts:[4]"),
+            ipc: id("ipc:[5]"),
+            user: id("user:[6]"),
+            cgroup: None,
+            time: id("time:[7]"),
+        };
+        let container = NamespaceInfo {
+            pid: id("pid:[11]"),
+            net: id("net:[12]"),
+            mnt: id("mnt:[13]"),
+            user: None,
+            ..host.clone()
+        };
+
+        assert_eq!(
+            container.diff(&host),
+            vec![
+                NamespaceKind::Pid,
+                NamespaceKind::Net,
+                NamespaceKind::Mnt,
+                NamespaceKind::User
+            ]
+        );
+        assert!(host.diff(&host).is_empty());
+        assert!(container.isolated_from(&host));
+        assert!(!host.isolated_from(&host));
+
+        assert!(container.shares_namespace(&host, NamespaceKind::Uts));
+        assert!(!container.shares_namespace(&host, NamespaceKind::Net));
+        // Unknown on one or both sides is never shared
+        assert!(!container.shares_namespace(&host, NamespaceKind::User));
+        assert!(!container.shares_namespace(&host, NamespaceKind::Cgroup));
+    }
+
+    /// A fake procfs at a fresh temp dir, with `self/ns` links for `namespaces`
+    fn fake_proc(test: &str, namespaces: &[&str]) -> std::path::PathBuf {
+        let proc = std::env::temp_dir().join(format!("vortex-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&proc);
+        std::fs::create_dir_all(proc.join("self/ns")).unwrap();
+        std::fs::create_dir_all(proc.join("sys/kernel")).unwrap();
+        for name in namespaces {
+            std::os::unix::fs::symlink("ns:[1]", proc.join("self/ns").join(name)).unwrap();
+        }
+        proc
+    }
+
+    #[test]
+    fn test_unsupported_namespace_is_named() {
+        let manager = NamespaceManager::new(NamespaceConfig::none().with_uts(true).with_ipc(true));
+
+        let proc = fake_proc("ns-missing", &["uts"]);
+        match manager.check_supported(&proc, true) {
+            Err(Error::NamespaceUnsupported { namespace, reason }) => {
+                assert_eq!(namespace, "ipc");
+                assert!(reason.contains("self/ns/ipc"), "{reason}");
+            }
+            other => panic!("expected NamespaceUnsupported, got {other:?}"),
+        }
+
+        std::os::unix::fs::symlink("ipc:[2]", proc.join("self/ns/ipc")).unwrap();
+        manager.check_supported(&proc, true).unwrap();
+        std::fs::remove_dir_all(&proc).unwrap();
+
+        // Nothing is known without procfs, so unshare(2) gets to decide
+        manager
+            .check_supported(Path::new("/nonexistent-proc"), true)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_time_namespace_needs_kernel_support() {
+        // Kernels before 5.6 have no time link
+        let manager = NamespaceManager::new(NamespaceConfig::none().with_time(true));
+        let proc = fake_proc("ns-time", &["pid", "net"]);
+        let Err(Error::NamespaceUnsupported { namespace, .. }) =
+            manager.check_supported(&proc, true)
+        else {
+            panic!("a time namespace should need self/ns/time");
+        };
+        assert_eq!(namespace, "time");
+
+        std::os::unix::fs::symlink("time:[3]", proc.join("self/ns/time")).unwrap();
+        manager.check_supported(&proc, true).unwrap();
+        std::fs::remove_dir_all(&proc).unwrap();
+    }
+
+    #[test]
+    fn test_unprivileged_userns_sysctl() {
+        let manager = NamespaceManager::new(NamespaceConfig::none().with_user(true));
+        let proc = fake_proc("ns-userns", &["user"]);
+
+        // Mainline kernels have no such sysctl
+        manager.check_supported(&proc, false).unwrap();
+
+        let sysctl = proc.join("sys/kernel/unprivileged_userns_clone");
+        std::fs::write(&sysctl, "1\n").unwrap();
+        manager.check_supported(&proc, false).unwrap();
+
+        std::fs::write(&sysctl, "0\n").unwrap();
+        let Err(Error::NamespaceUnsupported { namespace, reason }) =
+            manager.check_supported(&proc, false)
+        else {
+            panic!("unprivileged user namespaces should be refused");
+        };
+        assert_eq!(namespace, "user");
+        assert!(reason.contains("unprivileged_userns_clone"), "{reason}");
+        // Root is not bound by it
+        manager.check_supported(&proc, true).unwrap();
+
+        std::fs::remove_dir_all(&proc).unwrap();
+    }
+
+    #[test]
+    fn test_missing_procfs_is_an_error() {
+        let proc = std::env::temp_dir().join(format!("vortex-no-proc-{}", std::process::id()));
+        let Err(Error::Namespace { message }) = NamespaceManager::namespaces_in(&proc, 1, false)
+        else {
+            panic!("reading namespaces without procfs should fail");
+        };
+        assert!(message.contains("procfs not mounted"), "{message}");
+
+        // A process that is gone is no error; its namespaces are unknown
+        let gone = NamespaceManager::namespaces_for_pid(u32::MAX).unwrap();
+        assert!(gone.pid.is_none() && gone.net.is_none());
+    }
+
+    #[test]
+    fn test_namespaces_for_children_match_own() {
+        // Without an unshared PID namespace children are born into ours
+        let pid = std::process::id();
+        assert_eq!(
+            NamespaceManager::namespaces_for_children(pid).unwrap(),
+            NamespaceManager::namespaces_for_pid(pid).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_namespace_id() {
+        let id: NamespaceId = "net:[4026531905]".parse().unwrap();
+        assert_eq!(id.kind, "net");
+        assert_eq!(id.inode, 4_026_531_905);
+        assert_eq!(id.to_string(), "net:[4026531905]");
+
+        for link in ["net", "net:4026531905", "net:[abc]", "net:[1"] {
+            assert!(link.parse::<NamespaceId>().is_err(), "{link} was accepted");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_child_shares_net_namespace_inode() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("5")
+            .spawn()
+            .unwrap();
+        let own = NamespaceManager::namespaces_for_pid_async(std::process::id())
+            .await
+            .unwrap();
+        let theirs = NamespaceManager::namespaces_for_pid_async(child.id())
+            .await
+            .unwrap();
+        let _ = child.kill();
+        let _ = child.wait();
+
+        let (own, theirs) = (own.net.unwrap(), theirs.net.unwrap());
+        assert_eq!(own.inode, theirs.inode);
+        assert_eq!(own, theirs);
+    }
+
+    #[test]
+    #[ignore = "requires root privileges"]
+    fn test_new_net_namespace_has_other_inode() {
+        let config = NamespaceConfig::none().with_network(true);
+        let dir = std::env::temp_dir();
+        let log = dir.join(format!("vortex-netns-{}.log", std::process::id()));
+        let pid = crate::NamespaceExecutor::new(config)
+            .unwrap()
+            .spawn_detached("sleep", &["5".to_string()], &log, &log)
+            .unwrap();
+
+        let own = NamespaceManager::namespaces_for_pid(std::process::id()).unwrap();
+        let theirs = NamespaceManager::namespaces_for_pid(u32::try_from(pid).unwrap()).unwrap();
+        let _ = nix::sys::signal::kill(
+            nix::unistd::Pid::from_raw(pid),
+            nix::sys::signal::Signal::SIGKILL,
+        );
+        let _ = std::fs::remove_file(&log);
+
+        assert_ne!(own.net.unwrap().inode, theirs.net.unwrap().inode);
+        assert_eq!(own.mnt, theirs.mnt);
+    }
 }
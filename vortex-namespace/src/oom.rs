@@ -0,0 +1,116 @@
+//! OOM killer preference of the container process (`oom_score_adj`)
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use vortex_core::{Error, Result};
+
+const PROC_FILE: &str = "/proc/self/oom_score_adj";
+
+/// Adjustment added to the process's OOM score, from -1000 to 1000
+///
+/// Higher values make the kernel more likely to kill the process under
+/// global memory pressure; -1000 exempts it. Lowering the value below the
+/// current one needs `CAP_SYS_RESOURCE`. It is inherited across fork and
+/// exec, so everything the container starts shares it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(into = "i32", try_from = "i32")]
+pub struct OomScoreAdj(i32);
+
+impl OomScoreAdj {
+    /// Lowest adjustment, which disables OOM killing for the process
+    pub const MIN: i32 = -1000;
+    /// Highest adjustment, making the process the first to be killed
+    pub const MAX: i32 = 1000;
+
+    /// Create an adjustment
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidConfig`] if `value` is outside -1000..=1000
+    pub fn new(value: i32) -> Result<Self> {
+        if (Self::MIN..=Self::MAX).contains(&value) {
+            Ok(Self(value))
+        } else {
+            Err(Error::InvalidConfig {
+                message: format!(
+                    "OOM score adjustment {value} out of range ({}..={})",
+                    Self::MIN,
+                    Self::MAX
+                ),
+            })
+        }
+    }
+
+    /// Get the adjustment value
+    #[must_use]
+    pub const fn value(self) -> i32 {
+        self.0
+    }
+
+    /// Adjustment of the calling process
+    ///
+    /// # Errors
+    /// Returns error if `/proc/self/oom_score_adj` cannot be read or parsed
+    pub fn current() -> Result<Self> {
+        let content = std::fs::read_to_string(PROC_FILE)?;
+        content.trim().parse()
+    }
+
+    /// Set the adjustment of the calling process
+    pub(crate) fn apply(self) -> Result<()> {
+        std::fs::write(PROC_FILE, self.0.to_string()).map_err(|e| Error::PermissionDenied {
+            operation: format!("setting oom_score_adj to {}: {e}", self.0),
+        })
+    }
+}
+
+impl fmt::Display for OomScoreAdj {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<OomScoreAdj> for i32 {
+    fn from(adj: OomScoreAdj) -> Self {
+        adj.0
+    }
+}
+
+impl TryFrom<i32> for OomScoreAdj {
+    type Error = Error;
+
+    fn try_from(value: i32) -> Result<Self> {
+        Self::new(value)
+    }
+}
+
+impl FromStr for OomScoreAdj {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let value = s.parse().map_err(|_| Error::InvalidConfig {
+            message: format!("Invalid OOM score adjustment '{s}', expected -1000..=1000"),
+        })?;
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range() {
+        assert_eq!(OomScoreAdj::new(-1000).unwrap().value(), -1000);
+        assert_eq!("1000".parse::<OomScoreAdj>().unwrap().value(), 1000);
+        assert!(OomScoreAdj::new(1001).is_err());
+        assert!("-1001".parse::<OomScoreAdj>().is_err());
+        assert!("low".parse::<OomScoreAdj>().is_err());
+    }
+
+    #[test]
+    fn test_current_is_in_range() {
+        let current = OomScoreAdj::current().unwrap();
+        assert!((OomScoreAdj::MIN..=OomScoreAdj::MAX).contains(&current.value()));
+    }
+}
@@ -0,0 +1,282 @@
+//! Per-process resource limits (`setrlimit`) applied to the container process
+
+use nix::sys::resource::{Resource, getrlimit, setrlimit};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use vortex_core::{Error, Result};
+use vortex_security::{Capability, CapabilitySet};
+
+/// Value meaning "no limit"
+pub const RLIM_INFINITY: u64 = libc::RLIM_INFINITY;
+
+/// A soft/hard limit pair for one resource
+///
+/// Serialized in the same `name=soft:hard` form it is parsed from, so a
+/// deserialized limit is validated like a parsed one.
+#[allow(clippy::unsafe_derive_deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Rlimit {
+    /// Resource being limited
+    pub resource: Resource,
+    /// Soft limit (enforced value)
+    pub soft: u64,
+    /// Hard limit (ceiling for the soft limit)
+    pub hard: u64,
+}
+
+impl Rlimit {
+    /// Create a new limit
+    ///
+    /// # Errors
+    /// Returns error if the soft limit is greater than the hard limit
+    pub fn new(resource: Resource, soft: u64, hard: u64) -> Result<Self> {
+        if soft > hard {
+            return Err(Error::InvalidConfig {
+                message: format!(
+                    "{} soft limit {} exceeds hard limit {}",
+                    resource_name(resource),
+                    format_value(soft),
+                    format_value(hard)
+                ),
+            });
+        }
+
+        Ok(Self {
+            resource,
+            soft,
+            hard,
+        })
+    }
+
+    /// Look up a resource by its short name (`nofile`, `nproc`, ...)
+    #[must_use]
+    pub fn resource_from_name(name: &str) -> Option<Resource> {
+        RESOURCES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, resource)| *resource)
+    }
+
+    /// Whether applying the limit raises the current hard limit, which needs
+    /// `CAP_SYS_RESOURCE`
+    ///
+    /// # Errors
+    /// Returns error if `getrlimit` fails
+    pub fn raises_hard_limit(&self) -> Result<bool> {
+        let (_, current_hard) = getrlimit(self.resource)?;
+        Ok(self.hard > current_hard)
+    }
+
+    /// Check that the limit can be applied by the current process
+    ///
+    /// Raising a hard limit above its current value needs `CAP_SYS_RESOURCE`,
+    /// so this is reported up front instead of as a failed exec.
+    ///
+    /// # Errors
+    /// Returns [`Error::PermissionDenied`] if the hard limit would be raised
+    /// without privilege
+    pub fn check(&self) -> Result<()> {
+        // An unreadable set is left for setrlimit to refuse
+        let privileged =
+            CapabilitySet::effective().map_or(true, |caps| caps.contains(Capability::SysResource));
+        self.check_privileged(privileged)
+    }
+
+    /// [`Self::check`] for a process that has `CAP_SYS_RESOURCE` or not
+    fn check_privileged(&self, privileged: bool) -> Result<()> {
+        let (_, current_hard) = getrlimit(self.resource)?;
+
+        if self.hard > current_hard && !privileged {
+            return Err(Error::PermissionDenied {
+                operation: format!(
+                    "raising {} hard limit from {} to {} (requires CAP_SYS_RESOURCE)",
+                    resource_name(self.resource),
+                    format_value(current_hard),
+                    format_value(self.hard)
+                ),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Apply the limit to the calling process
+    ///
+    /// # Errors
+    /// Returns error if `setrlimit` fails
+    pub fn apply(&self) -> Result<()> {
+        setrlimit(self.resource, self.soft, self.hard).map_err(|e| Error::Namespace {
+            message: format!("Failed to set {self}: {e}"),
+        })
+    }
+}
+
+impl fmt::Display for Rlimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}={}:{}",
+            resource_name(self.resource),
+            format_value(self.soft),
+            format_value(self.hard)
+        )
+    }
+}
+
+/// Parse `name=soft[:hard]`, e.g. `nofile=1024:2048` or `core=unlimited`
+///
+/// A single value sets both the soft and hard limit.
+impl FromStr for Rlimit {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |message: String| Error::InvalidConfig { message };
+
+        let (name, values) = s
+            .split_once('=')
+            .ok_or_else(|| invalid(format!("Invalid ulimit '{s}', expected name=soft[:hard]")))?;
+
+        let resource = Self::resource_from_name(name).ok_or_else(|| {
+            let known: Vec<&str> = RESOURCES.iter().map(|(n, _)| *n).collect();
+            invalid(format!(
+                "Unknown ulimit '{name}' (supported: {})",
+                known.join(", ")
+            ))
+        })?;
+
+        let (soft, hard) = if let Some((soft, hard)) = values.split_once(':') {
+            (parse_value(soft, s)?, parse_value(hard, s)?)
+        } else {
+            let value = parse_value(values, s)?;
+            (value, value)
+        };
+
+        Self::new(resource, soft, hard)
+    }
+}
+
+impl From<Rlimit> for String {
+    fn from(rlimit: Rlimit) -> Self {
+        rlimit.to_string()
+    }
+}
+
+impl TryFrom<String> for Rlimit {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+/// Supported resources by short name
+const RESOURCES: &[(&str, Resource)] = &[
+    ("as", Resource::RLIMIT_AS),
+    ("core", Resource::RLIMIT_CORE),
+    ("cpu", Resource::RLIMIT_CPU),
+    ("data", Resource::RLIMIT_DATA),
+    ("fsize", Resource::RLIMIT_FSIZE),
+    ("memlock", Resource::RLIMIT_MEMLOCK),
+    ("nofile", Resource::RLIMIT_NOFILE),
+    ("nproc", Resource::RLIMIT_NPROC),
+    ("stack", Resource::RLIMIT_STACK),
+];
+
+fn resource_name(resource: Resource) -> &'static str {
+    RESOURCES
+        .iter()
+        .find(|(_, r)| *r == resource)
+        .map_or("unknown", |(name, _)| *name)
+}
+
+fn parse_value(value: &str, spec: &str) -> Result<u64> {
+    if value == "unlimited" {
+        return Ok(RLIM_INFINITY);
+    }
+
+    value.parse().map_err(|_| Error::InvalidConfig {
+        message: format!("Invalid ulimit value '{value}' in '{spec}'"),
+    })
+}
+
+fn format_value(value: u64) -> String {
+    if value == RLIM_INFINITY {
+        "unlimited".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_soft_and_hard() {
+        let limit: Rlimit = "nofile=1024:2048".parse().unwrap();
+        assert_eq!(limit.resource, Resource::RLIMIT_NOFILE);
+        assert_eq!(limit.soft, 1024);
+        assert_eq!(limit.hard, 2048);
+    }
+
+    #[test]
+    fn test_parse_single_value() {
+        let limit: Rlimit = "nproc=64".parse().unwrap();
+        assert_eq!(limit.soft, 64);
+        assert_eq!(limit.hard, 64);
+    }
+
+    #[test]
+    fn test_parse_unlimited() {
+        let limit: Rlimit = "core=0:unlimited".parse().unwrap();
+        assert_eq!(limit.hard, RLIM_INFINITY);
+        assert_eq!(limit.to_string(), "core=0:unlimited");
+    }
+
+    #[test]
+    fn test_soft_above_hard_rejected() {
+        assert!("nofile=4096:1024".parse::<Rlimit>().is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!("nofile".parse::<Rlimit>().is_err());
+        assert!("bogus=1".parse::<Rlimit>().is_err());
+        assert!("nofile=abc".parse::<Rlimit>().is_err());
+    }
+
+    #[test]
+    fn test_serde_uses_parsed_form() {
+        let limit: Rlimit = "nofile=1024:2048".parse().unwrap();
+        let json = serde_json::to_string(&limit).unwrap();
+        assert_eq!(json, r#""nofile=1024:2048""#);
+        assert_eq!(serde_json::from_str::<Rlimit>(&json).unwrap(), limit);
+        assert!(serde_json::from_str::<Rlimit>(r#""nofile=4096:1024""#).is_err());
+    }
+
+    #[test]
+    fn test_check_within_current_limit() {
+        let (soft, hard) = getrlimit(Resource::RLIMIT_NOFILE).unwrap();
+        let limit = Rlimit::new(Resource::RLIMIT_NOFILE, soft.min(hard), hard).unwrap();
+        assert!(limit.check().is_ok());
+        assert!(limit.check_privileged(false).is_ok());
+    }
+
+    #[test]
+    fn test_raising_hard_limit_needs_capability() {
+        let (_, hard) = getrlimit(Resource::RLIMIT_NOFILE).unwrap();
+        if hard == RLIM_INFINITY {
+            return;
+        }
+        let limit = Rlimit::new(Resource::RLIMIT_NOFILE, hard, hard + 1).unwrap();
+
+        // Being root is not enough without the capability
+        assert!(matches!(
+            limit.check_privileged(false),
+            Err(Error::PermissionDenied { .. })
+        ));
+        assert!(limit.check_privileged(true).is_ok());
+    }
+}
@@ -0,0 +1,265 @@
+//! Root filesystem the container process sees
+//!
+//! The child switches root with `pivot_root(2)` inside its own mount
+//! namespace, so every mount made here disappears with the container.
+
+use nix::mount::{MntFlags, MsFlags, mount, umount2};
+use nix::unistd::{AccessFlags, access, pivot_root};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use vortex_core::{Error, Result};
+
+/// Directory name of the merged overlay view, next to the work directory
+pub const MERGED_DIR: &str = "merged";
+
+/// Where the container's root filesystem comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RootfsSpec {
+    /// Use a directory as the root, writes go straight to it
+    Path(PathBuf),
+    /// Writable overlay over a read-only base, which is never modified
+    ///
+    /// Writes land in `upper`; `work` is scratch space for the kernel and
+    /// must be on the same filesystem as `upper`. The merged view is
+    /// mounted at [`MERGED_DIR`] in the parent directory of `work`.
+    Overlay {
+        /// Read-only base image layer
+        lower: PathBuf,
+        /// Directory receiving the container's changes
+        upper: PathBuf,
+        /// Empty scratch directory for overlayfs
+        work: PathBuf,
+    },
+}
+
+impl RootfsSpec {
+    /// Directory that becomes `/` in the container
+    #[must_use]
+    pub fn new_root(&self) -> PathBuf {
+        match self {
+            Self::Path(path) => path.clone(),
+            Self::Overlay { work, .. } => work
+                .parent()
+                .unwrap_or_else(|| Path::new("/"))
+                .join(MERGED_DIR),
+        }
+    }
+
+    /// Check the directories before anything is forked
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidConfig`] if a directory is missing, `upper`
+    /// or `work` is not writable, or they are on different filesystems
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: String| Error::InvalidConfig { message };
+
+        match self {
+            Self::Path(path) => require_dir(path, "Root filesystem"),
+            Self::Overlay { lower, upper, work } => {
+                require_dir(lower, "Overlay lower directory")?;
+                require_dir(upper, "Overlay upper directory")?;
+                require_dir(work, "Overlay work directory")?;
+
+                for dir in [upper, work] {
+                    access(dir.as_path(), AccessFlags::W_OK)
+                        .map_err(|e| invalid(format!("{} is not writable: {e}", dir.display())))?;
+                }
+
+                if device(upper)? != device(work)? {
+                    return Err(invalid(format!(
+                        "Overlay upper {} and work {} must be on the same filesystem",
+                        upper.display(),
+                        work.display()
+                    )));
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Mount the root filesystem and make it `/` of the calling process
+    ///
+    /// Meant to run in the forked child after it has entered a new mount
+    /// namespace: host mounts are made private first so nothing propagates
+    /// back, and the old root is detached once the new one is in place.
+    ///
+    /// # Errors
+    /// Returns error if a mount or `pivot_root` fails
+    pub fn enter(&self) -> Result<()> {
+        let fail = |what: String, e: nix::Error| Error::Namespace {
+            message: format!("{what}: {e}"),
+        };
+
+        mount(
+            None::<&str>,
+            "/",
+            None::<&str>,
+            MsFlags::MS_REC | MsFlags::MS_PRIVATE,
+            None::<&str>,
+        )
+        .map_err(|e| fail("Failed to make mounts private".to_string(), e))?;
+
+        let new_root = self.new_root();
+        match self {
+            // pivot_root needs the new root to be a mount point
+            Self::Path(path) => mount(
+                Some(path.as_path()),
+                path.as_path(),
+                None::<&str>,
+                MsFlags::MS_BIND | MsFlags::MS_REC,
+                None::<&str>,
+            )
+            .map_err(|e| fail(format!("Failed to bind mount {}", path.display()), e))?,
+            Self::Overlay { lower, upper, work } => {
+                std::fs::create_dir_all(&new_root).map_err(|e| Error::Namespace {
+                    message: format!("Failed to create {}: {e}", new_root.display()),
+                })?;
+                let options = format!(
+                    "lowerdir={},upperdir={},workdir={}",
+                    lower.display(),
+                    upper.display(),
+                    work.display()
+                );
+                mount(
+                    Some("overlay"),
+                    new_root.as_path(),
+                    Some("overlay"),
+                    MsFlags::empty(),
+                    Some(options.as_str()),
+                )
+                .map_err(|e| fail(format!("Failed to mount overlay ({options})"), e))?;
+            }
+        }
+
+        // Stack the old root under the new one, then detach it
+        std::env::set_current_dir(&new_root).map_err(|e| Error::Namespace {
+            message: format!("Failed to enter {}: {e}", new_root.display()),
+        })?;
+        pivot_root(".", ".").map_err(|e| fail("Failed to pivot root".to_string(), e))?;
+        umount2(".", MntFlags::MNT_DETACH)
+            .map_err(|e| fail("Failed to detach old root".to_string(), e))?;
+        std::env::set_current_dir("/").map_err(|e| Error::Namespace {
+            message: format!("Failed to change directory to /: {e}"),
+        })?;
+
+        // The host's /proc went with the old root
+        if Path::new("/proc").is_dir() {
+            mount(
+                Some("proc"),
+                "/proc",
+                Some("proc"),
+                MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOEXEC,
+                None::<&str>,
+            )
+            .map_err(|e| fail("Failed to mount /proc".to_string(), e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove the merged overlay directory once the container has exited
+    /// (best effort)
+    ///
+    /// The mounts themselves go away with the container's mount namespace.
+    pub fn cleanup(&self) {
+        if let Self::Overlay { .. } = self {
+            let merged = self.new_root();
+            if let Err(e) = std::fs::remove_dir(&merged)
+                && e.kind() != std::io::ErrorKind::NotFound
+            {
+                tracing::debug!(
+                    path = %merged.display(),
+                    error = %e,
+                    "Could not remove merged overlay directory"
+                );
+            }
+        }
+    }
+}
+
+fn require_dir(path: &Path, what: &str) -> Result<()> {
+    if path.is_dir() {
+        Ok(())
+    } else {
+        Err(Error::InvalidConfig {
+            message: format!("{what} {} does not exist", path.display()),
+        })
+    }
+}
+
+fn device(path: &Path) -> Result<u64> {
+    std::fs::metadata(path)
+        .map(|metadata| metadata.dev())
+        .map_err(|e| Error::InvalidConfig {
+            message: format!("Failed to stat {}: {e}", path.display()),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vortex-rootfs-{name}-{}", std::process::id()));
+        for sub in ["lower", "upper", "work"] {
+            std::fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        dir
+    }
+
+    fn overlay(dir: &Path) -> RootfsSpec {
+        RootfsSpec::Overlay {
+            lower: dir.join("lower"),
+            upper: dir.join("upper"),
+            work: dir.join("work"),
+        }
+    }
+
+    #[test]
+    fn test_overlay_is_merged_next_to_work() {
+        let spec = RootfsSpec::Overlay {
+            lower: "/images/base".into(),
+            upper: "/run/vortex/web/upper".into(),
+            work: "/run/vortex/web/work".into(),
+        };
+        assert_eq!(spec.new_root(), PathBuf::from("/run/vortex/web/merged"));
+        assert_eq!(
+            RootfsSpec::Path("/srv/root".into()).new_root(),
+            PathBuf::from("/srv/root")
+        );
+    }
+
+    #[test]
+    fn test_validate_overlay() {
+        let dir = scratch("valid");
+        assert!(overlay(&dir).validate().is_ok());
+
+        std::fs::remove_dir(dir.join("lower")).unwrap();
+        let err = overlay(&dir).validate().unwrap_err();
+        assert!(err.to_string().contains("lower"), "{err}");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_validate_rejects_different_filesystems() {
+        let dir = scratch("cross-fs");
+        // Writable by anyone, and a tmpfs of its own
+        let work = PathBuf::from(format!("/dev/shm/vortex-rootfs-{}", std::process::id()));
+        std::fs::create_dir_all(&work).unwrap();
+        let spec = RootfsSpec::Overlay {
+            lower: dir.join("lower"),
+            upper: dir.join("upper"),
+            work: work.clone(),
+        };
+
+        if device(&dir).unwrap() != device(&work).unwrap() {
+            let err = spec.validate().unwrap_err();
+            assert!(err.to_string().contains("same filesystem"), "{err}");
+        }
+
+        std::fs::remove_dir_all(&work).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
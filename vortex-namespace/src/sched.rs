@@ -0,0 +1,278 @@
+//! CPU scheduling policy and nice value of the container process
+
+use nix::errno::Errno;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use vortex_core::{Error, Result};
+
+/// Linux scheduling policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchedPolicy {
+    /// The default time-sharing policy (`SCHED_OTHER`)
+    Other,
+    /// Time-sharing for CPU-bound, non-interactive work (`SCHED_BATCH`)
+    Batch,
+    /// Runs only when nothing else wants the CPU (`SCHED_IDLE`)
+    Idle,
+    /// Real-time, first in first out (`SCHED_FIFO`)
+    Fifo,
+    /// Real-time with time slices (`SCHED_RR`)
+    #[serde(rename = "rr")]
+    RoundRobin,
+}
+
+impl SchedPolicy {
+    /// Highest real-time priority Linux accepts
+    pub const MAX_PRIORITY: i32 = 99;
+
+    /// Whether the policy is real-time and takes a priority from 1 to 99
+    #[must_use]
+    pub const fn is_realtime(self) -> bool {
+        matches!(self, Self::Fifo | Self::RoundRobin)
+    }
+
+    /// The policy constant passed to `sched_setscheduler`
+    #[must_use]
+    pub const fn raw(self) -> libc::c_int {
+        match self {
+            Self::Other => libc::SCHED_OTHER,
+            Self::Batch => libc::SCHED_BATCH,
+            Self::Idle => libc::SCHED_IDLE,
+            Self::Fifo => libc::SCHED_FIFO,
+            Self::RoundRobin => libc::SCHED_RR,
+        }
+    }
+}
+
+impl fmt::Display for SchedPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Other => "other",
+            Self::Batch => "batch",
+            Self::Idle => "idle",
+            Self::Fifo => "fifo",
+            Self::RoundRobin => "rr",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for SchedPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "other" | "normal" => Ok(Self::Other),
+            "batch" => Ok(Self::Batch),
+            "idle" => Ok(Self::Idle),
+            "fifo" => Ok(Self::Fifo),
+            "rr" => Ok(Self::RoundRobin),
+            _ => Err(Error::InvalidConfig {
+                message: format!(
+                    "Unknown scheduling policy '{s}', expected other, batch, idle, fifo or rr"
+                ),
+            }),
+        }
+    }
+}
+
+/// Scheduling settings of the container process
+///
+/// Both are inherited across fork and exec, so everything the container
+/// starts shares them. Real-time policies and lowering the nice value
+/// below the current one need `CAP_SYS_NICE`.
+// Deserialized values were saved from ones `new` checked, and the kernel
+// rejects bad ones when applied anyway
+#[allow(clippy::unsafe_derive_deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Scheduling {
+    policy: Option<(SchedPolicy, i32)>,
+    nice: Option<i32>,
+}
+
+impl Scheduling {
+    /// Lowest nice value, the highest precedence
+    pub const MIN_NICE: i32 = -20;
+    /// Highest nice value, the lowest precedence
+    pub const MAX_NICE: i32 = 19;
+
+    /// Validate a policy with its priority and a nice value
+    ///
+    /// Real-time policies need a priority from 1 to 99 and ignore the nice
+    /// value, the others take priority 0. `SCHED_IDLE` ignores the nice
+    /// value as well.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidConfig`] for a combination the kernel would
+    /// reject or silently ignore
+    pub fn new(
+        policy: Option<SchedPolicy>,
+        priority: Option<i32>,
+        nice: Option<i32>,
+    ) -> Result<Self> {
+        let invalid = |message: String| Err(Error::InvalidConfig { message });
+
+        let policy = match (policy, priority) {
+            (None, Some(_)) => return invalid("A priority needs a scheduling policy".to_string()),
+            (None, None) => None,
+            (Some(policy), priority) if policy.is_realtime() => match priority {
+                Some(priority @ 1..=SchedPolicy::MAX_PRIORITY) => Some((policy, priority)),
+                Some(priority) => {
+                    return invalid(format!(
+                        "Priority {priority} out of range for {policy} (1..={})",
+                        SchedPolicy::MAX_PRIORITY
+                    ));
+                }
+                None => return invalid(format!("Policy {policy} needs a priority")),
+            },
+            (Some(policy), None | Some(0)) => Some((policy, 0)),
+            (Some(policy), Some(_)) => {
+                return invalid(format!("Policy {policy} takes no priority"));
+            }
+        };
+
+        if let Some(nice) = nice {
+            if !(Self::MIN_NICE..=Self::MAX_NICE).contains(&nice) {
+                return invalid(format!(
+                    "Nice value {nice} out of range ({}..={})",
+                    Self::MIN_NICE,
+                    Self::MAX_NICE
+                ));
+            }
+            if let Some((policy, _)) = policy
+                && (policy.is_realtime() || policy == SchedPolicy::Idle)
+            {
+                return invalid(format!("Policy {policy} ignores the nice value"));
+            }
+        }
+
+        Ok(Self { policy, nice })
+    }
+
+    /// Get the policy and its priority
+    #[must_use]
+    pub const fn policy(self) -> Option<(SchedPolicy, i32)> {
+        self.policy
+    }
+
+    /// Get the nice value
+    #[must_use]
+    pub const fn nice(self) -> Option<i32> {
+        self.nice
+    }
+
+    /// Whether applying the settings may need `CAP_SYS_NICE`
+    ///
+    /// A nice value below the caller's counts, as does any real-time policy.
+    /// Unprivileged processes can still get these within `RLIMIT_NICE` and
+    /// `RLIMIT_RTPRIO`, so this errs on the side of asking.
+    #[must_use]
+    pub fn needs_privilege(self) -> bool {
+        let lowers_nice = self.nice.is_some_and(|nice| nice < current_nice());
+        lowers_nice || self.policy.is_some_and(|(policy, _)| policy.is_realtime())
+    }
+
+    /// Apply the settings to the calling process
+    pub(crate) fn apply(self) -> Result<()> {
+        if let Some(nice) = self.nice
+            && unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0
+        {
+            return Err(Error::PermissionDenied {
+                operation: format!(
+                    "setting nice value {nice}: {}",
+                    std::io::Error::last_os_error()
+                ),
+            });
+        }
+
+        if let Some((policy, priority)) = self.policy {
+            let param = libc::sched_param {
+                sched_priority: priority,
+            };
+            if unsafe { libc::sched_setscheduler(0, policy.raw(), &raw const param) } != 0 {
+                return Err(Error::PermissionDenied {
+                    operation: format!(
+                        "setting scheduling policy {policy}: {}",
+                        std::io::Error::last_os_error()
+                    ),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Formats as e.g. `fifo 50`, `batch, nice 10` or `nice -5`
+impl fmt::Display for Scheduling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some((policy, priority)) = self.policy {
+            parts.push(if policy.is_realtime() {
+                format!("{policy} {priority}")
+            } else {
+                policy.to_string()
+            });
+        }
+        if let Some(nice) = self.nice {
+            parts.push(format!("nice {nice}"));
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
+
+/// Nice value of the calling process
+fn current_nice() -> i32 {
+    // -1 is a valid result, so only errno tells a failure apart; assume the
+    // default then
+    Errno::clear();
+    let nice = unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) };
+    if Errno::last_raw() == 0 { nice } else { 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_policy() {
+        assert_eq!("batch".parse::<SchedPolicy>().unwrap(), SchedPolicy::Batch);
+        assert_eq!(
+            "RR".parse::<SchedPolicy>().unwrap(),
+            SchedPolicy::RoundRobin
+        );
+        assert_eq!("normal".parse::<SchedPolicy>().unwrap(), SchedPolicy::Other);
+        assert!("deadline".parse::<SchedPolicy>().is_err());
+        assert_eq!(SchedPolicy::Fifo.to_string(), "fifo");
+    }
+
+    #[test]
+    fn test_validate_combinations() {
+        let batch = Scheduling::new(Some(SchedPolicy::Batch), None, Some(10)).unwrap();
+        assert_eq!(batch.policy(), Some((SchedPolicy::Batch, 0)));
+        assert_eq!(batch.nice(), Some(10));
+
+        let fifo = Scheduling::new(Some(SchedPolicy::Fifo), Some(50), None).unwrap();
+        assert_eq!(fifo.policy(), Some((SchedPolicy::Fifo, 50)));
+        assert!(fifo.needs_privilege());
+
+        // Real-time policies need a priority and ignore nice
+        assert!(Scheduling::new(Some(SchedPolicy::Fifo), None, None).is_err());
+        assert!(Scheduling::new(Some(SchedPolicy::RoundRobin), Some(100), None).is_err());
+        assert!(Scheduling::new(Some(SchedPolicy::Fifo), Some(1), Some(0)).is_err());
+        // The others take no priority
+        assert!(Scheduling::new(Some(SchedPolicy::Batch), Some(5), None).is_err());
+        assert!(Scheduling::new(None, Some(5), None).is_err());
+        assert!(Scheduling::new(Some(SchedPolicy::Idle), None, Some(5)).is_err());
+        assert!(Scheduling::new(None, None, Some(20)).is_err());
+
+        let nice = Scheduling::new(None, None, Some(Scheduling::MAX_NICE)).unwrap();
+        assert!(!nice.needs_privilege());
+
+        assert_eq!(fifo.to_string(), "fifo 50");
+        assert_eq!(batch.to_string(), "batch, nice 10");
+        assert_eq!(nice.to_string(), "nice 19");
+    }
+}
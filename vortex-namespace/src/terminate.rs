@@ -0,0 +1,182 @@
+//! Stopping a process: a first signal, then `SIGKILL` after a grace period
+
+use std::fmt;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use nix::sys::signal::{Signal, kill, killpg};
+use nix::unistd::Pid;
+use serde::{Deserialize, Serialize};
+use vortex_core::{Error, Result};
+
+/// How to stop a process that has to end
+///
+/// `signal` goes first so the program can flush its state and exit on its
+/// own; `SIGKILL`, which cannot be caught, follows if it is still running
+/// once `grace` is over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TerminationConfig {
+    /// Signal asking the process to exit
+    #[serde(with = "signal_name")]
+    pub signal: Signal,
+    /// How long to wait after `signal` before sending `SIGKILL`
+    pub grace: Duration,
+}
+
+impl TerminationConfig {
+    /// Grace period unless set otherwise
+    pub const DEFAULT_GRACE: Duration = Duration::from_secs(10);
+
+    /// Send `signal` first and `SIGKILL` after `grace`
+    #[must_use]
+    pub const fn new(signal: Signal, grace: Duration) -> Self {
+        Self { signal, grace }
+    }
+}
+
+/// `SIGTERM`, then `SIGKILL` after [`TerminationConfig::DEFAULT_GRACE`]
+impl Default for TerminationConfig {
+    fn default() -> Self {
+        Self::new(Signal::SIGTERM, Self::DEFAULT_GRACE)
+    }
+}
+
+/// How a process that was asked to stop ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Termination {
+    /// It exited within the grace period after the first signal
+    Graceful,
+    /// It was still running after the grace period and got `SIGKILL`
+    Killed,
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Graceful => "graceful",
+            Self::Killed => "killed",
+        })
+    }
+}
+
+/// Parse a signal given as `INT`, `SIGINT`, `int` or `2`
+///
+/// # Errors
+/// Returns [`Error::InvalidConfig`] for a name or number Linux does not know
+pub fn parse_signal(name: &str) -> Result<Signal> {
+    let by_name = || {
+        let name = name.to_ascii_uppercase();
+        if name.starts_with("SIG") {
+            name.parse().ok()
+        } else {
+            format!("SIG{name}").parse().ok()
+        }
+    };
+    let signal = name
+        .parse::<i32>()
+        .map_or_else(|_| by_name(), |number| Signal::try_from(number).ok());
+    signal.ok_or_else(|| Error::InvalidConfig {
+        message: format!("Unknown signal '{name}'"),
+    })
+}
+
+/// Signals by name in serialized configs, e.g. `"SIGTERM"`
+mod signal_name {
+    use nix::sys::signal::Signal;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[allow(clippy::trivially_copy_pass_by_ref)] // serde's signature
+    pub fn serialize<S: Serializer>(signal: &Signal, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(signal.as_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Signal, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        super::parse_signal(&name).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Stops a child that runs past its timeout
+///
+/// A thread waits out the timeout, then signals the child's process group
+/// as the [`TerminationConfig`] says, unless [`Watchdog::finish`] tells it
+/// that the child exited first.
+pub(crate) struct Watchdog {
+    exited: Sender<()>,
+    thread: JoinHandle<Option<Termination>>,
+}
+
+impl Watchdog {
+    /// Start watching `child`, the leader of its own process group
+    pub fn start(child: Pid, timeout: Duration, config: TerminationConfig) -> Self {
+        let (exited, wait) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            if wait.recv_timeout(timeout) != Err(RecvTimeoutError::Timeout) {
+                return None;
+            }
+            tracing::warn!(
+                pid = child.as_raw(),
+                signal = %config.signal,
+                ?timeout,
+                "Command timed out, stopping it"
+            );
+            signal_group(child, config.signal);
+
+            if wait.recv_timeout(config.grace) != Err(RecvTimeoutError::Timeout) {
+                return Some(Termination::Graceful);
+            }
+            tracing::warn!(
+                pid = child.as_raw(),
+                grace = ?config.grace,
+                "Command still running after the grace period, killing it"
+            );
+            signal_group(child, Signal::SIGKILL);
+            Some(Termination::Killed)
+        });
+        Self { exited, thread }
+    }
+
+    /// Report that the child exited and learn whether it had to be stopped
+    ///
+    /// Call this before reaping the child, so that a signal sent in the
+    /// meantime cannot reach another process that got its PID.
+    pub fn finish(self) -> Option<Termination> {
+        let _ = self.exited.send(());
+        self.thread.join().unwrap_or(None)
+    }
+}
+
+/// Signal the process group led by `leader`, or only `leader` if it has not
+/// got a group of its own yet
+fn signal_group(leader: Pid, signal: Signal) {
+    if killpg(leader, signal).is_err() {
+        let _ = kill(leader, signal);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_signal() {
+        assert_eq!(parse_signal("INT").unwrap(), Signal::SIGINT);
+        assert_eq!(parse_signal("sigterm").unwrap(), Signal::SIGTERM);
+        assert_eq!(parse_signal("9").unwrap(), Signal::SIGKILL);
+        assert!(parse_signal("LOUD").is_err());
+        assert!(parse_signal("99").is_err());
+    }
+
+    #[test]
+    fn test_config_serde_names_signal() {
+        let config = TerminationConfig::new(Signal::SIGINT, Duration::from_secs(3));
+        let json = serde_json::to_value(config).unwrap();
+        assert_eq!(json["signal"], "SIGINT");
+        assert_eq!(
+            serde_json::from_value::<TerminationConfig>(json).unwrap(),
+            config
+        );
+    }
+}
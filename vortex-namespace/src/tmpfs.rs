@@ -0,0 +1,255 @@
+//! Memory-backed `tmpfs` mounts private to the container
+//!
+//! A tmpfs is mounted inside the child's mount namespace, so it goes away
+//! with the container and nothing written to it is ever visible on the host.
+//! Without a size limit the kernel caps it at half of RAM, which is why
+//! [`TmpfsMount::private_tmp`] always takes one.
+
+use nix::mount::{MsFlags, mount};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use vortex_core::{Error, MemorySize, Result};
+
+/// A tmpfs mounted at `target` inside the container
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TmpfsMount {
+    /// Absolute mount point inside the container
+    pub target: PathBuf,
+    /// Maximum size of the filesystem (default: half of RAM)
+    pub size: Option<MemorySize>,
+    /// Permissions of the root directory, e.g. `0o1777`
+    pub mode: Option<u32>,
+    /// Mount read-only
+    pub read_only: bool,
+    /// Forbid executing binaries from the mount
+    pub noexec: bool,
+}
+
+impl TmpfsMount {
+    /// A tmpfs at `target` with the kernel's default size
+    #[must_use]
+    pub fn new(target: impl Into<PathBuf>) -> Self {
+        Self {
+            target: target.into(),
+            size: None,
+            mode: None,
+            read_only: false,
+            noexec: false,
+        }
+    }
+
+    /// A world-writable `/tmp` of at most `size`
+    #[must_use]
+    pub fn private_tmp(size: MemorySize) -> Self {
+        Self {
+            size: Some(size),
+            mode: Some(0o1777),
+            ..Self::new("/tmp")
+        }
+    }
+
+    /// Check the mount point and size before anything is forked
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidConfig`] if the target is not an absolute
+    /// path below `/`, contains `..`, or the size is zero
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |reason: &str| Error::InvalidConfig {
+            message: format!("Invalid tmpfs target {}: {reason}", self.target.display()),
+        };
+
+        if !self.target.is_absolute() {
+            return Err(invalid("must be an absolute path"));
+        }
+        if self.target.components().any(|c| c == Component::ParentDir) {
+            return Err(invalid("must not contain '..'"));
+        }
+        if self.target == Path::new("/") {
+            return Err(invalid("cannot replace the root filesystem"));
+        }
+        if self.size.is_some_and(|size| size.as_bytes() == 0) {
+            return Err(Error::InvalidConfig {
+                message: format!("tmpfs size for {} must not be zero", self.target.display()),
+            });
+        }
+        if self.mode.is_some_and(|mode| mode > 0o7777) {
+            return Err(invalid("mode must be at most 7777"));
+        }
+
+        Ok(())
+    }
+
+    /// Mount flags; `nosuid` and `nodev` are always set
+    fn flags(&self) -> MsFlags {
+        let mut flags = MsFlags::MS_NOSUID | MsFlags::MS_NODEV;
+        if self.read_only {
+            flags |= MsFlags::MS_RDONLY;
+        }
+        if self.noexec {
+            flags |= MsFlags::MS_NOEXEC;
+        }
+        flags
+    }
+
+    /// Filesystem-specific options passed to `mount(2)`
+    fn data(&self) -> String {
+        let mut data = String::new();
+        if let Some(size) = self.size {
+            let _ = write!(data, "size={}", size.as_bytes());
+        }
+        if let Some(mode) = self.mode {
+            if !data.is_empty() {
+                data.push(',');
+            }
+            let _ = write!(data, "mode={mode:o}");
+        }
+        data
+    }
+
+    /// Mount the tmpfs, creating the mount point if needed
+    fn mount(&self) -> Result<()> {
+        std::fs::create_dir_all(&self.target).map_err(|e| Error::Namespace {
+            message: format!("Failed to create {}: {e}", self.target.display()),
+        })?;
+
+        let data = self.data();
+        mount(
+            Some("tmpfs"),
+            self.target.as_path(),
+            Some("tmpfs"),
+            self.flags(),
+            Some(data.as_str()),
+        )
+        .map_err(|e| Error::Namespace {
+            message: format!("Failed to mount tmpfs on {}: {e}", self.target.display()),
+        })
+    }
+}
+
+/// Parse `TARGET[:OPTION,...]`, e.g. `/tmp:size=64m,mode=1777,noexec`
+///
+/// Options are `size=` with an optional `k`, `m` or `g` suffix, `mode=` in
+/// octal, `ro`, `rw`, `noexec` and `exec`.
+impl FromStr for TmpfsMount {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: String| Error::InvalidConfig {
+            message: format!("Invalid tmpfs mount '{s}': {reason}"),
+        };
+
+        let (target, options) = s.split_once(':').unwrap_or((s, ""));
+        let mut tmpfs = Self::new(target);
+
+        for option in options.split(',').filter(|option| !option.is_empty()) {
+            match option.split_once('=') {
+                Some(("size", size)) => tmpfs.size = Some(parse_size(size).map_err(invalid)?),
+                Some(("mode", mode)) => {
+                    let mode = u32::from_str_radix(mode, 8)
+                        .map_err(|_| invalid(format!("mode '{mode}' is not octal")))?;
+                    tmpfs.mode = Some(mode);
+                }
+                Some(_) => return Err(invalid(format!("unknown option '{option}'"))),
+                None => match option {
+                    "ro" => tmpfs.read_only = true,
+                    "rw" => tmpfs.read_only = false,
+                    "noexec" => tmpfs.noexec = true,
+                    "exec" => tmpfs.noexec = false,
+                    _ => return Err(invalid(format!("unknown option '{option}'"))),
+                },
+            }
+        }
+
+        tmpfs.validate()?;
+        Ok(tmpfs)
+    }
+}
+
+/// Parse a size like `65536`, `512k`, `64m` or `1g`
+fn parse_size(value: &str) -> std::result::Result<MemorySize, String> {
+    let lower = value.to_ascii_lowercase();
+    let (number, scale): (&str, fn(u64) -> Result<MemorySize>) = match lower.char_indices().last() {
+        Some((i, 'k')) => (&lower[..i], MemorySize::try_from_kb),
+        Some((i, 'm')) => (&lower[..i], MemorySize::try_from_mb),
+        Some((i, 'g')) => (&lower[..i], MemorySize::try_from_gb),
+        _ => (&lower, |bytes| Ok(MemorySize::from_bytes(bytes))),
+    };
+
+    let number = number
+        .parse()
+        .map_err(|_| format!("size '{value}' is not a number (expected e.g. 64m)"))?;
+    scale(number).map_err(|e| e.to_string())
+}
+
+/// Mount every tmpfs in the calling process's mount namespace
+///
+/// Mounts are made private first so none of them propagates back to the
+/// host, also when the container shares the host's root filesystem.
+pub(crate) fn mount_all(mounts: &[TmpfsMount]) -> Result<()> {
+    mount(
+        None::<&str>,
+        "/",
+        None::<&str>,
+        MsFlags::MS_REC | MsFlags::MS_PRIVATE,
+        None::<&str>,
+    )
+    .map_err(|e| Error::Namespace {
+        message: format!("Failed to make mounts private: {e}"),
+    })?;
+
+    mounts.iter().try_for_each(TmpfsMount::mount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tmpfs_mount() {
+        let tmpfs: TmpfsMount = "/tmp:size=64m,mode=1777,noexec".parse().unwrap();
+        assert_eq!(tmpfs.target, PathBuf::from("/tmp"));
+        assert_eq!(tmpfs.size, Some(MemorySize::from_mb(64)));
+        assert_eq!(tmpfs.mode, Some(0o1777));
+        assert!(tmpfs.noexec);
+        assert!(!tmpfs.read_only);
+        assert_eq!(tmpfs.data(), "size=67108864,mode=1777");
+
+        let tmpfs: TmpfsMount = "/run".parse().unwrap();
+        assert_eq!(tmpfs, TmpfsMount::new("/run"));
+        assert_eq!(tmpfs.data(), "");
+
+        assert_eq!(
+            "/cache:size=512K,ro".parse::<TmpfsMount>().unwrap().size,
+            Some(MemorySize::from_kb(512))
+        );
+    }
+
+    #[test]
+    fn test_reject_invalid_tmpfs_mounts() {
+        for spec in [
+            "tmp:size=64m",
+            "/:size=64m",
+            "/tmp/../etc",
+            "/tmp:size=0",
+            "/tmp:size=lots",
+            "/tmp:size=99999999999g",
+            "/tmp:mode=999",
+            "/tmp:mode=17777",
+            "/tmp:uid=0",
+            "/tmp:suid",
+        ] {
+            assert!(spec.parse::<TmpfsMount>().is_err(), "{spec} was accepted");
+        }
+    }
+
+    #[test]
+    fn test_private_tmp() {
+        let tmpfs = TmpfsMount::private_tmp(MemorySize::from_mb(16));
+        assert!(tmpfs.validate().is_ok());
+        assert_eq!(tmpfs.target, PathBuf::from("/tmp"));
+        assert_eq!(tmpfs.data(), "size=16777216,mode=1777");
+        assert_eq!(tmpfs.flags(), MsFlags::MS_NOSUID | MsFlags::MS_NODEV);
+    }
+}
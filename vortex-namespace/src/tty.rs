@@ -0,0 +1,165 @@
+//! Pseudo-terminal support for interactive containers
+//!
+//! The child gets the slave side of a fresh pty as its controlling terminal
+//! and stdio. The parent puts its own terminal in raw mode and copies bytes
+//! between it and the master side until the child closes the pty.
+
+use nix::errno::Errno;
+use nix::pty::{OpenptyResult, Winsize, openpty};
+use nix::sys::termios::{SetArg, Termios, cfmakeraw, tcgetattr, tcsetattr};
+use std::os::fd::{AsRawFd, OwnedFd};
+use vortex_core::{Error, Result};
+
+/// Allocate a pty sized like the calling process's terminal
+pub fn open_pty() -> Result<OpenptyResult> {
+    let winsize = window_size();
+    openpty(winsize.as_ref(), None).map_err(|e| Error::Namespace {
+        message: format!("Failed to allocate pty: {e}"),
+    })
+}
+
+/// Make `slave` the controlling terminal and stdio of the calling process
+///
+/// Meant to run in the forked child: starts a new session so the pty can
+/// become its controlling terminal.
+pub fn attach_slave(slave: OwnedFd) -> Result<()> {
+    let fail = |what: &str| Error::Namespace {
+        message: format!("{what}: {}", std::io::Error::last_os_error()),
+    };
+
+    nix::unistd::setsid().map_err(|e| Error::Namespace {
+        message: format!("Failed to start new session: {e}"),
+    })?;
+
+    let fd = slave.as_raw_fd();
+    unsafe {
+        if libc::ioctl(fd, libc::TIOCSCTTY, 0) == -1 {
+            return Err(fail("Failed to set controlling terminal"));
+        }
+        for target in 0..=2 {
+            if libc::dup2(fd, target) == -1 {
+                return Err(fail("Failed to redirect stdio to pty"));
+            }
+        }
+    }
+
+    // Keep the descriptor if it already was one of 0..=2
+    if fd <= 2 {
+        std::mem::forget(slave);
+    }
+    Ok(())
+}
+
+/// Copy between the caller's stdin/stdout and the pty master until the
+/// child side is closed
+pub fn proxy(master: &OwnedFd) -> Result<()> {
+    let _raw = RawModeGuard::enable();
+
+    let master_fd = master.as_raw_fd();
+    let mut stdin_open = true;
+    let mut buf = [0u8; 4096];
+
+    loop {
+        let mut fds = [
+            libc::pollfd {
+                fd: master_fd,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            // poll ignores negative descriptors
+            libc::pollfd {
+                fd: if stdin_open { libc::STDIN_FILENO } else { -1 },
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+
+        if unsafe { libc::poll(fds.as_mut_ptr(), 2, -1) } == -1 {
+            let e = std::io::Error::last_os_error();
+            if e.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(Error::Namespace {
+                message: format!("Failed to poll pty: {e}"),
+            });
+        }
+
+        let ready = libc::POLLIN | libc::POLLHUP | libc::POLLERR;
+
+        if fds[0].revents & ready != 0 {
+            // Reads fail with EIO once every slave descriptor is closed
+            let Some(n) = read_fd(master_fd, &mut buf) else {
+                break;
+            };
+            write_all(libc::STDOUT_FILENO, &buf[..n]);
+        }
+
+        if fds[1].revents & ready != 0 {
+            match read_fd(libc::STDIN_FILENO, &mut buf) {
+                Some(n) => write_all(master_fd, &buf[..n]),
+                None => stdin_open = false,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Size of the terminal on stdin, if it is one
+fn window_size() -> Option<Winsize> {
+    let mut winsize: Winsize = unsafe { std::mem::zeroed() };
+    let ok = unsafe { libc::ioctl(libc::STDIN_FILENO, libc::TIOCGWINSZ, &raw mut winsize) } == 0;
+    ok.then_some(winsize)
+}
+
+/// Read into `buf`, returning `None` on EOF or error
+///
+/// A read interrupted by a signal is retried rather than taken for EOF.
+fn read_fd(fd: i32, buf: &mut [u8]) -> Option<usize> {
+    loop {
+        let n = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) };
+        if n == -1 && Errno::last() == Errno::EINTR {
+            continue;
+        }
+        return usize::try_from(n).ok().filter(|&n| n > 0);
+    }
+}
+
+fn write_all(fd: i32, mut data: &[u8]) {
+    while !data.is_empty() {
+        let n = unsafe { libc::write(fd, data.as_ptr().cast(), data.len()) };
+        match usize::try_from(n) {
+            Ok(n) if n > 0 => data = &data[n..],
+            _ if n == -1 && Errno::last() == Errno::EINTR => {}
+            _ => return,
+        }
+    }
+}
+
+/// Puts stdin in raw mode and restores the previous settings on drop
+struct RawModeGuard {
+    original: Termios,
+}
+
+impl RawModeGuard {
+    /// Returns `None` when stdin is not a terminal
+    fn enable() -> Option<Self> {
+        let stdin = std::io::stdin();
+        let original = tcgetattr(&stdin).ok()?;
+
+        let mut raw = original.clone();
+        cfmakeraw(&mut raw);
+        if let Err(e) = tcsetattr(&stdin, SetArg::TCSANOW, &raw) {
+            tracing::warn!(error = %e, "Failed to put terminal in raw mode");
+            return None;
+        }
+
+        Some(Self { original })
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = tcsetattr(std::io::stdin(), SetArg::TCSANOW, &self.original);
+    }
+}
@@ -0,0 +1,113 @@
+//! User, groups and umask the container process runs with
+
+use nix::sys::stat::{Mode, umask};
+use nix::unistd::{Gid, Uid, setgid, setgroups, setuid};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use vortex_core::{Error, Result};
+
+/// Numeric user and primary group to run as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct User {
+    /// User ID
+    pub uid: u32,
+    /// Primary group ID
+    pub gid: u32,
+}
+
+impl User {
+    /// Create a user from its IDs
+    #[must_use]
+    pub const fn new(uid: u32, gid: u32) -> Self {
+        Self { uid, gid }
+    }
+}
+
+impl fmt::Display for User {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.uid, self.gid)
+    }
+}
+
+/// Parse `uid[:gid]`, e.g. `1000:1000`
+///
+/// Without a group the primary group ID equals the user ID.
+impl FromStr for User {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parse = |id: &str| {
+            id.parse().map_err(|_| Error::InvalidConfig {
+                message: format!("Invalid user '{s}', expected numeric uid[:gid]"),
+            })
+        };
+
+        if let Some((uid, gid)) = s.split_once(':') {
+            Ok(Self::new(parse(uid)?, parse(gid)?))
+        } else {
+            let uid = parse(s)?;
+            Ok(Self::new(uid, uid))
+        }
+    }
+}
+
+/// Switch the calling process to the given credentials
+///
+/// The order is what makes dropping privileges safe: supplementary groups,
+/// then the primary group, then the user. Changing groups needs
+/// `CAP_SETGID`, which is gone once `setuid` leaves root, so doing it the
+/// other way round would fail or, worse, leave root's groups in place.
+/// Switching user without listing groups clears the supplementary groups
+/// for the same reason. The umask comes last as it needs no privilege.
+pub(crate) fn apply_credentials(
+    user: Option<User>,
+    groups: &[u32],
+    mask: Option<u32>,
+) -> Result<()> {
+    let fail = |what: String, e: nix::Error| Error::PermissionDenied {
+        operation: format!("{what}: {e}"),
+    };
+
+    if user.is_some() || !groups.is_empty() {
+        let gids: Vec<Gid> = groups.iter().copied().map(Gid::from_raw).collect();
+        setgroups(&gids).map_err(|e| fail(format!("setting groups {groups:?}"), e))?;
+    }
+
+    if let Some(user) = user {
+        setgid(Gid::from_raw(user.gid))
+            .map_err(|e| fail(format!("setting gid {}", user.gid), e))?;
+        setuid(Uid::from_raw(user.uid))
+            .map_err(|e| fail(format!("setting uid {}", user.uid), e))?;
+    }
+
+    if let Some(mask) = mask {
+        umask(Mode::from_bits_truncate(mask));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_uid_and_gid() {
+        let user: User = "1000:100".parse().unwrap();
+        assert_eq!(user, User::new(1000, 100));
+        assert_eq!(user.to_string(), "1000:100");
+    }
+
+    #[test]
+    fn test_parse_uid_only() {
+        assert_eq!("65534".parse::<User>().unwrap(), User::new(65534, 65534));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!("root".parse::<User>().is_err());
+        assert!("1000:".parse::<User>().is_err());
+        assert!("-1".parse::<User>().is_err());
+    }
+}
@@ -37,6 +37,9 @@ fn test_execution_result() {
         exit_code: 0,
         stdout: b"hello".to_vec(),
         stderr: Vec::new(),
+        truncated: false,
+        stopped: None,
+        isolated: Vec::new(),
     };
 
     assert_eq!(result.exit_code, 0);
@@ -50,6 +53,9 @@ fn test_execution_result_clone() {
         exit_code: 42,
         stdout: b"output".to_vec(),
         stderr: b"error".to_vec(),
+        truncated: false,
+        stopped: None,
+        isolated: Vec::new(),
     };
 
     let result2 = result1.clone();
@@ -116,3 +122,18 @@ fn test_nonexistent_command() {
     let result = result.unwrap();
     assert_ne!(result.exit_code, 0);
 }
+
+#[test]
+#[ignore] // Requires root
+fn test_isolated_namespaces_are_read_back() {
+    // The init is what puts the program in the new PID namespace
+    let config = NamespaceConfig::none().with_pid(true).with_mount(true);
+    let executor = NamespaceExecutor::new(config).unwrap().with_init(true);
+
+    let result = executor.execute("/bin/true", &[]).unwrap();
+
+    assert_eq!(result.exit_code, 0);
+    assert!(result.isolated.contains(&NamespaceKind::Pid));
+    assert!(result.isolated.contains(&NamespaceKind::Mnt));
+    assert!(!result.isolated.contains(&NamespaceKind::Net));
+}
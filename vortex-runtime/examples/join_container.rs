@@ -0,0 +1,39 @@
+//! Join a running container from a second process
+//!
+//! Start a container in one terminal:
+//!   sudo vortex run --id web -- sleep 600
+//! Then join it from another:
+//!   sudo cargo run -p vortex-runtime --example join_container -- web
+
+use std::process::Command;
+use vortex_core::ContainerId;
+use vortex_namespace::NamespaceManager;
+
+fn main() {
+    tracing_subscriber::fmt::init();
+
+    let Some(id) = std::env::args().nth(1) else {
+        eprintln!("usage: join_container <container-id>");
+        std::process::exit(2);
+    };
+
+    if let Err(e) = join(&id) {
+        eprintln!("❌ Failed to join '{id}': {e}");
+        std::process::exit(1);
+    }
+}
+
+fn join(id: &str) -> vortex_core::Result<()> {
+    let manager = NamespaceManager::with_defaults();
+    println!("Before:\n{}", manager.current_namespaces()?);
+
+    vortex_runtime::join(&ContainerId::new(id)?)?;
+    println!("After:\n{}", manager.current_namespaces()?);
+
+    // Our own PID namespace hasn't changed, but children are born in the
+    // container's: `ps` run from here sees the container's process tree
+    let status = Command::new("ps").arg("-ef").status()?;
+    println!("ps exited with {status}");
+
+    Ok(())
+}
@@ -0,0 +1,301 @@
+//! A container run from start to finish
+
+use std::time::Duration;
+use vortex_cgroup::{CGroupConfig, CGroupController, ResourceBackend};
+use vortex_core::{ContainerId, CpuLimit, Error, MemoryLimit, ResourceLimits, Result};
+use vortex_namespace::{ExecutionResult, NamespaceConfig, NamespaceExecutor, Stdio, User};
+use vortex_security::{SecurityConfig, SecurityManager};
+
+/// A command with its resource limits, isolation and security settings
+///
+/// [`Self::run`] goes through the whole lifecycle in the order the kernel
+/// needs it:
+/// 1. Create the cgroup and apply the limits
+/// 2. Fork; the child joins the cgroup before anything else
+/// 3. The child creates its namespaces, drops to its user and applies the
+///    security settings
+/// 4. The child execs the command while the parent waits for it
+/// 5. Remove the cgroup, also when a step above failed
+///
+/// Build one with [`Container::builder`].
+#[derive(Debug, Clone)]
+pub struct Container {
+    id: ContainerId,
+    limits: ResourceLimits,
+    namespaces: NamespaceConfig,
+    program: String,
+    args: Vec<String>,
+    env: Option<Vec<String>>,
+    user: Option<User>,
+    security: SecurityConfig,
+    cgroup: CGroupConfig,
+    stdio: Stdio,
+    timeout: Option<Duration>,
+}
+
+impl Container {
+    /// Start building a container
+    pub fn builder() -> ContainerBuilder {
+        ContainerBuilder::default()
+    }
+
+    /// Get the container ID
+    #[must_use]
+    pub const fn id(&self) -> &ContainerId {
+        &self.id
+    }
+
+    /// Get the resource limits applied to the container's cgroup
+    #[must_use]
+    pub const fn limits(&self) -> &ResourceLimits {
+        &self.limits
+    }
+
+    /// Get the namespaces the command runs in
+    #[must_use]
+    pub const fn namespaces(&self) -> &NamespaceConfig {
+        &self.namespaces
+    }
+
+    /// Run the command to completion and remove the cgroup
+    ///
+    /// The child is forked on a blocking thread, so the caller's runtime
+    /// keeps going while the command runs.
+    ///
+    /// # Errors
+    /// Returns error if the cgroup cannot be created or a limit cannot be
+    /// set, if the command fails to start, or if the cgroup cannot be
+    /// removed afterwards. A command that runs and exits non-zero is not an
+    /// error; see [`ExecutionResult::exit_code`].
+    #[tracing::instrument(skip_all, fields(container_id = %self.id))]
+    pub async fn run(&self) -> Result<ExecutionResult> {
+        let controller =
+            CGroupController::with_config(self.id.clone(), self.cgroup.clone()).await?;
+
+        let started = match controller.apply_limits(&self.limits).await {
+            Ok(()) => self.execute(&controller).await,
+            Err(e) => Err(e),
+        };
+        let result = match started {
+            Ok(result) => result,
+            Err(e) => {
+                // Nothing is left running, so don't leave the cgroup behind either
+                if let Err(cleanup) = controller.cleanup().await {
+                    tracing::warn!(error = %cleanup, "Failed to clean up after failed start");
+                }
+                return Err(e);
+            }
+        };
+
+        tracing::info!(exit_code = result.exit_code, "Container exited");
+        controller.cleanup().await?;
+        Ok(result)
+    }
+
+    /// Fork the command into the cgroup of `controller` and wait for it
+    async fn execute(&self, controller: &CGroupController) -> Result<ExecutionResult> {
+        let mut executor = NamespaceExecutor::new(self.namespaces.clone())?
+            .with_cgroup(controller.path())
+            .with_security(SecurityManager::with_config(self.security.clone()))
+            .with_stdio(self.stdio);
+        if let Some(ref env) = self.env {
+            executor = executor.with_env(env.clone());
+        }
+        if let Some(user) = self.user {
+            executor = executor.with_user(user);
+        }
+        if let Some(timeout) = self.timeout {
+            executor = executor.with_timeout(timeout);
+        }
+
+        let program = self.program.clone();
+        let args = self.args.clone();
+        tokio::task::spawn_blocking(move || executor.execute(&program, &args)).await?
+    }
+}
+
+/// Builder for a [`Container`]
+///
+/// Only the command is required. The ID is generated when not set, the
+/// namespaces default to [`NamespaceConfig::minimal`] and the security
+/// settings to [`SecurityConfig::default`].
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct ContainerBuilder {
+    id: Option<ContainerId>,
+    limits: ResourceLimits,
+    namespaces: Option<NamespaceConfig>,
+    command: Vec<String>,
+    env: Option<Vec<String>>,
+    user: Option<User>,
+    security: SecurityConfig,
+    cgroup: CGroupConfig,
+    stdio: Stdio,
+    timeout: Option<Duration>,
+}
+
+impl ContainerBuilder {
+    /// Set the container ID, which also names its cgroup
+    pub fn id(mut self, id: ContainerId) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Limit the container's CPU bandwidth
+    pub const fn cpu(mut self, limit: CpuLimit) -> Self {
+        self.limits.cpu = Some(limit);
+        self
+    }
+
+    /// Limit the container's memory
+    pub const fn memory(mut self, limit: MemoryLimit) -> Self {
+        self.limits.memory = Some(limit);
+        self
+    }
+
+    /// Limit how many processes the container may have
+    pub const fn pids(mut self, max: u64) -> Self {
+        self.limits.pids = Some(max);
+        self
+    }
+
+    /// Set every resource limit at once, replacing the ones set so far
+    pub fn limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Set the namespaces the command runs in
+    pub fn namespaces(mut self, namespaces: NamespaceConfig) -> Self {
+        self.namespaces = Some(namespaces);
+        self
+    }
+
+    /// Set the program and its arguments
+    pub fn command<I, S>(mut self, command: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.command = command.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Replace the command's environment with `KEY=VALUE` entries
+    pub fn env(mut self, env: Vec<String>) -> Self {
+        self.env = Some(env);
+        self
+    }
+
+    /// Run the command as `user` instead of the caller's user
+    pub const fn user(mut self, user: User) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    /// Set the security settings applied right before exec
+    pub const fn security(mut self, security: SecurityConfig) -> Self {
+        self.security = security;
+        self
+    }
+
+    /// Set where the container's cgroup is created
+    pub fn cgroup_config(mut self, config: CGroupConfig) -> Self {
+        self.cgroup = config;
+        self
+    }
+
+    /// Capture the command's output or let it inherit the caller's streams
+    pub const fn stdio(mut self, stdio: Stdio) -> Self {
+        self.stdio = stdio;
+        self
+    }
+
+    /// Stop the command once it has run for `timeout`
+    pub const fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Build the container without running it
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidConfig`] if no command was set
+    pub fn build(self) -> Result<Container> {
+        let mut command = self.command.into_iter();
+        let program = command.next().ok_or_else(|| Error::InvalidConfig {
+            message: "No command specified".to_string(),
+        })?;
+
+        Ok(Container {
+            id: self.id.unwrap_or_else(ContainerId::generate),
+            limits: self.limits,
+            namespaces: self.namespaces.unwrap_or_else(NamespaceConfig::minimal),
+            program,
+            args: command.collect(),
+            env: self.env,
+            user: self.user,
+            security: self.security,
+            cgroup: self.cgroup,
+            stdio: self.stdio,
+            timeout: self.timeout,
+        })
+    }
+
+    /// Build the container and run it, see [`Container::run`]
+    ///
+    /// # Errors
+    /// Returns error if the container cannot be built or run
+    pub async fn run(self) -> Result<ExecutionResult> {
+        self.build()?.run().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vortex_core::{CpuCores, MemorySize};
+
+    #[test]
+    fn test_builder_defaults() {
+        let container = Container::builder().command(["true"]).build().unwrap();
+
+        assert_eq!(container.program, "true");
+        assert!(container.args.is_empty());
+        assert!(container.limits().is_empty());
+        assert_eq!(
+            container.namespaces().enabled_namespaces(),
+            NamespaceConfig::minimal().enabled_namespaces()
+        );
+        assert!(container.security.no_new_privs);
+        assert_eq!(container.stdio, Stdio::Capture);
+    }
+
+    #[test]
+    fn test_builder_sets_limits_and_command() {
+        let container = Container::builder()
+            .id(ContainerId::new("web").unwrap())
+            .cpu(CpuLimit::new(CpuCores::new(0.5)))
+            .memory(MemoryLimit::new(MemorySize::from_mb(64)))
+            .pids(32)
+            .command(["sh", "-c", "exit 3"])
+            .build()
+            .unwrap();
+
+        assert_eq!(container.id().as_str(), "web");
+        assert_eq!(container.limits().pids, Some(32));
+        assert_eq!(
+            container.limits().memory,
+            Some(MemoryLimit::new(MemorySize::from_mb(64)))
+        );
+        assert!(container.limits().cpu.is_some());
+        assert_eq!(container.program, "sh");
+        assert_eq!(container.args, vec!["-c", "exit 3"]);
+    }
+
+    #[test]
+    fn test_builder_requires_command() {
+        let result = Container::builder().build();
+        assert!(matches!(result, Err(Error::InvalidConfig { .. })));
+    }
+}
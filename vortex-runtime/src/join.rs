@@ -0,0 +1,72 @@
+//! Moving the calling process into a running container
+
+use std::path::Path;
+use vortex_cgroup::{CGroupConfig, CGroupController};
+use vortex_core::{ContainerId, Error, Result};
+use vortex_namespace::NamespaceManager;
+
+/// Move the calling process into the running container `id`
+///
+/// This is `exec` without the fork: the container is looked up through its
+/// cgroup, then the caller joins that cgroup and the namespaces of one of
+/// the container's processes, see [`NamespaceManager::join`].
+///
+/// The caller's own PID namespace does not change; only children spawned
+/// afterwards are born in the container's. A mount namespace can only be
+/// joined by a single-threaded process, so call this before starting a
+/// tokio runtime or any other thread, and run the actual work in a child.
+///
+/// # Errors
+/// Returns [`Error::ContainerNotFound`] if the container has no cgroup or no
+/// process, or error if the cgroup or a namespace cannot be joined
+pub fn join(id: &ContainerId) -> Result<()> {
+    join_with_config(id, CGroupConfig::default())
+}
+
+/// [`join`] a container whose cgroup was created with `config`
+///
+/// # Errors
+/// See [`join`]
+pub fn join_with_config(id: &ContainerId, config: CGroupConfig) -> Result<()> {
+    let controller = CGroupController::attach_with_config(id.clone(), config)?;
+    let pid = first_process(controller.path())?
+        .ok_or_else(|| Error::ContainerNotFound { id: id.to_string() })?;
+
+    NamespaceManager::with_defaults().join(pid, controller.path())?;
+    tracing::info!(container_id = %id, pid, "Joined container");
+    Ok(())
+}
+
+/// First process in the cgroup at `cgroup`, `None` if it is empty
+///
+/// Read without tokio: its blocking pool would make the caller
+/// multithreaded, and the mount namespace couldn't be joined then.
+fn first_process(cgroup: &Path) -> Result<Option<i32>> {
+    let procs = std::fs::read_to_string(cgroup.join("cgroup.procs"))?;
+    Ok(procs
+        .lines()
+        .find_map(|line| line.trim().parse::<i32>().ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_unknown_container() {
+        let root = std::env::temp_dir().join(format!("vortex-join-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        let id = ContainerId::new("absent").unwrap();
+        let result = join_with_config(&id, CGroupConfig::new().with_root(&root));
+        assert!(matches!(result, Err(Error::ContainerNotFound { .. })));
+
+        // A cgroup without processes has nothing to join either
+        std::fs::create_dir_all(root.join("vortex").join("absent")).unwrap();
+        std::fs::write(root.join("vortex").join("absent").join("cgroup.procs"), "").unwrap();
+        let result = join_with_config(&id, CGroupConfig::new().with_root(&root));
+        assert!(matches!(result, Err(Error::ContainerNotFound { .. })));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
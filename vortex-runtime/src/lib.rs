@@ -0,0 +1,42 @@
+//! High-level container API
+//!
+//! This crate ties the cgroup, namespace and security crates together
+//! behind one [`Container`] type, so embedders get the setup order right
+//! without orchestrating [`CGroupController`] and [`NamespaceExecutor`]
+//! themselves:
+//!
+//! ```no_run
+//! use vortex_core::{ContainerId, CpuCores, CpuLimit, MemoryLimit, MemorySize};
+//! use vortex_runtime::Container;
+//!
+//! # async fn example() -> vortex_core::Result<()> {
+//! let result = Container::builder()
+//!     .id(ContainerId::new("web")?)
+//!     .cpu(CpuLimit::new(CpuCores::new(0.5)))
+//!     .memory(MemoryLimit::new(MemorySize::from_mb(256)))
+//!     .command(["echo", "hello"])
+//!     .run()
+//!     .await?;
+//! assert_eq!(result.exit_code, 0);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`join`] moves the calling process into a running container instead.
+//!
+//! [`CGroupController`]: vortex_cgroup::CGroupController
+//! [`NamespaceExecutor`]: vortex_namespace::NamespaceExecutor
+
+#![warn(missing_docs, clippy::all, clippy::pedantic, clippy::nursery)]
+#![allow(clippy::module_name_repetitions)]
+
+pub mod container;
+pub mod join;
+
+pub use container::{Container, ContainerBuilder};
+pub use join::{join, join_with_config};
+
+// Re-export the types a container is configured with
+pub use vortex_cgroup::CGroupConfig;
+pub use vortex_namespace::{ExecutionResult, NamespaceConfig, Stdio, User};
+pub use vortex_security::SecurityConfig;
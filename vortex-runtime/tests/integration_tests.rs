@@ -0,0 +1,106 @@
+use vortex_core::*;
+use vortex_runtime::*;
+
+/// Check if running as root
+fn is_root() -> bool {
+    unsafe { libc::getuid() == 0 }
+}
+
+fn temp_root(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("vortex-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[tokio::test]
+async fn test_container_runs_in_its_cgroup() {
+    let root = temp_root("runtime-run");
+
+    let result = Container::builder()
+        .id(ContainerId::new("runtime-run").unwrap())
+        .cgroup_config(CGroupConfig::new().with_root(&root))
+        .namespaces(NamespaceConfig::none())
+        .pids(16)
+        .command(["sh", "-c", "echo hello; exit 3"])
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(result.exit_code, 3);
+    assert_eq!(result.stdout, b"hello\n");
+
+    // A plain directory keeps its files, so the cgroup shows what was set up
+    let path = root.join("vortex").join("runtime-run");
+    let read = |file: &str| std::fs::read_to_string(path.join(file)).unwrap();
+    assert_eq!(read("pids.max"), "16");
+    assert!(!read("cgroup.procs").trim().is_empty());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_failed_start_removes_cgroup() {
+    let root = temp_root("runtime-fail");
+
+    let result = Container::builder()
+        .id(ContainerId::new("runtime-fail").unwrap())
+        .cgroup_config(CGroupConfig::new().with_root(&root))
+        .namespaces(NamespaceConfig::none())
+        .limits(ResourceLimits {
+            cpu_weight: Some(0),
+            ..ResourceLimits::default()
+        })
+        .command(["true"])
+        .run()
+        .await;
+
+    // Rejected before the command starts
+    assert!(result.is_err());
+    assert!(!root.join("vortex").join("runtime-fail").exists());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+#[ignore] // Requires root
+async fn test_container_removes_cgroup() {
+    if !is_root() {
+        return;
+    }
+
+    let id = ContainerId::new("runtime-removed").unwrap();
+    let config = CGroupConfig::default();
+    let path = config.root.join(&config.namespace).join(id.as_str());
+
+    let result = Container::builder()
+        .id(id)
+        .namespaces(NamespaceConfig::none())
+        .command(["cat", "/proc/self/cgroup"])
+        .run()
+        .await
+        .unwrap();
+
+    // The command ran inside the cgroup, which is gone once it exited
+    assert_eq!(result.exit_code, 0);
+    assert!(result.stdout_string().contains("/vortex/runtime-removed"));
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+#[ignore] // Requires root
+async fn test_container_in_namespaces() {
+    if !is_root() {
+        return;
+    }
+
+    let result = Container::builder()
+        .memory(MemoryLimit::new(MemorySize::from_mb(64)))
+        .command(["sh", "-c", "echo $$"])
+        .run()
+        .await
+        .unwrap();
+
+    // The shell is PID 1 of its own PID namespace
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(result.stdout, b"1\n");
+}
@@ -0,0 +1,184 @@
+//! Preflight check of the effective capabilities
+//!
+//! Setting up a container needs several privileges, and without one of them
+//! the setup fails halfway with a bare `EPERM`. Checking the effective set
+//! up front reports every missing capability at once, before any work is
+//! done.
+
+use std::fmt;
+use vortex_core::{Error, Result};
+
+/// A capability the runtime may need
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// Configure network interfaces
+    NetAdmin,
+    /// Create namespaces and mount filesystems
+    SysAdmin,
+    /// Raise hard resource limits and lower `oom_score_adj`
+    SysResource,
+    /// Use real-time scheduling and lower the nice value
+    SysNice,
+    /// Shift the clocks of a time namespace
+    SysTime,
+}
+
+impl Capability {
+    /// Bit number of the capability in the kernel's capability sets
+    #[must_use]
+    pub const fn number(self) -> u32 {
+        match self {
+            Self::NetAdmin => 12,
+            Self::SysAdmin => 21,
+            Self::SysNice => 23,
+            Self::SysResource => 24,
+            Self::SysTime => 25,
+        }
+    }
+
+    /// What the runtime needs the capability for
+    #[must_use]
+    pub const fn purpose(self) -> &'static str {
+        match self {
+            Self::NetAdmin => "networking",
+            Self::SysAdmin => "namespaces and mounts",
+            Self::SysResource => "raising resource limits or lowering the OOM score",
+            Self::SysNice => "real-time scheduling or a lower nice value",
+            Self::SysTime => "time namespace clock offsets",
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::NetAdmin => "CAP_NET_ADMIN",
+            Self::SysAdmin => "CAP_SYS_ADMIN",
+            Self::SysResource => "CAP_SYS_RESOURCE",
+            Self::SysNice => "CAP_SYS_NICE",
+            Self::SysTime => "CAP_SYS_TIME",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A set of capabilities as the kernel reports it, one bit per capability
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CapabilitySet(u64);
+
+impl CapabilitySet {
+    /// Effective capabilities of the calling process
+    ///
+    /// # Errors
+    /// Returns error if `/proc/self/status` cannot be read or has no
+    /// `CapEff` line
+    pub fn effective() -> Result<Self> {
+        let status = std::fs::read_to_string("/proc/self/status")?;
+        Self::from_status(&status).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "no CapEff line in /proc/self/status",
+            )
+            .into()
+        })
+    }
+
+    /// Parse the `CapEff` line of a `/proc/<pid>/status` file
+    #[must_use]
+    pub fn from_status(status: &str) -> Option<Self> {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix("CapEff:"))
+            .and_then(|mask| u64::from_str_radix(mask.trim(), 16).ok())
+            .map(Self)
+    }
+
+    /// Whether the set holds a capability
+    #[must_use]
+    pub const fn contains(self, capability: Capability) -> bool {
+        self.0 & (1 << capability.number()) != 0
+    }
+
+    /// The required capabilities not in the set, in the order given
+    #[must_use]
+    pub fn missing(self, required: &[Capability]) -> Vec<Capability> {
+        let mut missing: Vec<Capability> = Vec::new();
+        for &capability in required {
+            if !self.contains(capability) && !missing.contains(&capability) {
+                missing.push(capability);
+            }
+        }
+        missing
+    }
+
+    /// Check that every required capability is in the set
+    ///
+    /// # Errors
+    /// Returns [`Error::PermissionDenied`] listing every missing capability
+    pub fn require(self, required: &[Capability]) -> Result<()> {
+        let missing = self.missing(required);
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let list: Vec<String> = missing
+            .iter()
+            .map(|capability| format!("{capability} ({})", capability.purpose()))
+            .collect();
+        Err(Error::PermissionDenied {
+            operation: format!("container setup, missing {}", list.join(", ")),
+        })
+    }
+}
+
+/// Check that the calling process holds every required capability
+///
+/// # Errors
+/// Returns [`Error::PermissionDenied`] listing every missing capability, or
+/// an error if the effective set cannot be read
+pub fn require_capabilities(required: &[Capability]) -> Result<()> {
+    CapabilitySet::effective()?.require(required)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUS: &str = "Name:\tvortex\nCapInh:\t0000000000000000\n\
+                          CapPrm:\t000001ffffffffff\nCapEff:\t0000000000201000\n";
+
+    #[test]
+    fn test_parse_effective_set() {
+        let set = CapabilitySet::from_status(STATUS).unwrap();
+        assert!(set.contains(Capability::NetAdmin));
+        assert!(set.contains(Capability::SysAdmin));
+        assert!(!set.contains(Capability::SysResource));
+
+        assert_eq!(CapabilitySet::from_status("Name:\tvortex\n"), None);
+    }
+
+    #[test]
+    fn test_require_lists_every_missing_capability() {
+        let required = [
+            Capability::SysAdmin,
+            Capability::NetAdmin,
+            Capability::SysResource,
+            Capability::NetAdmin,
+        ];
+        let err = CapabilitySet::default().require(&required).unwrap_err();
+        assert!(matches!(err, Error::PermissionDenied { .. }));
+
+        let message = err.to_string();
+        assert!(
+            message.contains("CAP_SYS_ADMIN (namespaces and mounts)"),
+            "{message}"
+        );
+        assert!(message.contains("CAP_NET_ADMIN"), "{message}");
+        assert!(message.contains("CAP_SYS_RESOURCE"), "{message}");
+        assert_eq!(message.matches("CAP_NET_ADMIN").count(), 1, "{message}");
+
+        let set = CapabilitySet::from_status(STATUS).unwrap();
+        assert_eq!(set.missing(&required), vec![Capability::SysResource]);
+        assert!(set.require(&[Capability::SysAdmin]).is_ok());
+    }
+}